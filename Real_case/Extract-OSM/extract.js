@@ -7,6 +7,7 @@ import haversine from 'haversine-distance'
 
 function processWay(way, roads, nodes){
 	const highway = way.tags.highway;
+	let roadClass;
 	switch(highway){
 		case 'motorway':
 		case 'motorway_link':
@@ -14,13 +15,18 @@ function processWay(way, roads, nodes){
 		case 'trunk_link':
 		case 'primary':
 		case 'primary_link':
+			roadClass = 'arterial';
+			break;
 		case 'secondary':
 		case 'secondary_link':
 		case 'tertiary':
 		case 'tertiary_link':
+			roadClass = 'collector';
+			break;
 		case 'unclassified':
 		case 'residential':
 		case 'living_street':
+			roadClass = 'local';
 			break;
 		default:
 			return;
@@ -32,6 +38,7 @@ function processWay(way, roads, nodes){
 			p2: way.nodeRefs[i+1],
 			directed: way.tags.oneway === 'yes',
 			sidewalks: way.tags.sidewalk === 'both' ? [true, true] : way.tags.sidewalk === 'left' ? [true, false] : way.tags.sidewalk === 'right' ? [false, true] : [false, false],
+			class: roadClass,
 		};
 		const k = JSON.stringify({
 			p1: way.nodeRefs[i],
@@ -70,7 +77,7 @@ function finalize(file, roads, nodes, simplify){
 			for(let [n, rs] of adj) if(rs.length === 2){
 				const r1 = rs[0];
 				const r2 = rs[1];
-				if(r1.directed !== r2.directed || r1.sidewalks[0] !== r2.sidewalks[0] && r1.sidewalks[1] !== r2.sidewalks[1]) continue;
+				if(r1.directed !== r2.directed || r1.class !== r2.class || r1.sidewalks[0] !== r2.sidewalks[0] && r1.sidewalks[1] !== r2.sidewalks[1]) continue;
 				let r;
 				if(r1.p2 === r2.p1) r = { p1: r1.p1, p2: r2.p2 };
 				else if(r2.p2 === r1.p1) r = { p1: r2.p1, p2: r1.p2 };
@@ -80,6 +87,7 @@ function finalize(file, roads, nodes, simplify){
 				r.discriminator = n;
 				r.directed = r1.directed;
 				r.sidewalks = r1.sidewalks;
+				r.class = r1.class;
 				r.distance = r1.distance + r2.distance;
 				const reli = (rs) => {
 					if(rs.includes(r1)) rs.splice(rs.indexOf(r1), 1);
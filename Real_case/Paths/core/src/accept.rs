@@ -0,0 +1,55 @@
+//! Acceptance criteria of the annealing solver - when to keep a solution that is not better than the best one
+
+use crate::*;
+use rand::{Rng, RngCore};
+
+/// Decides whether the annealing keeps a candidate solution that doesn't improve on the best one
+pub trait AcceptanceCriterion {
+	/// Whether to accept the candidate
+	///
+	/// Arguments:
+	/// - `candidate`: value of the candidate solution
+	/// - `current`: value of the solution the candidate was derived from
+	/// - `best`: value of the best solution so far
+	/// - `temperature`: current annealing temperature
+	/// - `rng`: randomness source
+	fn accept(&mut self, candidate: N64, current: N64, best: N64, temperature: f64, rng: &mut dyn RngCore) -> bool;
+}
+
+/// Metropolis criterion: accept improvements over the current solution with probability decaying with the temperature
+#[derive(Clone, Copy, Debug, Default)]
+pub struct Metropolis;
+impl AcceptanceCriterion for Metropolis {
+	fn accept(&mut self, candidate: N64, current: N64, _best: N64, temperature: f64, rng: &mut dyn RngCore) -> bool {
+		candidate < current && n64(rng.gen_range(0.0..1.0)) < ((candidate - current) / temperature).exp()
+	}
+}
+
+/// Threshold accepting: deterministically accept anything within the temperature of the best solution
+#[derive(Clone, Copy, Debug, Default)]
+pub struct Threshold;
+impl AcceptanceCriterion for Threshold {
+	fn accept(&mut self, candidate: N64, _current: N64, best: N64, temperature: f64, _rng: &mut dyn RngCore) -> bool {
+		candidate - best < temperature
+	}
+}
+
+/// Great deluge: accept anything below a water level, that starts at the first current solution and sinks towards the best one at each decision
+#[derive(Clone, Copy, Debug)]
+pub struct GreatDeluge {
+	/// fraction of the distance between the water level and the best value that the level sinks by at each decision
+	pub rain: f64,
+	level: Option<N64>,
+}
+impl GreatDeluge {
+	pub fn new(rain: f64) -> Self {
+		Self { rain, level: None }
+	}
+}
+impl AcceptanceCriterion for GreatDeluge {
+	fn accept(&mut self, candidate: N64, current: N64, best: N64, _temperature: f64, _rng: &mut dyn RngCore) -> bool {
+		let level = self.level.unwrap_or(current);
+		self.level = Some(level - (level - best).max(n64(0.0)) * self.rain);
+		candidate <= level
+	}
+}
@@ -0,0 +1,102 @@
+//! Initial allocation strategies of the annealing solver - which vehicle starts out responsible for which edge
+
+use crate::*;
+use graph::*;
+
+use std::hash::Hash;
+use priority_queue::PriorityQueue;
+
+/// Allocates all snowy edges to some vehicle, before annealing
+///
+/// Type Parameters:
+/// - `NId`: node id
+/// - `N`: node type
+/// - `E`: edge type
+pub trait InitialAllocation<NId, N, E>
+where
+	NId: Clone + Copy + Hash + Eq,
+	E: Edge<NId>,
+{
+	/// Allocates edges
+	///
+	/// Arguments:
+	/// - `graph`: the graph
+	/// - `starts`: starting node of each vehicle
+	/// - `snowy`: edges to allocate
+	/// - `weight`: weight of an edge
+	/// - `capable`: whether a vehicle can clear an edge (every edge has a capable vehicle)
+	///
+	/// Returns: allocated edges, for each vehicle
+	fn allocate<'a>(&self, graph: &'a Graph<NId, N, E>, starts: &[NId], snowy: &[&'a E], weight: &dyn Fn(&E) -> N64, capable: &dyn Fn(usize, &E) -> bool) -> Vec<HashSet<&'a E>>;
+}
+
+/// Allocates each edge to the capable vehicle with the (geographically) closest start to either end, the less loaded if the ends disagree
+#[derive(Clone, Copy, Debug, Default)]
+pub struct Centroid {
+	/// how geographical distances are measured
+	pub metric: meta::Metric,
+}
+impl<NId, N, E> InitialAllocation<NId, N, E> for Centroid
+where
+	NId: Clone + Copy + Hash + Eq,
+	N: Positioned,
+	E: Edge<NId>,
+{
+	fn allocate<'a>(&self, graph: &'a Graph<NId, N, E>, starts: &[NId], snowy: &[&'a E], _weight: &dyn Fn(&E) -> N64, capable: &dyn Fn(usize, &E) -> bool) -> Vec<HashSet<&'a E>> {
+		let locs = spatial::Nearest::new(self.metric, starts.iter().enumerate().map(|(v, s)| (graph.get_node(*s).unwrap().pos(), v)));
+		let closest = |c: &(f64, f64), e: &E| *locs.nearest_where(*c, |v| capable(*v, e)).unwrap();
+		let mut allocations: Vec<_> = (0..starts.len()).map(|_| HashSet::default()).collect();
+		for e in snowy.iter().copied() {
+			let lv1 = closest(&graph.get_node(e.p1()).unwrap().pos(), e);
+			let lv2 = closest(&graph.get_node(e.p2()).unwrap().pos(), e);
+			let lv = if lv1 == lv2 || allocations[lv2].len() > allocations[lv1].len() { lv1 } else { lv2 };
+			allocations[lv].insert(e);
+		}
+		allocations
+	}
+}
+
+/// Allocates each edge to the capable vehicle with the closest start, by network distance, to either end, the less loaded if the ends disagree or are unreachable
+///
+/// Network distances respect one-ways with `DIRESPECT`.
+#[derive(Clone, Copy, Debug, Default)]
+pub struct Network<const DIRESPECT: bool>;
+impl<NId, N, E, const DIRESPECT: bool> InitialAllocation<NId, N, E> for Network<DIRESPECT>
+where
+	NId: Clone + Copy + Hash + Eq,
+	E: Edge<NId>,
+{
+	fn allocate<'a>(&self, graph: &'a Graph<NId, N, E>, starts: &[NId], snowy: &[&'a E], weight: &dyn Fn(&E) -> N64, capable: &dyn Fn(usize, &E) -> bool) -> Vec<HashSet<&'a E>> {
+		// multi-source Dijkstra, labelling each node with its closest vehicle
+		let mut closest: HashMap<NId, (N64, usize)> = HashMap::default();
+		let mut q = PriorityQueue::new();
+		for (v, s) in starts.iter().enumerate() {
+			if closest.insert(*s, (n64(0.0), v)).is_none() {
+				q.push(*s, -n64(0.0));
+			}
+		}
+		while let Some((u, _)) = q.pop() {
+			let (d, v) = closest[&u];
+			for e in graph.get_edges(u) {
+				if e.is_outgoing::<DIRESPECT>(u) {
+					let w = e.other(u);
+					let dw = d + weight(e);
+					if closest.get(&w).is_none_or(|(d, _)| *d > dw) {
+						closest.insert(w, (dw, v));
+						q.push(w, -dw);
+					}
+				}
+			}
+		}
+		let mut allocations: Vec<_> = (0..starts.len()).map(|_| HashSet::default()).collect();
+		for e in snowy.iter().copied() {
+			let mut lvs: Vec<_> = [e.p1(), e.p2()].iter().filter_map(|n| closest.get(n)).map(|(_, v)| *v).filter(|v| capable(*v, e)).collect();
+			if lvs.is_empty() {
+				lvs.extend((0..starts.len()).filter(|v| capable(*v, e)));
+			}
+			let lv = lvs.into_iter().min_by_key(|v| allocations[*v].len()).unwrap();
+			allocations[lv].insert(e);
+		}
+		allocations
+	}
+}
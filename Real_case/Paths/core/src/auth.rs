@@ -0,0 +1,132 @@
+//! API-key authentication and job quotas, for the server mode shared between departments
+//!
+//! Each client is identified by its API key, and may only run so many jobs at once.
+//! Jobs are answered as they complete, within their request, so there are no results to retrieve later - nor to keep from other clients.
+
+use std::{collections::HashMap, sync::{Arc, Mutex}};
+use serde::*;
+
+fn default_max_jobs() -> usize {
+	1
+}
+
+/// A client of the server
+#[derive(Serialize, Deserialize, Clone, PartialEq, Debug)]
+pub struct Client {
+	pub name: String,
+	/// API key, given in the `X-Api-Key` header
+	pub key: String,
+	/// maximum number of concurrently running jobs
+	#[serde(default = "default_max_jobs")]
+	pub max_jobs: usize,
+}
+
+/// Clients configuration
+pub type Clients = Vec<Client>;
+
+/// Why a request was denied
+#[derive(Clone, PartialEq, Debug)]
+pub enum Denied {
+	/// missing or unknown API key
+	Unauthorized,
+	/// the client already runs as many jobs as it may
+	QuotaExceeded(usize),
+}
+
+impl std::fmt::Display for Denied {
+	fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+		match self {
+			Denied::Unauthorized => write!(f, "missing or unknown API key"),
+			Denied::QuotaExceeded(max) => write!(f, "already running {} jobs", max),
+		}
+	}
+}
+
+/// Authentication and quotas
+#[derive(Clone, Debug)]
+pub struct Auth {
+	clients: Arc<HashMap<String, Client>>,
+	/// number of running jobs per client name
+	running: Arc<Mutex<HashMap<String, usize>>>,
+}
+
+/// Permission to run a job, counting against its client's quota until dropped
+#[derive(Debug)]
+pub struct Permit {
+	running: Arc<Mutex<HashMap<String, usize>>>,
+	client: String,
+}
+
+impl Drop for Permit {
+	fn drop(&mut self) {
+		if let Some(n) = self.running.lock().unwrap().get_mut(&self.client) {
+			*n = n.saturating_sub(1);
+		}
+	}
+}
+
+impl Auth {
+	pub fn new(clients: Clients) -> Self {
+		Auth {
+			clients: Arc::new(clients.into_iter().map(|c| (c.key.clone(), c)).collect()),
+			running: Default::default(),
+		}
+	}
+
+	/// Identifies the client by its API key
+	pub fn authenticate(&self, key: Option<&str>) -> Result<&Client, Denied> {
+		key.and_then(|key| self.clients.get(key)).ok_or(Denied::Unauthorized)
+	}
+
+	/// Starts a job for the client, within its quota
+	///
+	/// Returns: the permit to hold while the job runs
+	pub fn start(&self, client: &Client) -> Result<Permit, Denied> {
+		let mut running = self.running.lock().unwrap();
+		let n = running.entry(client.name.clone()).or_insert(0);
+		if *n >= client.max_jobs {
+			return Err(Denied::QuotaExceeded(client.max_jobs));
+		}
+		*n += 1;
+		Ok(Permit {
+			running: self.running.clone(),
+			client: client.name.clone(),
+		})
+	}
+}
+
+#[cfg(test)]
+mod test {
+	use super::*;
+
+	fn auth() -> Auth {
+		Auth::new(vec![
+			Client { name: "roads".to_string(), key: "k1".to_string(), max_jobs: 2 },
+			Client { name: "sidewalks".to_string(), key: "k2".to_string(), max_jobs: 1 },
+		])
+	}
+
+	#[test]
+	fn authenticates_by_key() {
+		let auth = auth();
+		assert_eq!(auth.authenticate(Some("k2")).unwrap().name, "sidewalks");
+		assert_eq!(auth.authenticate(Some("nope")), Err(Denied::Unauthorized));
+		assert_eq!(auth.authenticate(None), Err(Denied::Unauthorized));
+	}
+
+	#[test]
+	fn quotas_are_released_with_permits() {
+		let auth = auth();
+		let (roads, sidewalks) = (auth.authenticate(Some("k1")).unwrap().clone(), auth.authenticate(Some("k2")).unwrap().clone());
+		let first = auth.start(&roads).unwrap();
+		let second = auth.start(&roads).unwrap();
+		assert_eq!(auth.start(&roads).err(), Some(Denied::QuotaExceeded(2)));
+		// quotas are per client
+		let other = auth.start(&sidewalks).unwrap();
+		assert_eq!(auth.start(&sidewalks).err(), Some(Denied::QuotaExceeded(1)));
+		drop(first);
+		let third = auth.start(&roads).unwrap();
+		drop((second, third, other));
+		assert!(auth.start(&sidewalks).is_ok());
+	}
+}
@@ -0,0 +1,203 @@
+//! Benchmarks of the solvers on synthetic cities, for performance to be tracked on graphs of any size
+//!
+//! Cities are generated from a seed, so the same benchmark solves the same problems from one run to the next.
+
+use crate::*;
+use data::*;
+
+use std::time::Instant;
+use rand::{Rng, SeedableRng};
+use serde::*;
+
+/// Street layout of a synthetic city
+#[derive(Serialize, Deserialize, Clone, Copy, PartialEq, Eq, Debug)]
+pub enum Layout {
+	/// square grid of blocks, every 4th street a two-way arterial and the others alternating one-ways
+	#[serde(rename="grid")]
+	Grid,
+	/// rings around a center, joined by two-way arterial spokes
+	#[serde(rename="radial")]
+	Radial,
+}
+
+/// Solvers that can be benchmarked
+pub const SOLVERS: &[&str] = &["road", "sidewalk", "fly"];
+
+/// Synthetic city settings
+#[derive(Clone, Debug)]
+pub struct City {
+	pub layout: Layout,
+	/// blocks on a side of the grid, or rings around the center
+	pub size: usize,
+	/// block length, in m
+	pub block: f64,
+	/// vehicles of each kind (road, sidewalk, drone)
+	pub vehicles: usize,
+	/// probability that a segment is snowy
+	pub snowy: f64,
+	pub seed: u64,
+}
+
+/// Spokes of radial cities
+const SPOKES: usize = 16;
+
+/// Center of synthetic cities, `(lon, lat)`
+const CENTER: (f64, f64) = (-73.6, 45.5);
+
+/// Generates a city
+///
+/// Returns: its road graph, snow status, and vehicles at random nodes
+pub fn generate(city: &City) -> (RoadGraph, SnowStatuses, VehiclesConfiguration) {
+	let mut rng = rand::rngs::StdRng::seed_from_u64(city.seed);
+	let (dlat, dlon) = ((city.block / EARTH_RADIUS).to_degrees(), (city.block / (EARTH_RADIUS * CENTER.1.to_radians().cos())).to_degrees());
+	let mut nodes = Vec::new();
+	let mut roads = Vec::new();
+	let node = |id: String, (x, y): (f64, f64)| Node { id: id.into(), coordinates: (CENTER.0 + x * dlon, CENTER.1 + y * dlat) };
+	let mut road = |nodes: &[Node], a: usize, b: usize, directed: bool, class: RoadClass| roads.push(RoadSegment {
+		p1: nodes[a].id.clone(),
+		p2: nodes[b].id.clone(),
+		discriminator: None,
+		directed,
+		distance: n64(haversine(nodes[a].coordinates, nodes[b].coordinates)),
+		sidewalks: (true, class == RoadClass::Arterial || !directed),
+		class: Some(class),
+		priority: None,
+		grade: None,
+		speed: Some(n64(if class == RoadClass::Arterial { 50.0 } else { 30.0 })),
+		name: None,
+		width: None,
+		consumption: None,
+		zone: None,
+		geometry: Vec::new(),
+	});
+	match city.layout {
+		Layout::Grid => {
+			let n = city.size + 1;
+			for i in 0..n {
+				for j in 0..n {
+					nodes.push(node(format!("n{}_{}", i, j), (j as f64 - city.size as f64 / 2.0, i as f64 - city.size as f64 / 2.0)));
+				}
+			}
+			// street i runs one way if i is even, the other if odd - unless it is an avenue, or on the edge of the grid
+			for i in 0..n {
+				let (class, directed) = if i % 4 == 0 || i == city.size { (RoadClass::Arterial, false) } else { (RoadClass::Local, true) };
+				for j in 0..city.size {
+					let (a, b) = if i % 2 == 0 { (j, j + 1) } else { (j + 1, j) };
+					road(&nodes, i * n + a, i * n + b, directed, class);
+					road(&nodes, a * n + i, b * n + i, directed, class);
+				}
+			}
+		},
+		Layout::Radial => {
+			nodes.push(node("c".to_string(), (0.0, 0.0)));
+			for r in 1..=city.size {
+				for k in 0..SPOKES {
+					let angle = 2.0 * std::f64::consts::PI * k as f64 / SPOKES as f64;
+					nodes.push(node(format!("r{}_{}", r, k), (r as f64 * angle.cos(), r as f64 * angle.sin())));
+				}
+			}
+			let at = |r: usize, k: usize| if r == 0 { 0 } else { 1 + (r - 1) * SPOKES + k % SPOKES };
+			for r in 1..=city.size {
+				for k in 0..SPOKES {
+					road(&nodes, at(r - 1, k), at(r, k), false, RoadClass::Arterial);
+					road(&nodes, at(r, k), at(r, k + 1), false, if r % 4 == 0 { RoadClass::Collector } else { RoadClass::Local });
+				}
+			}
+		},
+	}
+	let mut snow = Vec::new();
+	for r in roads.iter().filter(|_| rng.gen_bool(city.snowy.clamp(0.0, 1.0))).collect::<Vec<_>>() {
+		snow.push(SnowStatusElement {
+			p1: r.p1.clone(),
+			p2: r.p2.clone(),
+			discriminator: None,
+			depth: n64(rng.gen_range(20.0..150.0)),
+			probability: None,
+			variance: None,
+			observed: None,
+			confidence: None,
+		});
+	}
+	let mut starts = || (0..city.vehicles).map(|_| Location::Node(nodes[rng.gen_range(0..nodes.len())].id.clone())).collect::<Vec<_>>();
+	let vehicles = VehiclesConfiguration {
+		road: starts(),
+		sidewalk: starts(),
+		road_max_depth: Vec::new(),
+		sidewalk_max_depth: Vec::new(),
+		road_profiles: Vec::new(),
+		sidewalk_profiles: Vec::new(),
+		road_capacity: Vec::new(),
+		road_ends: Vec::new(),
+		sidewalk_ends: Vec::new(),
+	};
+	(RoadGraph { roads, nodes: RoadGraphNodes { nodes }, restrictions: Vec::new(), depots: Vec::new() }, snow, vehicles)
+}
+
+/// Run of a solver
+#[derive(Serialize, Deserialize, Clone, PartialEq, Debug)]
+pub struct Run {
+	/// one of [`SOLVERS`]
+	pub solver: String,
+	/// wall-clock time, in s
+	pub wall_time: f64,
+	/// peak resident memory of the process during the run, in bytes, where the OS tells (Linux)
+	pub peak_memory: Option<u64>,
+	/// objective value of the tours, as per the meta parameters - flights clearing nothing
+	pub objective: f64,
+}
+
+/// Benchmark results
+#[derive(Serialize, Deserialize, Clone, PartialEq, Debug)]
+pub struct Bench {
+	pub layout: Layout,
+	pub size: usize,
+	pub seed: u64,
+	pub nodes: usize,
+	pub segments: usize,
+	pub snowy: usize,
+	pub runs: Vec<Run>,
+}
+
+/// Resets the peak resident memory of the process, where the OS allows
+fn reset_peak_memory() {
+	let _ = std::fs::write("/proc/self/clear_refs", "5");
+}
+
+/// Peak resident memory of the process since the last reset, in bytes (Linux only)
+fn peak_memory() -> Option<u64> {
+	let status = std::fs::read_to_string("/proc/self/status").ok()?;
+	let kb = status.lines().find_map(|l| l.strip_prefix("VmHWM:"))?.trim().trim_end_matches("kB").trim().parse::<u64>().ok()?;
+	Some(kb * 1024)
+}
+
+/// Benchmarks solvers on a city
+///
+/// Arguments:
+/// - `city`: the city to generate
+/// - `solvers`: solvers to run, of [`SOLVERS`], in order
+/// - `params`: meta parameters of the solvers
+/// - `speed`: speed of the vehicles, in km/h, for the objectives (unless costs are times already)
+///
+/// Returns: the time, memory and objective of each run, or the first solver failure
+pub fn run(city: &City, solvers: &[&str], params: &meta::Parameters, speed: f64) -> Result<Bench, error::Error> {
+	let (roads, snow, vehicles) = generate(city);
+	log::info!("Generated a {:?} city of {} nodes, {} segments, {} snowy", city.layout, roads.nodes.nodes.len(), roads.roads.len(), snow.len());
+	let mut runs = Vec::new();
+	for &solver in solvers {
+		reset_peak_memory();
+		let start = Instant::now();
+		let (tours, sidewalks) = match solver {
+			"road" => (report::road_tours(&plow::road::solve(roads.clone(), snow.clone(), Vec::new(), None, &[], &[], None, &[], vehicles.clone(), params)?.0), false),
+			"sidewalk" => (report::sidewalk_tours(&plow::sidewalk::solve(roads.clone(), snow.clone(), Vec::new(), None, &[], &[], vehicles.clone(), params)?.0), true),
+			"fly" => (report::road_tours(&plow::fly::solve(roads.clone(), vehicles.road.clone(), params)?), false),
+			_ => return Err(error::Error::solver(format!("No such solver {}, expected one of {}", solver, SOLVERS.join(", ")))),
+		};
+		let wall_time = start.elapsed().as_secs_f64();
+		let peak_memory = peak_memory();
+		let cleared = if solver == "fly" { Vec::new() } else { snow.clone() };
+		let (_, objective) = report::vehicle_metrics(&roads, &cleared, None, &tours, sidewalks, &vehicles.profiles(sidewalks), params, speed).map_err(error::Error::solver)?;
+		log::info!("{} solved in {:.2}s, objective {:.1}", solver, wall_time, objective);
+		runs.push(Run { solver: solver.to_string(), wall_time, peak_memory, objective });
+	}
+	Ok(Bench { layout: city.layout, size: city.size, seed: city.seed, nodes: roads.nodes.nodes.len(), segments: roads.roads.len(), snowy: snow.len(), runs })
+}
@@ -0,0 +1,116 @@
+//! Binary caches of road graphs, for repeated runs on the same graph to skip parsing its JSON
+//!
+//! The cache of `roads.json` is `roads.json.cache`, a MessagePack encoding of the road graph - followed by its solver graph with its node id mappings (see [`plow::Compiled`]), for solves, and its light graph and node indices, for analyses - keyed by the hash of the JSON file contents - so a cache gone stale by the graph changing is ignored rather than used.
+
+use crate::*;
+use data::{IndexedRoadGraph, RoadGraph};
+
+use std::{io::{BufRead, Read, Write}, path::{Path, PathBuf}};
+use error::Error;
+
+/// Leading bytes of caches, versioned with the format (and the documents spec)
+const MAGIC: &[u8] = b"WFBFA-ROAD-GRAPH-CACHE-3\n";
+
+/// Cache file of a road graph file
+pub fn path(file: impl AsRef<Path>) -> PathBuf {
+	let mut path = file.as_ref().as_os_str().to_owned();
+	path.push(".cache");
+	path.into()
+}
+
+/// Hash of the contents of a file, by 64-bit FNV-1a - unlike the standard library's hashers, it's specified, so caches stay valid across builds
+fn hash(file: impl AsRef<Path>) -> Result<u64, Error> {
+	const OFFSET: u64 = 0xcbf29ce484222325;
+	const PRIME: u64 = 0x100000001b3;
+	let mut r = std::io::BufReader::with_capacity(1 << 16, std::fs::File::open(&file).map_err(|e| Error::io(&file, e))?);
+	let mut hash = OFFSET;
+	loop {
+		let buf = r.fill_buf().map_err(|e| Error::io(&file, e))?;
+		if buf.is_empty() {
+			return Ok(hash);
+		}
+		for b in buf {
+			hash = (hash ^ *b as u64).wrapping_mul(PRIME);
+		}
+		let n = buf.len();
+		r.consume(n);
+	}
+}
+
+/// Compiles the cache of a road graph file
+///
+/// Graphs with segments referring to unknown nodes have no solver graph cached, solves reporting them.
+pub fn compile(file: impl AsRef<Path>, what: &str) -> Result<(), Error> {
+	let IndexedRoadGraph { roads, light, ids } = document::read::<RoadGraph>(&file, what)?.into();
+	let compiled = plow::Compiled::new(&roads).map_err(|e| log::warn!("{} has no solver graph cached: {}", what, e)).ok();
+	let hash = hash(&file)?;
+	let cache = path(&file);
+	let mut w = std::io::BufWriter::new(std::fs::File::create(&cache).map_err(|e| Error::io(&cache, e))?);
+	w.write_all(MAGIC).and_then(|_| w.write_all(&hash.to_le_bytes())).map_err(|e| Error::io(&cache, e))?;
+	// the road graph first, for reads to stop there, then what solves take, then what analyses take
+	rmp_serde::encode::write_named(&mut w, &roads)
+		.and_then(|_| rmp_serde::encode::write_named(&mut w, &compiled))
+		.and_then(|_| rmp_serde::encode::write_named(&mut w, &(light, ids)))
+		.map_err(|e| Error::io(&cache, std::io::Error::other(e)))?;
+	w.flush().map_err(|e| Error::io(&cache, e))
+}
+
+/// Opens the cache of a road graph file, if there is one up to date, past its header
+fn cached(file: impl AsRef<Path>) -> Option<std::io::BufReader<std::fs::File>> {
+	let cache = path(&file);
+	let mut r = std::io::BufReader::new(std::fs::File::open(&cache).ok()?);
+	let mut head = vec![0; MAGIC.len() + 8];
+	r.read_exact(&mut head).ok()?;
+	if &head[..MAGIC.len()] != MAGIC {
+		log::warn!("Ignoring {}, not a road graph cache of this version", cache.display());
+		return None;
+	}
+	if head[MAGIC.len()..] != hash(&file).ok()?.to_le_bytes() {
+		log::warn!("Ignoring stale {}, recompile it", cache.display());
+		return None;
+	}
+	Some(r)
+}
+
+/// Decodes the next value of a cache
+fn decode<T: serde::de::DeserializeOwned>(file: impl AsRef<Path>, r: &mut impl Read) -> Option<T> {
+	rmp_serde::decode::from_read(r).map_err(|e| log::warn!("Ignoring corrupt {}: {}", path(&file).display(), e)).ok()
+}
+
+/// Reads a road graph file, from its cache if up to date
+pub fn read(file: impl AsRef<Path>, what: &str) -> Result<RoadGraph, Error> {
+	match cached(&file).and_then(|mut r| decode(&file, &mut r)) {
+		Some(roads) => {
+			log::debug!("{} read from cache", what);
+			Ok(roads)
+		},
+		None => document::read(file, what),
+	}
+}
+
+/// Opens a road graph file for solving, read whole with its solver graph from its cache if up to date, or streamed (see [`document::RoadStream`])
+pub fn stream(file: impl AsRef<Path>, what: &str) -> Result<data::Roads, Error> {
+	match cached(&file).and_then(|mut r| Some((decode(&file, &mut r)?, decode::<Option<plow::Compiled>>(&file, &mut r)?))) {
+		Some((roads, Some(compiled))) => {
+			log::debug!("{} read from cache, with its solver graph", what);
+			Ok(data::Roads::Compiled(roads, Box::new(compiled)))
+		},
+		Some((roads, None)) => {
+			log::debug!("{} read from cache", what);
+			Ok(data::Roads::Whole(roads))
+		},
+		None => document::RoadStream::open(file, what).map(data::Roads::Streamed),
+	}
+}
+
+/// Reads a road graph file with its light graph and node indices, from its cache if up to date (building them otherwise)
+pub fn read_indexed(file: impl AsRef<Path>, what: &str) -> Result<IndexedRoadGraph, Error> {
+	let cached = cached(&file).and_then(|mut r| Some((decode(&file, &mut r)?, decode::<serde::de::IgnoredAny>(&file, &mut r)?, decode(&file, &mut r)?)));
+	match cached {
+		Some((roads, _, (light, ids))) => {
+			log::debug!("{} read from cache", what);
+			Ok(IndexedRoadGraph { roads, light, ids })
+		},
+		None => document::read::<RoadGraph>(file, what).map(Into::into),
+	}
+}
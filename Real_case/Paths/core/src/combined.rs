@@ -0,0 +1,112 @@
+//! Combined planning of the road and sidewalk fleets, against the same graph and snow status
+//!
+//! Plowing a road throws its snow onto the sidewalks, so sidewalks are only cleared once their road is plowed: sidewalk vehicles wait for the road plows where they would get ahead of them.
+
+use crate::*;
+use data::*;
+
+use std::collections::{HashMap, HashSet};
+use serde::*;
+
+/// Timing of a segment, its road and its sidewalks
+#[derive(Serialize, Deserialize, Clone, PartialEq, Debug)]
+pub struct Timing {
+	pub p1: NodeId,
+	pub p2: NodeId,
+	pub discriminator: Option<NodeId>,
+	/// when the road gets plowed, in hours from the start (if it does)
+	pub plowed: Option<f64>,
+	/// when its left sidewalk gets cleared, in hours from the start (if it does)
+	pub left: Option<f64>,
+	/// when its right sidewalk gets cleared, in hours from the start (if it does)
+	pub right: Option<f64>,
+}
+
+/// Consistent timing of the road and sidewalk plans
+#[derive(Serialize, Deserialize, Clone, PartialEq, Debug)]
+pub struct Schedule {
+	/// completion time of each road plow, in hours
+	pub plows: Vec<f64>,
+	/// completion time of each sidewalk vehicle, in hours
+	pub sidewalk_vehicles: Vec<f64>,
+	/// time each sidewalk vehicle spends waiting for the road plows, in hours
+	pub waits: Vec<f64>,
+	pub segments: Vec<Timing>,
+}
+
+/// Solves road and sidewalk plowing, sidewalks being cleared after their road is plowed
+///
+/// Arguments:
+/// - `vehicles`: both fleets
+/// - `speed`: vehicles speed, in km/h
+///
+/// Returns: the road plan, the sidewalk plan, and their schedule
+#[allow(clippy::too_many_arguments)]
+pub fn solve(roads: &RoadGraph, snow: SnowStatuses, snow_d: Option<f64>, overlay: &[CostMultiplier], vehicles: VehiclesConfiguration, params: &meta::Parameters, speed: f64) -> Result<(Paths, SidewalkPaths, Schedule), error::Error> {
+	let plan = plow::road::solve(roads.clone(), snow.clone(), Vec::new(), snow_d, overlay, &[], None, &[], vehicles.clone(), params)?.0;
+	log::info!("Constructed road paths");
+	let sidewalk_plan = plow::sidewalk::solve(roads.clone(), snow.clone(), Vec::new(), snow_d, overlay, &[], vehicles, params)?.0;
+	log::info!("Constructed sidewalk paths");
+	let segments: HashMap<_, _> = roads.roads.iter().map(|r| (segment_key(&r.p1, &r.p2, &r.discriminator), r)).collect();
+	let statuses: HashMap<_, _> = snow.iter().map(|s| (segment_key(&s.p1, &s.p2, &s.discriminator), s)).collect();
+	let snow_d = snow_d.filter(|d| *d > 0.0).map(n64);
+	let depth = |k: &SegmentKey| snow_d.or_else(|| statuses.get(k).map(|s| s.depth)).unwrap_or_else(|| n64(0.0));
+	let required = |k: &SegmentKey, r: &RoadSegment| match snow_d {
+		Some(d) => params.depths.is_snowy(r.class, d),
+		None => statuses.get(k).is_some_and(|s| params.depths.is_likely(params.depths.clearing_probability(r.class, s))),
+	};
+	// roads: each segment is plowed the first time a plow needing to clear it gets through
+	let mut plowed: HashMap<SegmentKey, f64> = HashMap::new();
+	let mut plows = Vec::new();
+	for path in &plan {
+		let mut cleared = HashSet::new();
+		let mut time = 0.0;
+		for k in report::path_segments(path) {
+			let r = segments.get(&k).ok_or_else(|| format!("Road path through unknown segment {:?}", k))?;
+			if required(&k, r) && cleared.insert(k.clone()) {
+				time += params.hours(r, depth(&k), speed)? * params.clearing_slowdown().raw();
+				let at = plowed.entry(k).or_insert(time);
+				*at = at.min(time);
+			} else {
+				time += params.hours(r, depth(&k), speed)?;
+			}
+		}
+		plows.push(time);
+	}
+	// sidewalks: cleared the first time a vehicle gets through them, waiting for their road to be plowed first
+	let mut sidewalks: HashMap<(SegmentKey, SidewalkSide), f64> = HashMap::new();
+	let mut sidewalk_vehicles = Vec::new();
+	let mut waits = Vec::new();
+	for tour in report::sidewalk_tours(&sidewalk_plan) {
+		let mut time = 0.0;
+		let mut waited = 0.0;
+		for (k, side) in tour {
+			let r = segments.get(&k).ok_or_else(|| format!("Sidewalk path through unknown segment {:?}", k))?;
+			match side {
+				Some(side) if required(&k, r) && !sidewalks.contains_key(&(k.clone(), side)) => {
+					if let Some(at) = plowed.get(&k).filter(|at| **at > time) {
+						waited += at - time;
+						time = *at;
+					}
+					time += params.hours(r, depth(&k), speed)? * params.clearing_slowdown().raw();
+					sidewalks.insert((k, side), time);
+				},
+				_ => time += params.hours(r, depth(&k), speed)?,
+			}
+		}
+		sidewalk_vehicles.push(time);
+		waits.push(waited);
+	}
+	let keys: HashSet<_> = plowed.keys().chain(sidewalks.keys().map(|(k, _)| k)).cloned().collect();
+	let mut timings: Vec<_> = keys.into_iter().map(|k| Timing {
+		plowed: plowed.get(&k).copied(),
+		left: sidewalks.get(&(k.clone(), SidewalkSide::Left)).copied(),
+		right: sidewalks.get(&(k.clone(), SidewalkSide::Right)).copied(),
+		p1: k.0,
+		p2: k.1,
+		discriminator: k.2,
+	}).collect();
+	let first = |t: &Timing| t.plowed.into_iter().chain(t.left).chain(t.right).fold(f64::INFINITY, f64::min);
+	timings.sort_by(|a, b| first(a).total_cmp(&first(b)).then_with(|| (&a.p1, &a.p2, &a.discriminator).cmp(&(&b.p1, &b.p2, &b.discriminator))));
+	Ok((plan, sidewalk_plan, Schedule { plows, sidewalk_vehicles, waits, segments: timings }))
+}
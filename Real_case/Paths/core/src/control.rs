@@ -0,0 +1,110 @@
+//! Runtime control of running solves
+//!
+//! A storm-night operator can adjust a solve that is already running - shorten its iteration budget, tell it to wrap up within some minutes, switch moves - without killing it.
+//! Adjustments are read from a control file, polled between solver iterations, and apply to every solve of the process.
+
+use crate::*;
+
+use std::{path::PathBuf, sync::Mutex, time::{Duration, Instant, SystemTime}};
+use serde::*;
+
+/// Adjustment of the meta parameters of running solves, only overriding what is given
+#[derive(Serialize, Deserialize, Clone, PartialEq, Default, Debug)]
+pub struct Adjustment {
+	/// main annealing iterations budget
+	#[serde(default)]
+	pub main_iterations: Option<u64>,
+	/// maximum polishing rounds
+	#[serde(default)]
+	pub polishing_rounds: Option<u64>,
+	/// wrap up (stop annealing, and polishing) within this many minutes of the adjustment
+	#[serde(default)]
+	pub wrap_up_in: Option<f64>,
+	#[serde(default)]
+	pub recycle: Option<meta::Recycle>,
+	#[serde(default)]
+	pub reorder: Option<meta::Reorder>,
+	#[serde(default)]
+	pub realloc: Option<meta::Realloc>,
+}
+
+struct State {
+	/// control file, if any
+	file: Option<PathBuf>,
+	/// modification time of the control file when last read
+	modified: Option<SystemTime>,
+	adjustment: Option<Adjustment>,
+	deadline: Option<Instant>,
+}
+
+static STATE: Mutex<State> = Mutex::new(State { file: None, modified: None, adjustment: None, deadline: None });
+
+/// Polls the control file for adjustments
+pub fn watch(file: PathBuf) {
+	STATE.lock().unwrap().file = Some(file);
+}
+
+/// Applies an adjustment, replacing the previous one
+pub fn adjust(adjustment: Adjustment) {
+	let mut state = STATE.lock().unwrap();
+	state.deadline = adjustment.wrap_up_in.map(|m| Instant::now() + Duration::from_secs_f64(m.max(0.0) * 60.0));
+	log::info!("Adjusted running solves: {:?}", adjustment);
+	state.adjustment = Some(adjustment);
+}
+
+/// Re-reads the control file, if it changed since last read
+fn poll() {
+	let (file, modified) = {
+		let state = STATE.lock().unwrap();
+		match &state.file {
+			Some(file) => (file.clone(), state.modified),
+			None => return,
+		}
+	};
+	let m = match std::fs::metadata(&file).and_then(|m| m.modified()) {
+		Ok(m) if Some(m) != modified => m,
+		_ => return,
+	};
+	STATE.lock().unwrap().modified = Some(m);
+	match std::fs::File::open(&file).map_err(|e| e.to_string()).and_then(|f| serde_yaml::from_reader(f).map_err(|e| e.to_string())) {
+		Ok(adjustment) => adjust(adjustment),
+		Err(e) => log::warn!("Ignoring invalid control file {}: {}", file.display(), e),
+	}
+}
+
+/// Meta parameters, with the current adjustment applied
+pub fn adjusted(params: &meta::Parameters) -> meta::Parameters {
+	poll();
+	let mut params = params.clone();
+	if let Some(a) = &STATE.lock().unwrap().adjustment {
+		if let Some(mi) = a.main_iterations {
+			params.annealing.main_iterations = mi;
+		}
+		if let Some(rounds) = a.polishing_rounds {
+			params.polishing.max_rounds = rounds;
+		}
+		if let Some(recycle) = a.recycle {
+			params.recycle = recycle;
+		}
+		if let Some(reorder) = a.reorder {
+			params.reorder = reorder;
+		}
+		if let Some(realloc) = a.realloc {
+			params.realloc = realloc;
+		}
+	}
+	params
+}
+
+/// Whether running solves have been told to wrap up by now
+pub fn expired() -> bool {
+	STATE.lock().unwrap().deadline.is_some_and(|d| Instant::now() >= d)
+}
+
+/// Marks a solve as wrapped up, so that the deadline doesn't carry over to the next solves
+pub fn wrapped_up() {
+	let mut state = STATE.lock().unwrap();
+	if state.deadline.is_some_and(|d| Instant::now() >= d) {
+		state.deadline = None;
+	}
+}
@@ -0,0 +1,54 @@
+//! Cost models of the plow solver - what traversing an edge costs a vehicle, deadheading or clearing it
+
+use crate::*;
+use data::VehicleProfile;
+
+/// Turns edge weights into the costs of vehicles traversing them: `weight × pace`, clearing in as many passes as it takes, plus a fixed service time
+pub trait CostModel: Send + Sync {
+	/// Cost factors of a vehicle, deadheading and clearing, per unit of edge weight
+	///
+	/// Arguments:
+	/// - `profile`: the vehicle's profile (the default one for vehicles without)
+	/// - `fastest`: fastest speed given in the vehicle's fleet, if any
+	fn paces(&self, profile: &VehicleProfile, fastest: Option<N64>) -> (N64, N64);
+	/// Fixed cost of clearing an edge, on top of its own service time
+	fn service_time(&self) -> N64;
+}
+
+/// Costs in edge weight units at the fastest speed given in the fleet, so that they are the edge weights if no speed is
+///
+/// Vehicles without a travel speed drive at the fastest one, and plow `slowdown` times slower than they drive without a plowing speed.
+#[derive(Clone, Copy, Debug)]
+pub struct Slowdown {
+	pub slowdown: N64,
+}
+impl CostModel for Slowdown {
+	fn paces(&self, profile: &VehicleProfile, fastest: Option<N64>) -> (N64, N64) {
+		let pace = |speed: Option<N64>| speed.filter(|s| *s > 0.0).zip(fastest).map(|(s, fastest)| fastest / s);
+		let deadhead = pace(profile.speed).unwrap_or_else(|| n64(1.0));
+		(deadhead, pace(profile.plow_speed).unwrap_or(deadhead * self.slowdown))
+	}
+	fn service_time(&self) -> N64 {
+		n64(0.0)
+	}
+}
+
+/// Costs in seconds, edge weights being lengths in meters: driven at the travel speed, cleared at the service speed (vehicles' own speeds taking precedence), plus a fixed service time
+#[derive(Clone, Copy, Debug)]
+pub struct Speeds {
+	/// travel speed, in km/h
+	pub travel: N64,
+	/// service (plowing) speed, in km/h
+	pub service: N64,
+	/// fixed time to clear an edge, in s
+	pub service_time: N64,
+}
+impl CostModel for Speeds {
+	fn paces(&self, profile: &VehicleProfile, _fastest: Option<N64>) -> (N64, N64) {
+		let pace = |speed: Option<N64>, default: N64| n64(3.6) / speed.filter(|s| *s > 0.0).unwrap_or(default);
+		(pace(profile.speed, self.travel), pace(profile.plow_speed, self.service))
+	}
+	fn service_time(&self) -> N64 {
+		self.service_time
+	}
+}
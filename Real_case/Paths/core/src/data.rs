@@ -0,0 +1,788 @@
+//! Crusty data types for the [Specification](https://github.com/WFBFA/Specs)
+
+use std::convert::TryFrom;
+use std::collections::HashMap;
+use indexmap::IndexMap;
+
+use crate::*;
+
+use serde::*;
+
+pub trait Distance {
+	type Measure;
+	fn distance(&self, other: &Self) -> Self::Measure;
+}
+
+impl Distance for (f64, f64) {
+	type Measure = f64;
+	fn distance(&self, othr: &Self) -> Self::Measure {
+		(self.0-othr.0)*(self.0-othr.0) + (self.1-othr.1)*(self.1-othr.1)
+	}
+}
+
+/// Mean Earth radius, in meters
+pub const EARTH_RADIUS: f64 = 6_371_008.8;
+
+/// Great-circle distance between 2 `(lon, lat)` coordinates, in meters
+pub fn haversine((lon1, lat1): (f64, f64), (lon2, lat2): (f64, f64)) -> f64 {
+	let (dlat, dlon) = ((lat2 - lat1).to_radians(), (lon2 - lon1).to_radians());
+	let h = (dlat / 2.0).sin().powi(2) + lat1.to_radians().cos() * lat2.to_radians().cos() * (dlon / 2.0).sin().powi(2);
+	2.0 * EARTH_RADIUS * h.sqrt().asin()
+}
+
+#[derive(Serialize, Deserialize, Clone, PartialEq, Debug)]
+pub struct RoadSegment {
+	pub p1: NodeId,
+	pub p2: NodeId,
+	pub discriminator: Option<NodeId>,
+	pub directed: bool,
+	pub distance: N64,
+	pub sidewalks: (bool, bool),
+	#[serde(default, skip_serializing_if = "Option::is_none")]
+	pub class: Option<RoadClass>,
+	/// priority tier, as the road class it is to be cleared like (its own class if not given)
+	#[serde(default, skip_serializing_if = "Option::is_none")]
+	pub priority: Option<RoadClass>,
+	/// grade (slope), in %
+	#[serde(default, skip_serializing_if = "Option::is_none")]
+	pub grade: Option<N64>,
+	/// speed limit, in km/h
+	#[serde(default, skip_serializing_if = "Option::is_none")]
+	pub speed: Option<N64>,
+	/// street name
+	#[serde(default, skip_serializing_if = "Option::is_none")]
+	pub name: Option<String>,
+	/// carriageway width, in m
+	#[serde(default, skip_serializing_if = "Option::is_none")]
+	pub width: Option<N64>,
+	/// consumable (salt, fuel...) used clearing the segment, per km
+	#[serde(default, skip_serializing_if = "Option::is_none")]
+	pub consumption: Option<N64>,
+	/// zone (ward, borough...) the segment is in, for vehicles restricted to zones
+	#[serde(default, skip_serializing_if = "Option::is_none")]
+	pub zone: Option<String>,
+	/// `(lon, lat)` vertices of the road between `p1` and `p2` (straight if none), in that direction
+	#[serde(default, skip_serializing_if = "Vec::is_empty")]
+	pub geometry: Vec<(f64, f64)>,
+}
+impl RoadSegment {
+	/// Priority tier of the segment: its `priority`, or its class
+	pub fn tier(&self) -> Option<RoadClass> {
+		self.priority.or(self.class)
+	}
+}
+
+/// Functional class of a road
+#[derive(Serialize, Deserialize, Clone, Copy, PartialEq, Eq, Hash, Debug)]
+pub enum RoadClass {
+	#[serde(rename="arterial")]
+	Arterial,
+	#[serde(rename="collector")]
+	Collector,
+	#[serde(rename="local")]
+	Local,
+}
+
+/// Road segment identity, regardless of the direction it is referred to in
+pub type SegmentKey = (NodeId, NodeId, Option<NodeId>);
+
+/// Key of the road segment between 2 nodes
+pub fn segment_key(p1: &NodeId, p2: &NodeId, discriminator: &Option<NodeId>) -> SegmentKey {
+	if p1 <= p2 {
+		(p1.clone(), p2.clone(), discriminator.clone())
+	} else {
+		(p2.clone(), p1.clone(), discriminator.clone())
+	}
+}
+
+#[derive(Serialize, Deserialize, Clone, Copy, PartialEq, Eq, Hash, Debug)]
+pub enum SidewalkSide {
+	#[serde(rename="left")]
+	Left,
+	#[serde(rename="right")]
+	Right,
+}
+
+#[derive(Serialize, Deserialize, Clone, PartialEq, Debug)]
+pub struct Node {
+	pub id: NodeId,
+	pub coordinates: (f64, f64),
+}
+impl From<&Node> for geo::Geometry<f64> {
+	fn from(n: &Node) -> Self {
+		geo::Point::from(n.coordinates).into()
+	}
+}
+
+#[derive(Serialize, Deserialize, Clone, Default, Debug)]
+pub struct RoadGraph {
+	pub roads: Vec<RoadSegment>,
+	#[serde(flatten)]
+	pub nodes: RoadGraphNodes,
+	/// turns vehicles may not make
+	#[serde(default, skip_serializing_if = "Vec::is_empty")]
+	pub restrictions: Vec<TurnRestriction>,
+	/// nodes where road vehicles can reload their consumable
+	#[serde(default, skip_serializing_if = "Vec::is_empty")]
+	pub depots: Vec<NodeId>,
+}
+
+/// A forbidden turn, from the segment between `from` and `via` into the segment between `via` and `to` (a U-turn if `to` is `from`)
+#[derive(Serialize, Deserialize, Clone, PartialEq, Eq, Hash, Debug)]
+pub struct TurnRestriction {
+	pub from: NodeId,
+	pub via: NodeId,
+	pub to: NodeId,
+}
+
+#[derive(Serialize, Deserialize, Clone, Default, Debug)]
+pub struct RoadGraphNodes {
+	pub nodes: Vec<Node>,
+}
+
+impl RoadGraphNodes {
+	/// Locates a location to the node on the graph, the closest one as per the metric
+	pub fn locate(&self, l: &Location, metric: meta::Metric) -> Option<NodeId> {
+		match l {
+			Location::Coordinates(lon, lat) => self.nodes.iter().min_by_key(|Node {coordinates, ..}| metric.measure((*lon, *lat), *coordinates)).map(|n| n.id.clone()),
+			Location::Node(n) => Some(n.clone()),
+		}
+	}
+	/// Locates locations to the nodes on the graph, the closest ones as per the metric - indexing the nodes once for all of them
+	pub fn locate_all(&self, ls: &[Location], metric: meta::Metric) -> Vec<Option<NodeId>> {
+		let nearest = spatial::Nearest::new(metric, self.nodes.iter().map(|n| (n.coordinates, &n.id)));
+		ls.iter().map(|l| match l {
+			Location::Coordinates(lon, lat) => nearest.nearest((*lon, *lat)).map(|n| (*n).clone()),
+			Location::Node(n) => Some(n.clone()),
+		}).collect()
+	}
+	/// Locates a location to geographical coordinates
+	pub fn dislocate(&self, l: &Location) -> geo::Geometry<f64> {
+		match l {
+			Location::Coordinates(lon, lat) => geo::Point::from((*lon, *lat)).into(),
+			Location::Node(nid) => self.nodes.iter().find(|n| &n.id == nid).unwrap().into(),
+		}
+	}
+}
+
+/// A road segment, as an edge of the [`LightGraph`]
+#[derive(Serialize, Deserialize, Clone, PartialEq, Eq, Hash, Debug)]
+pub struct SegmentEdge {
+	pub p1: usize,
+	pub p2: usize,
+	pub directed: bool,
+	/// index of the segment in the road graph
+	pub index: usize,
+}
+impl graph::Edge<usize> for SegmentEdge {
+	fn p1(&self) -> usize {
+		self.p1
+	}
+	fn p2(&self) -> usize {
+		self.p2
+	}
+	fn directed(&self) -> bool {
+		self.directed
+	}
+}
+
+/// Road graph for analyses, with nodes and segments referred to by their index in the [`RoadGraph`], and nodes positioned
+pub type LightGraph = graph::Graph<usize, (f64, f64), SegmentEdge>;
+
+/// Road graph with its light graph built, and its nodes indexed by id - as [cached](cache) for analyses
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct IndexedRoadGraph {
+	pub roads: RoadGraph,
+	pub light: LightGraph,
+	/// index of each node in the road graph, by id
+	pub ids: HashMap<NodeId, usize>,
+}
+impl From<RoadGraph> for IndexedRoadGraph {
+	fn from(roads: RoadGraph) -> Self {
+		Self {
+			light: roads.light(),
+			ids: roads.nodes.nodes.iter().enumerate().map(|(i, n)| (n.id.clone(), i)).collect(),
+			roads,
+		}
+	}
+}
+impl IndexedRoadGraph {
+	/// Index of a node, by id
+	pub fn node_index(&self, id: &NodeId) -> Option<usize> {
+		self.ids.get(id).copied()
+	}
+}
+
+/// Road graph to solve on: read whole, or with its segments streamed one at a time as they are parsed, so that they are never all held (see [`document::RoadStream`])
+///
+/// Cloning streamed road graphs only clones their nodes, for repeated solves to stream the segments again.
+#[derive(Clone, Debug)]
+pub enum Roads {
+	Whole(RoadGraph),
+	Streamed(document::RoadStream),
+	/// read whole with its solver graph, compiled (see [`cache`])
+	Compiled(RoadGraph, Box<plow::Compiled>),
+}
+impl From<RoadGraph> for Roads {
+	fn from(roads: RoadGraph) -> Self {
+		Self::Whole(roads)
+	}
+}
+impl Roads {
+	/// The whole road graph, reading it all if streamed, to change it: its compiled solver graph, if any, is dropped
+	pub fn whole(&mut self) -> Result<&mut RoadGraph, error::Error> {
+		match std::mem::replace(self, Self::Whole(RoadGraph::default())) {
+			Self::Streamed(stream) => *self = Self::Whole(stream.read()?),
+			Self::Whole(roads) | Self::Compiled(roads, _) => *self = Self::Whole(roads),
+		}
+		match self {
+			Self::Whole(roads) => Ok(roads),
+			Self::Streamed(_) | Self::Compiled(..) => unreachable!(),
+		}
+	}
+	/// The whole road graph, reading it all if streamed, keeping its compiled solver graph
+	pub fn graph(&mut self) -> Result<&RoadGraph, error::Error> {
+		if let Self::Streamed(stream) = self {
+			*self = Self::Whole(stream.read()?);
+		}
+		match self {
+			Self::Whole(roads) | Self::Compiled(roads, _) => Ok(roads),
+			Self::Streamed(_) => unreachable!(),
+		}
+	}
+	/// The whole road graph, reading it all if streamed
+	pub fn into_whole(self) -> Result<RoadGraph, error::Error> {
+		match self {
+			Self::Whole(roads) | Self::Compiled(roads, _) => Ok(roads),
+			Self::Streamed(stream) => stream.read(),
+		}
+	}
+	/// Compiled solver graph of the road graph, if any, taken
+	pub fn take_compiled(&mut self) -> Option<plow::Compiled> {
+		match std::mem::replace(self, Self::Whole(RoadGraph::default())) {
+			Self::Compiled(roads, compiled) => {
+				*self = Self::Whole(roads);
+				Some(*compiled)
+			},
+			roads => {
+				*self = roads;
+				None
+			},
+		}
+	}
+	/// Nodes, turn restrictions and depots of the road graph, leaving its segments
+	pub fn take_parts(&mut self) -> (Vec<Node>, Vec<TurnRestriction>, Vec<NodeId>) {
+		let (nodes, restrictions, depots) = match self {
+			Self::Whole(roads) | Self::Compiled(roads, _) => (&mut roads.nodes.nodes, &mut roads.restrictions, &mut roads.depots),
+			Self::Streamed(stream) => (&mut stream.nodes, &mut stream.restrictions, &mut stream.depots),
+		};
+		(std::mem::take(nodes), std::mem::take(restrictions), std::mem::take(depots))
+	}
+	/// Goes through the segments of the road graph, in order
+	pub fn for_each_segment(self, f: impl FnMut(RoadSegment) -> Result<(), error::Error>) -> Result<(), error::Error> {
+		match self {
+			Self::Whole(roads) | Self::Compiled(roads, _) => roads.roads.into_iter().try_for_each(f),
+			Self::Streamed(stream) => stream.for_each_segment(f),
+		}
+	}
+	/// Looks at the segments of the road graph, in order, keeping the road graph (streamed ones being parsed anew)
+	pub fn scan(&self, mut f: impl FnMut(&RoadSegment) -> Result<(), error::Error>) -> Result<(), error::Error> {
+		match self {
+			Self::Whole(roads) | Self::Compiled(roads, _) => roads.roads.iter().try_for_each(f),
+			Self::Streamed(stream) => stream.for_each_segment(|r| f(&r)),
+		}
+	}
+}
+
+impl RoadGraph {
+	/// Constructs the light graph of the road graph (segments referring to unknown nodes are left out)
+	pub fn light(&self) -> LightGraph {
+		let ids: HashMap<&NodeId, usize> = self.nodes.nodes.iter().enumerate().map(|(i, n)| (&n.id, i)).collect();
+		let mut g = LightGraph::default();
+		for (i, n) in self.nodes.nodes.iter().enumerate() {
+			g.add_node(i, n.coordinates);
+		}
+		for (index, r) in self.roads.iter().enumerate() {
+			if let (Some(p1), Some(p2)) = (ids.get(&r.p1), ids.get(&r.p2)) {
+				g.add_edge(SegmentEdge { p1: *p1, p2: *p2, directed: r.directed, index });
+			}
+		}
+		g
+	}
+	/// Restricts the road graph to a region, and a buffer around it
+	///
+	/// Arguments:
+	/// - `inside`: whether a node is inside the region
+	/// - `buffer`: how far (by road, in meters) outside the region the graph is kept, for connectivity
+	///
+	/// Returns: segments inside the region (both ends inside)
+	pub fn restrict(&mut self, inside: impl Fn(&Node) -> bool, buffer: f64) -> std::collections::HashSet<SegmentKey> {
+		let g = self.light();
+		let region: graph::HashSet<_> = self.nodes.nodes.iter().enumerate().filter(|(_, n)| inside(n)).map(|(i, _)| i).collect();
+		let kept = g.distances::<N64, _, false>(&region, n64(buffer.max(0.0)), |e| Some(self.roads[e.index].distance));
+		let ids: std::collections::HashSet<_> = kept.keys().map(|i| self.nodes.nodes[*i].id.clone()).collect();
+		let inside: std::collections::HashSet<_> = region.iter().map(|i| &self.nodes.nodes[*i].id).collect();
+		let segments = self.roads.iter().filter(|r| inside.contains(&r.p1) && inside.contains(&r.p2)).map(|r| segment_key(&r.p1, &r.p2, &r.discriminator)).collect();
+		log::info!("Region of {} nodes, with {} more in the buffer", region.len(), ids.len() - region.len());
+		self.roads.retain(|r| ids.contains(&r.p1) && ids.contains(&r.p2));
+		let discriminators: std::collections::HashSet<_> = self.roads.iter().filter_map(|r| r.discriminator.clone()).collect();
+		self.nodes.nodes.retain(|n| ids.contains(&n.id) || discriminators.contains(&n.id));
+		segments
+	}
+	/// Merges nodes within `tolerance` meters of each other, re-attaching their roads to the kept node.
+	///
+	/// Of each group of merged nodes, the first one (in order of appearance) is kept; roads that collapse into a loop on a single node are dropped.
+	///
+	/// Returns: id mapping of merged nodes to the node they were merged into
+	pub fn snap(&mut self, tolerance: f64) -> IdMapping {
+		use graph::mapmatch::{project, distance};
+		let nodes = &self.nodes.nodes;
+		if nodes.is_empty() || tolerance <= 0.0 {
+			return IdMapping::new();
+		}
+		let lat0 = nodes.iter().map(|n| n.coordinates.1).sum::<f64>() / nodes.len() as f64;
+		let ps: Vec<_> = nodes.iter().map(|n| project(n.coordinates, lat0)).collect();
+		let cell = |p: (f64, f64)| ((p.0 / tolerance).floor() as i64, (p.1 / tolerance).floor() as i64);
+		let mut grid: HashMap<(i64, i64), Vec<usize>> = HashMap::new();
+		for (i, p) in ps.iter().enumerate() {
+			grid.entry(cell(*p)).or_default().push(i);
+		}
+		// union-find, rooted at the first node of each group
+		let mut parent: Vec<usize> = (0..nodes.len()).collect();
+		fn root(parent: &mut [usize], i: usize) -> usize {
+			let mut r = i;
+			while parent[r] != r {
+				r = parent[r];
+			}
+			parent[i] = r;
+			r
+		}
+		for (i, p) in ps.iter().enumerate() {
+			let (cx, cy) = cell(*p);
+			for dx in -1..=1 {
+				for dy in -1..=1 {
+					for j in grid.get(&(cx + dx, cy + dy)).into_iter().flatten().copied().filter(|j| *j > i) {
+						if distance(*p, ps[j]) <= tolerance {
+							let (ri, rj) = (root(&mut parent, i), root(&mut parent, j));
+							parent[ri.max(rj)] = ri.min(rj);
+						}
+					}
+				}
+			}
+		}
+		let mapping: IdMapping = (0..nodes.len()).filter_map(|i| {
+			let r = root(&mut parent, i);
+			Some((nodes[i].id.clone(), nodes[r].id.clone())).filter(|_| r != i)
+		}).collect();
+		let roads = self.roads.len();
+		let kept = |id: &NodeId| mapping.get(id).unwrap_or(id).clone();
+		self.roads.retain(|r| r.p1 == r.p2 || kept(&r.p1) != kept(&r.p2));
+		if roads > self.roads.len() {
+			log::debug!("Dropped {} roads collapsed by snapping", roads - self.roads.len());
+		}
+		for r in &mut self.roads {
+			r.p1 = kept(&r.p1);
+			r.p2 = kept(&r.p2);
+		}
+		self.nodes.nodes.retain(|n| !mapping.contains_key(&n.id));
+		log::info!("Snapped {} nodes", mapping.len());
+		mapping
+	}
+}
+
+#[derive(Serialize, Deserialize, Clone, PartialEq, Debug)]
+#[serde(untagged)]
+pub enum Location {
+	Coordinates(f64, f64),
+	Node(NodeId),
+}
+
+pub type Drones = Vec<Location>;
+
+#[derive(Serialize, Deserialize, Clone, PartialEq, Debug)]
+pub struct VehiclesConfiguration {
+	pub road: Vec<Location>,
+	pub sidewalk: Vec<Location>,
+	/// maximum snow depth (in mm) each road vehicle can clear, in the order of `road` (unlimited if absent or null)
+	#[serde(default, skip_serializing_if = "Vec::is_empty")]
+	pub road_max_depth: Vec<Option<N64>>,
+	/// maximum snow depth (in mm) each sidewalk vehicle can clear, in the order of `sidewalk` (unlimited if absent or null)
+	#[serde(default, skip_serializing_if = "Vec::is_empty")]
+	pub sidewalk_max_depth: Vec<Option<N64>>,
+	/// capability profile of each road vehicle, in the order of `road` (unrestricted if absent or null)
+	#[serde(default, skip_serializing_if = "Vec::is_empty")]
+	pub road_profiles: Vec<Option<VehicleProfile>>,
+	/// capability profile of each sidewalk vehicle, in the order of `sidewalk` (unrestricted if absent or null)
+	#[serde(default, skip_serializing_if = "Vec::is_empty")]
+	pub sidewalk_profiles: Vec<Option<VehicleProfile>>,
+	/// consumable capacity of each road vehicle, in the order of `road` (unlimited if absent or null)
+	#[serde(default, skip_serializing_if = "Vec::is_empty")]
+	pub road_capacity: Vec<Option<N64>>,
+	/// where each road vehicle ends its tour (usually the depot), in the order of `road` (back at its start if absent or null)
+	#[serde(default, skip_serializing_if = "Vec::is_empty")]
+	pub road_ends: Vec<Option<Location>>,
+	/// where each sidewalk vehicle ends its tour, in the order of `sidewalk` (back at its start if absent or null)
+	#[serde(default, skip_serializing_if = "Vec::is_empty")]
+	pub sidewalk_ends: Vec<Option<Location>>,
+}
+impl VehiclesConfiguration {
+	/// Consumable capacity of each road vehicle, infinite if unlimited
+	pub fn capacities(&self) -> Vec<N64> {
+		(0..self.road.len()).map(|v| self.road_capacity.get(v).copied().flatten().unwrap_or_else(N64::infinity)).collect()
+	}
+	/// End location of each road (or sidewalk) vehicle, if not back at its start
+	pub fn ends(&self, sidewalk: bool) -> Vec<Option<Location>> {
+		let (vehicles, ends) = if sidewalk { (&self.sidewalk, &self.sidewalk_ends) } else { (&self.road, &self.road_ends) };
+		(0..vehicles.len()).map(|v| ends.get(v).cloned().flatten()).collect()
+	}
+	/// Capability profile of each road (or sidewalk) vehicle, unrestricted if not given
+	pub fn profiles(&self, sidewalk: bool) -> Vec<VehicleProfile> {
+		let (vehicles, profiles) = if sidewalk { (&self.sidewalk, &self.sidewalk_profiles) } else { (&self.road, &self.road_profiles) };
+		(0..vehicles.len()).map(|v| profiles.get(v).cloned().flatten().unwrap_or_default()).collect()
+	}
+	/// Maximum snow depth each road (or sidewalk) vehicle can clear, infinite if unlimited
+	pub fn max_depths(&self, sidewalk: bool) -> Vec<N64> {
+		let (vehicles, max_depths) = if sidewalk { (&self.sidewalk, &self.sidewalk_max_depth) } else { (&self.road, &self.road_max_depth) };
+		(0..vehicles.len()).map(|v| max_depths.get(v).copied().flatten().unwrap_or_else(N64::infinity)).collect()
+	}
+}
+
+/// What a sidewalk vehicle may do on roadways
+#[derive(Serialize, Deserialize, Clone, Copy, PartialEq, Eq, Debug)]
+pub enum Roadways {
+	/// drive through them, as shortcuts between sidewalks
+	#[serde(rename="drive")]
+	Drive,
+	/// keep to sidewalks
+	#[serde(rename="avoid")]
+	Avoid,
+	/// drive through them, and clear the narrow ones - those it clears in one pass
+	#[serde(rename="clear")]
+	Clear,
+}
+
+/// What a vehicle is capable of
+#[derive(Serialize, Deserialize, Clone, PartialEq, Debug, Default)]
+pub struct VehicleProfile {
+	/// travel speed, in km/h
+	#[serde(default, skip_serializing_if = "Option::is_none")]
+	pub speed: Option<N64>,
+	/// plowing speed, in km/h (the travel speed slowed down by `slowdown` if not given)
+	#[serde(default, skip_serializing_if = "Option::is_none")]
+	pub plow_speed: Option<N64>,
+	/// plow width, in m - roads wider than it take several passes
+	#[serde(default, skip_serializing_if = "Option::is_none")]
+	pub plow_width: Option<N64>,
+	/// road classes the vehicle can service (all of them if not given), unclassified roads always being serviceable
+	#[serde(default, skip_serializing_if = "Option::is_none")]
+	pub classes: Option<Vec<RoadClass>>,
+	/// zones the vehicle can service (all of them if not given), such as its contractor's boroughs - roads without a zone always being serviceable
+	#[serde(default, skip_serializing_if = "Option::is_none")]
+	pub zones: Option<Vec<String>>,
+	/// for sidewalk vehicles, what they may do on roadways (drive through them if not given)
+	#[serde(default, skip_serializing_if = "Option::is_none")]
+	pub roadways: Option<Roadways>,
+}
+impl VehicleProfile {
+	/// Whether the vehicle can service roads of a class
+	pub fn services(&self, class: Option<RoadClass>) -> bool {
+		self.classes.as_ref().is_none_or(|classes| class.is_none_or(|c| classes.contains(&c)))
+	}
+	/// Whether the vehicle can service roads in a zone
+	pub fn covers(&self, zone: Option<&str>) -> bool {
+		self.zones.as_ref().is_none_or(|zones| zone.is_none_or(|z| zones.iter().any(|zone| zone == z)))
+	}
+	/// Whether the sidewalk vehicle may drive through roadways
+	pub fn drives_roadways(&self) -> bool {
+		self.roadways != Some(Roadways::Avoid)
+	}
+	/// Whether the sidewalk vehicle can clear a roadway of a width: a narrow one, no wider than its plow (any if either isn't given)
+	pub fn clears_roadway(&self, width: Option<N64>) -> bool {
+		self.roadways == Some(Roadways::Clear) && self.passes(width) <= 1.0
+	}
+	/// Passes the vehicle takes to clear a road of a width
+	pub fn passes(&self, width: Option<N64>) -> N64 {
+		match (self.plow_width.filter(|w| *w > 0.0), width) {
+			(Some(plow), Some(width)) => (width / plow).ceil().max(n64(1.0)),
+			_ => n64(1.0),
+		}
+	}
+}
+
+#[derive(Serialize, Deserialize, Clone, PartialEq, Debug)]
+pub struct PathSegment {
+	pub node: NodeId,
+	pub discriminator: Option<NodeId>,
+	/// service time of the node tasks serviced upon reaching the node
+	#[serde(default, skip_serializing_if = "Option::is_none")]
+	pub service: Option<N64>,
+	/// whether the vehicle reloads its consumable at the node
+	#[serde(default, skip_serializing_if = "std::ops::Not::not")]
+	pub reload: bool,
+	/// estimated time of arrival at the node, in hours from the start of the tour
+	#[serde(default, skip_serializing_if = "Option::is_none")]
+	pub eta: Option<f64>,
+}
+
+#[derive(Serialize, Deserialize, Clone, PartialEq, Debug)]
+pub struct SidewalkPathSegment {
+	pub node: NodeId,
+	pub discriminator: Option<NodeId>,
+	pub side: Option<SidewalkSide>,
+	/// indices of the stops serviced on the way to the node
+	#[serde(default, skip_serializing_if = "Vec::is_empty")]
+	pub stops: Vec<usize>,
+}
+
+pub type Paths = Vec<Vec<PathSegment>>;
+pub type SidewalkPaths = Vec<Vec<SidewalkPathSegment>>;
+
+/// Totals of a vehicle's tour
+#[derive(Serialize, Deserialize, Clone, PartialEq, Debug)]
+pub struct VehicleMetrics {
+	/// distance driven, in meters
+	pub distance: f64,
+	/// estimated duration, in hours
+	pub duration: f64,
+	/// number of snowy segments (or sidewalks) cleared
+	pub cleared: usize,
+	/// distance driven without clearing anything, in meters
+	pub deadhead: f64,
+}
+
+/// Paths (or sidewalk paths), along with the totals of each vehicle's tour
+#[derive(Serialize, Deserialize, Clone, PartialEq, Debug)]
+pub struct Solution<P> {
+	pub paths: P,
+	pub vehicles: Vec<VehicleMetrics>,
+	/// objective value of the tours
+	pub objective: f64,
+}
+
+#[derive(Serialize, Deserialize, Clone, PartialEq, Debug)]
+pub struct SnowStatusElement {
+	pub p1: NodeId,
+	pub p2: NodeId,
+	pub discriminator: Option<NodeId>,
+	pub depth: N64,
+	/// probability that the observation is right (certain if absent)
+	#[serde(default, skip_serializing_if = "Option::is_none")]
+	pub probability: Option<N64>,
+	/// variance of the observed depth (exact if absent)
+	#[serde(default, skip_serializing_if = "Option::is_none")]
+	pub variance: Option<N64>,
+	/// when the depth was observed, in seconds since epoch
+	#[serde(default, skip_serializing_if = "Option::is_none")]
+	pub observed: Option<f64>,
+	/// confidence in the source of the observation, weighting it when merging by the `weighted` strategy (1 if absent)
+	#[serde(default, skip_serializing_if = "Option::is_none")]
+	pub confidence: Option<N64>,
+}
+
+pub type SnowStatuses = Vec<SnowStatusElement>;
+
+/// A road segment a roadside sensor observes
+#[derive(Serialize, Deserialize, Clone, PartialEq, Debug)]
+pub struct SensorSegment {
+	/// sensor id, as in its dumps
+	pub sensor: String,
+	pub p1: NodeId,
+	pub p2: NodeId,
+	pub discriminator: Option<NodeId>,
+}
+
+/// Road segments observed by roadside sensors (a sensor observing several segments being listed for each)
+pub type SensorSegments = Vec<SensorSegment>;
+
+/// Cost multiplier of a road segment (construction zones to avoid, bus routes to favor...)
+#[derive(Serialize, Deserialize, Clone, PartialEq, Debug)]
+pub struct CostMultiplier {
+	pub p1: NodeId,
+	pub p2: NodeId,
+	pub discriminator: Option<NodeId>,
+	pub multiplier: N64,
+}
+
+/// Cost multipliers overlaid on the road graph
+pub type CostOverlay = Vec<CostMultiplier>;
+
+/// Travel time of a segment when only driving through it (not clearing it), e.g. historical congestion for the storm hour
+#[derive(Serialize, Deserialize, Clone, PartialEq, Debug)]
+pub struct TravelTime {
+	pub p1: NodeId,
+	pub p2: NodeId,
+	pub discriminator: Option<NodeId>,
+	/// travel time, in the units of edge costs (seconds with the time objective)
+	pub time: N64,
+}
+
+/// Travel times overriding the segment costs when deadheading
+pub type TravelTimes = Vec<TravelTime>;
+
+/// Precomputed node-to-node distances or times, e.g. from a routing engine (OSRM, Valhalla...)
+#[derive(Serialize, Deserialize, Clone, PartialEq, Debug)]
+pub struct DistanceMatrix {
+	pub nodes: Vec<NodeId>,
+	/// from the `i`th node to the `j`th at `[i][j]`, in the units of edge costs (null if unreachable)
+	pub values: Vec<Vec<Option<N64>>>,
+}
+
+/// Work item at a node (intersection, crosswalk ramp, hydrant...), serviced in place
+#[derive(Serialize, Deserialize, Clone, PartialEq, Debug)]
+pub struct NodeTask {
+	pub node: NodeId,
+	/// service time, in the units of edge costs (seconds with the time objective)
+	pub service: N64,
+	/// what the task is, for information
+	#[serde(default, skip_serializing_if = "Option::is_none")]
+	pub kind: Option<String>,
+}
+
+/// Node tasks, to be serviced along with the snowy segments
+pub type NodeTasks = Vec<NodeTask>;
+
+/// Bus stop or crosswalk, to be cleared on the closest sidewalk
+#[derive(Serialize, Deserialize, Clone, PartialEq, Debug)]
+pub struct Stop {
+	pub coordinates: (f64, f64),
+	/// service time, in the units of edge costs (seconds with the time objective)
+	pub service: N64,
+	/// what the stop is, for information
+	#[serde(default, skip_serializing_if = "Option::is_none")]
+	pub kind: Option<String>,
+}
+
+/// Bus stops and crosswalks, to be serviced along with the snowy sidewalks
+pub type Stops = Vec<Stop>;
+
+/// Execution feedback: how far along its path each vehicle has gotten
+#[derive(Serialize, Deserialize, Clone, PartialEq, Debug)]
+pub struct Progress {
+	/// index, in its path, of the last node reached by each vehicle
+	pub reached: Vec<usize>,
+	/// fresh snow observations, if any
+	#[serde(default)]
+	pub snow: SnowStatuses,
+}
+
+/// A road segment actually traversed by a vehicle
+#[derive(Serialize, Deserialize, Clone, PartialEq, Debug)]
+pub struct Traversal {
+	pub p1: NodeId,
+	pub p2: NodeId,
+	pub discriminator: Option<NodeId>,
+	/// when the traversal happened, in seconds since epoch
+	pub timestamp: f64,
+}
+
+/// Execution log: segments actually traversed, for each vehicle
+pub type ExecutionLog = Vec<Vec<Traversal>>;
+
+/// A GPS fix of a vehicle
+#[derive(Serialize, Deserialize, Clone, PartialEq, Debug)]
+pub struct GpsPoint {
+	pub coordinates: (f64, f64),
+	/// when the fix was taken, in seconds since epoch
+	pub timestamp: f64,
+}
+
+/// GPS fixes of a vehicle, in chronological order
+pub type GpsTrace = Vec<GpsPoint>;
+pub type GpsTraces = Vec<GpsTrace>;
+
+/// Node id mapping, from old ids to new ids
+pub type IdMapping = IndexMap<NodeId, NodeId>;
+
+/// Data referring to nodes by id, that can be renamed
+pub trait RemapIds {
+	/// Renames node ids according to the mapping (ids not in the mapping are kept)
+	fn remap_ids(&mut self, mapping: &IdMapping);
+}
+impl<T: RemapIds> RemapIds for Vec<T> {
+	fn remap_ids(&mut self, mapping: &IdMapping) {
+		for t in self {
+			t.remap_ids(mapping);
+		}
+	}
+}
+impl<T: RemapIds> RemapIds for Option<T> {
+	fn remap_ids(&mut self, mapping: &IdMapping) {
+		if let Some(t) = self {
+			t.remap_ids(mapping);
+		}
+	}
+}
+impl RemapIds for NodeId {
+	fn remap_ids(&mut self, mapping: &IdMapping) {
+		if let Some(new) = mapping.get(self) {
+			*self = new.clone();
+		}
+	}
+}
+impl RemapIds for Node {
+	fn remap_ids(&mut self, mapping: &IdMapping) {
+		self.id.remap_ids(mapping);
+	}
+}
+impl RemapIds for RoadSegment {
+	fn remap_ids(&mut self, mapping: &IdMapping) {
+		self.p1.remap_ids(mapping);
+		self.p2.remap_ids(mapping);
+		self.discriminator.remap_ids(mapping);
+	}
+}
+impl RemapIds for TurnRestriction {
+	fn remap_ids(&mut self, mapping: &IdMapping) {
+		self.from.remap_ids(mapping);
+		self.via.remap_ids(mapping);
+		self.to.remap_ids(mapping);
+	}
+}
+impl RemapIds for RoadGraph {
+	/// Nodes renamed to the same id are merged (the first one is kept)
+	fn remap_ids(&mut self, mapping: &IdMapping) {
+		self.roads.remap_ids(mapping);
+		self.nodes.nodes.remap_ids(mapping);
+		self.restrictions.remap_ids(mapping);
+		self.depots.remap_ids(mapping);
+		let mut seen = std::collections::HashSet::new();
+		self.nodes.nodes.retain(|n| seen.insert(n.id.clone()));
+	}
+}
+impl RemapIds for Location {
+	fn remap_ids(&mut self, mapping: &IdMapping) {
+		if let Location::Node(id) = self {
+			id.remap_ids(mapping);
+		}
+	}
+}
+impl RemapIds for VehiclesConfiguration {
+	fn remap_ids(&mut self, mapping: &IdMapping) {
+		self.road.remap_ids(mapping);
+		self.sidewalk.remap_ids(mapping);
+	}
+}
+impl RemapIds for PathSegment {
+	fn remap_ids(&mut self, mapping: &IdMapping) {
+		self.node.remap_ids(mapping);
+		self.discriminator.remap_ids(mapping);
+	}
+}
+impl RemapIds for SidewalkPathSegment {
+	fn remap_ids(&mut self, mapping: &IdMapping) {
+		self.node.remap_ids(mapping);
+		self.discriminator.remap_ids(mapping);
+	}
+}
+impl RemapIds for NodeTask {
+	fn remap_ids(&mut self, mapping: &IdMapping) {
+		self.node.remap_ids(mapping);
+	}
+}
+impl RemapIds for SnowStatusElement {
+	fn remap_ids(&mut self, mapping: &IdMapping) {
+		self.p1.remap_ids(mapping);
+		self.p2.remap_ids(mapping);
+		self.discriminator.remap_ids(mapping);
+	}
+}
@@ -0,0 +1,334 @@
+//! Road graph diagnostics, and repairs thereof
+
+use crate::*;
+use data::*;
+
+use std::collections::HashSet;
+use std::path::Path;
+use indexmap::IndexMap;
+use serde::*;
+use serde_json::Value;
+
+/// An issue found in a road graph
+#[derive(Serialize, Deserialize, Clone, PartialEq, Debug)]
+#[serde(tag = "kind")]
+pub enum Issue {
+	/// Identical segments, listed multiple times
+	#[serde(rename = "duplicate-segment")]
+	DuplicateSegment {
+		segment: RoadSegment,
+		count: usize,
+	},
+	/// Segments sharing endpoints and discriminator, but differing otherwise - which one gets picked when following paths is arbitrary
+	#[serde(rename = "conflicting-segments")]
+	ConflictingSegments {
+		segments: Vec<RoadSegment>,
+		/// suggested single segment replacing all of them
+		resolution: RoadSegment,
+	},
+	/// Region of the graph that, respecting one-ways, can't be left (`sink`) or entered (`source`)
+	#[serde(rename = "one-way-pocket")]
+	OneWayPocket {
+		nodes: Vec<NodeId>,
+		sink: bool,
+		source: bool,
+		/// directed segments linking the region with the rest of the graph
+		links: Vec<RoadSegment>,
+	},
+	/// Parallel segment that was given a synthesized discriminator, to tell it apart from the others between the same nodes
+	#[serde(rename = "discriminated")]
+	Discriminated {
+		segment: RoadSegment,
+	},
+	/// Directed segment that gets de-directed when solving, to make the graph strongly connected
+	#[serde(rename = "de-directed")]
+	DeDirected {
+		segment: RoadSegment,
+	},
+	/// Segment referring to a node that is not in the graph
+	#[serde(rename = "dangling-node")]
+	DanglingNode {
+		segment: RoadSegment,
+		node: NodeId,
+	},
+	/// Node with non-finite or out of range coordinates (longitude in [-180, 180], latitude in [-90, 90])
+	#[serde(rename = "bad-coordinates")]
+	BadCoordinates {
+		node: NodeId,
+		coordinates: (f64, f64),
+	},
+	#[serde(rename = "negative-distance")]
+	NegativeDistance {
+		segment: RoadSegment,
+	},
+	/// Segment (by index in the graph) whose sidewalk flags are not a pair of booleans - taken as no sidewalks
+	#[serde(rename = "unknown-sidewalks")]
+	UnknownSidewalks {
+		index: usize,
+		sidewalks: Value,
+	},
+	/// Snow status of a segment that is not in the graph
+	#[serde(rename = "unknown-segment")]
+	UnknownSegment {
+		p1: NodeId,
+		p2: NodeId,
+		discriminator: Option<NodeId>,
+	},
+	/// Vehicle (by fleet and index) located at a node that is not in the graph, or that no segment reaches
+	#[serde(rename = "orphan-vehicle")]
+	OrphanVehicle {
+		fleet: String,
+		index: usize,
+		node: NodeId,
+	},
+	/// Segment whose cost expression fails to evaluate, or evaluates to a negative number
+	#[serde(rename = "invalid-cost")]
+	InvalidCost {
+		segment: RoadSegment,
+		message: String,
+	},
+	/// Cost multiplier that is not positive, which would make its segment free (or pay) to drive through
+	#[serde(rename = "invalid-multiplier")]
+	InvalidMultiplier {
+		p1: NodeId,
+		p2: NodeId,
+		discriminator: Option<NodeId>,
+		multiplier: N64,
+	},
+	/// Input that could not be read at all
+	#[serde(rename = "invalid-input")]
+	InvalidInput {
+		message: String,
+	},
+}
+
+impl From<error::Error> for Issue {
+	fn from(e: error::Error) -> Self {
+		Issue::InvalidInput { message: e.to_string() }
+	}
+}
+
+/// Takes the value of an input, or reports why it could not be read
+pub fn reported<T>(input: Result<T, error::Error>, issues: &mut Vec<Issue>) -> Option<T> {
+	input.map_err(|e| issues.push(e.into())).ok()
+}
+
+/// Segment in canonical orientation (undirected segments go from the lesser to the greater node)
+fn canonical(r: &RoadSegment) -> RoadSegment {
+	if !r.directed && r.p1 > r.p2 {
+		RoadSegment {
+			p1: r.p2.clone(),
+			p2: r.p1.clone(),
+			sidewalks: (r.sidewalks.1, r.sidewalks.0),
+			geometry: r.geometry.iter().rev().copied().collect(),
+			..r.clone()
+		}
+	} else {
+		r.clone()
+	}
+}
+
+/// Merges conflicting segments into one: directed only if all are directed the same way, shortest distance, sidewalks wherever any has them, and the first known class
+fn resolve(segments: &[RoadSegment]) -> RoadSegment {
+	let first = &segments[0];
+	let directed = segments.iter().all(|r| r.directed && r.p1 == first.p1);
+	let mut resolution = if directed { first.clone() } else { canonical(&RoadSegment { directed: false, ..first.clone() }) };
+	resolution.distance = segments.iter().map(|r| r.distance).min().unwrap();
+	resolution.sidewalks = segments.iter().fold((false, false), |(l, r), s| {
+		let s = if s.p1 == resolution.p1 { s.sidewalks } else { (s.sidewalks.1, s.sidewalks.0) };
+		(l || s.0, r || s.1)
+	});
+	resolution.class = segments.iter().find_map(|r| r.class);
+	resolution
+}
+
+/// Groups road segments by endpoints and discriminator, with the index of each group's first segment
+fn groups(roads: &RoadGraph) -> IndexMap<SegmentKey, (usize, Vec<RoadSegment>)> {
+	let mut groups: IndexMap<SegmentKey, (usize, Vec<RoadSegment>)> = IndexMap::new();
+	for (i, r) in roads.roads.iter().enumerate() {
+		groups.entry(segment_key(&r.p1, &r.p2, &r.discriminator)).or_insert_with(|| (i, Vec::new())).1.push(canonical(r));
+	}
+	groups
+}
+
+/// Whether a group of segments sharing endpoints is just a pair of opposite one-ways
+fn is_opposite_pair(segments: &[RoadSegment]) -> bool {
+	segments.len() == 2 && segments.iter().all(|r| r.directed) && segments[0].p1 != segments[1].p1
+}
+
+/// Finds duplicate and conflicting segments
+pub fn segment_issues(roads: &RoadGraph) -> Vec<Issue> {
+	groups(roads).into_iter().filter(|(_, (_, segments))| segments.len() > 1 && !is_opposite_pair(segments)).map(|(_, (_, segments))| {
+		if segments.iter().all(|r| r == &segments[0]) {
+			Issue::DuplicateSegment {
+				count: segments.len(),
+				segment: segments[0].clone(),
+			}
+		} else {
+			Issue::ConflictingSegments {
+				resolution: resolve(&segments),
+				segments,
+			}
+		}
+	}).collect()
+}
+
+/// Repairs duplicate and conflicting segments, replacing each group with its suggested resolution (in place of its first segment)
+///
+/// Returns: the issues that were repaired
+pub fn repair(roads: &mut RoadGraph) -> Vec<Issue> {
+	let issues = segment_issues(roads);
+	if issues.is_empty() {
+		return issues;
+	}
+	let mut replaced: IndexMap<usize, RoadSegment> = IndexMap::new();
+	let mut dropped = HashSet::new();
+	for (_, (first, segments)) in groups(roads).into_iter().filter(|(_, (_, segments))| segments.len() > 1 && !is_opposite_pair(segments)) {
+		replaced.insert(first, resolve(&segments));
+		dropped.insert(segment_key(&segments[0].p1, &segments[0].p2, &segments[0].discriminator));
+	}
+	roads.roads = std::mem::take(&mut roads.roads).into_iter().enumerate().filter_map(|(i, r)| match replaced.get(&i) {
+		Some(resolution) => Some(resolution.clone()),
+		None if dropped.contains(&segment_key(&r.p1, &r.p2, &r.discriminator)) => None,
+		None => Some(r),
+	}).collect();
+	issues
+}
+
+/// Synthesizes discriminators for distinct parallel segments between the same nodes that have none.
+///
+/// Of each group of distinct parallel segments, ordered by distance, the first one keeps no discriminator, the others get a new (orphan) node `{p1}~{p2}~{k}` half-way between their ends as discriminator.
+/// Identical segments are left as duplicates, see [`repair`].
+///
+/// Returns: the discriminated segments
+pub fn discriminate(roads: &mut RoadGraph) -> Vec<Issue> {
+	let coords: IndexMap<_, _> = roads.nodes.nodes.iter().map(|n| (n.id.clone(), n.coordinates)).collect();
+	let mut discriminators: Vec<(RoadSegment, NodeId)> = Vec::new();
+	for ((p1, p2, _), (_, segments)) in groups(roads).into_iter().filter(|((_, _, d), (_, segments))| d.is_none() && segments.len() > 1 && !is_opposite_pair(segments)) {
+		let mut distinct: Vec<RoadSegment> = Vec::new();
+		for r in segments {
+			if !distinct.contains(&r) {
+				distinct.push(r);
+			}
+		}
+		distinct.sort_by(|a, b| a.distance.cmp(&b.distance).then(a.directed.cmp(&b.directed)).then(a.p1.cmp(&b.p1)));
+		for (k, r) in distinct.into_iter().enumerate().skip(1) {
+			discriminators.push((r, format!("{}~{}~{}", p1, p2, k).into()));
+		}
+	}
+	let mut issues = Vec::new();
+	for (r, d) in discriminators {
+		if let (Some(c1), Some(c2)) = (coords.get(&r.p1), coords.get(&r.p2)) {
+			roads.nodes.nodes.push(Node { id: d.clone(), coordinates: ((c1.0 + c2.0) / 2.0, (c1.1 + c2.1) / 2.0) });
+		}
+		for road in roads.roads.iter_mut().filter(|road| road.discriminator.is_none() && canonical(road) == r) {
+			road.discriminator = Some(d.clone());
+		}
+		issues.push(Issue::Discriminated { segment: RoadSegment { discriminator: Some(d), ..r } });
+	}
+	issues
+}
+
+/// Reads a road graph for validation, reporting sidewalk flags that are not a pair of booleans (and taking them as no sidewalks) instead of failing on them
+pub fn read_roads(file: impl AsRef<Path>) -> Result<(RoadGraph, Vec<Issue>), error::Error> {
+	let mut data = document::read_data::<RoadGraph>(&file, "Road graph")?;
+	let mut issues = Vec::new();
+	if let Some(roads) = data.get_mut("roads").and_then(Value::as_array_mut) {
+		for (index, r) in roads.iter_mut().enumerate() {
+			if let Some(sidewalks) = r.get_mut("sidewalks").filter(|s| !s.as_array().is_some_and(|s| s.len() == 2 && s.iter().all(Value::is_boolean))) {
+				issues.push(Issue::UnknownSidewalks { index, sidewalks: std::mem::replace(sidewalks, serde_json::json!([false, false])) });
+			}
+		}
+	}
+	let roads = serde_json::from_value(data).map_err(|e| error::Error::invalid(&file, format!("Road graph {}", e)))?;
+	Ok((roads, issues))
+}
+
+/// Finds what in a road graph is against the spec: segments referring to unknown nodes or with negative distances, and nodes with bad coordinates
+pub fn spec_issues(roads: &RoadGraph) -> Vec<Issue> {
+	let nodes: HashSet<_> = roads.nodes.nodes.iter().map(|n| &n.id).collect();
+	let mut issues: Vec<_> = roads.nodes.nodes.iter().filter(|n| {
+		let (lon, lat) = n.coordinates;
+		!(-180.0..=180.0).contains(&lon) || !(-90.0..=90.0).contains(&lat)
+	}).map(|n| Issue::BadCoordinates { node: n.id.clone(), coordinates: n.coordinates }).collect();
+	for r in &roads.roads {
+		for node in [&r.p1, &r.p2].iter().copied() {
+			if !nodes.contains(node) {
+				issues.push(Issue::DanglingNode { segment: r.clone(), node: node.clone() });
+			}
+		}
+		if r.distance < 0.0 {
+			issues.push(Issue::NegativeDistance { segment: r.clone() });
+		}
+	}
+	issues
+}
+
+/// Finds snow statuses of segments that are not in the road graph
+pub fn snow_issues(roads: &RoadGraph, snow: &SnowStatuses) -> Vec<Issue> {
+	let segments: HashSet<_> = roads.roads.iter().map(|r| segment_key(&r.p1, &r.p2, &r.discriminator)).collect();
+	snow.iter().filter(|s| !segments.contains(&segment_key(&s.p1, &s.p2, &s.discriminator))).map(|s| Issue::UnknownSegment {
+		p1: s.p1.clone(),
+		p2: s.p2.clone(),
+		discriminator: s.discriminator.clone(),
+	}).collect()
+}
+
+/// Finds vehicles located at nodes that are not in the road graph, or that no segment reaches
+pub fn vehicle_issues(roads: &RoadGraph, vehicles: &VehiclesConfiguration) -> Vec<Issue> {
+	let nodes: HashSet<_> = roads.nodes.nodes.iter().map(|n| &n.id).collect();
+	let reached: HashSet<_> = roads.roads.iter().flat_map(|r| vec![&r.p1, &r.p2]).filter(|n| nodes.contains(n)).collect();
+	[("road", &vehicles.road), ("sidewalk", &vehicles.sidewalk)].iter().flat_map(|(fleet, locations)| {
+		locations.iter().enumerate().filter_map(|(index, l)| match l {
+			Location::Node(node) if !reached.contains(node) => Some(Issue::OrphanVehicle { fleet: fleet.to_string(), index, node: node.clone() }),
+			_ => None,
+		}).collect::<Vec<_>>()
+	}).collect()
+}
+
+/// Finds segments whose cost, per the meta parameters, can't be evaluated - with the snow depths if known
+pub fn cost_issues(roads: &RoadGraph, snow: &[SnowStatusElement], params: &meta::Parameters) -> Vec<Issue> {
+	let depths: IndexMap<_, _> = snow.iter().map(|s| (segment_key(&s.p1, &s.p2, &s.discriminator), s.depth)).collect();
+	roads.roads.iter().filter_map(|r| {
+		let depth = depths.get(&segment_key(&r.p1, &r.p2, &r.discriminator)).copied().unwrap_or_else(|| n64(0.0));
+		params.cost(r, depth).err().map(|message| Issue::InvalidCost { segment: r.clone(), message })
+	}).collect()
+}
+
+/// Finds cost multipliers that are not positive
+pub fn multiplier_issues(overlay: &[CostMultiplier]) -> Vec<Issue> {
+	overlay.iter().filter(|m| m.multiplier <= 0.0).map(|m| Issue::InvalidMultiplier {
+		p1: m.p1.clone(),
+		p2: m.p2.clone(),
+		discriminator: m.discriminator.clone(),
+		multiplier: m.multiplier,
+	}).collect()
+}
+
+/// Finds one-way traps: pockets of the road graph that can't be left or entered respecting one-ways (relative to the largest region), and the one-ways that get de-directed when solving because of them
+pub fn one_way_issues(roads: &RoadGraph) -> Vec<Issue> {
+	let g = roads.light();
+	let mut sccs = g.strongly_connected_components::<true, false>();
+	if sccs.len() < 2 {
+		return Vec::new();
+	}
+	sccs.sort_unstable_by_key(|s| -(s.len() as isize));
+	let links = g.weak_links(&sccs);
+	let mut issues: Vec<_> = sccs.iter().skip(1).filter_map(|scc| {
+		let leaving: Vec<_> = links.iter().filter(|e| scc.contains(&e.p1) && !scc.contains(&e.p2)).collect();
+		let entering: Vec<_> = links.iter().filter(|e| !scc.contains(&e.p1) && scc.contains(&e.p2)).collect();
+		if leaving.is_empty() == entering.is_empty() {
+			return None;
+		}
+		let mut nodes: Vec<_> = scc.iter().map(|i| roads.nodes.nodes[*i].id.clone()).collect();
+		nodes.sort_unstable();
+		Some(Issue::OneWayPocket {
+			nodes,
+			sink: leaving.is_empty(),
+			source: entering.is_empty(),
+			links: leaving.into_iter().chain(entering).map(|e| roads.roads[e.index].clone()).collect(),
+		})
+	}).collect();
+	issues.extend(links.into_iter().map(|e| Issue::DeDirected { segment: roads.roads[e.index].clone() }));
+	issues
+}
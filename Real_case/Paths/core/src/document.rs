@@ -0,0 +1,349 @@
+//! Versioned documents
+//!
+//! Every JSON document emitted is wrapped as `{ "version": n, "kind": "...", "data": ... }`, so that formats can evolve without breaking archived files.
+//! Documents of older versions - including bare, unversioned documents of version 1 - are upgraded to the current version when read.
+
+use crate::*;
+
+use std::path::{Path, PathBuf};
+use error::Error;
+use serde::{Serialize, Deserialize, de::DeserializeOwned};
+use serde_json::Value;
+
+/// Current version of the documents spec
+pub const VERSION: u64 = 2;
+
+/// Versioned document envelope
+#[derive(Serialize, Deserialize, Clone, PartialEq, Debug)]
+pub struct Document<T = Value> {
+	pub version: u64,
+	pub kind: String,
+	pub data: T,
+}
+
+/// Kind of document a type is read from & written as
+pub trait Kind {
+	const KIND: &'static str;
+}
+
+macro_rules! kinds {
+	($($t:ty => $k:literal),* $(,)?) => {
+		$(impl Kind for $t {
+			const KIND: &'static str = $k;
+		})*
+		/// All known kinds of documents
+		pub const KINDS: &[&str] = &[$($k),*];
+	};
+}
+
+kinds! {
+	data::RoadGraph => "road-graph",
+	data::Drones => "drones",
+	data::VehiclesConfiguration => "vehicles",
+	data::SnowStatuses => "snow-status",
+	data::SensorSegments => "sensor-segments",
+	data::Paths => "paths",
+	data::SidewalkPaths => "sidewalk-paths",
+	data::Solution<data::Paths> => "solution",
+	data::Solution<data::SidewalkPaths> => "sidewalk-solution",
+	data::CostOverlay => "cost-multipliers",
+	data::TravelTimes => "travel-times",
+	data::DistanceMatrix => "distance-matrix",
+	data::Progress => "progress",
+	data::ExecutionLog => "execution-log",
+	data::GpsTraces => "gps-traces",
+	data::IdMapping => "id-mapping",
+	data::NodeTasks => "node-tasks",
+	data::Stops => "stops",
+	Vec<diagnostics::Issue> => "issues",
+	Vec<legality::Violation> => "violations",
+	legality::Verification => "verification",
+	report::Adherence => "adherence",
+	report::Explanation => "explanation",
+	report::Comparison => "comparison",
+	report::Stats => "stats",
+	montecarlo::Fragility => "fragility",
+	salting::Schedule => "plow-salt-schedule",
+	combined::Schedule => "plow-both-schedule",
+	bench::Bench => "bench",
+	auth::Clients => "clients",
+}
+
+/// Oldest version whose data is that of the current version, bare documents (of version 1) being streamed as they are read if it is 1
+const SAME_DATA_SINCE: u64 = 1;
+
+/// Migrates document data from a version to the next
+fn migrate(version: u64, _kind: &str, data: Value) -> Value {
+	match version {
+		// 1 -> 2: only introduced the envelope
+		1 => data,
+		_ => unreachable!("no migration from version {}", version),
+	}
+}
+
+/// Whether a document is wrapped in a versioned envelope (as opposed to bare)
+fn is_versioned(doc: &Value) -> bool {
+	doc.get("version").is_some_and(Value::is_u64) && doc.get("kind").is_some_and(Value::is_string) && doc.get("data").is_some()
+}
+
+/// Upgrades a document to the current version
+///
+/// Arguments:
+/// - `doc`: the document, versioned or bare
+/// - `kind`: expected kind of the document, required for bare documents
+///
+/// Returns: the upgraded document, or what's wrong with it
+pub fn upgrade(doc: Value, kind: Option<&str>) -> Result<Document, String> {
+	let Document { mut version, kind: found, mut data } = if is_versioned(&doc) {
+		serde_json::from_value(doc).map_err(|e| e.to_string())?
+	} else {
+		Document {
+			version: 1,
+			kind: kind.ok_or("is an unversioned document of unknown kind")?.to_string(),
+			data: doc,
+		}
+	};
+	if let Some(kind) = kind.filter(|kind| *kind != found) {
+		return Err(format!("is a {} document, expected {}", found, kind));
+	}
+	if version > VERSION {
+		return Err(format!("is of version {}, newer than the supported {}", version, VERSION));
+	}
+	while version < VERSION {
+		data = migrate(version, &found, data);
+		version += 1;
+	}
+	Ok(Document { version, kind: found, data })
+}
+
+/// Parses a document of a kind, upgrading it if needed
+pub fn parse<T: Kind + DeserializeOwned>(doc: Value) -> Result<T, String> {
+	serde_json::from_value(upgrade(doc, Some(T::KIND))?.data).map_err(|e| e.to_string())
+}
+
+/// Parses a document of any kind into an untagged type, upgrading it if needed - bare documents are taken as they are
+pub fn parse_any<T: DeserializeOwned>(doc: Value) -> Result<T, String> {
+	let data = if is_versioned(&doc) { upgrade(doc, None)?.data } else { doc };
+	serde_json::from_value(data).map_err(|e| e.to_string())
+}
+
+/// Current-version document of a kind, deserialized straight from the token stream
+struct Streamed<T>(T);
+
+impl<'de, T: Kind + DeserializeOwned> Deserialize<'de> for Streamed<T> {
+	fn deserialize<D: serde::Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+		struct Visitor<T>(std::marker::PhantomData<T>);
+		impl<'de, T: Kind + DeserializeOwned> serde::de::Visitor<'de> for Visitor<T> {
+			type Value = Streamed<T>;
+			fn expecting(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+				write!(f, "a version {} {} document", VERSION, T::KIND)
+			}
+			fn visit_map<A: serde::de::MapAccess<'de>>(self, mut map: A) -> Result<Self::Value, A::Error> {
+				use serde::de::Error;
+				let (mut version, mut kind, mut data) = (None, None, None);
+				while let Some(key) = map.next_key::<String>()? {
+					match key.as_str() {
+						"version" => version = Some(map.next_value::<u64>()?),
+						"kind" => kind = Some(map.next_value::<String>()?),
+						// only once known to be of the current version and kind, the envelope being written in that order
+						"data" if version == Some(VERSION) && kind.as_deref() == Some(T::KIND) => data = Some(map.next_value::<T>()?),
+						_ => return Err(A::Error::custom("not streamable")),
+					}
+				}
+				data.map(Streamed).ok_or_else(|| A::Error::missing_field("data"))
+			}
+		}
+		deserializer.deserialize_map(Visitor(std::marker::PhantomData))
+	}
+}
+
+/// Reads a document of a kind, upgrading it if needed
+///
+/// Documents of the current version - and bare ones, while their data is that of the current version - are deserialized as they are read, without holding their whole JSON tree in memory (which takes several times the size of the data for road graphs).
+/// Others - older documents, and invalid ones for their errors to be reported - are read again through their JSON tree.
+pub fn read<T: Kind + DeserializeOwned>(file: impl AsRef<Path>, what: &str) -> Result<T, Error> {
+	match serde_json::from_reader::<_, Streamed<T>>(open(&file)?) {
+		Ok(Streamed(data)) => return Ok(data),
+		Err(e) => log::debug!("{} not streamed as a versioned document: {}", what, e),
+	}
+	if SAME_DATA_SINCE == 1 {
+		match serde_json::from_reader::<_, T>(open(&file)?) {
+			Ok(data) => return Ok(data),
+			Err(e) => log::debug!("{} not streamed as a bare document: {}", what, e),
+		}
+	}
+	serde_json::from_value(read_data::<T>(&file, what)?).map_err(|e| Error::invalid(&file, format!("{} {}", what, e)))
+}
+
+/// Road graph document, read in two passes so that its segments are never all held: the first gathers its nodes, turn restrictions and depots, the second goes through its segments one at a time as they are parsed
+///
+/// For metropolitan road graphs, whose segments take several times the memory of their nodes.
+/// Segments can be gone through again, parsing them anew, for repeated solves.
+#[derive(Clone, Debug)]
+pub struct RoadStream {
+	file: PathBuf,
+	what: String,
+	pub nodes: Vec<data::Node>,
+	pub restrictions: Vec<data::TurnRestriction>,
+	pub depots: Vec<NodeId>,
+}
+
+/// Sink of the segments of a [`RoadStream`] pass
+type SegmentSink<'f> = &'f mut dyn FnMut(data::RoadSegment) -> Result<(), Error>;
+
+/// Pass through a road graph document, bare or versioned - gathering all but its segments, or sinking them
+struct Pass<'f> {
+	segments: Option<SegmentSink<'f>>,
+	nodes: Option<Vec<data::Node>>,
+	restrictions: Vec<data::TurnRestriction>,
+	depots: Vec<NodeId>,
+	/// error of the sink, which stopped the pass
+	failed: Option<Error>,
+}
+
+impl<'de, 'f> serde::de::DeserializeSeed<'de> for &mut Pass<'f> {
+	type Value = ();
+	fn deserialize<D: serde::Deserializer<'de>>(self, deserializer: D) -> Result<(), D::Error> {
+		deserializer.deserialize_map(self)
+	}
+}
+
+impl<'de, 'f> serde::de::Visitor<'de> for &mut Pass<'f> {
+	type Value = ();
+	fn expecting(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+		write!(f, "a road graph document")
+	}
+	fn visit_map<A: serde::de::MapAccess<'de>>(self, mut map: A) -> Result<(), A::Error> {
+		use serde::de::{Error, IgnoredAny};
+		while let Some(key) = map.next_key::<String>()? {
+			match key.as_str() {
+				"version" => match map.next_value::<u64>()? {
+					v if v > VERSION => return Err(A::Error::custom(format!("is of version {}, newer than the supported {}", v, VERSION))),
+					v if v < SAME_DATA_SINCE => return Err(A::Error::custom(format!("is of version {}, migrate it to stream it", v))),
+					_ => {},
+				},
+				"kind" => match map.next_value::<String>()? {
+					k if k != data::RoadGraph::KIND => return Err(A::Error::custom(format!("is a {} document, expected {}", k, data::RoadGraph::KIND))),
+					_ => {},
+				},
+				"data" => map.next_value_seed(&mut *self)?,
+				"roads" => match self.segments.as_mut() {
+					Some(sink) => map.next_value_seed(Segments { sink: &mut **sink, failed: &mut self.failed })?,
+					None => {
+						map.next_value::<IgnoredAny>()?;
+					},
+				},
+				"nodes" if self.segments.is_none() => self.nodes = Some(map.next_value()?),
+				"restrictions" if self.segments.is_none() => self.restrictions = map.next_value()?,
+				"depots" if self.segments.is_none() => self.depots = map.next_value()?,
+				_ => {
+					map.next_value::<IgnoredAny>()?;
+				},
+			}
+		}
+		Ok(())
+	}
+}
+
+/// Segments of a road graph, each sunk as soon as it is parsed
+struct Segments<'a, 'f> {
+	sink: &'a mut (dyn FnMut(data::RoadSegment) -> Result<(), Error> + 'f),
+	failed: &'a mut Option<Error>,
+}
+
+impl<'de, 'a, 'f> serde::de::DeserializeSeed<'de> for Segments<'a, 'f> {
+	type Value = ();
+	fn deserialize<D: serde::Deserializer<'de>>(self, deserializer: D) -> Result<(), D::Error> {
+		deserializer.deserialize_seq(self)
+	}
+}
+
+impl<'de, 'a, 'f> serde::de::Visitor<'de> for Segments<'a, 'f> {
+	type Value = ();
+	fn expecting(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+		write!(f, "road segments")
+	}
+	fn visit_seq<A: serde::de::SeqAccess<'de>>(self, mut seq: A) -> Result<(), A::Error> {
+		while let Some(segment) = seq.next_element::<data::RoadSegment>()? {
+			if let Err(e) = (self.sink)(segment) {
+				let message = e.to_string();
+				*self.failed = Some(e);
+				return Err(serde::de::Error::custom(message));
+			}
+		}
+		Ok(())
+	}
+}
+
+impl RoadStream {
+	/// Opens a road graph document, reading all but its segments
+	pub fn open(file: impl AsRef<Path>, what: &str) -> Result<Self, Error> {
+		let mut pass = Pass { segments: None, nodes: None, restrictions: Vec::new(), depots: Vec::new(), failed: None };
+		Self::pass(&file, what, &mut pass)?;
+		Ok(Self {
+			file: file.as_ref().to_path_buf(),
+			what: what.to_string(),
+			nodes: pass.nodes.ok_or_else(|| Error::invalid(&file, format!("{} has no nodes", what)))?,
+			restrictions: pass.restrictions,
+			depots: pass.depots,
+		})
+	}
+	/// Goes through the segments, in order, as they are parsed
+	pub fn for_each_segment(&self, mut f: impl FnMut(data::RoadSegment) -> Result<(), Error>) -> Result<(), Error> {
+		let mut pass = Pass { segments: Some(&mut f), nodes: None, restrictions: Vec::new(), depots: Vec::new(), failed: None };
+		Self::pass(&self.file, &self.what, &mut pass)
+	}
+	/// Reads the whole road graph after all
+	pub fn read(&self) -> Result<data::RoadGraph, Error> {
+		read(&self.file, &self.what)
+	}
+	fn pass(file: impl AsRef<Path>, what: &str, pass: &mut Pass) -> Result<(), Error> {
+		use serde::de::DeserializeSeed;
+		let mut de = serde_json::Deserializer::from_reader(open(&file)?);
+		let parsed = pass.deserialize(&mut de).and_then(|_| de.end());
+		match (parsed, pass.failed.take()) {
+			(_, Some(e)) => Err(e),
+			(Ok(()), None) => Ok(()),
+			(Err(e), None) => Err(Error::json(&file, what, e)),
+		}
+	}
+}
+
+/// Reads the data of a document of a kind, upgrading it if needed, without parsing it yet
+pub fn read_data<T: Kind>(file: impl AsRef<Path>, what: &str) -> Result<Value, Error> {
+	let doc = serde_json::from_reader(open(&file)?).map_err(|e| Error::json(&file, what, e))?;
+	upgrade(doc, Some(T::KIND)).map(|doc| doc.data).map_err(|e| Error::invalid(&file, format!("{} {}", what, e)))
+}
+
+/// Reads a document of any kind into an untagged type, upgrading it if needed - bare documents are taken as they are
+pub fn read_any<T: DeserializeOwned>(file: impl AsRef<Path>, what: &str) -> Result<T, Error> {
+	let doc: Value = serde_json::from_reader(open(&file)?).map_err(|e| Error::json(&file, what, e))?;
+	let data = if is_versioned(&doc) { upgrade(doc, None).map_err(|e| Error::invalid(&file, format!("{} {}", what, e)))?.data } else { doc };
+	serde_json::from_value(data).map_err(|e| Error::invalid(&file, format!("{} is invalid: {}", what, e)))
+}
+
+/// Opens an input file
+fn open(file: impl AsRef<Path>) -> Result<std::io::BufReader<std::fs::File>, Error> {
+	std::fs::File::open(&file).map(std::io::BufReader::new).map_err(|e| Error::io(&file, e))
+}
+
+/// Wraps data in a document of the current version
+pub fn wrap<T: Kind>(data: &T) -> Document<&T> {
+	Document {
+		version: VERSION,
+		kind: T::KIND.to_string(),
+		data,
+	}
+}
+
+/// Writes a document of the current version
+pub fn write<T: Kind + Serialize>(file: impl AsRef<Path>, data: &T) -> std::io::Result<()> {
+	serde_json::to_writer(&std::fs::File::create(file)?, &wrap(data))?;
+	Ok(())
+}
+
+/// Prints a document of the current version to stdout
+pub fn print<T: Kind + Serialize>(data: &T) -> std::io::Result<()> {
+	serde_json::to_writer_pretty(std::io::stdout(), &wrap(data))?;
+	Ok(())
+}
@@ -0,0 +1,99 @@
+//! Errors of loading inputs and solving, with enough context to tell what to fix
+//!
+//! The CLI turns them into failures, and so into stable exit codes.
+
+use std::path::Path;
+
+#[derive(Debug)]
+pub enum Error {
+	/// a file could not be read or written
+	Io { file: Option<String>, source: std::io::Error },
+	/// a file is not valid JSON (or YAML, for meta parameters)
+	Parse { file: String, line: usize, column: usize, message: String },
+	/// an input is well-formed but invalid, e.g. of the wrong document kind or with a bad cost expression
+	Validation { file: Option<String>, message: String },
+	/// the solver could not construct a solution
+	Solver { message: String, ids: Vec<String> },
+	/// the solver ran out of wall-clock time before finding a feasible solution
+	Timeout { message: String },
+}
+
+fn name(file: impl AsRef<Path>) -> String {
+	file.as_ref().to_string_lossy().into_owned()
+}
+
+impl Error {
+	pub fn io(file: impl AsRef<Path>, source: std::io::Error) -> Self {
+		Self::Io { file: Some(name(file)), source }
+	}
+	/// Error of a JSON file, located where parsing stopped
+	pub fn json(file: impl AsRef<Path>, what: &str, e: serde_json::Error) -> Self {
+		let message = e.to_string();
+		let message = message.strip_suffix(&format!(" at line {} column {}", e.line(), e.column())).unwrap_or(&message);
+		Self::Parse { file: name(file), line: e.line(), column: e.column(), message: format!("{} is invalid JSON: {}", what, message) }
+	}
+	/// Error of a YAML file, located where parsing stopped if known
+	pub fn yaml(file: impl AsRef<Path>, what: &str, e: serde_yaml::Error) -> Self {
+		let (line, column) = e.location().map_or((0, 0), |l| (l.line(), l.column()));
+		let message = e.to_string();
+		let message = message.strip_suffix(&format!(" at line {} column {}", line, column)).unwrap_or(&message);
+		Self::Parse { file: name(file), line, column, message: format!("{} is invalid YAML: {}", what, message) }
+	}
+	pub fn invalid(file: impl AsRef<Path>, message: impl ToString) -> Self {
+		Self::Validation { file: Some(name(file)), message: message.to_string() }
+	}
+	pub fn solver(message: impl ToString) -> Self {
+		Self::Solver { message: message.to_string(), ids: Vec::new() }
+	}
+	pub fn timeout(message: impl ToString) -> Self {
+		Self::Timeout { message: message.to_string() }
+	}
+}
+
+impl std::fmt::Display for Error {
+	fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+		match self {
+			Self::Io { file: Some(file), source } => write!(f, "{}: {}", file, source),
+			Self::Io { file: None, source } => write!(f, "{}", source),
+			Self::Parse { file, line: 0, message, .. } => write!(f, "{}: {}", file, message),
+			Self::Parse { file, line, column, message } => write!(f, "{}:{}:{}: {}", file, line, column, message),
+			Self::Validation { file: Some(file), message } => write!(f, "{}: {}", file, message),
+			Self::Validation { file: None, message } | Self::Solver { message, .. } | Self::Timeout { message } => write!(f, "{}", message),
+		}
+	}
+}
+
+impl std::error::Error for Error {
+	fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+		match self {
+			Self::Io { source, .. } => Some(source),
+			_ => None,
+		}
+	}
+}
+
+impl From<std::io::Error> for Error {
+	fn from(source: std::io::Error) -> Self {
+		Self::Io { file: None, source }
+	}
+}
+
+/// Solver failures reported as plain messages
+impl From<String> for Error {
+	fn from(message: String) -> Self {
+		Self::solver(message)
+	}
+}
+
+/// For the parts still speaking [`std::io::Result`]; invalid inputs stay invalid data
+impl From<Error> for std::io::Error {
+	fn from(e: Error) -> Self {
+		match e {
+			Error::Io { source, file: None } => source,
+			Error::Io { ref source, .. } => std::io::Error::new(source.kind(), e.to_string()),
+			Error::Parse { .. } | Error::Validation { .. } => std::io::Error::new(std::io::ErrorKind::InvalidData, e.to_string()),
+			Error::Solver { .. } => std::io::Error::other(e.to_string()),
+			Error::Timeout { .. } => std::io::Error::new(std::io::ErrorKind::TimedOut, e.to_string()),
+		}
+	}
+}
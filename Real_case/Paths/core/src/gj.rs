@@ -0,0 +1,455 @@
+//! GeoJSON conversion functions
+//!
+//! Converts "proprietary" data (in JSON following the [Spec](https://github.com/WFBFA/Spec)) to GeoJSON, mainly for visualization.
+//! In some cases the conversion is reverisble, in which case GeoJSON to data converters are provided.
+
+use crate::*;
+use data::*;
+use geo::{GeometryCollection, intersects::Intersects};
+use graph::Edge;
+
+use std::{collections::{HashMap, HashSet}, convert::{TryFrom, TryInto}};
+use geojson::*;
+use indexmap::{IndexMap, IndexSet, indexmap};
+
+pub type Nodes = IndexMap<NodeId, Node>;
+
+pub fn roads_to_nodes(g: RoadGraphNodes) -> Nodes {
+	g.nodes.into_iter().map(|n| (n.id.clone(), n)).collect()
+}
+
+pub fn locations_to_geojson(g: &RoadGraphNodes, l: Vec<data::Location>) -> FeatureCollection {
+	FeatureCollection {
+		features: l.into_iter().map(|l| Feature {
+			geometry: Some((&g.dislocate(&l)).try_into().unwrap()),
+			properties: None,
+			bbox: None,
+			foreign_members: None,
+			id: None
+		}).collect(),
+		bbox: None,
+		foreign_members: None,
+	}
+}
+
+/// Shapes of the road segments of a graph, following their geometry
+struct Shapes<'g> {
+	coords: IndexMap<&'g NodeId, (f64, f64)>,
+	segments: IndexMap<SegmentKey, &'g RoadSegment>,
+}
+
+impl<'g> Shapes<'g> {
+	fn new(g: &'g RoadGraph) -> Self {
+		Self {
+			coords: g.nodes.nodes.iter().map(|n| (&n.id, n.coordinates)).collect(),
+			segments: g.roads.iter().map(|r| (segment_key(&r.p1, &r.p2, &r.discriminator), r)).collect(),
+		}
+	}
+	/// Vertices of the segment between 2 nodes, from one to the other - straight if it is unknown or has no geometry
+	fn line(&self, from: &NodeId, to: &NodeId, discriminator: &Option<NodeId>) -> Option<Vec<Vec<f64>>> {
+		let (c1, c2) = (self.coords.get(from)?, self.coords.get(to)?);
+		let geometry: Vec<_> = match self.segments.get(&segment_key(from, to, discriminator)) {
+			Some(r) if &r.p1 == from => r.geometry.clone(),
+			Some(r) => r.geometry.iter().rev().copied().collect(),
+			None => Vec::new(),
+		};
+		Some(std::iter::once(*c1).chain(geometry).chain(std::iter::once(*c2)).map(|(lon, lat)| vec![lon, lat]).collect())
+	}
+}
+
+/// `[lon, lat]` vertices of a path, following the geometry of its segments
+pub fn path_to_line(g: &RoadGraph, path: &[PathSegment]) -> Vec<Vec<f64>> {
+	let shapes = Shapes::new(g);
+	let mut line: Vec<_> = path.first().and_then(|s| shapes.coords.get(&s.node)).map(|c| vec![c.0, c.1]).into_iter().collect();
+	for w in path.windows(2) {
+		line.extend(shapes.line(&w[0].node, &w[1].node, &w[1].discriminator).into_iter().flatten().skip(1));
+	}
+	line
+}
+
+/// LineString of a path, following the geometry of its segments
+pub fn path_to_geojson(g: &RoadGraph, path: Vec<PathSegment>) -> Geometry {
+	Geometry::new(Value::LineString(path_to_line(g, &path)))
+}
+
+/// Paths as LineString features to be replayed as an animation, one per vehicle, timed by their estimated arrival times
+///
+/// Each vertex is timed in ms since epoch - interpolated along segments by distance - both in a `times` property (as per Leaflet.TimeDimension) and as a 4th coordinate (as per Kepler.gl trips).
+///
+/// Arguments:
+/// - `paths`: the paths, with arrival times (see [`report::etas`])
+/// - `start`: when the vehicles start, in seconds since epoch
+///
+/// Returns: the features, or `None` if some path step has no arrival time
+pub fn timed_paths_to_geofeatures(g: &RoadGraph, paths: &Paths, start: f64) -> Option<FeatureCollection> {
+	let shapes = Shapes::new(g);
+	let at = |eta: f64| (start + eta * 3600.0) * 1000.0;
+	let mut features = Vec::new();
+	for (i, path) in paths.iter().enumerate() {
+		let mut line: Vec<Vec<f64>> = Vec::new();
+		let mut times = Vec::new();
+		if let Some((s, c)) = path.first().and_then(|s| Some((s, shapes.coords.get(&s.node)?))) {
+			line.push(vec![c.0, c.1]);
+			times.push(at(s.eta?));
+		}
+		for w in path.windows(2) {
+			let (from, to) = (w[0].eta?, w[1].eta?);
+			let vertices = match shapes.line(&w[0].node, &w[1].node, &w[1].discriminator) {
+				Some(vertices) => vertices,
+				None => continue,
+			};
+			let lengths: Vec<_> = vertices.windows(2).scan(0.0, |d, v| {
+				*d += haversine((v[0][0], v[0][1]), (v[1][0], v[1][1]));
+				Some(*d)
+			}).collect();
+			let total = lengths.last().copied().unwrap_or_default();
+			for (v, d) in vertices.into_iter().skip(1).zip(lengths) {
+				line.push(v);
+				times.push(at(from + (to - from) * if total > 0.0 { d / total } else { 1.0 }));
+			}
+		}
+		let coordinates = line.into_iter().zip(&times).map(|(v, t)| vec![v[0], v[1], 0.0, *t]).collect();
+		features.push(Feature {
+			geometry: Some(Geometry::new(Value::LineString(coordinates))),
+			properties: Some(indexmap!{
+				"vehicle".to_string() => serde_json::Value::from(i),
+				"times".to_string() => serde_json::to_value(&times).unwrap(),
+			}.into_iter().collect()),
+			bbox: None,
+			foreign_members: None,
+			id: None,
+		});
+	}
+	Some(FeatureCollection {
+		features,
+		bbox: None,
+		foreign_members: None,
+	})
+}
+
+/// Length of a line within a polygonal geometry, and its whole length, in meters
+///
+/// The line is cut where it crosses the geometry's boundary, and each piece is in or out as per its middle.
+fn overlap(geometry: &geo::Geometry<f64>, line: &[Vec<f64>]) -> (f64, f64) {
+	let rings: Vec<&geo::LineString<f64>> = match geometry {
+		geo::Geometry::Polygon(p) => std::iter::once(p.exterior()).chain(p.interiors()).collect(),
+		geo::Geometry::MultiPolygon(mp) => mp.0.iter().flat_map(|p| std::iter::once(p.exterior()).chain(p.interiors())).collect(),
+		_ => Vec::new(),
+	};
+	let cross = |(x1, y1): (f64, f64), (x2, y2): (f64, f64)| x1 * y2 - y1 * x2;
+	let (mut inside, mut total) = (0.0, 0.0);
+	for w in line.windows(2) {
+		let (a, b) = ((w[0][0], w[0][1]), (w[1][0], w[1][1]));
+		let ab = (b.0 - a.0, b.1 - a.1);
+		let mut ts = vec![0.0, 1.0];
+		for l in rings.iter().flat_map(|r| r.lines()) {
+			let (c, cd) = ((l.start.x, l.start.y), (l.end.x - l.start.x, l.end.y - l.start.y));
+			let denom = cross(ab, cd);
+			if denom.abs() > f64::EPSILON {
+				let ac = (c.0 - a.0, c.1 - a.1);
+				let (t, u) = (cross(ac, cd) / denom, cross(ac, ab) / denom);
+				if (0.0..=1.0).contains(&t) && (0.0..=1.0).contains(&u) {
+					ts.push(t);
+				}
+			}
+		}
+		ts.sort_by(f64::total_cmp);
+		let length = haversine(a, b);
+		total += length;
+		for t in ts.windows(2).filter(|t| t[1] > t[0]) {
+			let m = (t[0] + t[1]) / 2.0;
+			if geometry.intersects(&geo::Geometry::from(geo::Point::new(a.0 + ab.0 * m, a.1 + ab.1 * m))) {
+				inside += length * (t[1] - t[0]);
+			}
+		}
+	}
+	(inside, total)
+}
+
+/// Reads snow statuses from GeoJSON features with a `snow` depth property
+///
+/// Polygons (e.g. drone imagery footprints) may give per-pixel depth statistics instead, `mean` (or else `max`) being the depth. Each road segment crossing a polygon is assigned its depth proportionally to the length of its geometry within it, so that a segment half surveyed gets half the depth.
+/// Any other geometry assigns its depth to the road segments of nodes it intersects.
+pub fn geofeatures_to_snow(g: &RoadGraph, feat: FeatureCollection) -> data::SnowStatuses {
+	use geo::bounding_rect::BoundingRect;
+	let nodes = spatial::Within::new(g.nodes.nodes.iter().map(|n| (n.coordinates, n)));
+	let shapes = Shapes::new(g);
+	let mut snow = Vec::new();
+	for f in feat.features {
+		let probability = f.property("probability").and_then(|j| j.as_f64()).map(n64);
+		let variance = f.property("variance").and_then(|j| j.as_f64()).map(n64);
+		let observed = f.property("observed").and_then(|j| j.as_f64());
+		let confidence = f.property("confidence").and_then(|j| j.as_f64()).map(n64);
+		let depth = ["snow", "mean", "max"].iter().find_map(|p| f.property(p).and_then(|j| j.as_f64()));
+		if let (Some(depth), Some(geometry)) = (depth, f.geometry) {
+			let geometry: geo::Geometry<f64> = geometry.value.try_into().unwrap();
+			let status = |e: &RoadSegment, depth: f64| SnowStatusElement {
+				p1: e.p1.clone(),
+				p2: e.p2.clone(),
+				discriminator: e.discriminator.clone(),
+				depth: n64(depth),
+				probability,
+				variance,
+				observed,
+				confidence,
+			};
+			match (&geometry, geometry.bounding_rect()) {
+				(geo::Geometry::Polygon(_) | geo::Geometry::MultiPolygon(_), Some(rect)) => {
+					let (min, max) = (rect.min(), rect.max());
+					for e in &g.roads {
+						let line = match shapes.line(&e.p1, &e.p2, &e.discriminator) {
+							// bounding boxes overlap
+							Some(line) if line.iter().any(|c| c[0] >= min.x) && line.iter().any(|c| c[0] <= max.x) && line.iter().any(|c| c[1] >= min.y) && line.iter().any(|c| c[1] <= max.y) => line,
+							_ => continue,
+						};
+						let (inside, total) = overlap(&geometry, &line);
+						if inside > 0.0 {
+							snow.push(status(e, depth * (inside / total).min(1.0)));
+						}
+					}
+				},
+				(_, rect) => {
+					let isect: HashSet<_> = match rect {
+						Some(rect) => nodes.rect(rect.min().x_y(), rect.max().x_y()).filter(|n| geometry.intersects(&geo::Geometry::<f64>::from(**n))).map(|n| &n.id).collect(),
+						None => HashSet::new(),
+					};
+					snow.extend(g.roads.iter().filter(|e| isect.contains(&e.p1) || isect.contains(&e.p2)).map(|e| status(e, depth)));
+				},
+			}
+		}
+	}
+	snow
+}
+
+/// Builds a road graph from LineString (and MultiLineString) features, e.g. drawn or exported from a GIS - one segment per line
+///
+/// Line ends within `tolerance` meters of each other are snapped into the same node, the vertices in between becoming the segment's geometry; lines looping back onto themselves are split in the middle.
+/// Properties are read like OpenStreetMap tags, as most tools export them: `oneway` (`yes`, `-1`...), `sidewalk` (`both`, `left`, `right`) or `sidewalk:left` & `sidewalk:right`, `highway` for the class, `maxspeed`, `incline` and `name` - the road graph's own `class`, `speed` and `grade` taking precedence, and `distance` defaulting to the haversine length along the line.
+/// Distinct lines between the same nodes are told apart by synthesized discriminators, see [`diagnostics::discriminate`].
+///
+/// Returns: the road graph, and the discriminated segments
+pub fn geofeatures_to_roadgraph(feat: FeatureCollection, tolerance: f64) -> (RoadGraph, Vec<diagnostics::Issue>) {
+	let lines: Vec<(Vec<_>, _)> = feat.features.into_iter().flat_map(|f| {
+		let tags: HashMap<String, String> = f.properties.iter().flatten().filter_map(|(k, v)| match v {
+			serde_json::Value::String(s) => Some((k.clone(), s.clone())),
+			serde_json::Value::Bool(b) => Some((k.clone(), if *b { "yes" } else { "no" }.to_string())),
+			serde_json::Value::Number(n) => Some((k.clone(), n.to_string())),
+			_ => None,
+		}).collect();
+		let lines = match f.geometry.map(|g| g.value) {
+			Some(Value::LineString(line)) => vec![line],
+			Some(Value::MultiLineString(lines)) => lines,
+			_ => Vec::new(),
+		};
+		lines.into_iter().map(move |line| (line.into_iter().filter(|c| c.len() >= 2).map(|c| (c[0], c[1])).collect(), tags.clone()))
+	}).collect();
+	let vertices = lines.iter().map(|(line, _)| line.len()).sum::<usize>().max(1);
+	let lat0 = lines.iter().flat_map(|(line, _)| line.iter().map(|c| c.1)).sum::<f64>() / vertices as f64;
+	let mut snapping = Snapping { lat0, tolerance, cells: HashMap::new(), nodes: Vec::new() };
+	let mut roads = Vec::new();
+	for (line, tags) in &lines {
+		let (directed, reversed) = osm::oneway(tags);
+		let (left, right) = osm::sidewalks(tags);
+		let number = |k: &str| tags.get(k).and_then(|v| v.parse::<f64>().ok()).filter(|v| v.is_finite()).map(n64);
+		let grade = number("grade").or_else(|| osm::grade(tags));
+		let mut pieces = vec![&line[..]];
+		while let Some(piece) = pieces.pop() {
+			if piece.len() < 2 {
+				continue;
+			}
+			let (p1, p2) = (snapping.snap(piece[0]), snapping.snap(piece[piece.len() - 1]));
+			if p1 == p2 {
+				if piece.len() > 2 {
+					let mid = piece.len() / 2;
+					pieces.push(&piece[mid..]);
+					pieces.push(&piece[..=mid]);
+				} else {
+					log::warn!("Skipping a line looping onto itself at {}", p1);
+				}
+				continue;
+			}
+			let mut geometry = piece[1..piece.len() - 1].to_vec();
+			if reversed {
+				geometry.reverse();
+			}
+			roads.push(RoadSegment {
+				p1: if reversed { p2.clone() } else { p1.clone() },
+				p2: if reversed { p1 } else { p2 },
+				discriminator: None,
+				directed,
+				distance: number("distance").unwrap_or_else(|| n64(piece.windows(2).map(|w| haversine(w[0], w[1])).sum())),
+				sidewalks: if reversed { (right, left) } else { (left, right) },
+				class: tags.get("class").and_then(|c| serde_json::from_value(serde_json::Value::from(c.as_str())).ok()).or_else(|| osm::class(tags)),
+				priority: tags.get("priority").and_then(|c| serde_json::from_value(serde_json::Value::from(c.as_str())).ok()),
+				grade: grade.map(|g| if reversed { -g } else { g }),
+				speed: number("speed").or_else(|| osm::speed(tags)),
+				name: tags.get("name").cloned(),
+				width: number("width").or_else(|| osm::width(tags)),
+				consumption: number("consumption"),
+				zone: tags.get("zone").cloned(),
+				geometry,
+			});
+		}
+	}
+	let mut roads = RoadGraph { roads, nodes: RoadGraphNodes { nodes: snapping.nodes }, restrictions: Vec::new(), depots: Vec::new() };
+	let issues = diagnostics::discriminate(&mut roads);
+	(roads, issues)
+}
+
+/// Nodes snapped from line ends, indexed in a grid of `tolerance`-sized cells
+struct Snapping {
+	lat0: f64,
+	tolerance: f64,
+	cells: HashMap<(i64, i64), Vec<usize>>,
+	nodes: Vec<Node>,
+}
+
+impl Snapping {
+	/// Node at coordinates: the closest one within tolerance, or a new one
+	fn snap(&mut self, c: (f64, f64)) -> NodeId {
+		use graph::mapmatch::{project, distance};
+		let p = project(c, self.lat0);
+		let size = self.tolerance.max(1e-3);
+		let cell = ((p.0 / size).floor() as i64, (p.1 / size).floor() as i64);
+		let closest = (cell.0 - 1..=cell.0 + 1).flat_map(|x| (cell.1 - 1..=cell.1 + 1).map(move |y| (x, y)))
+			.flat_map(|k| self.cells.get(&k).into_iter().flatten().copied())
+			.map(|i| (i, distance(project(self.nodes[i].coordinates, self.lat0), p)))
+			.filter(|(_, d)| *d <= self.tolerance)
+			.min_by(|a, b| a.1.total_cmp(&b.1).then(a.0.cmp(&b.0)));
+		match closest {
+			Some((i, _)) => self.nodes[i].id.clone(),
+			None => {
+				let id: NodeId = format!("n{}", self.nodes.len()).into();
+				self.cells.entry(cell).or_default().push(self.nodes.len());
+				self.nodes.push(Node { id: id.clone(), coordinates: c });
+				id
+			},
+		}
+	}
+}
+
+pub fn snows_to_geofeatures(g: &RoadGraph, snow: data::SnowStatuses) -> FeatureCollection {
+	let shapes = Shapes::new(g);
+	FeatureCollection {
+		features: snow.into_iter().map(|s| Feature {
+			geometry: Some(Geometry::new(Value::LineString(shapes.line(&s.p1, &s.p2, &s.discriminator).unwrap()))),
+			properties: Some(indexmap!{ "snow".to_string() => serde_json::to_value(s.depth).unwrap() }.into_iter()
+				.chain(s.probability.map(|p| ("probability".to_string(), serde_json::to_value(p).unwrap())))
+				.chain(s.variance.map(|v| ("variance".to_string(), serde_json::to_value(v).unwrap())))
+				.collect()),
+			bbox: None,
+			foreign_members: None,
+			id: None,
+		}).collect(),
+		bbox: None,
+		foreign_members: None,
+	}
+}
+
+/// Reconstructs paths from LineString features (one path per feature, in order), e.g. edited manually in a GIS
+///
+/// Each vertex is snapped to the closest node within `tolerance` meters (vertices farther from any node are dropped).
+/// Successive nodes are linked by the (shortest) road segment that can be driven from one to the other if there is one, and by the shortest path between them otherwise.
+pub fn geofeatures_to_paths(g: &IndexedRoadGraph, feat: FeatureCollection, tolerance: f64) -> data::Paths {
+	use graph::mapmatch::{project, distance};
+	let (light, g) = (&g.light, &g.roads);
+	let lat0 = g.nodes.nodes.iter().map(|n| n.coordinates.1).sum::<f64>() / g.nodes.nodes.len().max(1) as f64;
+	let coords: Vec<_> = g.nodes.nodes.iter().map(|n| project(n.coordinates, lat0)).collect();
+	let snap = |c: &Vec<f64>| {
+		let p = project((c[0], c[1]), lat0);
+		let (i, d) = coords.iter().map(|c| distance(p, *c)).enumerate().min_by(|(_, a), (_, b)| a.total_cmp(b))?;
+		if d > tolerance {
+			log::warn!("No node within {}m of ({}, {}), skipping", tolerance, c[0], c[1]);
+			None
+		} else {
+			Some(i)
+		}
+	};
+	let mut paths = Vec::new();
+	for f in feat.features {
+		let line = match f.geometry.map(|g| g.value) {
+			Some(Value::LineString(line)) => line,
+			_ => continue,
+		};
+		let mut nodes: Vec<_> = line.iter().filter_map(snap).collect();
+		nodes.dedup();
+		let mut path = Vec::new();
+		if let Some(first) = nodes.first() {
+			path.push(PathSegment { node: g.nodes.nodes[*first].id.clone(), discriminator: None, service: None, reload: false, eta: None });
+		}
+		for w in nodes.windows(2) {
+			let direct = light.get_edges_between(w[0], w[1]).into_iter().filter(|e| e.is_outgoing::<true>(w[0])).min_by_key(|e| g.roads[e.index].distance);
+			let hops: Vec<_> = match direct {
+				Some(e) => vec![(e.index, w[1])],
+				None => match light.pathfind::<N64, _, true>(w[0], w[1], |e| Some(g.roads[e.index].distance)) {
+					Some(es) => graph::Graph::<usize, (f64, f64), SegmentEdge>::path_to_nodes(es.into_iter(), w[0]).into_iter().filter_map(|(n, e)| e.map(|e| (e.index, n))).collect(),
+					None => {
+						log::warn!("No path between {} and {}, path broken", g.nodes.nodes[w[0]].id, g.nodes.nodes[w[1]].id);
+						Vec::new()
+					},
+				},
+			};
+			path.extend(hops.into_iter().map(|(e, n)| PathSegment { node: g.nodes.nodes[n].id.clone(), discriminator: g.roads[e].discriminator.clone(), service: None, reload: false, eta: None }));
+		}
+		paths.push(path);
+	}
+	paths
+}
+
+/// Collects features out of any GeoJSON (a lone geometry becomes a feature)
+pub fn geojson_to_geofeatures(gj: GeoJson) -> FeatureCollection {
+	match gj {
+		GeoJson::FeatureCollection(fc) => fc,
+		GeoJson::Feature(f) => FeatureCollection { features: vec![f], bbox: None, foreign_members: None },
+		GeoJson::Geometry(g) => FeatureCollection { features: vec![Feature { geometry: Some(g), properties: None, bbox: None, foreign_members: None, id: None }], bbox: None, foreign_members: None },
+	}
+}
+
+/// Geometries of features, e.g. polygons delimiting a region
+pub fn geofeatures_to_geometries(feat: FeatureCollection) -> Vec<geo::Geometry<f64>> {
+	feat.features.into_iter().filter_map(|f| f.geometry).filter_map(|g| g.value.try_into().ok()).collect()
+}
+
+/// Map diff of two plans: a LineString feature for each segment traversed by either, with its `plan` property telling by which (`a`, `b` or `both`)
+pub fn coverage_diff_to_geofeatures(g: &RoadGraph, a: &IndexSet<SegmentKey>, b: &IndexSet<SegmentKey>) -> FeatureCollection {
+	let shapes = Shapes::new(g);
+	FeatureCollection {
+		features: a.union(b).filter_map(|k| {
+			let line = shapes.line(&k.0, &k.1, &k.2)?;
+			let plan = match (a.contains(k), b.contains(k)) {
+				(true, true) => "both",
+				(true, false) => "a",
+				_ => "b",
+			};
+			Some(Feature {
+				geometry: Some(Geometry::new(Value::LineString(line))),
+				properties: Some(indexmap!{ "plan".to_string() => serde_json::Value::from(plan) }.into_iter().collect()),
+				bbox: None,
+				foreign_members: None,
+				id: None,
+			})
+		}).collect(),
+		bbox: None,
+		foreign_members: None,
+	}
+}
+
+/// Snowy segments left uncovered by a plan, as LineString features styled red (`stroke` as per the simplestyle spec, rendered by geojson.io), with their `snow` depth and `side`
+pub fn uncovered_to_geofeatures(g: &RoadGraph, uncovered: &[legality::Uncovered]) -> FeatureCollection {
+	let shapes = Shapes::new(g);
+	FeatureCollection {
+		features: uncovered.iter().filter_map(|u| Some(Feature {
+			geometry: Some(Geometry::new(Value::LineString(shapes.line(&u.p1, &u.p2, &u.discriminator)?))),
+			properties: Some(indexmap!{
+				"stroke".to_string() => serde_json::Value::from("#ff0000"),
+				"stroke-width".to_string() => serde_json::Value::from(4),
+				"snow".to_string() => serde_json::to_value(u.depth).unwrap(),
+			}.into_iter().chain(u.side.map(|s| ("side".to_string(), serde_json::to_value(s).unwrap()))).collect()),
+			bbox: None,
+			foreign_members: None,
+			id: None,
+		})).collect(),
+		bbox: None,
+		foreign_members: None,
+	}
+}
@@ -0,0 +1,18 @@
+//! GPX export of paths, for loading routes onto GPS units
+
+use crate::*;
+use data::*;
+
+/// Converts paths into a GPX document: a track per vehicle, of a single segment following the geometry of the road segments
+pub fn paths_to_gpx(g: &RoadGraph, paths: &Paths) -> String {
+	let mut gpx = String::from("<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n<gpx version=\"1.1\" creator=\"wfbfa-solver-cli\" xmlns=\"http://www.topografix.com/GPX/1/1\">\n");
+	for (i, path) in paths.iter().enumerate() {
+		gpx.push_str(&format!("\t<trk>\n\t\t<name>Vehicle {}</name>\n\t\t<trkseg>\n", i));
+		for c in gj::path_to_line(g, path) {
+			gpx.push_str(&format!("\t\t\t<trkpt lat=\"{}\" lon=\"{}\"/>\n", c[1], c[0]));
+		}
+		gpx.push_str("\t\t</trkseg>\n\t</trk>\n");
+	}
+	gpx.push_str("</gpx>\n");
+	gpx
+}
@@ -0,0 +1,2293 @@
+//! Data structures and algorithms to operate on mixed generic graphs.
+//! 
+//! Historically, this was built after the algorithms in `brr` proved themselves worthy,
+//! but the implementation was not sufficiently generic to be re-used for road, and then sidewalk, plowing.
+
+use std::hash::Hash;
+
+use indexmap::IndexMap;
+use priority_queue::PriorityQueue;
+
+/// Hasher with fixed keys, so that maps & sets iterate in the same order on every run, and seeded solves are reproducible
+pub type FixedState = std::hash::BuildHasherDefault<std::collections::hash_map::DefaultHasher>;
+pub type HashMap<K, V> = std::collections::HashMap<K, V, FixedState>;
+pub type HashSet<T> = std::collections::HashSet<T, FixedState>;
+
+/// Number of nodes from which strongly connected components are detected in parallel
+pub const PARALLEL_SCC_NODES: usize = 100_000;
+
+/// An edge of a graph
+///
+/// Type Parameters:
+/// - `NId`: node id
+pub trait Edge<NId: Clone + Copy + Hash + Eq> : Clone + Hash + PartialEq + Eq {
+	/// First (starting) vertex
+	fn p1(&self) -> NId;
+	/// Second (ending) vertex
+	fn p2(&self) -> NId;
+	/// Whether the edge is directed, i.e. can only be traversed `p1`→`p2`
+	fn directed(&self) -> bool;
+	/// Whether the edge is cyclic, i.e. goes from a vertex to itself
+	fn is_cyclic(&self) -> bool {
+		self.p1() == self.p2()
+	}
+	/// Assuming `id` is one end of the edge, what is the other end
+	fn other(&self, id: NId) -> NId {
+		if id == self.p1() {
+			self.p2()
+		} else {
+			self.p1()
+		}
+	}
+	/// Whether one can traverse the edge and end up in the specified node
+	///
+	/// Type Parameters:
+	/// - `DIRESPECT`: whether the directionality of the edge is respected
+	fn is_incoming<const DIRESPECT: bool>(&self, id: NId) -> bool {
+		id == self.p2() || (id == self.p1() && (!DIRESPECT || !self.directed()))
+	}
+	/// Whether one can traverse the edge starting from the specified node
+	///
+	/// Type Parameters:
+	/// - `DIRESPECT`: whether the directionality of the edge is respected
+	fn is_outgoing<const DIRESPECT: bool>(&self, id: NId) -> bool {
+		id == self.p1() || (id == self.p2() && (!DIRESPECT || !self.directed()))
+	}
+}
+
+/// A node with a geographical position
+pub trait Positioned {
+	/// Position, as `(lon, lat)`
+	fn pos(&self) -> (f64, f64);
+}
+
+impl Positioned for (f64, f64) {
+	fn pos(&self) -> (f64, f64) {
+		*self
+	}
+}
+
+/// Metadata payload of a [`WeightedEdge`]
+pub trait EdgeMeta: Clone {
+	/// What tells apart edges between the same nodes, with the same discriminator
+	type Key: Hash + Eq;
+	/// Key of the edge, on top of its ends and discriminator
+	fn key(&self) -> Self::Key;
+	/// Whether the edge is directed
+	fn directed(&self) -> bool;
+}
+
+impl EdgeMeta for () {
+	type Key = ();
+	fn key(&self) {}
+	fn directed(&self) -> bool {
+		false
+	}
+}
+
+/// An edge with a weight, and a metadata payload for all else
+///
+/// Type Parameters:
+/// - `NId`: node id
+/// - `Meta`: metadata payload
+#[derive(Clone, Debug, serde::Serialize, serde::Deserialize)]
+pub struct WeightedEdge<NId, Meta> {
+	pub p1: NId,
+	pub p2: NId,
+	/// node telling apart edges between the same nodes, if any
+	pub discriminator: Option<NId>,
+	pub weight: crate::N64,
+	pub meta: Meta,
+}
+
+impl<NId: PartialEq, Meta: EdgeMeta> PartialEq for WeightedEdge<NId, Meta> {
+	fn eq(&self, other: &Self) -> bool {
+		self.p1 == other.p1 && self.p2 == other.p2 && self.discriminator == other.discriminator && self.meta.key() == other.meta.key()
+	}
+}
+
+impl<NId: Eq, Meta: EdgeMeta> Eq for WeightedEdge<NId, Meta> {}
+
+impl<NId: Hash, Meta: EdgeMeta> Hash for WeightedEdge<NId, Meta> {
+	fn hash<H: std::hash::Hasher>(&self, h: &mut H) {
+		(&self.p1, &self.p2, &self.discriminator, self.meta.key()).hash(h)
+	}
+}
+
+impl<NId: Clone + Copy + Hash + Eq, Meta: EdgeMeta> Edge<NId> for WeightedEdge<NId, Meta> {
+	fn p1(&self) -> NId {
+		self.p1
+	}
+	fn p2(&self) -> NId {
+		self.p2
+	}
+	fn directed(&self) -> bool {
+		self.meta.directed()
+	}
+}
+
+/// Cached paths (or their absence) by `(from, to, weight profile)`, and their keys in the order they were cached
+type CachedPaths<NId, E> = (HashMap<(NId, NId, u64), Option<Vec<E>>>, std::collections::VecDeque<(NId, NId, u64)>);
+
+/// Cache of the shortest paths between nodes of a graph, by weight profile, shared across threads - the oldest evicted when full
+///
+/// Type Parameters:
+/// - `NId`: node id
+/// - `E`: edge type
+#[derive(Debug)]
+pub struct DistanceCache<NId, E> {
+	/// maximum number of paths cached (none if 0)
+	capacity: usize,
+	paths: std::sync::Mutex<CachedPaths<NId, E>>,
+}
+
+impl<NId, E> DistanceCache<NId, E>
+where
+	NId: Clone + Copy + Hash + Eq,
+	E: Edge<NId>,
+{
+	/// Constructs an empty cache, of up to `capacity` paths
+	pub fn new(capacity: usize) -> Self {
+		Self { capacity, paths: Default::default() }
+	}
+	/// Shortest path between 2 nodes, from the cache or found (and cached)
+	///
+	/// Arguments:
+	/// - `g`: the graph, always the same
+	/// - `n1`: first node
+	/// - `n2`: second node
+	/// - `profile`: weight profile of the path, as paths by different weights differ
+	/// - `find`: finds the path, if not cached
+	///
+	/// Returns: edges path from `n1` to `n2`, if such exists
+	pub fn path<'g, N>(&self, g: &'g Graph<NId, N, E>, n1: NId, n2: NId, profile: u64, find: impl FnOnce() -> Option<Vec<&'g E>>) -> Option<Vec<&'g E>> {
+		if self.capacity == 0 {
+			return find();
+		}
+		let key = (n1, n2, profile);
+		if let Some(path) = self.paths.lock().unwrap().0.get(&key) {
+			let mut at = n1;
+			return path.as_ref().map(|path| path.iter().map(|e| {
+				let e = g.get_edges(at).get(e).unwrap();
+				at = e.other(at);
+				e
+			}).collect());
+		}
+		let path = find();
+		let mut cache = self.paths.lock().unwrap();
+		let (paths, order) = &mut *cache;
+		if paths.len() >= self.capacity {
+			if let Some(oldest) = order.pop_front() {
+				paths.remove(&oldest);
+			}
+		}
+		if paths.insert(key, path.as_ref().map(|path| path.iter().map(|e| (*e).clone()).collect())).is_none() {
+			order.push_back(key);
+		}
+		path
+	}
+}
+
+/// A graph
+///
+/// Type Parameters:
+/// - `NId`: (lightweight) node id type
+/// - `N`: Node type (can contain arbitrary node information)
+/// - `E`: Edge type
+#[derive(Clone, Debug, serde::Serialize, serde::Deserialize)]
+pub struct Graph<NId, N, E> 
+where 
+	NId: Clone + Copy + Hash + Eq,
+	E: Edge<NId>,
+{
+	nodes: HashMap<NId, N>,
+	edges: IndexMap<NId, HashSet<E>>,
+	/// An always empty set of edges (useful for [`get_edges`] on a non-existing node)
+	#[serde(skip, default = "HashSet::default")]
+	_empty: HashSet<E>,
+}
+
+impl<NId, N, E> Default for Graph<NId, N, E>
+where 
+	NId: Clone + Copy + Hash + Eq,
+	E: Edge<NId>,
+{
+	fn default() -> Self {
+		Self {
+			nodes: Default::default(),
+			edges: Default::default(),
+			_empty: Default::default(),
+		}
+	}
+}
+
+impl<NId, N, E> Graph<NId, N, E>
+where 
+	NId: Clone + Copy + Hash + Eq,
+	E: Edge<NId>,
+{
+	/// Constructs new graph with `nodes` and `edges`
+	pub fn new(nodes: HashMap<NId, N>, edges: IndexMap<NId, HashSet<E>>) -> Self {
+		Self { nodes, edges, ..Default::default() }
+	}
+	/// Get node by id
+	pub fn get_node(&self, n: NId) -> Option<&N> {
+		self.nodes.get(&n)
+	}
+	/// Get all edges of a node
+	pub fn get_edges(&self, n: NId) -> &HashSet<E> {
+		self.edges.get(&n).unwrap_or(&self._empty)
+	}
+	/// Whether the given node has no edges
+	pub fn is_orphan(&self, n: NId) -> bool {
+		self.get_edges(n).is_empty()
+	}
+	/// Get all edges between 2 nodes
+	pub fn get_edges_between(&self, n1: NId, n2: NId) -> Vec<&E> {
+		self.edges.get(&n1).iter().flat_map(|es| es.iter()).filter(|e| e.other(n1) == n2).collect()
+	}
+	/// Get all nodes
+	pub fn nodes(&self) -> impl Iterator<Item=(NId,&N)> {
+		self.nodes.iter().map(|(id, n)| (*id, n))
+	}
+	/// Get all edges
+	pub fn edges(&self) -> impl Iterator<Item=&E> {
+		self.edges.iter().flat_map(|(n, es)| es.iter().filter(move |e| e.is_cyclic() || e.p1() == *n))
+	}
+	/// Number of nodes
+	pub fn node_count(&self) -> usize {
+		self.nodes.len()
+	}
+	/// Number of edges
+	pub fn edge_count(&self) -> usize {
+		self.edges().count()
+	}
+	/// Whether the graph is empty
+	pub fn is_empty(&self) -> bool {
+		self.nodes.is_empty()
+	}
+	/// Whether the graph has no edges
+	pub fn is_edge_empty(&self) -> bool {
+		self.edges.values().all(HashSet::is_empty)
+	}
+	/// Adds (or replaces) a node
+	pub fn add_node(&mut self, id: NId, n: N) -> Option<N> {
+		self.nodes.insert(id, n)
+	}
+	/// Adds an edge
+	pub fn add_edge(&mut self, e: E) -> bool {
+		if self.nodes.contains_key(&e.p1()) && self.nodes.contains_key(&e.p2()) {
+			if !e.is_cyclic() {
+				self.edges.entry(e.p1()).or_default().insert(e.clone());
+			}
+			self.edges.entry(e.p2()).or_default().insert(e);
+			true
+		} else {
+			false
+		}
+	}
+	/// Removes an edge
+	pub fn remove_edge(&mut self, e: &E) -> bool {
+		if self.nodes.contains_key(&e.p1()) && self.nodes.contains_key(&e.p2()) {
+			if !e.is_cyclic() {
+				self.edges.entry(e.p1()).or_default().remove(e);
+			}
+			self.edges.entry(e.p2()).or_default().remove(e);
+			true
+		} else {
+			false
+		}
+	}
+	/// Removes a node, and its edges
+	///
+	/// Returns: the node, if it was in the graph
+	pub fn remove_node(&mut self, n: NId) -> Option<N> {
+		for e in self.edges.shift_remove(&n).into_iter().flatten() {
+			if let Some(es) = self.edges.get_mut(&e.other(n)).filter(|_| !e.is_cyclic()) {
+				es.remove(&e);
+			}
+		}
+		self.nodes.remove(&n)
+	}
+	/// Retains only the nodes (and edges) matching the predicate
+	pub fn retain_nodes(&mut self, f: impl Fn(NId) -> bool){
+		self.nodes.retain(|n, _| f(*n));
+		self.retain_nodes_edges(f);
+	}
+	/// Retains only the edges of nodes matching the predicate
+	pub fn retain_nodes_edges(&mut self, f: impl Fn(NId) -> bool){
+		self.edges.retain(|n, _| f(*n));
+		for (u, es) in &mut self.edges {
+			es.retain(|e| f(e.other(*u)));
+		}
+	}
+	/// Find all edges going from one region to another
+	///
+	/// Arguments:
+	/// - `DIRESPECT`: whether the directionality of edges is respected
+	/// - `n1`: nodes of the first region
+	/// - `n2`: nodes of the second region
+	///
+	/// Returns: nodes `n1` and `n2` in the 1st and 2nd regions resp and the edge from `n1` to `n2`
+	pub fn get_edges_between_regions<const DIRESPECT: bool>(&self, n1: &HashSet<NId>, n2: &HashSet<NId>) -> Vec<(NId, NId, &E)> {
+		let mut es = Vec::new();
+		for n1 in n1.iter().cloned() {
+			for n2 in n2.iter().cloned() {
+				for e in self.get_edges_between(n1, n2) {
+					if e.is_outgoing::<DIRESPECT>(n1) && e.is_incoming::<DIRESPECT>(n2) {
+						es.push((n1, n2, e));
+					}
+				}
+			}
+		}
+		es
+	}
+	/// Find shortest path between 2 points, edge-weighted by a function
+	///
+	/// Currently uses heap-optimized Dijkstra's shortest path algorithm.
+	///
+	/// Type Parameters:
+	/// - `Weight`: weight of an edge
+	/// - `DIRESPECT`: whether the directionality of edges is respected
+	///
+	/// Arguments:
+	/// - `n1`: first node
+	/// - `n2`: second node
+	/// - `weight`: filtering weight function - returns the weight of the edge, iff it can be traversed
+	///
+	/// Returns: edges path from `n1` to `n2`, if such exists
+	pub fn pathfind<Weight, FW, const DIRESPECT: bool>(&self, n1: NId, n2: NId, weight: FW) -> Option<Vec<&E>>
+	where
+		Weight: Clone + Copy + Ord + Default + std::ops::Add<Weight, Output = Weight> + std::ops::Neg<Output = Weight>,
+		FW: Fn(&E) -> Option<Weight>,
+	{
+		let mut dp: HashMap<NId, (Weight, Option<&E>)> = HashMap::default();
+		dp.insert(n1.clone(), (Weight::default(), None));
+		let mut q = PriorityQueue::new();
+		q.push(n1.clone(), Weight::default());
+		while let Some((u, _)) = q.pop() {
+			if u == n2 {
+				let mut path = Vec::new();
+				let mut v = u;
+				while let Some((_, Some(e))) = dp.get(&v) {
+					v = e.other(v);
+					path.push(e.clone());
+				}
+				path.reverse();
+				return Some(path);
+			}
+			let d = dp.get(&u).unwrap().0;
+			for e in self.get_edges(u) {
+				if !DIRESPECT || !e.directed() || e.p1() == u {
+					if let Some(ed) = weight(e){
+						let v = e.other(u);
+						let d = d + ed;
+						if dp.get(&v).is_none_or(|(vd, _)| vd > &d) {
+							dp.insert(v.clone(), (d, Some(e)));
+							q.push(v.clone(), -d);
+						}
+					}
+				}
+			}
+		}
+		None
+	}
+	/// Find shortest path between 2 points, edge-weighted by a function, guided by a heuristic
+	///
+	/// Uses heap-optimized A*: nodes are explored by their distance from `n1` plus their estimated distance to `n2`, so that fewer are than by Dijkstra's.
+	///
+	/// Type Parameters:
+	/// - `Weight`: weight of an edge
+	/// - `DIRESPECT`: whether the directionality of edges is respected
+	///
+	/// Arguments:
+	/// - `n1`: first node
+	/// - `n2`: second node
+	/// - `weight`: filtering weight function - returns the weight of the edge, iff it can be traversed
+	/// - `heuristic`: estimated distance from a node to `n2` - never over, for the path to be the shortest (e.g. the straight-line distance between positioned nodes)
+	///
+	/// Returns: edges path from `n1` to `n2`, if such exists
+	pub fn pathfind_astar<Weight, FW, FH, const DIRESPECT: bool>(&self, n1: NId, n2: NId, weight: FW, heuristic: FH) -> Option<Vec<&E>>
+	where
+		Weight: Clone + Copy + Ord + Default + std::ops::Add<Weight, Output = Weight> + std::ops::Neg<Output = Weight>,
+		FW: Fn(&E) -> Option<Weight>,
+		FH: Fn(NId) -> Weight,
+	{
+		let mut dp: HashMap<NId, (Weight, Option<&E>)> = HashMap::default();
+		dp.insert(n1, (Weight::default(), None));
+		let mut q = PriorityQueue::new();
+		q.push(n1, -heuristic(n1));
+		while let Some((u, _)) = q.pop() {
+			if u == n2 {
+				let mut path = Vec::new();
+				let mut v = u;
+				while let Some((_, Some(e))) = dp.get(&v) {
+					v = e.other(v);
+					path.push(*e);
+				}
+				path.reverse();
+				return Some(path);
+			}
+			let d = dp.get(&u).unwrap().0;
+			for e in self.get_edges(u) {
+				if e.is_outgoing::<DIRESPECT>(u) {
+					if let Some(ed) = weight(e) {
+						let v = e.other(u);
+						let d = d + ed;
+						if dp.get(&v).is_none_or(|(vd, _)| *vd > d) {
+							dp.insert(v, (d, Some(e)));
+							q.push(v, -(d + heuristic(v)));
+						}
+					}
+				}
+			}
+		}
+		None
+	}
+	/// Find shortest path between 2 nodes, edge-weighted by a function, making allowed turns only
+	///
+	/// Currently uses heap-optimized Dijkstra's shortest path algorithm, over nodes along with the edge they are reached by.
+	///
+	/// Type Parameters:
+	/// - `Weight`: weight of an edge
+	/// - `DIRESPECT`: whether the directionality of edges is respected
+	///
+	/// Arguments:
+	/// - `n1`: first node
+	/// - `e1`: edge `n1` is reached by, if any
+	/// - `n2`: second node
+	/// - `e2`: edge to turn into from `n2`, if any
+	/// - `weight`: filtering weight function - returns the weight of the edge, iff it can be traversed
+	/// - `turn`: filtering turn function - returns the weight of turning at a node from an edge into another, iff it is allowed
+	///
+	/// Returns: edges path from `n1` to `n2`, if such exists (empty if `e1` can turn into `e2` right away)
+	pub fn pathfind_turns<'a, Weight, FW, FT, const DIRESPECT: bool>(&'a self, n1: NId, e1: Option<&'a E>, n2: NId, e2: Option<&E>, weight: FW, turn: FT) -> Option<Vec<&'a E>>
+	where
+		Weight: Clone + Copy + Ord + Default + std::ops::Add<Weight, Output = Weight> + std::ops::Neg<Output = Weight>,
+		FW: Fn(&E) -> Option<Weight>,
+		FT: Fn(NId, &E, &E) -> Option<Weight>,
+	{
+		let mut dp: HashMap<_, (Weight, Option<_>)> = HashMap::default();
+		dp.insert((n1, e1), (Weight::default(), None));
+		let mut q = PriorityQueue::new();
+		q.push((n1, e1), Weight::default());
+		while let Some(((u, eu), _)) = q.pop() {
+			if u == n2 && eu.zip(e2).is_none_or(|(eu, e2)| turn(u, eu, e2).is_some()) {
+				let mut path = Vec::new();
+				let mut s = (u, eu);
+				while let Some((_, Some(prev))) = dp.get(&s) {
+					path.push(s.1.unwrap());
+					s = *prev;
+				}
+				path.reverse();
+				return Some(path);
+			}
+			let d = dp.get(&(u, eu)).unwrap().0;
+			for e in self.get_edges(u) {
+				if !DIRESPECT || !e.directed() || e.p1() == u {
+					let td = match eu {
+						Some(eu) => turn(u, eu, e),
+						None => Some(Weight::default()),
+					};
+					if let (Some(ed), Some(td)) = (weight(e), td) {
+						let v = (e.other(u), Some(e));
+						let d = d + td + ed;
+						if dp.get(&v).is_none_or(|(vd, _)| vd > &d) {
+							dp.insert(v, (d, Some((u, eu))));
+							q.push(v, -d);
+						}
+					}
+				}
+			}
+		}
+		None
+	}
+	/// Find shortest path between 2 regions, edge-weighted by a function
+	///
+	/// Currently uses heap-optimized Dijkstra's shortest path algorithm.
+	///
+	/// Type Parameters:
+	/// - `Weight`: weight of an edge
+	/// - `DIRESPECT`: whether the directionality of edges is respected
+	///
+	/// Arguments:
+	/// - `n1`: nodes of the first region
+	/// - `n2`: nodes of the second region
+	/// - `weight`: filtering weight function - returns the weight of the edge, iff it can be traversed
+	///
+	/// Returns: nodes `n1` and `n2` in the 1st and 2nd regions resp and the edges path from `n1` to `n2`, if such exists
+	pub fn pathfind_regions<Weight, FW, const DIRESPECT: bool>(&self, n1: &HashSet<NId>, n2: &HashSet<NId>, weight: FW) -> Option<(NId, NId, Vec<&E>)>
+	where
+		Weight: Clone + Copy + Ord + Default + std::ops::Add<Weight, Output = Weight> + std::ops::Neg<Output = Weight>,
+		FW: Fn(&E) -> Option<Weight>,
+	{
+		if n1.is_empty() || n2.is_empty() {
+			return None;
+		}
+		let mut dp: HashMap<NId, (Weight, Option<&E>)> = HashMap::default();
+		let mut q = PriorityQueue::new();
+		for n1 in n1 {
+			dp.insert(n1.clone(), (Weight::default(), None));
+			q.push(n1.clone(), Weight::default());
+		}
+		while let Some((u, _)) = q.pop() {
+			if n2.contains(&u) {
+				let mut path = Vec::new();
+				let mut v = u;
+				while let Some((_, Some(e))) = dp.get(&v) {
+					v = e.other(v);
+					path.push(e.clone());
+				}
+				path.reverse();
+				return Some((v, u, path));
+			}
+			let d = dp.get(&u).unwrap().0;
+			for e in self.get_edges(u) {
+				if !DIRESPECT || !e.directed() || e.p1() == u {
+					if let Some(ed) = weight(e){
+						let v = e.other(u);
+						let d = d + ed;
+						if dp.get(&v).is_none_or(|(vd, _)| vd > &d) {
+							dp.insert(v.clone(), (d, Some(e)));
+							q.push(v.clone(), -d);
+						}
+					}
+				}
+			}
+		}
+		None
+	}
+	/// Find shortest distances from a region to all nodes within a bound, edge-weighted by a function
+	///
+	/// Currently uses heap-optimized Dijkstra's shortest path algorithm.
+	///
+	/// Type Parameters:
+	/// - `Weight`: weight of an edge
+	/// - `DIRESPECT`: whether the directionality of edges is respected
+	///
+	/// Arguments:
+	/// - `n1`: nodes of the region
+	/// - `bound`: maximum distance
+	/// - `weight`: filtering weight function - returns the weight of the edge, iff it can be traversed
+	///
+	/// Returns: distance to every node within the bound
+	pub fn distances<Weight, FW, const DIRESPECT: bool>(&self, n1: &HashSet<NId>, bound: Weight, weight: FW) -> HashMap<NId, Weight>
+	where
+		Weight: Clone + Copy + Ord + Default + std::ops::Add<Weight, Output = Weight> + std::ops::Neg<Output = Weight>,
+		FW: Fn(&E) -> Option<Weight>,
+	{
+		let mut dp: HashMap<NId, Weight> = HashMap::default();
+		let mut q = PriorityQueue::new();
+		for n1 in n1 {
+			dp.insert(*n1, Weight::default());
+			q.push(*n1, Weight::default());
+		}
+		while let Some((u, _)) = q.pop() {
+			let d = dp[&u];
+			for e in self.get_edges(u) {
+				if e.is_outgoing::<DIRESPECT>(u) {
+					if let Some(ed) = weight(e) {
+						let v = e.other(u);
+						let d = d + ed;
+						if d <= bound && dp.get(&v).is_none_or(|vd| *vd > d) {
+							dp.insert(v, d);
+							q.push(v, -d);
+						}
+					}
+				}
+			}
+		}
+		dp
+	}
+	/// Detect all strongly connected components in the graph
+	///
+	/// Uses unrecursed Tarjan's SCC algorithm, or the parallel forward-backward one on graphs of [`PARALLEL_SCC_NODES`] nodes or more.
+	///
+	/// Arguments:
+	/// - `DIRESPECT`: whether the directionality of edges is respected
+	/// - `ORPHANS`: whether orphan nodes are included as SCCs
+	pub fn strongly_connected_components<const DIRESPECT: bool, const ORPHANS: bool>(&self) -> Vec<HashSet<NId>>
+	where NId: std::fmt::Display + Send + Sync, N: Sync, E: Sync {
+		if self.nodes.len() >= PARALLEL_SCC_NODES {
+			self.forward_backward_sccs::<DIRESPECT, ORPHANS>()
+		} else {
+			self.tarjan_sccs::<DIRESPECT, ORPHANS>()
+		}
+	}
+	/// Detect all strongly connected components in the graph, by Tarjan's algorithm (unrecursed)
+	fn tarjan_sccs<const DIRESPECT: bool, const ORPHANS: bool>(&self) -> Vec<HashSet<NId>> {
+		use std::cmp::min;
+		let mut sccs = Vec::new();
+		let mut index = 0usize;
+		let mut stack = Vec::new();
+		let mut inf: HashMap<_, (bool, usize, usize)> = HashMap::default();
+		let mut q = Vec::new();
+		for u in self.nodes.keys().copied() {
+			if self.is_orphan(u) && !ORPHANS {
+				continue;
+			}
+			if !inf.contains_key(&u) {
+				q.push((u, self.get_edges(u).iter().collect::<Vec<_>>(), false));
+				// "strongconnect"
+				'unrec: while let Some((u, es, jr)) = q.last_mut() {
+					let u = *u;
+					// first visit
+					if let std::collections::hash_map::Entry::Vacant(slot) = inf.entry(u) {
+						stack.push(u);
+						slot.insert((true, index, index));
+						index += 1;
+					}
+					// look at successors
+					while let Some(e) = es.last() {
+						if e.is_outgoing::<DIRESPECT>(u) {
+							let v = e.other(u);
+							let iv = inf.get(&v).cloned();
+							let (.., ull) = inf.get_mut(&u).unwrap();
+							match iv {
+								// v has not yet been visited
+								None => {
+									*jr = true;
+									q.push((v, self.get_edges(v).iter().collect::<Vec<_>>(), false));
+									continue 'unrec;
+								},
+								// v was just visited
+								Some((.., vll)) if *jr => {
+									*ull = min(*ull, vll);
+									*jr = false;
+								},
+								// v is in current scc
+								Some((true, vidx, ..)) => {
+									*ull = min(*ull, vidx)
+								},
+								_ => {}
+							}
+						}
+						es.pop();
+					}
+					// generate scc
+					let (_, idx, ll) = inf.get(&u).cloned().unwrap();
+					if idx == ll {
+						let mut scc = HashSet::default();
+						loop {
+							let v = stack.pop().unwrap();
+							inf.get_mut(&v).unwrap().0 = false;
+							scc.insert(v);
+							if v == u {
+								break;
+							}
+						}
+						sccs.push(scc);
+					}
+					q.pop();
+				}
+			}
+		}
+		sccs
+	}
+	/// Detect all strongly connected components in the graph, by the forward-backward algorithm
+	///
+	/// Sets of nodes made of whole SCCs are split in rounds, all the sets of a round in parallel, until none is left.
+	fn forward_backward_sccs<const DIRESPECT: bool, const ORPHANS: bool>(&self) -> Vec<HashSet<NId>>
+	where NId: Send + Sync, N: Sync, E: Sync {
+		use rayon::prelude::*;
+		let (orphans, nodes): (Vec<_>, Vec<_>) = self.nodes.keys().copied().partition(|n| self.is_orphan(*n));
+		let mut sccs: Vec<HashSet<NId>> = if ORPHANS { orphans.into_iter().map(|n| std::iter::once(n).collect()).collect() } else { Vec::new() };
+		let mut sets = vec![nodes.into_iter().collect::<HashSet<_>>()];
+		while !sets.is_empty() {
+			let (found, rest): (Vec<_>, Vec<_>) = sets.into_par_iter().map(|set| self.forward_backward::<DIRESPECT>(set)).unzip();
+			sccs.extend(found.into_iter().flatten());
+			sets = rest.into_iter().flatten().collect();
+		}
+		sccs
+	}
+	/// Splits a set of nodes made of whole SCCs: trims off the nodes alone in theirs, then splits off the SCC of a pivot node, from the nodes it reaches, those reaching it, and the others
+	///
+	/// Returns: the SCCs split off, and the remaining sets (made of whole SCCs)
+	fn forward_backward<const DIRESPECT: bool>(&self, mut set: HashSet<NId>) -> (Vec<HashSet<NId>>, Vec<HashSet<NId>>)
+	where NId: Send + Sync, N: Sync, E: Sync {
+		let neighbours = |u: NId, forward: bool| self.get_edges(u).iter().filter(move |e| if forward { e.is_outgoing::<DIRESPECT>(u) } else { e.is_incoming::<DIRESPECT>(u) }).map(move |e| e.other(u));
+		let mut sccs = Vec::new();
+		// nodes without successors or predecessors in the set are alone in their SCC
+		let mut trim: Vec<_> = set.iter().copied().collect();
+		while let Some(u) = trim.pop() {
+			if set.contains(&u) && (!neighbours(u, true).any(|v| set.contains(&v)) || !neighbours(u, false).any(|v| set.contains(&v))) {
+				set.remove(&u);
+				sccs.push(std::iter::once(u).collect());
+				trim.extend(neighbours(u, true).chain(neighbours(u, false)).filter(|v| set.contains(v)));
+			}
+		}
+		let pivot = match set.iter().next() {
+			Some(pivot) => *pivot,
+			None => return (sccs, Vec::new()),
+		};
+		let reach = |forward: bool| {
+			let mut reached: HashSet<_> = std::iter::once(pivot).collect();
+			let mut q = vec![pivot];
+			while let Some(u) = q.pop() {
+				for v in neighbours(u, forward) {
+					if set.contains(&v) && reached.insert(v) {
+						q.push(v);
+					}
+				}
+			}
+			reached
+		};
+		let (fw, bw) = rayon::join(|| reach(true), || reach(false));
+		let scc: HashSet<_> = fw.intersection(&bw).copied().collect();
+		let rest = vec![
+			fw.iter().filter(|n| !scc.contains(n)).copied().collect::<HashSet<_>>(),
+			bw.iter().filter(|n| !scc.contains(n)).copied().collect(),
+			set.iter().filter(|n| !fw.contains(n) && !bw.contains(n)).copied().collect(),
+		];
+		sccs.push(scc);
+		(sccs, rest.into_iter().filter(|s| !s.is_empty()).collect())
+	}
+	/// Detect bridges and articulation points, regardless of the directionality of edges (unrecursed lowlink search)
+	///
+	/// Returns: the bridges, and the articulation points
+	fn cut_edges_and_points(&self) -> (Vec<&E>, HashSet<NId>) {
+		let mut bridges = Vec::new();
+		let mut points = HashSet::default();
+		// discovery index and lowlink of each visited node
+		let mut inf: HashMap<NId, (usize, usize)> = HashMap::default();
+		for root in self.nodes.keys().copied() {
+			if inf.contains_key(&root) {
+				continue;
+			}
+			inf.insert(root, (inf.len(), inf.len()));
+			let mut children = 0;
+			let mut q: Vec<(NId, Option<&E>, Vec<&E>)> = vec![(root, None, self.get_edges(root).iter().collect())];
+			while let Some((u, parent, es)) = q.last_mut() {
+				let (u, parent) = (*u, *parent);
+				if let Some(e) = es.pop() {
+					if e.is_cyclic() || parent == Some(e) {
+						continue;
+					}
+					let v = e.other(u);
+					match inf.get(&v).copied() {
+						None => {
+							inf.insert(v, (inf.len(), inf.len()));
+							q.push((v, Some(e), self.get_edges(v).iter().collect()));
+						},
+						Some((vidx, _)) => {
+							let ul = &mut inf.get_mut(&u).unwrap().1;
+							*ul = (*ul).min(vidx);
+						},
+					}
+					continue;
+				}
+				q.pop();
+				let (Some((p, ..)), Some(e)) = (q.last(), parent) else { continue };
+				let p = *p;
+				let ul = inf[&u].1;
+				let (pidx, pl) = inf.get_mut(&p).unwrap();
+				*pl = (*pl).min(ul);
+				if ul > *pidx {
+					bridges.push(e);
+				}
+				if p == root {
+					children += 1;
+				} else if ul >= *pidx {
+					points.insert(p);
+				}
+			}
+			if children > 1 {
+				points.insert(root);
+			}
+		}
+		(bridges, points)
+	}
+	/// Detect bridges - edges whose removal disconnects their ends, regardless of the directionality of edges
+	pub fn bridges(&self) -> Vec<&E> {
+		self.cut_edges_and_points().0
+	}
+	/// Detect articulation points - nodes whose removal disconnects their neighbours, regardless of the directionality of edges
+	pub fn articulation_points(&self) -> HashSet<NId> {
+		self.cut_edges_and_points().1
+	}
+	/// Finds weak links between regions - the directed edges going from one to another
+	///
+	/// Arguments:
+	/// - `regions`: regions between which to look for weak links, assumed SCCs
+	///
+	/// Returns: the weak links, each once
+	pub fn weak_links(&self, regions: &[HashSet<NId>]) -> Vec<&E> {
+		let mut seen = HashSet::default();
+		let mut links = Vec::new();
+		for i in 0..regions.len() {
+			for j in (i+1)..regions.len() {
+				for (.., e) in self.get_edges_between_regions::<false>(&regions[i], &regions[j]) {
+					if e.directed() && seen.insert(e) {
+						links.push(e);
+					}
+				}
+			}
+		}
+		links
+	}
+	/// Patches weak links between regions
+	///
+	/// _SCCs together stronk!_
+	///
+	/// Arguments:
+	/// - `DIRESPECT`: whether the directionality of edges is respected (weakly linked SCCs can only exist in mixed graphs, calling this without respect is no-op)
+	/// - `regions`: regions between which to patch weak links, assumed SCCs
+	/// - `dedirect`: function that transforms a directed edge into an undirected one, preserving all other properties (the function is always and only fed directed edges)
+	pub fn patch_sccs<FD, const DIRESPECT: bool>(&mut self, regions: &Vec<HashSet<NId>>, dedirect: FD)
+	where
+		FD: Fn(E) -> E,
+	{
+		if DIRESPECT {
+			let redir: Vec<E> = self.weak_links(regions).into_iter().cloned().collect();
+			for e in &redir {
+				self.remove_edge(e);
+			}
+			for e in redir.into_iter().map(dedirect) {
+				self.add_edge(e);
+			}
+		}
+	}
+	/// Contracts chains of degree-2 nodes into single edges, as long as their edges merge
+	///
+	/// Contracted nodes are left in the graph, as orphans.
+	///
+	/// Arguments:
+	/// - `keep`: nodes not to contract, even of degree 2
+	/// - `merge`: merges 2 edges meeting at a node into one between their other ends (in either direction), if they can be
+	///
+	/// Returns: the original edges each contracted edge stands for, in order from its `p1` to its `p2`
+	pub fn simplify(&mut self, keep: impl Fn(NId) -> bool, mut merge: impl FnMut(&E, NId, &E) -> Option<E>) -> HashMap<E, Vec<E>> {
+		let mut contracted: HashMap<E, Vec<E>> = HashMap::default();
+		let candidates: Vec<_> = self.edges.keys().copied().filter(|n| !keep(*n)).collect();
+		for v in candidates {
+			let es: Vec<_> = self.get_edges(v).iter().cloned().collect();
+			if es.len() != 2 || es.iter().any(Edge::is_cyclic) || es[0].other(v) == es[1].other(v) {
+				continue;
+			}
+			let (a, b) = (&es[0], &es[1]);
+			let (u, w) = (a.other(v), b.other(v));
+			let m = match merge(a, v, b) {
+				Some(m) if (m.p1(), m.p2()) == (u, w) || (m.p1(), m.p2()) == (w, u) => m,
+				_ => continue,
+			};
+			if self.get_edges(u).contains(&m) {
+				continue;
+			}
+			let mut originals = |e: &E, from: NId| {
+				let mut chain = contracted.remove(e).unwrap_or_else(|| vec![e.clone()]);
+				if e.p1() != from {
+					chain.reverse();
+				}
+				chain
+			};
+			let (mut chain, tail) = if m.p1() == u { (originals(a, u), originals(b, v)) } else { (originals(b, w), originals(a, v)) };
+			chain.extend(tail);
+			self.remove_edge(a);
+			self.remove_edge(b);
+			self.add_edge(m.clone());
+			contracted.insert(m, chain);
+		}
+		contracted
+	}
+	/// Expands the contracted edges of a path, as per [`Graph::simplify`], into the original ones
+	///
+	/// Arguments:
+	/// - `path`: the path
+	/// - `n`: starting node
+	/// - `contracted`: the original edges of each contracted edge
+	pub fn expand<'a>(path: impl Iterator<Item = &'a E>, n: NId, contracted: &'a HashMap<E, Vec<E>>) -> Vec<&'a E> {
+		let mut expanded = Vec::new();
+		let mut at = n;
+		for e in path {
+			match contracted.get(e) {
+				Some(chain) if e.p1() == at => expanded.extend(chain.iter()),
+				Some(chain) => expanded.extend(chain.iter().rev()),
+				None => expanded.push(e),
+			}
+			at = e.other(at);
+		}
+		expanded
+	}
+	/// Converts a path consisting of successive edges to successively visited nodes (with associated edges).
+	///
+	/// Example:
+	/// - for path starting at `a` of edges `[(a;b),(c;b);(c;d)]` - returns `[a,b,c,d]`, or `[(a,_),(b,(a;b)),(c,(c;b)),(d,(c;d))]` with associated edeges.
+	/// - an empty path starting at `a` - returns `[(a,_)]`
+	pub fn path_to_nodes<'a>(path: impl Iterator<Item = &'a E>, n: NId) -> Vec<(NId, Option<&'a E>)> { //TODO this can become a generator one day!
+		let mut vs = vec![(n, None)];
+		for e in path {
+			vs.push((e.other(vs.last().unwrap().0), Some(e)));
+		}
+		vs
+	}
+}
+
+/// Graph construction adapters, for when your ids don't copy
+pub mod adapt {
+	use super::*;
+	/// A Node that has an id associated to it
+	pub trait IdentifiableNode {
+		/// Id type
+		type Id: Clone + Hash + Eq;
+		/// Id of the node
+		fn id(&self) -> &Self::Id;
+	}
+	/// A graph construction (id) adapter, to construct a graph with additional id mapping.
+	///
+	/// For alogrithmic performance reasons, [`Graph`] requires that node ids are [`Copy`].
+	/// However that is not always the case.
+	/// [`GraphAdapter`] hence allows you to construct a graph, and keep it up to date, by providing a stored "your node id" ↔ "graph node id" mapping.
+	///
+	/// Type Parameters:
+	/// - `NId`: (lightweight) node id, used by the [`Graph`]
+	/// - `E`: edge type
+	/// - `N`: node type, that identifies itself to (heavy) node id
+	/// - `IdAcc`: intermediate accumulator type value useful for [`GraphAdapter::new`]
+	pub struct GraphAdapter<NId, N, E, IdAcc, Gen>
+	where
+		NId: Clone + Copy + Hash + Eq,
+		E: Edge<NId>,
+		N: IdentifiableNode,
+		Gen: Fn(&N::Id, IdAcc) -> (NId, IdAcc),
+	{
+		pub graph: Graph<NId, N, E>,
+		fwd: HashMap<N::Id, NId>,
+		last_id: IdAcc,
+		next_id: Gen,
+	}
+	impl<NId, N, E, IdAcc, Gen> GraphAdapter<NId, N, E, IdAcc, Gen>
+	where
+		NId: Clone + Copy + Hash + Eq,
+		E: Edge<NId>,
+		N: IdentifiableNode,
+		IdAcc: Default,
+		Gen: Fn(&N::Id, IdAcc) -> (NId, IdAcc),
+	{
+		/// Construct a new adapter.
+		///
+		/// Arguments:
+		/// - `gen`: lightweight id generator function - given heavy node id and intermediate accumulator value, provide lightweight id and the next accumulator value
+		/// - `acc`: initial intermediate accumulator value
+		pub fn new(acc: IdAcc, gen: Gen) -> Self {
+			Self {
+				graph: Default::default(),
+				fwd: Default::default(),
+				last_id: acc,
+				next_id: gen,
+			}
+		}
+		/// Replace the graph of the adapter, and its id mappings, e.g. with those of [`GraphAdapter::into_parts`]
+		///
+		/// Arguments:
+		/// - `fwd`: light id of each heavy id of the graph
+		/// - `acc`: intermediate accumulator value for the next node added
+		pub fn restore(&mut self, graph: Graph<NId, N, E>, fwd: HashMap<N::Id, NId>, acc: IdAcc) {
+			self.graph = graph;
+			self.fwd = fwd;
+			self.last_id = acc;
+		}
+		/// Graph of the adapter, with its id mappings
+		///
+		/// Returns: the graph, light id of each heavy id, and intermediate accumulator value for the next node added
+		#[allow(clippy::type_complexity)]
+		pub fn into_parts(self) -> (Graph<NId, N, E>, HashMap<N::Id, NId>, IdAcc) {
+			(self.graph, self.fwd, self.last_id)
+		}
+		/// Map heavy id to light id
+		pub fn id2nid(&self, n: &N::Id) -> Option<NId> {
+			self.fwd.get(n).map(|nid| *nid)
+		}
+		/// Map light id to node
+		pub fn nid2node(&self, nid: NId) -> Option<&N> {
+			self.graph.get_node(nid)
+		}
+		/// Map light id to heavy id
+		pub fn nid2id(&self, nid: NId) -> Option<&N::Id> {
+			self.nid2node(nid).map(|n| n.id())
+		}
+		/// Heavy ids and nodes of the graph
+		pub fn iter(&self) -> impl Iterator<Item = (&N::Id, &N)> {
+			self.graph.nodes().map(|(_, n)| (n.id(), n))
+		}
+		/// Add a node to the graph, with id mappings.
+		///
+		/// A node of an id already in the graph replaces the node of that id, keeping its edges, as [`GraphAdapter::update_node`] does.
+		///
+		/// Returns: the replaced node, if any
+		pub fn add_node(&mut self, n: N) -> Option<N> {
+			match self.update_node(n) {
+				Ok(replaced) => Some(replaced),
+				Err(n) => {
+					let (nid, acc) = (self.next_id)(n.id(), std::mem::take(&mut self.last_id));
+					self.last_id = acc;
+					self.fwd.insert(n.id().clone(), nid);
+					self.graph.add_node(nid, n);
+					None
+				},
+			}
+		}
+		/// Replace the node of the same id, keeping its edges
+		///
+		/// Returns: the replaced node, or `n` back if there is none
+		pub fn update_node(&mut self, n: N) -> Result<N, N> {
+			match self.id2nid(n.id()) {
+				Some(nid) => Ok(self.graph.add_node(nid, n).unwrap()),
+				None => Err(n),
+			}
+		}
+		/// Remove a node, its edges and id mappings
+		///
+		/// Returns: the node, if it was in the graph
+		pub fn remove_node(&mut self, id: &N::Id) -> Option<N> {
+			let nid = self.fwd.remove(id)?;
+			self.graph.remove_node(nid)
+		}
+		/// Add an edge
+		pub fn add_edge(&mut self, e: E) -> &mut Self {
+			self.graph.add_edge(e);
+			self
+		}
+		/// Remove an edge
+		///
+		/// Returns: whether the edge was in the graph
+		pub fn remove_edge(&mut self, e: &E) -> bool {
+			self.graph.get_edges(e.p1()).contains(e) && self.graph.remove_edge(e)
+		}
+	}
+}
+
+/// Heuristic graph algorithms
+pub mod heuristics {
+	use super::*;
+	
+	/// How shortest paths between nodes are searched for
+	pub enum Search<'x, NId, E, Weight> {
+		/// Dijkstra's algorithm
+		Dijkstra,
+		/// A*, by an estimated (never over) distance between nodes
+		AStar(&'x dyn Fn(NId, NId) -> Weight),
+		/// the contraction hierarchy of the graph, built with the same weights
+		Hierarchy(&'x ch::Hierarchy<NId, E, Weight>),
+	}
+	impl<NId, E, Weight> Clone for Search<'_, NId, E, Weight> {
+		fn clone(&self) -> Self {
+			*self
+		}
+	}
+	impl<NId, E, Weight> Copy for Search<'_, NId, E, Weight> {}
+
+	/// Solve Positioned Windy Rural Postman
+	///
+	/// Arguments:
+	/// - `DIRESPECT`: respect directionality of edges
+	/// - `g`: eulirian graph
+	/// - `sp`: starting node
+	/// - `alloc`: set of edges that need to be visited
+	/// - `weight`: filtering weight function
+	/// - `legs`: external node-to-node distances (e.g. from a routing engine), if known, to choose connecting legs by - the legs themselves still follow the shortest path on the graph
+	/// - `exact`: allocations of up to this many edges are solved exactly instead, where [`exact::solve_cpp`] can
+	/// - `search`: how paths between nodes are searched for - by A* if their distance can be estimated, or by the graph's contraction hierarchy if it was built
+	/// - `cache`: cache of shortest paths, shared across calls, and the weight profile of `weight` in it
+	///
+	/// Returns: the path visiting all allocated edges on success, or the allocated edges that can't be reached otherwise
+	#[allow(clippy::too_many_arguments)]
+	pub fn solve_pwrp<'a, NId, N, E, Weight, FW, const DIRESPECT: bool>(g: &'a Graph<NId, N, E>, sp: NId, mut alloc: HashSet<&'a E>, weight: FW, legs: Option<&dyn Fn(NId, NId) -> Option<Weight>>, exact: usize, search: Search<NId, E, Weight>, cache: Option<(&DistanceCache<NId, E>, u64)>) -> Result<Vec<&'a E>, HashSet<&'a E>>
+	where 
+		NId: Clone + Copy + Hash + Eq,
+		E: Edge<NId>,
+		Weight: Clone + Copy + PartialEq + Ord + Default + std::ops::Add<Weight, Output = Weight> + std::ops::Neg<Output = Weight>,
+		FW: Fn(&E) -> Option<Weight>,
+	{
+		log::trace!("Solving PWRP, starting with {}", alloc.len());
+		if alloc.len() <= exact {
+			if let Some(sol) = exact::solve_cpp::<_, _, _, _, _, DIRESPECT>(g, sp, &alloc, |e| weight(e)) {
+				log::trace!("solved exactly visiting {} segments", sol.len());
+				return Ok(sol);
+			}
+		}
+		let mut sol: Vec<&E> = Vec::new();
+		macro_rules! sol_inject {
+			($inj:expr,$y:expr) => {
+				log::trace!("of {}", $inj.len());
+				for e in &$inj {
+					alloc.remove(e);
+				}
+				log::trace!("remaining {}", alloc.len());
+				sol.splice($y..$y, $inj);
+			}
+		}
+		while !alloc.is_empty() {
+			if let Some((u, y, e)) = Graph::<NId, N, E>::path_to_nodes(sol.iter().map(|e| *e), sp).into_iter().enumerate().find_map(|(i, (u, _))| if let Some(e) = g.get_edges(u).iter().find(|e| e.is_outgoing::<DIRESPECT>(u) && alloc.contains(e)) { Some((u, i, e)) } else { None }) {
+				log::trace!("injecting a cycle");
+				let v = e.other(u);
+				if let Some(mut p) = path::<_, _, _, _, _, DIRESPECT>(g, v, u, &weight, search, cache) {
+					p.insert(0, e);
+					sol_inject!(p, y);
+				} else {
+					panic!("it's a trap!");
+				}
+			} else {
+				log::trace!("connecting to a distant isle");
+				let mut vs: HashSet<_> = alloc.iter().flat_map(|e| if !DIRESPECT || !e.directed() { vec![e.p1(), e.p2()] } else { vec![e.p1()] }).collect();
+				let us: IndexMap<_, _> = Graph::<NId, N, E>::path_to_nodes(sol.iter().map(|e| *e), sp).into_iter().enumerate().map(|(i, (u, _))| (u, i)).collect();
+				let connect = |vs: &HashSet<NId>| {
+					if let Some(legs) = legs {
+						if let Some((_, u, v)) = us.keys().flat_map(|u| vs.iter().filter_map(move |v| legs(*u, *v).map(|d| (d, *u, *v)))).min_by_key(|(d, _, _)| *d) {
+							if let Some(p) = path::<_, _, _, _, _, DIRESPECT>(g, u, v, &weight, search, cache) {
+								return Some((u, v, p));
+							}
+						}
+					}
+					match search {
+						Search::Hierarchy(h) => h.pathfind_regions(g, &us.keys().cloned().collect(), vs),
+						_ => g.pathfind_regions::<_, _, DIRESPECT>(&us.keys().cloned().collect(), vs, |e| weight(e)),
+					}
+				};
+				if let Some((inj, y)) = loop {
+					if let Some((u, v, mut p)) = connect(&vs) {
+						if let Some((e, mut pb)) = g.get_edges(v).iter().find_map(|e| if e.is_outgoing::<DIRESPECT>(v) && alloc.contains(e) {
+							path::<_, _, _, _, _, DIRESPECT>(g, e.other(v), u, &weight, search, cache).map(|path| (e, path))
+						} else { None }) {
+							p.push(e);
+							p.append(&mut pb);
+							// log::trace!("connecting {} to {} to {} to {}", u, v, e.other(v), u);
+							break Some((p, *us.get(&u).unwrap()));
+						} else {
+							log::trace!("Can go from u to v, but not back; discarding v");
+							vs.remove(&v);
+						}
+					} else {
+						break None;
+					}
+				} {
+					sol_inject!(inj, y);
+				} else {
+					log::trace!("failed to reach");
+					return Err(alloc);
+				}
+			}
+		}
+		log::trace!("solved visiting {} segments", sol.len());
+		Ok(sol)
+	}
+
+	/// Shortest path between 2 nodes, from the cache if any, or searched for
+	fn path<'a, NId, N, E, Weight, FW, const DIRESPECT: bool>(g: &'a Graph<NId, N, E>, n1: NId, n2: NId, weight: &FW, search: Search<NId, E, Weight>, cache: Option<(&DistanceCache<NId, E>, u64)>) -> Option<Vec<&'a E>>
+	where
+		NId: Clone + Copy + Hash + Eq,
+		E: Edge<NId>,
+		Weight: Clone + Copy + Ord + Default + std::ops::Add<Weight, Output = Weight> + std::ops::Neg<Output = Weight>,
+		FW: Fn(&E) -> Option<Weight>,
+	{
+		let find = || match search {
+			Search::Dijkstra => g.pathfind::<_, _, DIRESPECT>(n1, n2, weight),
+			Search::AStar(h) => g.pathfind_astar::<_, _, _, DIRESPECT>(n1, n2, weight, |n| h(n, n2)),
+			Search::Hierarchy(h) => h.pathfind(g, n1, n2),
+		};
+		match cache {
+			Some((cache, profile)) => cache.path(g, n1, n2, profile, find),
+			None => find(),
+		}
+	}
+
+	/// Pheromone never evaporates below this, so that every transition can still be taken
+	const MIN_PHEROMONE: f64 = 1e-3;
+
+	/// Parameters of an ant colony
+	#[derive(Clone, Copy, Debug)]
+	pub struct Colony {
+		/// number of ants, each building a tour, per iteration
+		pub ants: usize,
+		/// number of iterations
+		pub iterations: usize,
+		/// fraction of the pheromone evaporating after each iteration
+		pub evaporation: f64,
+		/// influence of the pheromone on the choice of the next edge
+		pub alpha: f64,
+		/// influence of the closeness of the next edge
+		pub beta: f64,
+	}
+
+	/// Solve Positioned Windy Rural Postman by ant colony optimization
+	///
+	/// Ants go from the starting node through each allocated edge in turn, deadheading along shortest paths, and back.
+	/// The next edge (and the way it's traversed, unless directed) is picked randomly, favouring the pheromone on the transition from the last one, and its closeness.
+	/// After each iteration, pheromone evaporates, and ants leave some on the transitions they took: as much as the shortest tour so far is shorter than theirs.
+	///
+	/// Arguments:
+	/// - `DIRESPECT`: respect directionality of edges
+	/// - `g`: eulirian graph
+	/// - `sp`: starting node
+	/// - `alloc`: set of edges that need to be visited
+	/// - `weight`: filtering weight function
+	/// - `legs`: external node-to-node distances, only used as per [`solve_pwrp`]
+	/// - `exact`: allocations of up to this many edges are solved exactly instead, where [`exact::solve_cpp`] can
+	/// - `search`: how paths between nodes are searched for, as per [`solve_pwrp`]
+	/// - `cache`: cache of shortest paths, as per [`solve_pwrp`]
+	/// - `colony`: parameters of the colony
+	/// - `seed`: seed of the ants' random choices
+	///
+	/// Returns: the shortest tour found, or as per [`solve_pwrp`] if no ant could complete a tour (as some edges are out of reach)
+	#[allow(clippy::too_many_arguments)]
+	pub fn solve_aco<'a, NId, N, E, Weight, FW, const DIRESPECT: bool>(g: &'a Graph<NId, N, E>, sp: NId, alloc: HashSet<&'a E>, weight: FW, legs: Option<&dyn Fn(NId, NId) -> Option<Weight>>, exact: usize, search: Search<NId, E, Weight>, cache: Option<(&DistanceCache<NId, E>, u64)>, colony: &Colony, seed: u64) -> Result<Vec<&'a E>, HashSet<&'a E>>
+	where
+		NId: Clone + Copy + Hash + Eq,
+		E: Edge<NId>,
+		Weight: Clone + Copy + PartialEq + Ord + Default + std::ops::Add<Weight, Output = Weight> + std::ops::Neg<Output = Weight> + Into<f64>,
+		FW: Fn(&E) -> Option<Weight>,
+	{
+		use rand::{Rng, SeedableRng};
+		log::trace!("Solving PWRP by ant colony, starting with {}", alloc.len());
+		if alloc.len() <= exact {
+			if let Some(sol) = exact::solve_cpp::<_, _, _, _, _, DIRESPECT>(g, sp, &alloc, |e| weight(e)) {
+				log::trace!("solved exactly visiting {} segments", sol.len());
+				return Ok(sol);
+			}
+		}
+		// each way to traverse each edge, as (edge, from, to)
+		let targets: Vec<&E> = alloc.iter().copied().collect();
+		let arcs: Vec<(usize, NId, NId)> = targets.iter().enumerate().flat_map(|(t, e)| {
+			IntoIterator::into_iter([(e.p1(), e.p2()), (e.p2(), e.p1())]).take(if e.is_cyclic() { 1 } else { 2 }).filter(move |(u, _)| e.is_outgoing::<DIRESPECT>(*u)).map(move |(u, v)| (t, u, v))
+		}).collect();
+		let nodes: HashSet<NId> = arcs.iter().flat_map(|(_, u, v)| IntoIterator::into_iter([*u, *v])).chain(Some(sp)).collect();
+		let distances: HashMap<NId, HashMap<NId, Weight>> = nodes.iter().map(|n| (*n, exact::distances::<_, _, _, _, _, DIRESPECT>(g, *n, &nodes, &weight))).collect();
+		let distance = |u: NId, v: NId| -> Option<f64> { distances[&u].get(&v).map(|d| (*d).into()) };
+		let length = |e: &E| -> f64 { weight(e).unwrap_or_default().into() };
+		let n = arcs.len();
+		// pheromone on the transition from each arc (or the start, last) to each arc
+		let mut pheromone = vec![1.0f64; (n + 1) * n];
+		let mut rng = rand::rngs::StdRng::seed_from_u64(seed);
+		let mut best: Option<(f64, Vec<usize>)> = None;
+		for _ in 0..colony.iterations {
+			let tours: Vec<(f64, Vec<usize>)> = (0..colony.ants).filter_map(|_| {
+				let mut left = vec![true; targets.len()];
+				let (mut at, mut last, mut total, mut tour) = (sp, n, 0.0, Vec::with_capacity(targets.len()));
+				for _ in 0..targets.len() {
+					let options: Vec<_> = arcs.iter().enumerate().filter(|(_, (t, _, _))| left[*t]).filter_map(|(a, (_, u, _))| distance(at, *u).map(|d| (a, d))).collect();
+					let closest = options.iter().map(|(_, d)| *d).min_by(f64::total_cmp)?;
+					let attraction = options.iter().map(|(a, d)| pheromone[last * n + a].powf(colony.alpha) * ((closest + 1.0) / (d + 1.0)).powf(colony.beta));
+					let pick = rand::distributions::WeightedIndex::new(attraction).ok()?;
+					let (a, d) = options[rng.sample(pick)];
+					let (t, _, v) = arcs[a];
+					left[t] = false;
+					total += d + length(targets[t]);
+					(at, last) = (v, a);
+					tour.push(a);
+				}
+				Some((total + distance(at, sp)?, tour))
+			}).collect();
+			if let Some(shortest) = tours.iter().min_by(|a, b| a.0.total_cmp(&b.0)).filter(|(l, _)| best.as_ref().is_none_or(|(b, _)| l < b)) {
+				best = Some(shortest.clone());
+			}
+			let Some((shortest, _)) = best else { continue };
+			for p in pheromone.iter_mut() {
+				*p = (*p * (1.0 - colony.evaporation)).max(MIN_PHEROMONE);
+			}
+			for (total, tour) in &tours {
+				let mut last = n;
+				for a in tour {
+					pheromone[last * n + a] += shortest / total;
+					last = *a;
+				}
+			}
+		}
+		// follow the best tour, skipping edges already traversed deadheading
+		let fallback = |alloc| solve_pwrp::<_, _, _, _, _, DIRESPECT>(g, sp, alloc, |e| weight(e), legs, 0, search, cache);
+		let Some((_, tour)) = best else {
+			log::trace!("no ant completed a tour");
+			return fallback(alloc);
+		};
+		let (mut sol, mut done, mut at) = (Vec::new(), HashSet::default(), sp);
+		for a in tour {
+			let (t, u, v) = arcs[a];
+			if done.contains(targets[t]) {
+				continue;
+			}
+			let Some(leg) = path::<_, _, _, _, _, DIRESPECT>(g, at, u, &weight, search, cache) else { return fallback(alloc) };
+			done.extend(leg.iter().copied());
+			sol.extend(leg);
+			sol.push(targets[t]);
+			done.insert(targets[t]);
+			at = v;
+		}
+		match path::<_, _, _, _, _, DIRESPECT>(g, at, sp, &weight, search, cache) {
+			Some(back) => sol.extend(back),
+			None => return fallback(alloc),
+		}
+		log::trace!("solved visiting {} segments", sol.len());
+		Ok(sol)
+	}
+}
+
+/// Exact graph algorithms, for small instances
+pub mod exact {
+	use super::*;
+
+	/// Allocations with more odd nodes than this aren't matched exactly (the matching takes 2^n)
+	const MAX_ODD: usize = 16;
+	/// Allocations with more undirected edges than this aren't oriented exactly, when directionality is respected (each orientation is tried)
+	const MAX_UNDIRECTED: usize = 10;
+
+	/// Shortest distances from a node to target nodes, edge-weighted by a function
+	pub(super) fn distances<NId, N, E, Weight, FW, const DIRESPECT: bool>(g: &Graph<NId, N, E>, n1: NId, targets: &HashSet<NId>, weight: &FW) -> HashMap<NId, Weight>
+	where
+		NId: Clone + Copy + Hash + Eq,
+		E: Edge<NId>,
+		Weight: Clone + Copy + Ord + Default + std::ops::Add<Weight, Output = Weight> + std::ops::Neg<Output = Weight>,
+		FW: Fn(&E) -> Option<Weight>,
+	{
+		let mut dp: HashMap<NId, Weight> = HashMap::default();
+		let mut found = HashMap::default();
+		dp.insert(n1, Weight::default());
+		let mut q = PriorityQueue::new();
+		q.push(n1, Weight::default());
+		while let Some((u, _)) = q.pop() {
+			let d = dp[&u];
+			if targets.contains(&u) {
+				found.insert(u, d);
+				if found.len() == targets.len() {
+					break;
+				}
+			}
+			for e in g.get_edges(u) {
+				if e.is_outgoing::<DIRESPECT>(u) {
+					if let Some(ed) = weight(e) {
+						let v = e.other(u);
+						let d = d + ed;
+						if dp.get(&v).is_none_or(|vd| *vd > d) {
+							dp.insert(v, d);
+							q.push(v, -d);
+						}
+					}
+				}
+			}
+		}
+		found
+	}
+
+	/// Minimum cost perfect matching of nodes, by dynamic programming over the subsets of matched nodes
+	///
+	/// Returns: the matched pairs (as indices), if all nodes can be matched
+	fn matching<Weight>(cost: &[Vec<Option<Weight>>]) -> Option<Vec<(usize, usize)>>
+	where
+		Weight: Clone + Copy + Ord + Default + std::ops::Add<Weight, Output = Weight>,
+	{
+		let n = cost.len();
+		let mut dp: Vec<Option<(Weight, usize, usize)>> = vec![None; 1 << n];
+		dp[0] = Some((Weight::default(), 0, 0));
+		for mask in 0..(1usize << n) {
+			let Some((d, _, _)) = dp[mask] else { continue };
+			let Some(i) = (0..n).find(|i| mask & (1 << i) == 0) else { continue };
+			for (j, c) in cost[i].iter().enumerate().skip(i + 1) {
+				if let Some(c) = c.filter(|_| mask & (1 << j) == 0) {
+					let next = mask | (1 << i) | (1 << j);
+					if dp[next].is_none_or(|(nd, _, _)| nd > d + c) {
+						dp[next] = Some((d + c, i, j));
+					}
+				}
+			}
+		}
+		let mut mask = (1 << n) - 1;
+		let mut pairs = Vec::new();
+		while mask != 0 {
+			let (_, i, j) = dp[mask]?;
+			pairs.push((i, j));
+			mask &= !((1 << i) | (1 << j));
+		}
+		Some(pairs)
+	}
+
+	/// Minimum cost transportation of supplies to demands, by successive shortest paths
+	///
+	/// Returns: the total cost and units shipped between each supply & demand, if all demands can be met
+	fn transport<Weight>(supplies: &[usize], demands: &[usize], cost: &[Vec<Option<Weight>>]) -> Option<(Weight, Vec<Vec<usize>>)>
+	where
+		Weight: Clone + Copy + Ord + Default + std::ops::Add<Weight, Output = Weight> + std::ops::Neg<Output = Weight>,
+	{
+		let (s, t) = (supplies.len(), demands.len());
+		let mut flow = vec![vec![0; t]; s];
+		let (mut shipped, mut received) = (vec![0; s], vec![0; t]);
+		let mut total = Weight::default();
+		for _ in 0..supplies.iter().sum::<usize>() {
+			// Bellman-Ford over the residual graph: supplies with some left are sources, shipments can be undone
+			let mut ds: Vec<Option<(Weight, Option<usize>)>> = (0..s).map(|i| (shipped[i] < supplies[i]).then(|| (Weight::default(), None))).collect();
+			let mut dt: Vec<Option<(Weight, usize)>> = vec![None; t];
+			loop {
+				let mut changed = false;
+				for i in 0..s {
+					for j in 0..t {
+						if let (Some((d, _)), Some(c)) = (ds[i], cost[i][j]) {
+							if dt[j].is_none_or(|(dj, _)| dj > d + c) {
+								dt[j] = Some((d + c, i));
+								changed = true;
+							}
+						}
+						if let Some((d, _)) = dt[j].filter(|_| flow[i][j] > 0) {
+							let d = d + -cost[i][j].unwrap();
+							if ds[i].is_none_or(|(di, _)| di > d) {
+								ds[i] = Some((d, Some(j)));
+								changed = true;
+							}
+						}
+					}
+				}
+				if !changed {
+					break;
+				}
+			}
+			let (mut j, (d, _)) = (0..t).filter(|j| received[*j] < demands[*j]).filter_map(|j| dt[j].map(|d| (j, d))).min_by_key(|(_, (d, _))| *d)?;
+			total = total + d;
+			received[j] += 1;
+			loop {
+				let i = dt[j].unwrap().1;
+				flow[i][j] += 1;
+				match ds[i].unwrap().1 {
+					Some(prev) => {
+						flow[i][prev] -= 1;
+						j = prev;
+					},
+					None => {
+						shipped[i] += 1;
+						break;
+					},
+				}
+			}
+		}
+		Some((total, flow))
+	}
+
+	/// Solve Chinese Postman exactly over the allocated edges, deadheading between them along shortest paths
+	///
+	/// The allocated edges are augmented into an eulerian multigraph at minimum cost: by matching their odd nodes if directionality isn't respected, or by transporting the excess of their unbalanced nodes otherwise - trying every orientation of the undirected ones.
+	/// This is optimal as long as the allocated edges are connected; a tour starting elsewhere gets there, and back, along shortest paths.
+	///
+	/// Arguments:
+	/// - `DIRESPECT`: respect directionality of edges
+	/// - `g`: eulirian graph
+	/// - `sp`: starting node
+	/// - `alloc`: set of edges that need to be visited
+	/// - `weight`: filtering weight function
+	///
+	/// Returns: the path visiting all allocated edges, unless they aren't connected, reachable, or small enough to be solved exactly
+	pub fn solve_cpp<'a, NId, N, E, Weight, FW, const DIRESPECT: bool>(g: &'a Graph<NId, N, E>, sp: NId, alloc: &HashSet<&'a E>, weight: FW) -> Option<Vec<&'a E>>
+	where
+		NId: Clone + Copy + Hash + Eq,
+		E: Edge<NId>,
+		Weight: Clone + Copy + PartialEq + Ord + Default + std::ops::Add<Weight, Output = Weight> + std::ops::Neg<Output = Weight>,
+		FW: Fn(&E) -> Option<Weight>,
+	{
+		if alloc.is_empty() {
+			return Some(Vec::new());
+		}
+		let alloc: Vec<&E> = alloc.iter().copied().collect();
+		let undirected: Vec<usize> = (0..alloc.len()).filter(|i| DIRESPECT && !alloc[*i].directed() && !alloc[*i].is_cyclic()).collect();
+		if undirected.len() > MAX_UNDIRECTED {
+			return None;
+		}
+		// connected allocation
+		let nodes: IndexMap<NId, usize> = alloc.iter().flat_map(|e| [e.p1(), e.p2()]).map(|n| (n, 0)).collect();
+		let mut components: Vec<usize> = (0..nodes.len()).collect();
+		fn root(components: &mut [usize], mut i: usize) -> usize {
+			while components[i] != i {
+				components[i] = components[components[i]];
+				i = components[i];
+			}
+			i
+		}
+		for e in &alloc {
+			let (a, b) = (root(&mut components, nodes.get_index_of(&e.p1()).unwrap()), root(&mut components, nodes.get_index_of(&e.p2()).unwrap()));
+			components[a] = b;
+		}
+		let r = root(&mut components, 0);
+		if (0..nodes.len()).any(|i| root(&mut components, i) != r) {
+			return None;
+		}
+		let mut targets: HashSet<NId> = nodes.keys().copied().collect();
+		targets.insert(sp);
+		let dist: HashMap<NId, HashMap<NId, Weight>> = targets.iter().map(|n| (*n, distances::<_, _, _, _, _, DIRESPECT>(g, *n, &targets, &weight))).collect();
+		let d = |u: &NId, v: &NId| dist[u].get(v).copied();
+		// required arcs (as `(from, to, edge)`) and deadheading legs (as `(from, to)`)
+		let (arcs, legs): (Vec<(NId, NId, &E)>, Vec<_>) = if DIRESPECT {
+			let mut best: Option<(Weight, usize, Vec<_>)> = None;
+			for orientation in 0..(1usize << undirected.len()) {
+				let flipped = |i: usize| undirected.iter().position(|u| *u == i).is_some_and(|b| orientation & (1 << b) != 0);
+				let mut excess: HashMap<NId, isize> = HashMap::default();
+				for (i, e) in alloc.iter().enumerate() {
+					let (a, b) = if flipped(i) { (e.p2(), e.p1()) } else { (e.p1(), e.p2()) };
+					*excess.entry(a).or_insert(0) -= 1;
+					*excess.entry(b).or_insert(0) += 1;
+				}
+				let sources: Vec<(NId, usize)> = excess.iter().filter(|(_, x)| **x > 0).map(|(n, x)| (*n, *x as usize)).collect();
+				let sinks: Vec<(NId, usize)> = excess.iter().filter(|(_, x)| **x < 0).map(|(n, x)| (*n, (-*x) as usize)).collect();
+				let cost: Vec<Vec<_>> = sources.iter().map(|(u, _)| sinks.iter().map(|(v, _)| d(u, v)).collect()).collect();
+				if let Some((c, flow)) = transport(&sources.iter().map(|(_, x)| *x).collect::<Vec<_>>(), &sinks.iter().map(|(_, x)| *x).collect::<Vec<_>>(), &cost) {
+					if best.as_ref().is_none_or(|(bc, _, _)| *bc > c) {
+						let legs = flow.iter().enumerate().flat_map(|(i, f)| f.iter().enumerate().flat_map(move |(j, n)| std::iter::repeat_n((i, j), *n))).map(|(i, j)| (sources[i].0, sinks[j].0)).collect();
+						best = Some((c, orientation, legs));
+					}
+				}
+			}
+			let (_, orientation, legs) = best?;
+			let flipped = |i: usize| undirected.iter().position(|u| *u == i).is_some_and(|b| orientation & (1 << b) != 0);
+			(alloc.iter().enumerate().map(|(i, e)| if flipped(i) { (e.p2(), e.p1(), *e) } else { (e.p1(), e.p2(), *e) }).collect(), legs)
+		} else {
+			let mut degrees: HashMap<NId, usize> = HashMap::default();
+			for e in &alloc {
+				*degrees.entry(e.p1()).or_insert(0) += 1;
+				*degrees.entry(e.p2()).or_insert(0) += 1;
+			}
+			let odd: Vec<NId> = nodes.keys().copied().filter(|n| degrees[n] % 2 == 1).collect();
+			if odd.len() > MAX_ODD {
+				return None;
+			}
+			let cost: Vec<Vec<_>> = odd.iter().map(|u| odd.iter().map(|v| d(u, v)).collect()).collect();
+			(alloc.iter().map(|e| (e.p1(), e.p2(), *e)).collect(), matching(&cost)?.into_iter().map(|(i, j)| (odd[i], odd[j])).collect())
+		};
+		// eulerian multigraph, of arcs if directionality is respected, or edges otherwise
+		let mut items = arcs;
+		for (u, v) in legs {
+			let mut n = u;
+			for e in g.pathfind::<_, _, DIRESPECT>(u, v, &weight)? {
+				let m = e.other(n);
+				items.push((n, m, e));
+				n = m;
+			}
+		}
+		// entered from the start, at its closest node
+		let entry = *nodes.keys().filter(|n| d(&sp, n).is_some() && d(n, &sp).is_some()).min_by_key(|n| d(&sp, n).unwrap() + d(n, &sp).unwrap())?;
+		let mut adjacency: HashMap<NId, Vec<usize>> = HashMap::default();
+		for (i, (a, b, _)) in items.iter().enumerate() {
+			adjacency.entry(*a).or_default().push(i);
+			if !DIRESPECT && a != b {
+				adjacency.entry(*b).or_default().push(i);
+			}
+		}
+		// Hierholzer's algorithm
+		let mut used = vec![false; items.len()];
+		let mut next: HashMap<NId, usize> = HashMap::default();
+		let mut stack: Vec<(NId, Option<usize>)> = vec![(entry, None)];
+		let mut circuit = Vec::new();
+		while let Some(&(u, _)) = stack.last() {
+			let adjacent = adjacency.get(&u).map(Vec::as_slice).unwrap_or_default();
+			let p = next.entry(u).or_insert(0);
+			while *p < adjacent.len() && used[adjacent[*p]] {
+				*p += 1;
+			}
+			if let Some(&i) = adjacent.get(*p) {
+				used[i] = true;
+				let (a, b, _) = items[i];
+				stack.push((if a == u { b } else { a }, Some(i)));
+			} else if let Some((_, Some(i))) = stack.pop() {
+				circuit.push(items[i].2);
+			}
+		}
+		if circuit.len() != items.len() {
+			return None;
+		}
+		circuit.reverse();
+		let mut sol = g.pathfind::<_, _, DIRESPECT>(sp, entry, &weight)?;
+		sol.append(&mut circuit);
+		sol.append(&mut g.pathfind::<_, _, DIRESPECT>(entry, sp, &weight)?);
+		Some(sol)
+	}
+}
+
+/// Contraction hierarchies, for fast repeated shortest path queries on a graph whose weights don't change
+///
+/// Nodes are contracted one by one, the least important first (by edge difference): shortcuts replace the shortest paths through them between their remaining neighbours, unless a witness path avoids them.
+/// Queries then only search from both ends towards more important nodes, over a small fraction of the graph.
+pub mod ch {
+	use super::*;
+	use std::cmp::Reverse;
+
+	/// Maximum number of nodes settled by a witness search - beyond, shortcuts are added anyway (which is only redundant)
+	const WITNESS_SETTLED: usize = 64;
+
+	/// What an arc of the hierarchy stands for
+	#[derive(Clone, Copy, Debug)]
+	enum Via {
+		/// an edge of the graph
+		Edge(usize),
+		/// 2 arcs through a contracted node
+		Shortcut(usize, usize),
+	}
+
+	/// An arc of the hierarchy, from a node to another
+	#[derive(Clone, Copy, Debug)]
+	struct Arc<NId, Weight> {
+		from: NId,
+		to: NId,
+		weight: Weight,
+		via: Via,
+	}
+
+	/// Contraction hierarchy of a graph, by the weights of its edges
+	///
+	/// Type Parameters:
+	/// - `NId`: node id
+	/// - `E`: edge type
+	/// - `Weight`: weight of an edge
+	#[derive(Clone, Debug)]
+	pub struct Hierarchy<NId, E, Weight> {
+		/// edges of the graph traversed by the arcs
+		edges: Vec<E>,
+		arcs: Vec<Arc<NId, Weight>>,
+		/// arcs from each node to more important ones
+		up: HashMap<NId, Vec<usize>>,
+		/// arcs into each node from more important ones
+		down: HashMap<NId, Vec<usize>>,
+	}
+
+	impl<NId, E, Weight> Hierarchy<NId, E, Weight>
+	where
+		NId: Clone + Copy + Hash + Eq,
+		E: Edge<NId>,
+		Weight: Clone + Copy + Ord + Default + std::ops::Add<Weight, Output = Weight> + std::ops::Neg<Output = Weight>,
+	{
+		/// Builds the contraction hierarchy of a graph
+		///
+		/// Type Parameters:
+		/// - `DIRESPECT`: whether the directionality of edges is respected
+		///
+		/// Arguments:
+		/// - `g`: the graph
+		/// - `weight`: filtering weight function - returns the weight of the edge, iff it can be traversed
+		pub fn new<N, FW, const DIRESPECT: bool>(g: &Graph<NId, N, E>, weight: FW) -> Self
+		where
+			FW: Fn(&E) -> Option<Weight>,
+		{
+			let mut h = Self { edges: Vec::new(), arcs: Vec::new(), up: HashMap::default(), down: HashMap::default() };
+			// lightest arc between each pair of remaining nodes, and the remaining neighbours of each, out and in
+			let mut best: HashMap<(NId, NId), usize> = HashMap::default();
+			let mut outs: HashMap<NId, HashSet<NId>> = HashMap::default();
+			let mut ins: HashMap<NId, HashSet<NId>> = HashMap::default();
+			let add = |h: &mut Self, arc: Arc<NId, Weight>, best: &mut HashMap<(NId, NId), usize>, outs: &mut HashMap<NId, HashSet<NId>>, ins: &mut HashMap<NId, HashSet<NId>>| {
+				if best.get(&(arc.from, arc.to)).is_none_or(|a| h.arcs[*a].weight > arc.weight) {
+					best.insert((arc.from, arc.to), h.arcs.len());
+					outs.entry(arc.from).or_default().insert(arc.to);
+					ins.entry(arc.to).or_default().insert(arc.from);
+					h.arcs.push(arc);
+				}
+			};
+			for e in g.edges().filter(|e| !e.is_cyclic()) {
+				if let Some(weight) = weight(e) {
+					let via = Via::Edge(h.edges.len());
+					h.edges.push(e.clone());
+					for (from, to) in IntoIterator::into_iter([(e.p1(), e.p2()), (e.p2(), e.p1())]).filter(|(u, _)| e.is_outgoing::<DIRESPECT>(*u)) {
+						add(&mut h, Arc { from, to, weight, via }, &mut best, &mut outs, &mut ins);
+					}
+				}
+			}
+			// shortcuts replacing the paths through a node, as (in arc, out arc)
+			let shortcuts = |h: &Self, best: &HashMap<(NId, NId), usize>, outs: &HashMap<NId, HashSet<NId>>, ins: &HashMap<NId, HashSet<NId>>, v: NId| -> Vec<(usize, usize)> {
+				let mut shortcuts = Vec::new();
+				let targets: Vec<_> = outs.get(&v).into_iter().flatten().map(|x| best[&(v, *x)]).collect();
+				for u in ins.get(&v).into_iter().flatten() {
+					let a1 = best[&(*u, v)];
+					let targets: Vec<_> = targets.iter().copied().filter(|a2| h.arcs[*a2].to != *u).collect();
+					let Some(bound) = targets.iter().map(|a2| h.arcs[a1].weight + h.arcs[*a2].weight).max() else { continue };
+					// witness search, around v
+					let mut dp: HashMap<NId, Weight> = HashMap::default();
+					dp.insert(*u, Weight::default());
+					let mut q = PriorityQueue::new();
+					q.push(*u, Weight::default());
+					let mut settled = 0;
+					while let Some((w, _)) = q.pop() {
+						let d = dp[&w];
+						settled += 1;
+						if d > bound || settled > WITNESS_SETTLED {
+							break;
+						}
+						for x in outs.get(&w).into_iter().flatten().filter(|x| **x != v) {
+							let d = d + h.arcs[best[&(w, *x)]].weight;
+							if dp.get(x).is_none_or(|xd| *xd > d) {
+								dp.insert(*x, d);
+								q.push(*x, -d);
+							}
+						}
+					}
+					shortcuts.extend(targets.into_iter().filter(|a2| dp.get(&h.arcs[*a2].to).is_none_or(|d| *d > h.arcs[a1].weight + h.arcs[*a2].weight)).map(|a2| (a1, a2)));
+				}
+				shortcuts
+			};
+			let degree = |outs: &HashMap<NId, HashSet<NId>>, ins: &HashMap<NId, HashSet<NId>>, v: NId| (outs.get(&v).map_or(0, HashSet::len) + ins.get(&v).map_or(0, HashSet::len)) as i64;
+			// contract the least important nodes first: those adding the fewest shortcuts for the arcs they remove, and with the fewest neighbours contracted already
+			let mut contracted: HashMap<NId, i64> = HashMap::default();
+			let mut q = PriorityQueue::new();
+			for (v, _) in g.nodes() {
+				q.push(v, Reverse(shortcuts(&h, &best, &outs, &ins, v).len() as i64 - degree(&outs, &ins, v)));
+			}
+			while let Some((v, _)) = q.pop() {
+				let added = shortcuts(&h, &best, &outs, &ins, v);
+				let priority = added.len() as i64 - degree(&outs, &ins, v) + contracted.get(&v).copied().unwrap_or_default();
+				if q.peek().is_some_and(|(_, Reverse(next))| priority > *next) {
+					q.push(v, Reverse(priority));
+					continue;
+				}
+				for (a1, a2) in added {
+					let arc = Arc { from: h.arcs[a1].from, to: h.arcs[a2].to, weight: h.arcs[a1].weight + h.arcs[a2].weight, via: Via::Shortcut(a1, a2) };
+					add(&mut h, arc, &mut best, &mut outs, &mut ins);
+				}
+				let (vout, vin) = (outs.remove(&v).unwrap_or_default(), ins.remove(&v).unwrap_or_default());
+				h.up.insert(v, vout.iter().map(|x| best[&(v, *x)]).collect());
+				h.down.insert(v, vin.iter().map(|u| best[&(*u, v)]).collect());
+				for x in vout {
+					ins.get_mut(&x).map(|xs| xs.remove(&v));
+					*contracted.entry(x).or_default() += 1;
+				}
+				for u in vin {
+					outs.get_mut(&u).map(|us| us.remove(&v));
+					*contracted.entry(u).or_default() += 1;
+				}
+			}
+			log::debug!("Contraction hierarchy of {} arcs, {} of them shortcuts", h.arcs.len(), h.arcs.iter().filter(|a| matches!(a.via, Via::Shortcut(..))).count());
+			h
+		}
+		/// Upward search from nodes, over the arcs out of them (or into them, backward)
+		///
+		/// Returns: distance to each node reached, and the arc it is reached by
+		fn search(&self, from: impl Iterator<Item = NId>, forward: bool) -> HashMap<NId, (Weight, Option<usize>)> {
+			let mut dp: HashMap<NId, (Weight, Option<usize>)> = HashMap::default();
+			let mut q = PriorityQueue::new();
+			for n in from {
+				dp.insert(n, (Weight::default(), None));
+				q.push(n, Weight::default());
+			}
+			while let Some((u, _)) = q.pop() {
+				let d = dp[&u].0;
+				for a in (if forward { &self.up } else { &self.down }).get(&u).into_iter().flatten() {
+					let arc = &self.arcs[*a];
+					let v = if forward { arc.to } else { arc.from };
+					let d = d + arc.weight;
+					if dp.get(&v).is_none_or(|(vd, _)| *vd > d) {
+						dp.insert(v, (d, Some(*a)));
+						q.push(v, -d);
+					}
+				}
+			}
+			dp
+		}
+		/// Shortest path from some nodes to others
+		///
+		/// Returns: the nodes the path goes from and to, and its edges
+		fn query<'g, N>(&self, g: &'g Graph<NId, N, E>, n1: impl Iterator<Item = NId>, n2: impl Iterator<Item = NId>) -> Option<(NId, NId, Vec<&'g E>)> {
+			let (fw, bw) = (self.search(n1, true), self.search(n2, false));
+			let (meet, _) = fw.iter().filter_map(|(n, (d, _))| bw.get(n).map(|(b, _)| (*n, *d + *b))).min_by_key(|(_, d)| *d)?;
+			let mut arcs = Vec::new();
+			let mut from = meet;
+			while let Some((_, Some(a))) = fw.get(&from) {
+				arcs.push(*a);
+				from = self.arcs[*a].from;
+			}
+			arcs.reverse();
+			let mut to = meet;
+			while let Some((_, Some(a))) = bw.get(&to) {
+				arcs.push(*a);
+				to = self.arcs[*a].to;
+			}
+			let mut path = Vec::new();
+			let mut at = from;
+			let mut stack: Vec<_> = arcs.into_iter().rev().collect();
+			while let Some(a) = stack.pop() {
+				match self.arcs[a].via {
+					Via::Edge(i) => {
+						let e = g.get_edges(at).get(&self.edges[i])?;
+						at = e.other(at);
+						path.push(e);
+					},
+					Via::Shortcut(a1, a2) => {
+						stack.push(a2);
+						stack.push(a1);
+					},
+				}
+			}
+			Some((from, to, path))
+		}
+		/// Find shortest path between 2 nodes, by the weights the hierarchy was built with
+		///
+		/// Arguments:
+		/// - `g`: the graph the hierarchy was built of
+		/// - `n1`: first node
+		/// - `n2`: second node
+		///
+		/// Returns: edges path from `n1` to `n2`, if such exists
+		pub fn pathfind<'g, N>(&self, g: &'g Graph<NId, N, E>, n1: NId, n2: NId) -> Option<Vec<&'g E>> {
+			self.query(g, std::iter::once(n1), std::iter::once(n2)).map(|(.., path)| path)
+		}
+		/// Find shortest path between 2 regions, by the weights the hierarchy was built with
+		///
+		/// Arguments:
+		/// - `g`: the graph the hierarchy was built of
+		/// - `n1`: first region
+		/// - `n2`: second region
+		///
+		/// Returns: nodes `n1` and `n2` in the 1st and 2nd regions resp and the edges path from `n1` to `n2`, if such exists
+		pub fn pathfind_regions<'g, N>(&self, g: &'g Graph<NId, N, E>, n1: &HashSet<NId>, n2: &HashSet<NId>) -> Option<(NId, NId, Vec<&'g E>)> {
+			self.query(g, n1.iter().copied(), n2.iter().copied())
+		}
+	}
+}
+
+/// Map matching of (GPS) point sequences to edge sequences
+pub mod mapmatch {
+	use super::*;
+	use noisy_float::prelude::*;
+
+	/// Approximate meters per degree of latitude
+	const M_PER_DEG: f64 = 111_320.0;
+
+	/// Projects `(lon, lat)` coordinates to local planar meters around a reference latitude
+	pub fn project((lon, lat): (f64, f64), lat0: f64) -> (f64, f64) {
+		(lon * M_PER_DEG * lat0.to_radians().cos(), lat * M_PER_DEG)
+	}
+
+	/// Planar distance between 2 points
+	pub fn distance(a: (f64, f64), b: (f64, f64)) -> f64 {
+		((a.0 - b.0)*(a.0 - b.0) + (a.1 - b.1)*(a.1 - b.1)).sqrt()
+	}
+
+	/// Closest point on a line segment `a`-`b` to a point `p`
+	///
+	/// Returns: distance to the line segment, and relative position of the closest point along it (0 at `a`, 1 at `b`)
+	pub fn point_segment(p: (f64, f64), a: (f64, f64), b: (f64, f64)) -> (f64, f64) {
+		let (dx, dy) = (b.0 - a.0, b.1 - a.1);
+		let l2 = dx*dx + dy*dy;
+		let t = if l2 > 0.0 { (((p.0 - a.0)*dx + (p.1 - a.1)*dy) / l2).clamp(0.0, 1.0) } else { 0.0 };
+		(distance(p, (a.0 + t*dx, a.1 + t*dy)), t)
+	}
+
+	/// Map matching parameters
+	#[derive(Clone, Copy, PartialEq, Debug)]
+	pub struct Matching {
+		/// maximum distance of a point from its matched edge, in meters
+		pub radius: f64,
+		/// standard deviation of the points noise, in meters
+		pub sigma: f64,
+		/// tolerance for detours, in meters - the route between successive matches is expected to be about as long as the straight line between the points
+		pub beta: f64,
+		/// maximum number of candidate edges for each point
+		pub candidates: usize,
+		/// minimum number of points a path must be matched from, paths matched from fewer being taken as noise
+		pub points: usize,
+	}
+	impl Default for Matching {
+		fn default() -> Self {
+			Self {
+				radius: 30.0,
+				sigma: 10.0,
+				beta: 50.0,
+				candidates: 8,
+				points: 1,
+			}
+		}
+	}
+
+	/// A point matched to an edge
+	#[derive(Clone, Copy, Debug)]
+	struct Candidate<'a, E> {
+		edge: &'a E,
+		/// relative position along the edge
+		along: f64,
+		/// distance of the point from the edge
+		distance: f64,
+	}
+
+	/// Matches a sequence of points to paths on the graph.
+	///
+	/// Uses a hidden Markov model, decoded with Viterbi's algorithm:
+	/// - the states for each point are the edges within `radius` of it, the closer the likelier
+	/// - transitions between states are the likelier the closer the route between them is in length to the straight line between the points, and impossible if there is no such route
+	///
+	/// Type Parameters:
+	/// - `DIRESPECT`: whether the directionality of edges is respected
+	///
+	/// Arguments:
+	/// - `g`: graph with `(lon, lat)` positioned nodes
+	/// - `points`: `(lon, lat)` points, in order
+	/// - `length`: length of an edge, in meters
+	/// - `m`: matching parameters
+	///
+	/// Returns: matched paths - successive matched edges, with gaps between them filled by shortest paths; the matching breaks into multiple paths wherever no transition is possible, those matched from fewer than [`Matching::points`] points being left out
+	pub fn match_points<'a, NId, N, E, FL, const DIRESPECT: bool>(g: &'a Graph<NId, N, E>, points: &[(f64, f64)], length: FL, m: &Matching) -> Vec<Vec<&'a E>>
+	where
+		NId: Clone + Copy + Hash + Eq,
+		N: Positioned,
+		E: Edge<NId>,
+		FL: Fn(&E) -> f64,
+	{
+		if points.is_empty() {
+			return Vec::new();
+		}
+		let lat0 = points.iter().map(|p| p.1).sum::<f64>() / points.len() as f64;
+		let coords: HashMap<NId, (f64, f64)> = g.nodes().map(|(id, n)| (id, project(n.pos(), lat0))).collect();
+		let index = crate::spatial::Segments::new(g.edges().filter_map(|e| Some(((*coords.get(&e.p1())?, *coords.get(&e.p2())?), e))));
+		let candidates = |p: (f64, f64)| -> Vec<Candidate<'a, E>> {
+			let mut cs: Vec<_> = index.near(p, m.radius).filter_map(|e| {
+				let (distance, along) = point_segment(p, *coords.get(&e.p1())?, *coords.get(&e.p2())?);
+				Some(Candidate { edge: *e, along, distance }).filter(|c| c.distance <= m.radius)
+			}).collect();
+			cs.sort_unstable_by(|a, b| a.distance.total_cmp(&b.distance));
+			cs.truncate(m.candidates);
+			cs
+		};
+		let emission = |c: &Candidate<E>| c.distance * c.distance / (2.0 * m.sigma * m.sigma);
+		// ways to leave/enter an edge from a position along it, with the distance to the respective vertex
+		let exits = |c: &Candidate<E>| {
+			let l = length(c.edge);
+			let mut xs = vec![(c.edge.p2(), (1.0 - c.along) * l)];
+			if !DIRESPECT || !c.edge.directed() {
+				xs.push((c.edge.p1(), c.along * l));
+			}
+			xs
+		};
+		let entries = |c: &Candidate<E>| {
+			let l = length(c.edge);
+			let mut xs = vec![(c.edge.p1(), c.along * l)];
+			if !DIRESPECT || !c.edge.directed() {
+				xs.push((c.edge.p2(), (1.0 - c.along) * l));
+			}
+			xs
+		};
+		// bounded multi-source Dijkstra
+		let reach = |sources: Vec<(NId, f64)>, bound: f64| {
+			let mut dist: HashMap<NId, f64> = HashMap::default();
+			let mut q = PriorityQueue::new();
+			for (u, d) in sources {
+				if dist.get(&u).is_none_or(|du| *du > d) {
+					dist.insert(u, d);
+					q.push(u, -n64(d));
+				}
+			}
+			while let Some((u, d)) = q.pop() {
+				let d = -d.raw();
+				for e in g.get_edges(u) {
+					if e.is_outgoing::<DIRESPECT>(u) {
+						let v = e.other(u);
+						let dv = d + length(e);
+						if dv <= bound && dist.get(&v).is_none_or(|d| *d > dv) {
+							dist.insert(v, dv);
+							q.push(v, -n64(dv));
+						}
+					}
+				}
+			}
+			dist
+		};
+		let decode = |hist: &Vec<Vec<(Candidate<'a, E>, Option<usize>)>>, costs: &Vec<f64>| -> Vec<&'a E> {
+			let mut i = (0..costs.len()).min_by(|a, b| costs[*a].total_cmp(&costs[*b])).unwrap();
+			let mut matched = Vec::new();
+			for layer in hist.iter().rev() {
+				matched.push(layer[i].0.edge);
+				i = layer[i].1.unwrap_or(0);
+			}
+			matched.reverse();
+			matched.dedup();
+			let mut path: Vec<&E> = Vec::new();
+			for e in matched {
+				if let Some(last) = path.last() {
+					let us: HashSet<_> = if !DIRESPECT || !last.directed() { vec![last.p1(), last.p2()] } else { vec![last.p2()] }.into_iter().collect();
+					let vs: HashSet<_> = if !DIRESPECT || !e.directed() { vec![e.p1(), e.p2()] } else { vec![e.p1()] }.into_iter().collect();
+					if us.is_disjoint(&vs) {
+						if let Some((.., gap)) = g.pathfind_regions::<N64, _, DIRESPECT>(&us, &vs, |e| Some(n64(length(e)))) {
+							path.extend(gap);
+						}
+					}
+				}
+				path.push(e);
+			}
+			path
+		};
+		let mut paths = Vec::new();
+		let mut hist: Vec<Vec<(Candidate<E>, Option<usize>)>> = Vec::new();
+		let mut costs: Vec<f64> = Vec::new();
+		let mut prev = (0.0, 0.0);
+		for p in points.iter().map(|p| project(*p, lat0)) {
+			let cands = candidates(p);
+			if cands.is_empty() {
+				continue;
+			}
+			let mut next_costs = vec![f64::INFINITY; cands.len()];
+			let mut backs = vec![None; cands.len()];
+			if let Some(last) = hist.last() {
+				let straight = distance(prev, p);
+				let bound = 2.0 * straight + 10.0 * m.beta;
+				for (i, (c, _)) in last.iter().enumerate() {
+					let dist = reach(exits(c), bound);
+					for (j, c2) in cands.iter().enumerate() {
+						let route = if c.edge == c2.edge {
+							Some((c2.along - c.along).abs() * length(c.edge))
+						} else {
+							entries(c2).into_iter().filter_map(|(v, dv)| dist.get(&v).map(|d| d + dv)).min_by(|a, b| a.total_cmp(b))
+						};
+						if let Some(route) = route {
+							let cost = costs[i] + (route - straight).abs() / m.beta + emission(c2);
+							if cost < next_costs[j] {
+								next_costs[j] = cost;
+								backs[j] = Some(i);
+							}
+						}
+					}
+				}
+				if next_costs.iter().all(|c| c.is_infinite()) {
+					log::trace!("map matching broke, starting over");
+					if hist.len() >= m.points {
+						paths.push(decode(&hist, &costs));
+					}
+					hist.clear();
+				}
+			}
+			if hist.is_empty() {
+				next_costs = cands.iter().map(emission).collect();
+				backs = vec![None; cands.len()];
+			}
+			costs = next_costs;
+			hist.push(cands.into_iter().zip(backs).collect());
+			prev = p;
+		}
+		if !hist.is_empty() && hist.len() >= m.points {
+			paths.push(decode(&hist, &costs));
+		}
+		paths
+	}
+}
+
+/// Random graph strategies, for property tests of graph algorithms
+#[cfg(test)]
+mod testgen {
+	use super::*;
+	use proptest::prelude::*;
+
+	/// Metadata of generated edges: their directionality
+	#[derive(Clone, Copy, Debug)]
+	pub struct Directed(pub bool);
+	impl EdgeMeta for Directed {
+		type Key = ();
+		fn key(&self) {}
+		fn directed(&self) -> bool {
+			self.0
+		}
+	}
+
+	/// Generated edge, told apart from its parallel ones by their index as discriminator
+	pub type TestEdge = WeightedEdge<u64, Directed>;
+	/// Generated graph, with nodes positioned around `(0, 0)`
+	pub type TestGraph = Graph<u64, (f64, f64), TestEdge>;
+
+	/// Shape of generated graphs
+	#[derive(Clone, Copy, Debug)]
+	pub struct Shape {
+		/// number of nodes
+		pub nodes: usize,
+		/// number of edges on top of those connecting the nodes
+		pub extra_edges: usize,
+		/// probability of an edge being directed
+		pub directed: f64,
+		/// probability of an edge having a parallel one
+		pub parallel: f64,
+		/// maximum weight of an edge (weights are at least 1)
+		pub max_weight: f64,
+	}
+
+	impl Default for Shape {
+		fn default() -> Self {
+			Self { nodes: 50, extra_edges: 50, directed: 0.3, parallel: 0.1, max_weight: 100.0 }
+		}
+	}
+
+	/// Copies of an edge between 2 nodes - whether it is flipped, whether it is directed, and its weight - possibly doubled by a parallel one
+	fn copies(shape: Shape) -> impl Strategy<Value = Vec<(bool, bool, f64)>> {
+		let copy = (any::<bool>(), prop::bool::weighted(shape.directed), 1.0..shape.max_weight.max(1.0 + f64::EPSILON));
+		(copy.clone(), prop::option::weighted(shape.parallel, copy)).prop_map(|(a, b)| std::iter::once(a).chain(b).collect())
+	}
+
+	/// Random mixed graphs, connected regardless of the directionality of edges
+	///
+	/// Nodes are connected by a random spanning tree, then random edges are added - any of them possibly directed, and doubled by a parallel one.
+	pub fn connected(shape: Shape) -> impl Strategy<Value = TestGraph> {
+		let nodes = shape.nodes.max(1) as u64;
+		let positions = prop::collection::vec((-0.01..0.01, -0.01..0.01), nodes as usize);
+		let tree: Vec<_> = (1..nodes).map(|v| (0..v, Just(v), copies(shape))).collect();
+		let extra = prop::collection::vec((0..nodes, 0..nodes, copies(shape)), shape.extra_edges);
+		(positions, tree, extra).prop_map(|(positions, tree, extra)| {
+			let mut g = TestGraph::default();
+			for (n, pos) in positions.into_iter().enumerate() {
+				g.add_node(n as u64, pos);
+			}
+			let mut index = 0;
+			for (u, v, copies) in tree.into_iter().chain(extra.into_iter().filter(|(u, v, _)| u != v)) {
+				for (flipped, directed, weight) in copies {
+					let (p1, p2) = if flipped { (v, u) } else { (u, v) };
+					g.add_edge(TestEdge { p1, p2, discriminator: Some(index), weight: crate::n64(weight), meta: Directed(directed) });
+					index += 1;
+				}
+			}
+			g
+		})
+	}
+
+	/// Checks that a path is made of successive edges from a node to another, traversable one after the other
+	pub fn is_path<const DIRESPECT: bool>(path: &[&TestEdge], n1: u64, n2: u64) -> Result<(), TestCaseError> {
+		let mut at = n1;
+		for e in path {
+			prop_assert!(e.is_outgoing::<DIRESPECT>(at), "edge {}->{} can't be traversed from {}", e.p1, e.p2, at);
+			at = e.other(at);
+		}
+		prop_assert_eq!(at, n2, "path from {} doesn't end at {}", n1, n2);
+		Ok(())
+	}
+}
+
+#[cfg(test)]
+mod test {
+	use super::*;
+	use proptest::prelude::*;
+
+	impl Edge<u64> for (u64, u64) {
+		fn p1(&self) -> u64 {
+			self.0
+		}
+		fn p2(&self) -> u64 {
+			self.1
+		}
+		fn directed(&self) -> bool {
+			true
+		}
+	}
+	impl<W: Hash + Eq + Clone> Edge<u64> for (u64, u64, W) {
+		fn p1(&self) -> u64 {
+			self.0
+		}
+		fn p2(&self) -> u64 {
+			self.1
+		}
+		fn directed(&self) -> bool {
+			true
+		}
+	}
+
+	macro_rules! graph {
+		($edges:expr) => {
+			{
+				let mut g: Graph<_, _, _> = Default::default();
+				for e in $edges {
+					g.add_node(e.p1(), ());
+					g.add_node(e.p2(), ());
+					g.add_edge(e);
+				}
+				g
+			}
+		};
+	}
+
+	macro_rules! assert_eq_unordered {
+		($left:expr, $right:expr) => {
+			match (&$left, &$right) {
+				(left, right) => {
+					if left.len() != right.len() {
+						assert_eq!(left, right);
+					} else {
+						for i in left {
+							if !right.contains(i) {
+								assert_eq!(left, right);
+							}
+						}
+					}
+				}
+			}
+		};
+	}
+
+	#[test]
+	fn test_sccs(){
+		let g = graph!(vec![(0, 1)]);
+		assert_eq_unordered!(g.strongly_connected_components::<true, false>(), vec![vec![0].into_iter().collect(), vec![1].into_iter().collect()]);
+		assert_eq_unordered!(g.strongly_connected_components::<false, false>(), vec![vec![0, 1].into_iter().collect()]);
+		let g = graph!(vec![(0, 1), (1, 2), (2, 0), (3, 1), (3, 2), (4, 5), (5, 4)]);
+		assert_eq_unordered!(g.strongly_connected_components::<true, false>(), vec![vec![0, 1, 2].into_iter().collect(), vec![3].into_iter().collect(), vec![4, 5].into_iter().collect()]);
+		assert_eq_unordered!(g.strongly_connected_components::<false, false>(), vec![vec![0, 1, 2, 3].into_iter().collect(), vec![4, 5].into_iter().collect()]);
+		assert_eq_unordered!(g.forward_backward_sccs::<true, false>(), vec![vec![0, 1, 2].into_iter().collect(), vec![3].into_iter().collect(), vec![4, 5].into_iter().collect()]);
+		assert_eq_unordered!(g.forward_backward_sccs::<false, false>(), vec![vec![0, 1, 2, 3].into_iter().collect(), vec![4, 5].into_iter().collect()]);
+	}
+
+	#[test]
+	fn test_bridges(){
+		let g = graph!(vec![(0, 1), (1, 2), (2, 0), (2, 3), (3, 4), (4, 5), (5, 3), (6, 7)]);
+		assert_eq_unordered!(g.bridges(), vec![&(2, 3), &(6, 7)]);
+		assert_eq!(g.articulation_points(), vec![2, 3].into_iter().collect());
+		let g = graph!(vec![(0, 1, 'a'), (1, 0, 'b'), (1, 2, 'c')]);
+		assert_eq!(g.bridges(), vec![&(1, 2, 'c')]);
+		assert_eq!(g.articulation_points(), vec![1].into_iter().collect());
+	}
+
+	#[derive(Clone, PartialEq, Debug)]
+	struct Named(&'static str, u32);
+	impl adapt::IdentifiableNode for Named {
+		type Id = &'static str;
+		fn id(&self) -> &Self::Id {
+			&self.0
+		}
+	}
+
+	#[test]
+	fn test_adapter_nodes(){
+		let mut g: adapt::GraphAdapter<u64, Named, (u64, u64), u64, _> = adapt::GraphAdapter::new(0, |_, id| (id, id+1));
+		assert_eq!(g.add_node(Named("a", 1)), None);
+		assert_eq!(g.add_node(Named("b", 1)), None);
+		let (a, b) = (g.id2nid(&"a").unwrap(), g.id2nid(&"b").unwrap());
+		assert_ne!(a, b);
+		g.add_edge((a, b));
+		// same id: replaced in place, keeping its light id and edges
+		assert_eq!(g.add_node(Named("a", 2)), Some(Named("a", 1)));
+		assert_eq!(g.id2nid(&"a"), Some(a));
+		assert_eq!(g.nid2node(a), Some(&Named("a", 2)));
+		assert_eq!(g.graph.get_edges(a).len(), 1);
+		assert_eq!(g.update_node(Named("b", 2)), Ok(Named("b", 1)));
+		assert_eq!(g.update_node(Named("c", 1)), Err(Named("c", 1)));
+		assert_eq!(g.id2nid(&"c"), None);
+		assert_eq!(g.remove_node(&"a"), Some(Named("a", 2)));
+		assert_eq!(g.id2nid(&"a"), None);
+		assert!(g.graph.get_edges(b).is_empty());
+		assert!(!g.remove_edge(&(a, b)));
+	}
+
+	proptest! {
+		#![proptest_config(ProptestConfig::with_cases(50))]
+
+		#[test]
+		fn test_pathfind_connects(g in testgen::connected(Default::default()), pairs in prop::collection::vec((0..50u64, 0..50u64), 20)){
+			let weight = |e: &testgen::TestEdge| Some(e.weight);
+			let h = ch::Hierarchy::new::<_, _, true>(&g, weight);
+			for (n1, n2) in pairs {
+				let path = g.pathfind::<_, _, false>(n1, n2, weight);
+				prop_assert!(path.is_some(), "no path from {} to {} in a connected graph", n1, n2);
+				testgen::is_path::<false>(&path.unwrap(), n1, n2)?;
+				let path = g.pathfind::<_, _, true>(n1, n2, weight);
+				if let Some(path) = &path {
+					testgen::is_path::<true>(path, n1, n2)?;
+				}
+				let length = |path: &Option<Vec<&testgen::TestEdge>>| path.as_ref().map(|path| path.iter().map(|e| e.weight).sum::<crate::N64>());
+				let shortcut = h.pathfind(&g, n1, n2);
+				prop_assert_eq!(length(&shortcut), length(&path), "contraction hierarchy path from {} to {}", n1, n2);
+			}
+		}
+
+		#[test]
+		fn test_sccs_partition(g in testgen::connected(testgen::Shape { extra_edges: 20, directed: 0.7, ..Default::default() })){
+			let nodes: HashSet<_> = g.nodes().map(|(n, _)| n).filter(|n| !g.is_orphan(*n)).collect();
+			for sccs in IntoIterator::into_iter([g.tarjan_sccs::<true, false>(), g.forward_backward_sccs::<true, false>()]) {
+				let mut covered = HashSet::default();
+				for n in sccs.iter().flatten() {
+					prop_assert!(covered.insert(*n), "node {} in several SCCs", n);
+				}
+				prop_assert!(covered == nodes, "SCCs cover {} nodes out of {}", covered.len(), nodes.len());
+			}
+		}
+
+		#[test]
+		fn test_patch_sccs(mut g in testgen::connected(testgen::Shape { directed: 0.5, ..Default::default() })){
+			let regions = g.strongly_connected_components::<true, false>();
+			g.patch_sccs::<_, true>(&regions, |e| testgen::TestEdge { meta: testgen::Directed(false), ..e });
+			prop_assert_eq!(g.strongly_connected_components::<true, false>().len(), 1, "SCCs left after patching {}", regions.len());
+		}
+	}
+}
@@ -0,0 +1,130 @@
+//! Kafka event bus driver, for live storm operations integrated with the city's infrastructure
+//!
+//! Consumes snow observations ([`data::SnowStatuses`] documents) and plow progress events ([`VehicleEvent`] documents) from their topics, maintaining the live snow & vehicles state, and periodically publishes updated road plowing plans ([`data::Paths`] documents) while the state keeps changing.
+
+use crate::*;
+use data::*;
+
+use std::time::{Duration, Instant};
+use rdkafka::{ClientConfig, Message, consumer::{BaseConsumer, Consumer}, producer::{BaseProducer, BaseRecord, Producer}};
+use serde::*;
+
+/// Live progress event: a vehicle just traversed (and cleared) a road segment, and is now at its `p2`
+#[derive(Serialize, Deserialize, Clone, PartialEq, Debug)]
+pub struct VehicleEvent {
+	/// index of the vehicle, in the vehicles configuration
+	pub vehicle: usize,
+	pub p1: NodeId,
+	pub p2: NodeId,
+	pub discriminator: Option<NodeId>,
+}
+
+impl document::Kind for VehicleEvent {
+	const KIND: &'static str = "vehicle-event";
+}
+
+/// Kafka settings
+#[derive(Clone, Debug)]
+pub struct Kafka<'a> {
+	/// bootstrap servers
+	pub brokers: &'a str,
+	/// consumer group
+	pub group: &'a str,
+	/// topic of snow status updates
+	pub snow_topic: &'a str,
+	/// topic of vehicle progress events
+	pub progress_topic: &'a str,
+	/// topic plans are published to
+	pub plans_topic: &'a str,
+	/// minimum period between plans
+	pub interval: Duration,
+}
+
+/// Live state, as updated by events
+struct State {
+	snow: SnowStatuses,
+	vehicles: VehiclesConfiguration,
+	/// whether the state changed since the last plan
+	dirty: bool,
+}
+
+impl State {
+	/// Applies a snow status update
+	fn observe(&mut self, snow: SnowStatuses) {
+		self.snow = snow::merge(std::mem::take(&mut self.snow).into_iter().chain(snow), &snow::Mean);
+		self.dirty = true;
+	}
+
+	/// Applies a vehicle progress event: the segment is cleared, and the vehicle is at its end
+	fn progress(&mut self, event: VehicleEvent) {
+		let key = segment_key(&event.p1, &event.p2, &event.discriminator);
+		self.snow.retain(|s| segment_key(&s.p1, &s.p2, &s.discriminator) != key);
+		match self.vehicles.road.get_mut(event.vehicle) {
+			Some(location) => *location = Location::Node(event.p2),
+			None => log::warn!("Progress of unknown vehicle {}", event.vehicle),
+		}
+		self.dirty = true;
+	}
+
+	/// Applies an event, skipping invalid ones
+	fn handle(&mut self, message: &impl Message, settings: &Kafka) {
+		let doc = match message.payload().map(serde_json::from_slice::<serde_json::Value>) {
+			Some(Ok(doc)) => doc,
+			Some(Err(e)) => return log::warn!("Skipping invalid JSON event on {}: {}", message.topic(), e),
+			None => return,
+		};
+		if message.topic() == settings.snow_topic {
+			match document::parse(doc) {
+				Ok(snow) => self.observe(snow),
+				Err(e) => log::warn!("Skipping invalid snow status: {}", e),
+			}
+		} else {
+			match document::parse(doc) {
+				Ok(event) => self.progress(event),
+				Err(e) => log::warn!("Skipping invalid vehicle event: {}", e),
+			}
+		}
+	}
+}
+
+/// Consumes events forever, publishing plans as the state changes
+pub fn run(roads: RoadGraph, snow: SnowStatuses, overlay: &CostOverlay, vehicles: VehiclesConfiguration, params: &meta::Parameters, settings: &Kafka) -> rdkafka::error::KafkaResult<()> {
+	let consumer: BaseConsumer = ClientConfig::new()
+		.set("bootstrap.servers", settings.brokers)
+		.set("group.id", settings.group)
+		.set("enable.auto.commit", "true")
+		.create()?;
+	consumer.subscribe(&[settings.snow_topic, settings.progress_topic])?;
+	let producer: BaseProducer = ClientConfig::new()
+		.set("bootstrap.servers", settings.brokers)
+		.create()?;
+	let mut state = State { snow, vehicles, dirty: true };
+	let mut last_plan: Option<Instant> = None;
+	log::info!("Consuming {} and {}", settings.snow_topic, settings.progress_topic);
+	loop {
+		match consumer.poll(Duration::from_millis(500)) {
+			Some(Ok(message)) => state.handle(&message, settings),
+			Some(Err(e)) => {
+				log::warn!("Failed to consume: {}", e);
+				std::thread::sleep(Duration::from_secs(1));
+			},
+			None => {},
+		}
+		if state.dirty && last_plan.is_none_or(|at| at.elapsed() >= settings.interval) {
+			state.dirty = false;
+			last_plan = Some(Instant::now());
+			log::info!("Re-solving, {} segments in snow status", state.snow.len());
+			match plow::road::solve(roads.clone(), state.snow.clone(), Vec::new(), None, overlay, &[], None, &[], state.vehicles.clone(), params) {
+				Ok((paths, _)) => {
+					let payload = serde_json::to_vec(&document::wrap(&paths)).unwrap();
+					let published = producer.send(BaseRecord::<(), _>::to(settings.plans_topic).payload(&payload)).map_err(|(e, _)| e).and_then(|_| producer.flush(Duration::from_secs(10)));
+					match published {
+						Ok(()) => log::info!("Published plan to {}", settings.plans_topic),
+						Err(e) => log::error!("Failed to publish plan: {}", e),
+					}
+				},
+				Err(e) => log::error!("Re-solve failed: {}", e),
+			}
+		}
+	}
+}
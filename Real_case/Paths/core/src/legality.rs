@@ -0,0 +1,287 @@
+//! Legality checks of paths - whether they can actually be driven on the road graph, and cover the snowy segments
+
+use crate::*;
+use data::*;
+
+use std::collections::{HashMap, HashSet};
+use serde::*;
+
+/// A way in which a path can't be driven
+#[derive(Serialize, Deserialize, Clone, PartialEq, Debug)]
+#[serde(tag = "kind")]
+pub enum Violation {
+	/// the node doesn't exist
+	#[serde(rename = "unknown-node")]
+	UnknownNode {
+		vehicle: usize,
+		step: usize,
+		node: NodeId,
+	},
+	/// there is no segment between successive nodes
+	#[serde(rename = "discontinuity")]
+	Discontinuity {
+		vehicle: usize,
+		step: usize,
+		from: NodeId,
+		to: NodeId,
+	},
+	/// there are segments between successive nodes, but none with the discriminator
+	#[serde(rename = "unknown-discriminator")]
+	UnknownDiscriminator {
+		vehicle: usize,
+		step: usize,
+		from: NodeId,
+		to: NodeId,
+		discriminator: Option<NodeId>,
+	},
+	/// the segment is one-way, in the other direction
+	#[serde(rename = "wrong-way")]
+	WrongWay {
+		vehicle: usize,
+		step: usize,
+		from: NodeId,
+		to: NodeId,
+	},
+	/// the segment has no sidewalk on that side
+	#[serde(rename = "missing-sidewalk")]
+	MissingSidewalk {
+		vehicle: usize,
+		step: usize,
+		from: NodeId,
+		to: NodeId,
+		side: SidewalkSide,
+	},
+	/// the turn is restricted
+	#[serde(rename = "forbidden-turn")]
+	ForbiddenTurn {
+		vehicle: usize,
+		step: usize,
+		from: NodeId,
+		via: NodeId,
+		to: NodeId,
+	},
+}
+
+/// Snowy segment (or sidewalk) that no path covers
+#[derive(Serialize, Deserialize, Clone, PartialEq, Debug)]
+pub struct Uncovered {
+	pub p1: NodeId,
+	pub p2: NodeId,
+	pub discriminator: Option<NodeId>,
+	/// the sidewalk, when verifying sidewalk paths
+	#[serde(default, skip_serializing_if = "Option::is_none")]
+	pub side: Option<SidewalkSide>,
+	pub depth: N64,
+}
+
+/// Verification of the coverage of paths
+#[derive(Serialize, Deserialize, Clone, PartialEq, Debug)]
+pub struct Verification {
+	/// snowy segments (or sidewalks) no path traverses
+	pub uncovered: Vec<Uncovered>,
+	/// traversals of segments (or sidewalks) that don't exist, or not in that direction
+	pub nonexistent: Vec<Violation>,
+}
+impl Verification {
+	/// Whether the paths cover every snowy segment, through existing ones only
+	pub fn is_ok(&self) -> bool {
+		self.uncovered.is_empty() && self.nonexistent.is_empty()
+	}
+}
+
+/// Road paths as sidewalk paths, on the roads
+fn on_roads(paths: &Paths) -> SidewalkPaths {
+	paths.iter().map(|path| path.iter().map(|s| SidewalkPathSegment {
+		node: s.node.clone(),
+		discriminator: s.discriminator.clone(),
+		side: None,
+		stops: Vec::new(),
+	}).collect()).collect()
+}
+
+/// Checks that road paths can be driven, without making any restricted turn
+pub fn check_paths(roads: &RoadGraph, paths: &Paths) -> Vec<Violation> {
+	let mut violations = check_sidewalk_paths(roads, &on_roads(paths));
+	let restrictions: HashSet<_> = roads.restrictions.iter().map(|r| (&r.from, &r.via, &r.to)).collect();
+	for (vehicle, path) in paths.iter().enumerate() {
+		for (step, w) in path.windows(3).enumerate() {
+			if restrictions.contains(&(&w[0].node, &w[1].node, &w[2].node)) {
+				violations.push(Violation::ForbiddenTurn { vehicle, step: step + 2, from: w[0].node.clone(), via: w[1].node.clone(), to: w[2].node.clone() });
+			}
+		}
+	}
+	violations
+}
+
+/// Checks that sidewalk paths can be driven - on the roads where they have no side, and on existing sidewalks otherwise
+pub fn check_sidewalk_paths(roads: &RoadGraph, paths: &SidewalkPaths) -> Vec<Violation> {
+	let nodes: HashSet<_> = roads.nodes.nodes.iter().map(|n| &n.id).collect();
+	let mut segments: HashMap<(&NodeId, &NodeId), Vec<&RoadSegment>> = HashMap::new();
+	for r in &roads.roads {
+		segments.entry((&r.p1, &r.p2)).or_default().push(r);
+		if r.p1 != r.p2 {
+			segments.entry((&r.p2, &r.p1)).or_default().push(r);
+		}
+	}
+	let mut violations = Vec::new();
+	for (vehicle, path) in paths.iter().enumerate() {
+		for (step, s) in path.iter().enumerate() {
+			if !nodes.contains(&s.node) {
+				violations.push(Violation::UnknownNode { vehicle, step, node: s.node.clone() });
+				continue;
+			}
+			let from = match step.checked_sub(1).map(|i| &path[i].node) {
+				Some(from) => from,
+				None => continue,
+			};
+			let (from_, to_) = (from.clone(), s.node.clone());
+			let between = match segments.get(&(from, &s.node)) {
+				Some(between) => between,
+				None => {
+					violations.push(Violation::Discontinuity { vehicle, step, from: from_, to: to_ });
+					continue;
+				},
+			};
+			let matching: Vec<_> = between.iter().filter(|r| r.discriminator == s.discriminator).collect();
+			if matching.is_empty() {
+				violations.push(Violation::UnknownDiscriminator { vehicle, step, from: from_, to: to_, discriminator: s.discriminator.clone() });
+				continue;
+			}
+			match &s.side {
+				None => if !matching.iter().any(|r| !r.directed || &r.p1 == from) {
+					violations.push(Violation::WrongWay { vehicle, step, from: from_, to: to_ });
+				},
+				Some(side) => if !matching.iter().any(|r| match side {
+					SidewalkSide::Left => r.sidewalks.0,
+					SidewalkSide::Right => r.sidewalks.1,
+				}) {
+					violations.push(Violation::MissingSidewalk { vehicle, step, from: from_, to: to_, side: *side });
+				},
+			}
+		}
+	}
+	violations
+}
+
+/// Verifies that road paths cover every snowy segment, driving one-way segments the right way
+///
+/// Arguments:
+/// - `params`: meta parameters, for the segments needing clearing as the solver tells them (any with snow if not given)
+///
+/// Returns: the snowy segments left uncovered, and the traversals of segments that don't exist
+pub fn verify_paths(roads: &RoadGraph, snow: &SnowStatuses, paths: &Paths, params: Option<&meta::Parameters>) -> Verification {
+	verify(roads, snow, &on_roads(paths), false, params)
+}
+
+/// Verifies that sidewalk paths cover both sides of every snowy segment, where it has sidewalks
+///
+/// Arguments:
+/// - `params`: meta parameters, for the segments needing clearing as the solver tells them (any with snow if not given)
+///
+/// Returns: the snowy sidewalks left uncovered, and the traversals of sidewalks (or roads) that don't exist
+pub fn verify_sidewalk_paths(roads: &RoadGraph, snow: &SnowStatuses, paths: &SidewalkPaths, params: Option<&meta::Parameters>) -> Verification {
+	verify(roads, snow, paths, true, params)
+}
+
+/// Verifies that paths cover the snowy segments, or their sidewalks - only traversals [`check_sidewalk_paths`] finds no fault with counting
+fn verify(roads: &RoadGraph, snow: &SnowStatuses, paths: &SidewalkPaths, sidewalks: bool, params: Option<&meta::Parameters>) -> Verification {
+	let nonexistent = check_sidewalk_paths(roads, paths);
+	let faulty: HashSet<_> = nonexistent.iter().filter_map(|v| match v {
+		Violation::UnknownNode { vehicle, step, .. } | Violation::Discontinuity { vehicle, step, .. } | Violation::UnknownDiscriminator { vehicle, step, .. } | Violation::WrongWay { vehicle, step, .. } | Violation::MissingSidewalk { vehicle, step, .. } => Some((*vehicle, *step)),
+		Violation::ForbiddenTurn { .. } => None,
+	}).collect();
+	let mut covered = HashSet::new();
+	for (vehicle, path) in paths.iter().enumerate() {
+		for (step, w) in path.windows(2).enumerate() {
+			if !faulty.contains(&(vehicle, step + 1)) && (w[1].side.is_some() == sidewalks) {
+				covered.insert((segment_key(&w[0].node, &w[1].node, &w[1].discriminator), w[1].side));
+			}
+		}
+	}
+	let segments: HashMap<_, _> = roads.roads.iter().map(|r| (segment_key(&r.p1, &r.p2, &r.discriminator), r)).collect();
+	let mut uncovered = Vec::new();
+	for s in snow {
+		let k = segment_key(&s.p1, &s.p2, &s.discriminator);
+		let r = match segments.get(&k) {
+			Some(r) => r,
+			None => continue,
+		};
+		let required = match params {
+			Some(params) => s.depth > 0.0 && params.depths.is_likely(params.depths.clearing_probability(r.class, s)),
+			None => s.depth > 0.0,
+		};
+		if !required {
+			continue;
+		}
+		let sides = if sidewalks {
+			[(r.sidewalks.0, Some(SidewalkSide::Left)), (r.sidewalks.1, Some(SidewalkSide::Right))].iter().filter(|(has, _)| *has).map(|(_, side)| *side).collect()
+		} else {
+			vec![None]
+		};
+		for side in sides {
+			if !covered.contains(&(k.clone(), side)) {
+				uncovered.push(Uncovered { p1: s.p1.clone(), p2: s.p2.clone(), discriminator: s.discriminator.clone(), side, depth: s.depth });
+			}
+		}
+	}
+	Verification { uncovered, nonexistent }
+}
+
+#[cfg(test)]
+mod test {
+	use super::*;
+
+	/// A two-way segment with a left sidewalk, then a one-way segment, both snowy
+	fn instance() -> (RoadGraph, SnowStatuses) {
+		let roads = serde_json::from_value(serde_json::json!({
+			"nodes": [{ "id": "a", "coordinates": [-73.6, 45.5] }, { "id": "b", "coordinates": [-73.6, 45.501] }, { "id": "c", "coordinates": [-73.6, 45.502] }],
+			"roads": [
+				{ "p1": "a", "p2": "b", "directed": false, "sidewalks": [true, false], "distance": 111.0 },
+				{ "p1": "b", "p2": "c", "directed": true, "sidewalks": [false, false], "distance": 111.0 },
+			],
+		})).unwrap();
+		let snow = serde_json::from_value(serde_json::json!([{ "p1": "a", "p2": "b", "depth": 50 }, { "p1": "b", "p2": "c", "depth": 50 }])).unwrap();
+		(roads, snow)
+	}
+
+	fn paths(tours: serde_json::Value) -> Paths {
+		serde_json::from_value(tours).unwrap()
+	}
+
+	#[test]
+	fn covering_paths_verify() {
+		let (roads, snow) = instance();
+		let verification = verify_paths(&roads, &snow, &paths(serde_json::json!([[{ "node": "a" }, { "node": "b" }, { "node": "c" }]])), None);
+		assert!(verification.is_ok(), "{:?}", verification);
+	}
+
+	#[test]
+	fn wrong_way_doesnt_cover() {
+		let (roads, snow) = instance();
+		let verification = verify_paths(&roads, &snow, &paths(serde_json::json!([[{ "node": "c" }, { "node": "b" }, { "node": "a" }]])), None);
+		assert_eq!(verification.nonexistent, vec![Violation::WrongWay { vehicle: 0, step: 1, from: "c".into(), to: "b".into() }]);
+		assert_eq!(verification.uncovered.len(), 1);
+		assert_eq!((verification.uncovered[0].p1.as_ref(), verification.uncovered[0].p2.as_ref()), ("b", "c"));
+	}
+
+	#[test]
+	fn discontinuities_and_unknown_nodes() {
+		let (roads, snow) = instance();
+		let verification = verify_paths(&roads, &snow, &paths(serde_json::json!([[{ "node": "a" }, { "node": "c" }, { "node": "x" }]])), None);
+		assert_eq!(verification.nonexistent, vec![
+			Violation::Discontinuity { vehicle: 0, step: 1, from: "a".into(), to: "c".into() },
+			Violation::UnknownNode { vehicle: 0, step: 2, node: "x".into() },
+		]);
+		assert_eq!(verification.uncovered.len(), 2);
+	}
+
+	#[test]
+	fn sidewalks_need_their_side() {
+		let (roads, snow) = instance();
+		let tours = |side: &str| -> SidewalkPaths { serde_json::from_value(serde_json::json!([[{ "node": "a" }, { "node": "b", "side": side }]])).unwrap() };
+		assert!(verify_sidewalk_paths(&roads, &snow, &tours("left"), None).is_ok());
+		let verification = verify_sidewalk_paths(&roads, &snow, &tours("right"), None);
+		assert_eq!(verification.nonexistent, vec![Violation::MissingSidewalk { vehicle: 0, step: 1, from: "a".into(), to: "b".into(), side: SidewalkSide::Right }]);
+		assert_eq!(verification.uncovered.len(), 1);
+	}
+}
@@ -0,0 +1,64 @@
+//! # _make 'em fly & make 'em plow_
+//!
+//! Solvers for flight & plow problems, as well as conversions of spec'd data to/from GeoJSON - the CLI being a thin wrapper around them.
+//!
+//! Entry points are the solvers ([`plow::fly::solve`], [`plow::road::solve`], [`plow::sidewalk::solve`]) over the [`data`] types, with [`meta::Parameters`], failing with an [`error::Error`].
+
+use std::borrow::Cow;
+
+pub mod data;
+pub mod graph;
+pub mod meta;
+pub mod plow;
+pub mod gj;
+pub mod gpx;
+pub mod rolling;
+pub mod report;
+pub mod traces;
+pub mod diagnostics;
+pub mod accept;
+pub mod allocation;
+pub mod cost;
+pub mod legality;
+pub mod document;
+pub mod cache;
+pub mod watch;
+pub mod metrics;
+pub mod progress;
+pub mod control;
+pub mod error;
+pub mod auth;
+pub mod serve;
+pub mod montecarlo;
+pub mod repro;
+pub mod salting;
+pub mod combined;
+pub mod osm;
+pub mod spatial;
+pub mod snow;
+pub mod sensors;
+pub mod bench;
+#[cfg(feature = "kafka")]
+pub mod kafka;
+pub use try_all::{TryAll, TryMapAll};
+pub use noisy_float::prelude::{N64, n64, Float};
+
+pub type NodeId = Cow<'static, str>;
+
+/// Snow status, either formal or as GeoJSON
+#[derive(serde::Serialize, serde::Deserialize, Clone, PartialEq, Debug)]
+#[serde(untagged)]
+pub enum SnuwDapg {
+	Formal(data::SnowStatuses),
+	Geo(geojson::FeatureCollection),
+}
+
+/// Marks the road segments of cleared layers as clear of snow, whatever their merged samples said
+pub fn clear_snow_statuses(snow: &mut data::SnowStatuses, cleared: impl Iterator<Item = data::SnowStatuses>) {
+	let cleared: std::collections::HashSet<_> = cleared.flatten().map(|s| data::segment_key(&s.p1, &s.p2, &s.discriminator)).collect();
+	for s in snow.iter_mut().filter(|s| cleared.contains(&data::segment_key(&s.p1, &s.p2, &s.discriminator))) {
+		s.depth = n64(0.0);
+		s.probability = None;
+		s.variance = None;
+	}
+}
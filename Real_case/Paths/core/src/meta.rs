@@ -0,0 +1,845 @@
+//! Meta parameters for the ❄ plow problem annealing solver
+
+use crate::*;
+use serde::*;
+use std::convert::TryFrom;
+
+#[derive(Serialize, Deserialize, Clone, Copy, PartialEq, Default, Debug)]
+pub enum Recycle {
+	/// do not move cycles
+	No,
+	/// move cycles between adjacent tours from expensive to cheap tour
+	#[default]
+	ExpensiveToCheap,
+}
+
+#[derive(Serialize, Deserialize, Clone, Copy, PartialEq, Default, Debug)]
+pub enum Clearing {
+	/// the vehicle clears only the allocated edges
+	OnlyAllocated,
+	/// the vehicle clears all edges
+	#[default]
+	All,
+}
+
+#[derive(Serialize, Deserialize, Clone, Copy, PartialEq, Default, Debug)]
+pub enum Reorder {
+	/// don't reorder
+	No,
+	/// swap 2 at random
+	Swap2Random,
+	/// generate new random order
+	#[default]
+	RandomReorder,
+	/// swap most and least used
+	Swap2MostLeast,
+}
+
+#[derive(Serialize, Deserialize, Clone, Copy, PartialEq, Default, Debug)]
+pub enum Realloc {
+	/// don't
+	#[default]
+	No,
+	/// swap 2 random links
+	Swap2Random,
+	/// move a link from vehicle that does most to one that does least
+	MostToLeast,
+}
+
+#[derive(Serialize, Deserialize, Clone, Copy, PartialEq, Debug)]
+#[serde(default)]
+pub struct Annealing {
+	pub main_iterations: u64, //MI
+	pub ft_iterations: u64, //II
+	pub starting_temperature: f64, //ST
+	/// factor the temperature is multiplied by every `ft_iterations`, in (0, 1)
+	pub cooling_factor: f64, //RC
+	/// wall-clock budget of annealing and polishing, in minutes, after which the best solution so far is returned
+	#[serde(skip_serializing_if = "Option::is_none")]
+	pub max_wall_time: Option<f64>,
+	/// whether the schedule tunes itself: starting at the standard deviation of objective values along a random walk (`starting_temperature` if they don't vary), and cooling faster or slower to keep to the `target_acceptance` rate, from the `cooling_factor`
+	pub adaptive: bool,
+	/// fraction of candidate solutions to accept, with the adaptive schedule, in (0, 1)
+	pub target_acceptance: f64,
+	/// number of independent runs of the whole procedure (polishing included), the best solution of which is kept
+	pub restarts: u32,
+	/// whether restarts run in parallel threads
+	pub parallel: bool,
+}
+impl Default for Annealing {
+	fn default() -> Self {
+		Self {
+			main_iterations: 8,
+			ft_iterations: 2,
+			starting_temperature: 1000.0,
+			cooling_factor: 0.3,
+			max_wall_time: None,
+			adaptive: false,
+			target_acceptance: 0.3,
+			restarts: 1,
+			parallel: false,
+		}
+	}
+}
+
+/// Annealing algorithm variant
+#[derive(Serialize, Deserialize, Clone, Copy, PartialEq, Default, Debug)]
+pub enum Algorithm {
+	/// a single annealing chain
+	#[default]
+	SingleChain,
+	/// a pool of chains, sharing new best solutions, and periodically resampled by their Boltzmann weights
+	Population {
+		/// number of chains
+		size: usize,
+		/// resampling period, in main iterations (0 never resamples)
+		resample: u64,
+	},
+}
+
+/// Metaheuristic searching allocations and evaluation orders
+#[derive(Serialize, Deserialize, Clone, Copy, PartialEq, Default, Debug)]
+pub enum Solver {
+	/// simulated annealing, as per [`Parameters::annealing`] and [`Parameters::algorithm`]
+	#[default]
+	Annealing,
+	/// tabu search, moving each iteration to the best sampled neighbour whose move isn't tabu (unless it beats the best solution so far)
+	Tabu {
+		/// number of iterations undoing a move stays tabu for
+		tenure: u64,
+		/// number of neighbours sampled each iteration, moving a segment between vehicles or swapping two vehicles in evaluation order
+		neighbours: usize,
+	},
+	/// genetic algorithm, evolving a population of allocations: children cross their parents' allocations over a random line, are mutated by a [`Parameters::realloc`] move, and replace all but the best individuals
+	Genetic {
+		/// number of individuals
+		population: usize,
+		/// number of generations, instead of [`Annealing::main_iterations`]
+		generations: u64,
+		/// number of best individuals carried over to the next generation as is
+		elitism: usize,
+	},
+}
+impl Solver {
+	/// Iterations budget of the solver
+	pub fn iterations(&self, annealing: &Annealing) -> u64 {
+		match self {
+			Solver::Genetic { generations, .. } => *generations,
+			_ => annealing.main_iterations,
+		}
+	}
+}
+
+/// Local-search polishing phase, run after annealing
+#[derive(Serialize, Deserialize, Clone, Copy, PartialEq, Debug)]
+pub struct Polishing {
+	/// maximum number of improving rounds (0 disables polishing)
+	#[serde(default = "Polishing::default_max_rounds")]
+	pub max_rounds: u64,
+	/// whether to try swapping neighbours in evaluation order (only matters when clearing all), re-routing every vehicle per swap
+	#[serde(default)]
+	pub reorder: bool,
+}
+impl Default for Polishing {
+	fn default() -> Self {
+		Self {
+			max_rounds: Self::default_max_rounds(),
+			reorder: false,
+		}
+	}
+}
+impl Polishing {
+	fn default_max_rounds() -> u64 {
+		16
+	}
+}
+
+/// Minimum snow depths (in mm), per road class, above which a segment needs clearing
+#[derive(Serialize, Deserialize, Clone, Copy, PartialEq, Debug)]
+pub struct DepthThresholds {
+	#[serde(default)]
+	pub arterial: N64,
+	#[serde(default)]
+	pub collector: N64,
+	#[serde(default)]
+	pub local: N64,
+	/// for segments without a class
+	#[serde(default)]
+	pub unclassified: N64,
+	/// minimum probability of exceeding the threshold, for uncertain observations, for a segment to need clearing
+	#[serde(default = "DepthThresholds::default_likelihood")]
+	pub likelihood: N64,
+}
+impl Default for DepthThresholds {
+	fn default() -> Self {
+		Self {
+			arterial: n64(0.0),
+			collector: n64(0.0),
+			local: n64(0.0),
+			unclassified: n64(0.0),
+			likelihood: Self::default_likelihood(),
+		}
+	}
+}
+impl DepthThresholds {
+	/// Minimum depth for a road class
+	pub fn threshold(&self, class: Option<data::RoadClass>) -> N64 {
+		match class {
+			Some(data::RoadClass::Arterial) => self.arterial,
+			Some(data::RoadClass::Collector) => self.collector,
+			Some(data::RoadClass::Local) => self.local,
+			None => self.unclassified,
+		}
+	}
+	/// Whether a segment of given class with given depth needs clearing
+	pub fn is_snowy(&self, class: Option<data::RoadClass>, depth: N64) -> bool {
+		depth > self.threshold(class)
+	}
+	/// Probability that a segment of given class needs clearing, given an (uncertain) observation.
+	///
+	/// Observed depth is assumed normally distributed with the observation's variance.
+	pub fn clearing_probability(&self, class: Option<data::RoadClass>, s: &data::SnowStatusElement) -> N64 {
+		let threshold = self.threshold(class);
+		let p = match s.variance.filter(|v| *v > 0.0) {
+			Some(v) => n64(1.0) - normal_cdf((threshold - s.depth) / v.sqrt()),
+			None if s.depth > threshold => n64(1.0),
+			None => n64(0.0),
+		};
+		p * s.probability.unwrap_or_else(|| n64(1.0))
+	}
+	/// Whether a segment needing clearing with given probability is to be cleared
+	pub fn is_likely(&self, p: N64) -> bool {
+		p > 0.0 && p >= self.likelihood
+	}
+	fn default_likelihood() -> N64 {
+		n64(0.5)
+	}
+}
+
+/// Priority weights of road classes (priority tiers), for the latency objective
+#[derive(Serialize, Deserialize, Clone, Copy, PartialEq, Debug)]
+pub struct Priorities {
+	#[serde(default = "Priorities::default_weight")]
+	pub arterial: N64,
+	#[serde(default = "Priorities::default_weight")]
+	pub collector: N64,
+	#[serde(default = "Priorities::default_weight")]
+	pub local: N64,
+	/// for segments without a class
+	#[serde(default = "Priorities::default_weight")]
+	pub unclassified: N64,
+}
+impl Default for Priorities {
+	fn default() -> Self {
+		Self {
+			arterial: Self::default_weight(),
+			collector: Self::default_weight(),
+			local: Self::default_weight(),
+			unclassified: Self::default_weight(),
+		}
+	}
+}
+impl Priorities {
+	/// Priority weight of a road class
+	pub fn weight(&self, class: Option<data::RoadClass>) -> N64 {
+		match class {
+			Some(data::RoadClass::Arterial) => self.arterial,
+			Some(data::RoadClass::Collector) => self.collector,
+			Some(data::RoadClass::Local) => self.local,
+			None => self.unclassified,
+		}
+	}
+	fn default_weight() -> N64 {
+		n64(1.0)
+	}
+}
+
+/// Turning rules and penalties at intersections
+#[derive(Serialize, Deserialize, Clone, Copy, PartialEq, Debug)]
+pub struct Turns {
+	/// whether vehicles may make U-turns (they always may at dead ends)
+	#[serde(default = "Turns::default_u_turns")]
+	pub u_turns: bool,
+	/// cost of turning (by more than 45°) from a segment into another
+	#[serde(default)]
+	pub penalty: N64,
+	/// cost of making a U-turn
+	#[serde(default)]
+	pub u_turn_penalty: N64,
+}
+impl Default for Turns {
+	fn default() -> Self {
+		Self {
+			u_turns: Self::default_u_turns(),
+			penalty: n64(0.0),
+			u_turn_penalty: n64(0.0),
+		}
+	}
+}
+impl Turns {
+	fn default_u_turns() -> bool {
+		true
+	}
+}
+
+/// Standard normal cumulative distribution function
+///
+/// Uses Abramowitz & Stegun 7.1.26 approximation of erf.
+fn normal_cdf(x: N64) -> N64 {
+	let z = x.raw().abs() / 2f64.sqrt();
+	let t = 1.0 / (1.0 + 0.3275911 * z);
+	let erf = 1.0 - t * (0.254829592 + t * (-0.284496736 + t * (1.421413741 + t * (-1.453152027 + t * 1.061405429)))) * (-z * z).exp();
+	n64(if x >= 0.0 { (1.0 + erf) / 2.0 } else { (1.0 - erf) / 2.0 })
+}
+
+/// Aggregation of solution values across snow scenarios
+#[derive(Serialize, Deserialize, Clone, Copy, PartialEq, Default, Debug)]
+pub enum Robustness {
+	/// value in the worst scenario
+	#[default]
+	WorstCase,
+	/// conditional value at risk - mean value of the worst `1-alpha` fraction of scenarios
+	CVaR { alpha: f64 },
+}
+impl Robustness {
+	/// Aggregates values across scenarios
+	pub fn aggregate(&self, mut values: Vec<N64>) -> N64 {
+		values.sort_unstable_by(|a, b| b.cmp(a));
+		let tail = match self {
+			Self::WorstCase => 1,
+			Self::CVaR { alpha } => (((1.0 - alpha) * values.len() as f64).ceil() as usize).max(1),
+		};
+		let tail = &values[..tail.min(values.len())];
+		tail.iter().copied().sum::<N64>() / n64(tail.len() as f64)
+	}
+}
+
+/// How tours costing more than [`Parameters::max_tour_cost`] are dealt with
+#[derive(Serialize, Deserialize, Clone, Copy, PartialEq, Debug)]
+pub enum TourCap {
+	/// their cost over the cap, times a weight, is added to the objective value
+	Penalty { weight: N64 },
+	/// solutions with any are infeasible, valued infinitely
+	Reject,
+}
+impl Default for TourCap {
+	fn default() -> Self {
+		Self::Penalty { weight: n64(100.0) }
+	}
+}
+
+/// Scale of a problem instance, for normalizing objective values
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub struct Scale {
+	/// (expected) number of edges to clear
+	pub edges: N64,
+	/// (expected) total weight of the edges to clear
+	pub required: N64,
+	pub vehicles: usize,
+}
+impl Scale {
+	/// Weight of an ideally balanced tour, clearing its share of the required edges and driving nothing else
+	fn ideal(&self) -> N64 {
+		self.required / n64(self.vehicles.max(1) as f64)
+	}
+}
+
+/// Divides, unless by 0
+fn ratio(v: N64, by: N64) -> N64 {
+	if by > 0.0 { v / by } else { v }
+}
+
+/// Normalization of objective terms, so that weights transfer across instances of different sizes
+#[derive(Serialize, Deserialize, Clone, Copy, PartialEq, Default, Debug)]
+pub enum Normalization {
+	/// raw costs
+	#[default]
+	None,
+	/// costs per edge to clear
+	PerEdge,
+	/// total cost relative to the required weight, and maximum tour cost relative to an ideally balanced tour
+	PerVehicle,
+	/// total cost relative to the required weight, and maximum tour cost as its z-score among tours
+	ZScore,
+}
+impl Normalization {
+	/// Normalizes tour costs
+	///
+	/// Returns: normalized total and maximum tour costs
+	pub fn costs(&self, costs: &[N64], scale: &Scale) -> (N64, N64) {
+		let total: N64 = costs.iter().copied().sum();
+		let max = costs.iter().copied().max().unwrap_or_else(|| n64(0.0));
+		match self {
+			Self::None => (total, max),
+			Self::PerEdge => (ratio(total, scale.edges), ratio(max, scale.edges)),
+			Self::PerVehicle => (ratio(total, scale.required), ratio(max, scale.ideal())),
+			Self::ZScore => {
+				let n = n64(costs.len().max(1) as f64);
+				let mean = total / n;
+				let stddev = (costs.iter().map(|c| (*c - mean) * (*c - mean)).sum::<N64>() / n).sqrt();
+				(ratio(total, scale.required), ratio(max - mean, stddev))
+			},
+		}
+	}
+	/// Normalizes the priority-weighted latency, relative to clearing every edge at the end of an ideally balanced tour
+	pub fn latency(&self, latency: N64, scale: &Scale) -> N64 {
+		match self {
+			Self::None => latency,
+			_ => ratio(latency, scale.edges * scale.ideal()),
+		}
+	}
+}
+
+/// Initial allocation strategy, see [`allocation`]
+#[derive(Serialize, Deserialize, Clone, Copy, PartialEq, Default, Debug)]
+pub enum Allocation {
+	/// to the geographically closest vehicle
+	#[default]
+	Centroid,
+	/// to the closest vehicle by network distance
+	Network,
+}
+impl Allocation {
+	/// Instantiates the strategy, measuring geographical distances with the metric, and network distances respecting one-ways with `DIRESPECT`
+	pub fn strategy<NId, N, E, const DIRESPECT: bool>(&self, metric: Metric) -> Box<dyn allocation::InitialAllocation<NId, N, E>>
+	where
+		NId: Clone + Copy + std::hash::Hash + Eq,
+		N: graph::Positioned,
+		E: graph::Edge<NId>,
+	{
+		match self {
+			Self::Centroid => Box::new(allocation::Centroid { metric }),
+			Self::Network => Box::new(allocation::Network::<DIRESPECT>),
+		}
+	}
+}
+
+/// How vehicles are routed through their allocated segments
+#[derive(Serialize, Deserialize, Clone, Copy, PartialEq, Default, Debug)]
+pub enum Routing {
+	/// deterministic heuristic, injecting cycles through the segments into the tour
+	#[default]
+	Insertion,
+	/// ant colony optimization
+	AntColony {
+		/// number of ants, each building a tour, per iteration
+		ants: usize,
+		/// number of iterations
+		iterations: usize,
+		/// fraction of the pheromone evaporating after each iteration
+		evaporation: f64,
+		/// influence of the pheromone on the choice of the next segment
+		alpha: f64,
+		/// influence of the closeness of the next segment
+		beta: f64,
+	},
+}
+
+/// Preprocessing of the graph, for shortest paths between nodes to be found faster
+#[derive(Serialize, Deserialize, Clone, Copy, PartialEq, Eq, Default, Debug)]
+pub enum Preprocess {
+	/// search the graph as is
+	#[default]
+	No,
+	/// build the contraction hierarchy of the graph by deadheading costs
+	ContractionHierarchy,
+}
+
+/// Local search over the order each vehicle clears its segments in, improving the tours of every accepted solution
+#[derive(Serialize, Deserialize, Clone, Copy, PartialEq, Eq, Default, Debug)]
+pub enum LocalSearch {
+	/// keep tours as routed
+	#[default]
+	No,
+	/// reverse runs of two-way segments
+	TwoOpt,
+	/// relocate runs of up to 3 segments
+	OrOpt,
+	/// both 2-opt and Or-opt moves
+	Both,
+}
+
+impl LocalSearch {
+	pub fn two_opt(&self) -> bool {
+		matches!(self, LocalSearch::TwoOpt | LocalSearch::Both)
+	}
+	pub fn or_opt(&self) -> bool {
+		matches!(self, LocalSearch::OrOpt | LocalSearch::Both)
+	}
+}
+
+/// Where vehicles end their tours
+#[derive(Serialize, Deserialize, Clone, Copy, PartialEq, Eq, Default, Debug)]
+pub enum Tours {
+	/// back at the vehicle's end location, its start if it has none
+	#[default]
+	Closed,
+	/// right after the last segment the vehicle is routed through to clear
+	Open,
+}
+
+/// How geographical distances between coordinates are measured, to locate vehicles to the graph and allocate edges to the closest vehicle
+#[derive(Serialize, Deserialize, Clone, Copy, PartialEq, Eq, Default, Debug)]
+pub enum Metric {
+	/// straight on longitudes and latitudes - fine near the equator, distorted at high latitudes
+	#[default]
+	Euclidean,
+	/// great-circle (haversine)
+	Geodesic,
+}
+impl Metric {
+	/// Measure of the distance between 2 `(lon, lat)` coordinates, only meant for comparing distances (in squared degrees, or in meters when geodesic)
+	pub fn measure(&self, a: (f64, f64), b: (f64, f64)) -> N64 {
+		use data::Distance;
+		n64(match self {
+			Self::Euclidean => a.distance(&b),
+			Self::Geodesic => data::haversine(a, b),
+		})
+	}
+}
+
+/// Annealing acceptance criterion, see [`accept`]
+#[derive(Serialize, Deserialize, Clone, Copy, PartialEq, Default, Debug)]
+pub enum Acceptance {
+	/// randomly, with probability decaying with the temperature
+	#[default]
+	Metropolis,
+	/// anything within the temperature of the best value
+	Threshold,
+	/// anything below a sinking water level
+	GreatDeluge { rain: f64 },
+}
+impl Acceptance {
+	/// Instantiates the criterion
+	pub fn criterion(&self) -> Box<dyn accept::AcceptanceCriterion> {
+		match self {
+			Self::Metropolis => Box::new(accept::Metropolis),
+			Self::Threshold => Box::new(accept::Threshold),
+			Self::GreatDeluge { rain } => Box::new(accept::GreatDeluge::new(*rain)),
+		}
+	}
+}
+
+/// Cost model of vehicles traversing segments, see [`cost`]
+#[derive(Serialize, Deserialize, Clone, Copy, PartialEq, Default, Debug)]
+pub enum Costing {
+	/// costs in edge weight units, at the fastest speed given in the fleet, clearing [`Parameters::slowdown`] times slower
+	#[default]
+	Slowdown,
+	/// costs in seconds, edge weights being lengths in meters, driven and cleared at speeds (vehicles' own speeds taking precedence), plus a fixed time to clear each segment
+	Speeds {
+		/// travel speed, in km/h
+		travel_speed: N64,
+		/// service (plowing) speed, in km/h
+		service_speed: N64,
+		/// fixed time to clear a segment, in s
+		#[serde(default)]
+		service_time: N64,
+	},
+}
+
+/// What edge weights represent
+#[derive(Serialize, Deserialize, Clone, Copy, PartialEq, Default, Debug)]
+pub enum Objective {
+	/// segment length, in meters
+	#[default]
+	Distance,
+	/// travel time, in seconds, at the segment's speed limit, capped by the vehicles' top speed
+	Time {
+		/// vehicles' top speed, in km/h
+		speed: N64,
+		/// speed on segments without a speed limit, in km/h
+		default_speed: N64,
+	},
+	/// fuel or energy consumption, in the unit of `consumption`
+	Energy {
+		/// vehicles' consumption per km on flat roads
+		consumption: N64,
+		/// additional consumption per km, relative to flat roads, per % of grade
+		climb: N64,
+	},
+}
+impl Objective {
+	/// Weight of a segment
+	pub fn weight(&self, r: &data::RoadSegment) -> N64 {
+		match self {
+			Self::Distance => r.distance,
+			Self::Time { speed, default_speed } => r.distance * n64(3.6) / r.speed.unwrap_or(*default_speed).min(*speed),
+			Self::Energy { consumption, climb } => r.distance / n64(1000.0) * *consumption * (n64(1.0) + *climb * r.grade.map_or(n64(0.0), N64::abs)),
+		}
+	}
+}
+
+/// User-defined edge cost formula
+///
+/// Evaluated over variables:
+/// - `length`: segment length, in meters
+/// - `weight`: segment weight per the `objective` (the length by default)
+/// - `speed`: segment speed limit, in km/h (0 if unknown)
+/// - `depth`: snow depth on the segment, in mm (0 if unknown)
+/// - `priority`: priority tier rank - 3 arterial, 2 collector, 1 local, 0 unclassified
+/// - `grade`: segment grade, in % (0 if unknown)
+#[derive(Serialize, Deserialize, Clone, PartialEq, Debug)]
+#[serde(try_from = "String", into = "String")]
+pub struct CostExpression {
+	source: String,
+	tree: evalexpr::Node,
+}
+impl TryFrom<String> for CostExpression {
+	type Error = String;
+	fn try_from(source: String) -> Result<Self, Self::Error> {
+		let tree = evalexpr::build_operator_tree(&source).map_err(|e| format!("Invalid cost expression {:?}: {}", source, e))?;
+		Ok(Self { source, tree })
+	}
+}
+impl From<CostExpression> for String {
+	fn from(c: CostExpression) -> Self {
+		c.source
+	}
+}
+impl CostExpression {
+	/// Evaluates the cost of a segment
+	///
+	/// Returns: the cost, if it is a non-negative number
+	pub fn eval(&self, r: &data::RoadSegment, weight: N64, depth: N64) -> Result<N64, String> {
+		use evalexpr::{ContextWithMutableVariables, Value};
+		let priority = match r.tier() {
+			Some(data::RoadClass::Arterial) => 3,
+			Some(data::RoadClass::Collector) => 2,
+			Some(data::RoadClass::Local) => 1,
+			None => 0,
+		};
+		let mut ctx = evalexpr::HashMapContext::new();
+		ctx.set_value("length".into(), Value::Float(r.distance.raw())).unwrap();
+		ctx.set_value("weight".into(), Value::Float(weight.raw())).unwrap();
+		ctx.set_value("speed".into(), Value::Float(r.speed.map_or(0.0, N64::raw))).unwrap();
+		ctx.set_value("depth".into(), Value::Float(depth.raw())).unwrap();
+		ctx.set_value("priority".into(), Value::Float(priority as f64)).unwrap();
+		ctx.set_value("grade".into(), Value::Float(r.grade.map_or(0.0, N64::raw))).unwrap();
+		let cost = self.tree.eval_number_with_context(&ctx).map_err(|e| format!("Failed to evaluate cost expression {:?} for segment {}-{}: {}", self.source, r.p1, r.p2, e))?;
+		N64::try_new(cost).filter(|c| *c >= 0.0).ok_or_else(|| format!("Cost expression {:?} evaluated to {} for segment {}-{}", self.source, cost, r.p1, r.p2))
+	}
+}
+
+#[derive(Serialize, Deserialize, Clone, PartialEq, Debug)]
+pub struct Parameters {
+	#[serde(default)]
+	pub recycle: Recycle, //IV
+	#[serde(default)]
+	pub clearing: Clearing, //MD
+	#[serde(default)]
+	pub reorder: Reorder, //ChV
+	#[serde(default)]
+	pub realloc: Realloc, //MV
+	/// intra-tour improvement of accepted solutions
+	#[serde(default)]
+	pub local_search: LocalSearch,
+	#[serde(default)]
+	pub annealing: Annealing,
+	#[serde(default)]
+	pub algorithm: Algorithm,
+	/// metaheuristic in use
+	#[serde(default)]
+	pub solver: Solver,
+	#[serde(default)]
+	pub allocation: Allocation,
+	/// how geographical distances are measured
+	#[serde(default)]
+	pub metric: Metric,
+	/// where vehicles end their tours
+	#[serde(default)]
+	pub tours: Tours,
+	/// maximum number of segments allocated to a vehicle for its tour to be routed exactly, as Chinese Postman
+	#[serde(default = "Parameters::default_exact")]
+	pub exact: usize,
+	/// how tours are routed through allocated segments (not solved exactly)
+	#[serde(default)]
+	pub routing: Routing,
+	/// whether to contract chains of degree-2 nodes into single segments before solving (not with turn penalties)
+	#[serde(default)]
+	pub simplify: bool,
+	/// maximum number of shortest paths between nodes cached across iterations, the oldest evicted first (0 not to cache)
+	#[serde(default = "Parameters::default_path_cache")]
+	pub path_cache: usize,
+	/// how the graph is preprocessed for shortest paths, once simplified
+	#[serde(default)]
+	pub preprocess: Preprocess,
+	#[serde(default)]
+	pub acceptance: Acceptance,
+	#[serde(default)]
+	pub polishing: Polishing,
+	#[serde(default)]
+	pub depths: DepthThresholds,
+	/// how to aggregate values across snow scenarios
+	#[serde(default)]
+	pub robustness: Robustness,
+	/// what edge weights represent
+	#[serde(default)]
+	pub objective: Objective,
+	/// edge cost formula (the segment weight if not specified)
+	#[serde(default, skip_serializing_if = "Option::is_none")]
+	pub cost: Option<CostExpression>,
+	/// cost model of vehicles traversing segments
+	#[serde(default)]
+	pub costing: Costing,
+	/// how many times slower clearing is than driving, with the [`Costing::Slowdown`] cost model
+	#[serde(default = "Parameters::default_slowdown")]
+	pub slowdown: N64,
+	/// consumable used clearing segments without a consumption of their own, per km
+	#[serde(default)]
+	pub consumption: N64,
+	/// cost of reloading the consumable at a depot
+	#[serde(default)]
+	pub reload_cost: N64,
+	/// weight of the total tour cost
+	#[serde(default = "Parameters::default_weight_total")]
+	pub weight_total: N64,
+	/// weight of the maximum tour cost
+	#[serde(default = "Parameters::default_weight_max")]
+	pub weight_max: N64,
+	/// weight of the priority-weighted clearing latency
+	#[serde(default)]
+	pub weight_latency: N64,
+	/// maximum tour cost of each vehicle, such as its shift length in seconds with the `Time` objective (uncapped if not specified)
+	#[serde(default, skip_serializing_if = "Option::is_none")]
+	pub max_tour_cost: Option<N64>,
+	/// how tours over `max_tour_cost` are dealt with
+	#[serde(default)]
+	pub tour_cap: TourCap,
+	/// whether solving fails if the best solution found still has tours over `max_tour_cost`
+	#[serde(default)]
+	pub fail_over_cap: bool,
+	/// normalization of the objective terms
+	#[serde(default)]
+	pub normalization: Normalization,
+	/// priority weights of road classes (priority tiers), for the latency
+	#[serde(default)]
+	pub priorities: Priorities,
+	/// turning rules and penalties (road plowing only)
+	#[serde(default)]
+	pub turns: Turns,
+	/// seed of the random number generator, for reproducible runs (from entropy if not specified)
+	#[serde(default, skip_serializing_if = "Option::is_none")]
+	pub seed: Option<u64>,
+}
+/// Reads meta parameters (YAML, or JSON), defaulting those not given
+///
+/// Returns: the parameters, or where they are unreadable or out of range
+pub fn read(file: impl AsRef<std::path::Path>) -> Result<Parameters, error::Error> {
+	let f = std::fs::File::open(&file).map_err(|e| error::Error::io(&file, e))?;
+	let params: Parameters = serde_yaml::from_reader(f).map_err(|e| error::Error::yaml(&file, "Meta parameters", e))?;
+	match params.problems() {
+		problems if problems.is_empty() => Ok(params),
+		problems => Err(error::Error::invalid(&file, format!("Meta parameters out of range: {}", problems.join("; ")))),
+	}
+}
+
+impl Parameters {
+	fn default_exact() -> usize {
+		16
+	}
+	fn default_slowdown() -> N64 {
+		n64(1.0)
+	}
+	fn default_path_cache() -> usize {
+		10_000
+	}
+	fn default_weight_total() -> N64 {
+		n64(1.0)
+	}
+	fn default_weight_max() -> N64 {
+		n64(10.0)
+	}
+	/// Parameters out of their range, as messages naming them
+	pub fn problems(&self) -> Vec<String> {
+		let mut problems = Vec::new();
+		let mut check = |ok: bool, message: std::fmt::Arguments| if !ok {
+			problems.push(message.to_string());
+		};
+		let a = &self.annealing;
+		check(a.cooling_factor > 0.0 && a.cooling_factor < 1.0, format_args!("annealing.cooling_factor must be in (0, 1), not {}", a.cooling_factor));
+		check(a.starting_temperature >= 0.0, format_args!("annealing.starting_temperature must be non-negative, not {}", a.starting_temperature));
+		check(a.target_acceptance > 0.0 && a.target_acceptance < 1.0, format_args!("annealing.target_acceptance must be in (0, 1), not {}", a.target_acceptance));
+		check(a.restarts >= 1, format_args!("annealing.restarts must be at least 1, not {}", a.restarts));
+		check(a.max_wall_time.is_none_or(|m| m >= 0.0), format_args!("annealing.max_wall_time must be non-negative, not {}", a.max_wall_time.unwrap_or_default()));
+		for (name, weight) in [("weight_total", self.weight_total), ("weight_max", self.weight_max), ("weight_latency", self.weight_latency), ("consumption", self.consumption), ("reload_cost", self.reload_cost)] {
+			check(weight >= 0.0, format_args!("{} must be non-negative, not {}", name, weight));
+		}
+		check(self.max_tour_cost.is_none_or(|c| c > 0.0), format_args!("max_tour_cost must be positive, not {}", self.max_tour_cost.unwrap_or_default()));
+		if let TourCap::Penalty { weight } = self.tour_cap {
+			check(weight >= 0.0, format_args!("tour_cap penalty weight must be non-negative, not {}", weight));
+		}
+		check(self.slowdown > 0.0, format_args!("slowdown must be positive, not {}", self.slowdown));
+		check(self.depths.likelihood >= 0.0 && self.depths.likelihood <= 1.0, format_args!("depths.likelihood must be in [0, 1], not {}", self.depths.likelihood));
+		if let Costing::Speeds { travel_speed, service_speed, service_time } = self.costing {
+			check(travel_speed > 0.0 && service_speed > 0.0, format_args!("costing speeds must be positive, not {} and {}", travel_speed, service_speed));
+			check(service_time >= 0.0, format_args!("costing.service_time must be non-negative, not {}", service_time));
+		}
+		if let Robustness::CVaR { alpha } = self.robustness {
+			check((0.0..1.0).contains(&alpha), format_args!("robustness alpha must be in [0, 1), not {}", alpha));
+		}
+		if let Routing::AntColony { evaporation, .. } = self.routing {
+			check((0.0..=1.0).contains(&evaporation), format_args!("routing evaporation must be in [0, 1], not {}", evaporation));
+		}
+		problems
+	}
+	/// Instantiates the cost model
+	pub fn cost_model(&self) -> Box<dyn cost::CostModel> {
+		match self.costing {
+			Costing::Slowdown => Box::new(cost::Slowdown { slowdown: self.slowdown }),
+			Costing::Speeds { travel_speed, service_speed, service_time } => Box::new(cost::Speeds { travel: travel_speed, service: service_speed, service_time }),
+		}
+	}
+	/// How many times slower clearing is than driving, as per the cost model
+	pub fn clearing_slowdown(&self) -> N64 {
+		match self.costing {
+			Costing::Slowdown => self.slowdown,
+			Costing::Speeds { travel_speed, service_speed, .. } => travel_speed / service_speed,
+		}
+	}
+	/// Random number generator, seeded with the seed if any
+	pub fn rng(&self) -> rand::rngs::StdRng {
+		use rand::SeedableRng;
+		match self.seed {
+			Some(seed) => rand::rngs::StdRng::seed_from_u64(seed),
+			None => rand::rngs::StdRng::from_entropy(),
+		}
+	}
+	/// Fixes the seed, drawing one if there is none, so that the run can be reproduced
+	pub fn seeded(&mut self) -> u64 {
+		*self.seed.get_or_insert_with(rand::random)
+	}
+	/// Objective value of tour costs and priority-weighted latency, normalized per the instance scale, and of tour costs over the cap as per [`Parameters::tour_cap`]
+	///
+	/// Returns: value and the maximum tour cost
+	pub fn value(&self, costs: &[N64], latency: N64, scale: &Scale) -> (N64, N64) {
+		let (total, max) = self.normalization.costs(costs, scale);
+		let value = self.weight_total * total + self.weight_max * max + self.weight_latency * self.normalization.latency(latency, scale);
+		let over: N64 = self.max_tour_cost.map_or_else(|| n64(0.0), |cap| costs.iter().map(|c| (*c - cap).max(n64(0.0))).sum());
+		let value = match self.tour_cap {
+			_ if over == 0.0 => value,
+			TourCap::Penalty { weight } => value + weight * over,
+			TourCap::Reject => N64::infinity(),
+		};
+		(value, costs.iter().copied().max().unwrap_or_else(|| n64(0.0)))
+	}
+	/// Cost of traversing a segment, with the given snow depth on it
+	pub fn cost(&self, r: &data::RoadSegment, depth: N64) -> Result<N64, String> {
+		let weight = self.objective.weight(r);
+		match &self.cost {
+			Some(cost) => cost.eval(r, weight, depth),
+			None => Ok(weight),
+		}
+	}
+	/// Time to drive a segment, in hours, at the given speed (in km/h) - costs being in meters, or in seconds with the time objective
+	pub fn hours(&self, r: &data::RoadSegment, depth: N64, speed: f64) -> Result<f64, String> {
+		Ok(self.cost_hours(self.cost(r, depth)?.raw(), speed))
+	}
+	/// Time a cost stands for, in hours, at the given speed (in km/h) - costs being in meters, or in seconds with the time objective
+	pub fn cost_hours(&self, cost: f64, speed: f64) -> f64 {
+		match self.objective {
+			Objective::Time { .. } => cost / 3600.0,
+			_ => cost / (speed * 1000.0),
+		}
+	}
+}
@@ -0,0 +1,81 @@
+//! Solver metrics, exposed on a `/metrics` endpoint in the Prometheus text format by long-running modes
+//!
+//! So that a stalled solve can be alerted upon: no iterations in a while, while a job is running.
+
+use std::sync::atomic::{AtomicU64, Ordering};
+
+static JOBS_RUNNING: AtomicU64 = AtomicU64::new(0);
+static JOBS_TOTAL: AtomicU64 = AtomicU64::new(0);
+static ITERATIONS_TOTAL: AtomicU64 = AtomicU64::new(0);
+/// bits of the [`f64`] objective value of the best solution found by the latest solve (NaN before any)
+static INCUMBENT: AtomicU64 = AtomicU64::new(0x7ff8_0000_0000_0000);
+
+/// A solve job, counted as running until dropped
+pub struct Job(());
+
+impl Job {
+	pub fn start() -> Self {
+		JOBS_RUNNING.fetch_add(1, Ordering::Relaxed);
+		JOBS_TOTAL.fetch_add(1, Ordering::Relaxed);
+		Job(())
+	}
+}
+
+impl Drop for Job {
+	fn drop(&mut self) {
+		JOBS_RUNNING.fetch_sub(1, Ordering::Relaxed);
+	}
+}
+
+/// Records an iteration of a solve, with the objective value of its best solution so far
+pub fn iteration(incumbent: f64) {
+	ITERATIONS_TOTAL.fetch_add(1, Ordering::Relaxed);
+	INCUMBENT.store(incumbent.to_bits(), Ordering::Relaxed);
+}
+
+/// Resident memory of the process, in bytes (where the OS tells)
+fn resident_memory() -> Option<u64> {
+	let status = std::fs::read_to_string("/proc/self/status").ok()?;
+	let kb: u64 = status.lines().find_map(|l| l.strip_prefix("VmRSS:"))?.trim().trim_end_matches("kB").trim().parse().ok()?;
+	Some(kb * 1024)
+}
+
+/// Renders all metrics in the Prometheus text format
+pub fn render() -> String {
+	let mut out = String::new();
+	let mut metric = |name: &str, kind: &str, help: &str, value: String| {
+		out.push_str(&format!("# HELP {} {}\n# TYPE {} {}\n{} {}\n", name, help, name, kind, name, value));
+	};
+	metric("wfbfa_solver_jobs_running", "gauge", "Solve jobs currently running", JOBS_RUNNING.load(Ordering::Relaxed).to_string());
+	metric("wfbfa_solver_jobs_total", "counter", "Solve jobs started", JOBS_TOTAL.load(Ordering::Relaxed).to_string());
+	metric("wfbfa_solver_iterations_total", "counter", "Solver iterations (annealing and polishing)", ITERATIONS_TOTAL.load(Ordering::Relaxed).to_string());
+	let incumbent = f64::from_bits(INCUMBENT.load(Ordering::Relaxed));
+	metric("wfbfa_solver_incumbent_objective", "gauge", "Objective value of the best solution of the latest solve", match incumbent {
+		v if v == f64::INFINITY => "+Inf".to_string(),
+		v if v == f64::NEG_INFINITY => "-Inf".to_string(),
+		v => v.to_string(),
+	});
+	if let Some(bytes) = resident_memory() {
+		metric("process_resident_memory_bytes", "gauge", "Resident memory size in bytes", bytes.to_string());
+	}
+	out
+}
+
+/// Serves the metrics on `/metrics` at an address, in the background
+pub fn serve(addr: &str) -> std::io::Result<()> {
+	let server = tiny_http::Server::http(addr).map_err(std::io::Error::other)?;
+	log::info!("Serving metrics on http://{}/metrics", addr);
+	std::thread::spawn(move || {
+		for request in server.incoming_requests() {
+			let response = if request.url() == "/metrics" {
+				tiny_http::Response::from_string(render()).with_header(tiny_http::Header::from_bytes("Content-Type", "text/plain; version=0.0.4").unwrap())
+			} else {
+				tiny_http::Response::from_string("Not found").with_status_code(404)
+			};
+			if let Err(e) = request.respond(response) {
+				log::warn!("Failed to serve metrics: {}", e);
+			}
+		}
+	});
+	Ok(())
+}
@@ -0,0 +1,204 @@
+//! Monte Carlo robustness evaluation of a plan
+//!
+//! Samples perturbed snow depths, vehicle speeds and vehicle availability, and replays the plan in each sample, to tell how fragile it is before committing to it.
+
+use crate::*;
+use data::*;
+
+use std::collections::HashMap;
+use rand::Rng;
+use serde::*;
+
+/// Perturbations settings
+#[derive(Clone, Debug)]
+pub struct MonteCarlo {
+	pub samples: usize,
+	/// nominal vehicles speed, in km/h
+	pub speed: f64,
+	/// relative standard deviation of each vehicle's speed
+	pub speed_noise: f64,
+	/// relative standard deviation of each segment's snow depth (on top of the observation variance, if any)
+	pub depth_noise: f64,
+	/// probability that a vehicle is unavailable - its tour is then taken over by the vehicle that finishes first
+	pub unavailability: f64,
+	/// deadline for completion, in hours
+	pub deadline: Option<f64>,
+}
+
+/// Distribution of a sampled quantity
+#[derive(Serialize, Deserialize, Clone, PartialEq, Debug)]
+pub struct Distribution {
+	pub mean: f64,
+	pub stddev: f64,
+	pub min: f64,
+	pub p50: f64,
+	pub p90: f64,
+	pub p95: f64,
+	pub p99: f64,
+	pub max: f64,
+}
+
+impl Distribution {
+	fn of(mut values: Vec<f64>) -> Self {
+		values.sort_by(f64::total_cmp);
+		let n = values.len() as f64;
+		let mean = values.iter().sum::<f64>() / n;
+		let quantile = |q: f64| values[((q * n).ceil() as usize).clamp(1, values.len()) - 1];
+		Distribution {
+			mean,
+			stddev: (values.iter().map(|v| (v - mean).powi(2)).sum::<f64>() / n).sqrt(),
+			min: values[0],
+			p50: quantile(0.5),
+			p90: quantile(0.9),
+			p95: quantile(0.95),
+			p99: quantile(0.99),
+			max: values[values.len() - 1],
+		}
+	}
+}
+
+/// How fragile a plan is
+#[derive(Serialize, Deserialize, Clone, PartialEq, Debug)]
+pub struct Fragility {
+	pub samples: usize,
+	/// completion time of the whole plan (the last vehicle to finish), in hours, in samples where it completes at all
+	pub completion: Option<Distribution>,
+	/// probability that the plan doesn't complete at all, no vehicle being available
+	pub incomplete: f64,
+	/// deadline, in hours
+	pub deadline: Option<f64>,
+	/// probability of missing the deadline
+	pub missed_deadline: Option<f64>,
+	/// mean number of unavailable vehicles
+	pub unavailable: f64,
+}
+
+/// Standard normal sample (Box-Muller)
+fn normal(rng: &mut impl Rng) -> f64 {
+	let (u1, u2): (f64, f64) = (rng.gen_range(f64::EPSILON..1.0), rng.gen());
+	(-2.0 * u1.ln()).sqrt() * (2.0 * std::f64::consts::PI * u2).cos()
+}
+
+/// Evaluates the robustness of a road plowing plan
+///
+/// Tours take their segments' costs (as per [`meta::Parameters::cost`]) in meters at the vehicle's speed - or in seconds with the time objective, the speed noise applying to them - snowy segments being `slowdown` times slower the first time the vehicle drives them.
+pub fn evaluate(roads: &RoadGraph, snow: &SnowStatuses, plan: &Paths, params: &meta::Parameters, settings: &MonteCarlo) -> Result<Fragility, error::Error> {
+	let invalid = |message| error::Error::Validation { file: None, message };
+	if settings.samples == 0 {
+		return Err(invalid("Monte Carlo evaluation needs at least 1 sample".to_string()));
+	}
+	let segments: HashMap<_, _> = roads.roads.iter().map(|r| (segment_key(&r.p1, &r.p2, &r.discriminator), r)).collect();
+	let observed: HashMap<_, _> = snow.iter().map(|s| (segment_key(&s.p1, &s.p2, &s.discriminator), s)).collect();
+	let tours: Vec<Vec<&RoadSegment>> = plan.iter().map(|path| report::path_segments(path).filter_map(|k| segments.get(&k).copied()).collect()).collect();
+	let mut rng = params.rng();
+	let mut completions = Vec::with_capacity(settings.samples);
+	let mut unavailable = 0;
+	for _ in 0..settings.samples {
+		let mut depths = HashMap::new();
+		let mut depth = |r: &RoadSegment, rng: &mut rand::rngs::StdRng| *depths.entry(segment_key(&r.p1, &r.p2, &r.discriminator)).or_insert_with(|| match observed.get(&segment_key(&r.p1, &r.p2, &r.discriminator)) {
+			Some(s) => {
+				let noise = s.variance.map_or(0.0, |v| v.raw().sqrt() * normal(rng));
+				n64(((s.depth.raw() + noise) * (1.0 + settings.depth_noise * normal(rng))).max(0.0))
+			},
+			None => n64(0.0),
+		});
+		let mut times = Vec::new();
+		let mut orphaned = 0.0;
+		for tour in &tours {
+			let pace = (1.0 + settings.speed_noise * normal(&mut rng)).max(0.1);
+			let speed = match params.objective {
+				meta::Objective::Time { .. } => 3600.0 * pace,
+				_ => settings.speed * 1000.0 * pace,
+			};
+			let mut cleared = std::collections::HashSet::new();
+			let mut time = 0.0;
+			for r in tour {
+				let d = depth(r, &mut rng);
+				let slowdown = if params.depths.is_snowy(r.class, d) && cleared.insert(segment_key(&r.p1, &r.p2, &r.discriminator)) { params.clearing_slowdown().raw() } else { 1.0 };
+				time += params.cost(r, d).map_err(invalid)?.raw() * slowdown / speed;
+			}
+			if rng.gen::<f64>() < settings.unavailability {
+				unavailable += 1;
+				orphaned += time;
+			} else {
+				times.push(time);
+			}
+		}
+		let completion = match times.iter_mut().min_by(|a, b| a.total_cmp(b)) {
+			Some(first) => {
+				*first += orphaned;
+				times.iter().copied().fold(0.0, f64::max)
+			},
+			None if orphaned > 0.0 => f64::INFINITY,
+			None => 0.0,
+		};
+		completions.push(completion);
+	}
+	let missed_deadline = settings.deadline.map(|deadline| completions.iter().filter(|c| **c > deadline).count() as f64 / settings.samples as f64);
+	let incomplete = completions.iter().filter(|c| c.is_infinite()).count() as f64 / settings.samples as f64;
+	completions.retain(|c| c.is_finite());
+	Ok(Fragility {
+		samples: settings.samples,
+		completion: if completions.is_empty() { None } else { Some(Distribution::of(completions)) },
+		incomplete,
+		deadline: settings.deadline,
+		missed_deadline,
+		unavailable: unavailable as f64 / settings.samples as f64,
+	})
+}
+
+#[cfg(test)]
+mod test {
+	use super::*;
+
+	/// A 2 segments road graph, a snowy one and a bare one, driven by a single vehicle
+	fn instance() -> (RoadGraph, SnowStatuses, Paths) {
+		let roads = serde_json::from_value(serde_json::json!({
+			"nodes": [{ "id": "a", "coordinates": [-73.6, 45.5] }, { "id": "b", "coordinates": [-73.6, 45.501] }, { "id": "c", "coordinates": [-73.6, 45.502] }],
+			"roads": [
+				{ "p1": "a", "p2": "b", "directed": false, "sidewalks": [false, false], "distance": 1000.0 },
+				{ "p1": "b", "p2": "c", "directed": false, "sidewalks": [false, false], "distance": 1000.0 },
+			],
+		})).unwrap();
+		let snow = serde_json::from_value(serde_json::json!([{ "p1": "a", "p2": "b", "depth": 50 }])).unwrap();
+		let plan = serde_json::from_value(serde_json::json!([[{ "node": "a" }, { "node": "b" }, { "node": "c" }]])).unwrap();
+		(roads, snow, plan)
+	}
+
+	fn params() -> meta::Parameters {
+		serde_json::from_value(serde_json::json!({ "seed": 1 })).unwrap()
+	}
+
+	fn settings(samples: usize) -> MonteCarlo {
+		MonteCarlo { samples, speed: 10.0, speed_noise: 0.0, depth_noise: 0.0, unavailability: 0.0, deadline: Some(1.0) }
+	}
+
+	#[test]
+	fn unperturbed_samples_agree() {
+		let (roads, snow, plan) = instance();
+		let params = params();
+		let fragility = evaluate(&roads, &snow, &plan, &params, &settings(20)).unwrap();
+		let completion = fragility.completion.unwrap();
+		// 1 km snowy at the clearing slowdown, then 1 km driven, at 10 km/h
+		let hours = (1.0 + params.clearing_slowdown().raw()) / 10.0;
+		assert!((completion.min - hours).abs() < 1e-9 && (completion.max - hours).abs() < 1e-9, "{:?}", completion);
+		assert!(completion.stddev < 1e-9, "{:?}", completion);
+		assert_eq!(fragility.incomplete, 0.0);
+		assert_eq!(fragility.missed_deadline, Some(if hours > 1.0 { 1.0 } else { 0.0 }));
+	}
+
+	#[test]
+	fn unavailable_fleet_never_completes() {
+		let (roads, snow, plan) = instance();
+		let fragility = evaluate(&roads, &snow, &plan, &params(), &MonteCarlo { unavailability: 1.0, ..settings(10) }).unwrap();
+		assert_eq!(fragility.completion, None);
+		assert_eq!(fragility.incomplete, 1.0);
+		assert_eq!(fragility.unavailable, 1.0);
+	}
+
+	#[test]
+	fn no_samples_is_an_error() {
+		let (roads, snow, plan) = instance();
+		assert!(evaluate(&roads, &snow, &plan, &params(), &settings(0)).is_err());
+	}
+}
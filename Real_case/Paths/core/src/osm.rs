@@ -0,0 +1,579 @@
+//! Import of OpenStreetMap extracts, `.osm.pbf` or `.osm.xml`, as road graphs
+//!
+//! Highway ways become road segments, split at their ends and at the nodes they share with other ways (junctions).
+//! Foot, cycle & bridle ways, steps, and highways that are not built (yet) are left out.
+//! Distinct ways between the same junctions are told apart by synthesized discriminators, see [`diagnostics::discriminate`].
+
+use crate::*;
+use data::{RoadGraph, RoadGraphNodes, RoadSegment, RoadClass, Node};
+use error::Error;
+use std::{collections::{HashMap, HashSet}, convert::TryFrom, io::{BufRead, Read}, path::Path};
+
+/// Highway values of ways that are not roads for vehicles
+const NOT_ROADS: &[&str] = &["footway", "path", "cycleway", "bridleway", "steps", "pedestrian", "corridor", "elevator", "platform", "bus_stop", "proposed", "construction", "abandoned", "raceway", "rest_area", "services"];
+
+/// Largest blob header the PBF format allows
+const MAX_HEADER: usize = 64 * 1024;
+/// Largest blob the PBF format allows, compressed or not
+const MAX_BLOB: usize = 32 * 1024 * 1024;
+
+/// Highway way
+struct Way {
+	nodes: Vec<i64>,
+	tags: HashMap<String, String>,
+}
+
+/// What is needed of an extract: node coordinates and highway ways
+#[derive(Default)]
+struct Extract {
+	coordinates: HashMap<i64, (f64, f64)>,
+	ways: Vec<Way>,
+}
+
+/// Reads an extract as a road graph - as PBF if it is named `*.pbf`, as XML otherwise
+///
+/// Returns: the road graph, and the segments that were discriminated from their parallel ones, or left out as duplicates
+pub fn read(file: impl AsRef<Path>) -> Result<(RoadGraph, Vec<diagnostics::Issue>), Error> {
+	let input = std::io::BufReader::new(std::fs::File::open(&file).map_err(|e| Error::io(&file, e))?);
+	let extract = if file.as_ref().extension().is_some_and(|e| e == "pbf") {
+		read_pbf(input)
+	} else {
+		read_xml(input)
+	}.map_err(|e| Error::invalid(&file, format!("OSM extract {}", e)))?;
+	log::info!("Read {} nodes, {} highway ways", extract.coordinates.len(), extract.ways.len());
+	Ok(extract.into_roadgraph())
+}
+
+impl Extract {
+	fn add_way(&mut self, nodes: Vec<i64>, tags: HashMap<String, String>) {
+		let road = tags.get("highway").is_some_and(|h| !NOT_ROADS.contains(&h.as_str())) && tags.get("area").is_none_or(|a| a != "yes");
+		if road && nodes.len() > 1 {
+			self.ways.push(Way { nodes, tags });
+		}
+	}
+
+	/// Road graph of the ways, split at junctions
+	///
+	/// Nodes missing from the extract (clipped ways) are skipped.
+	/// Where ways make several segments between the same 2 nodes, or loops, they are split further at one of their inner nodes.
+	/// Segments without any are discriminated from the others between the same nodes, unless identical to one of them - such duplicates are left out.
+	///
+	/// Returns: the road graph, and the discriminated and duplicate segments
+	fn into_roadgraph(self) -> (RoadGraph, Vec<diagnostics::Issue>) {
+		let Extract { coordinates, ways } = self;
+		let ways: Vec<_> = ways.into_iter().map(|mut w| {
+			w.nodes.retain(|n| coordinates.contains_key(n));
+			w.nodes.dedup();
+			w
+		}).filter(|w| w.nodes.len() > 1).collect();
+		let mut uses: HashMap<i64, usize> = HashMap::new();
+		for n in ways.iter().flat_map(|w| &w.nodes) {
+			*uses.entry(*n).or_default() += 1;
+		}
+		let mut keys = HashSet::new();
+		let mut nodes = Vec::new();
+		let mut added = HashSet::new();
+		let mut roads = Vec::new();
+		for w in &ways {
+			let (directed, reversed) = oneway(&w.tags);
+			let (left, right) = sidewalks(&w.tags);
+			let grade = grade(&w.tags);
+			let mut pieces = Vec::new();
+			let mut start = 0;
+			for i in 1..w.nodes.len() {
+				if i == w.nodes.len() - 1 || uses[&w.nodes[i]] > 1 {
+					split(&w.nodes[start..=i], &mut keys, &mut pieces);
+					start = i;
+				}
+			}
+			for piece in pieces {
+				let (p1, p2) = if reversed { (piece[piece.len() - 1], piece[0]) } else { (piece[0], piece[piece.len() - 1]) };
+				let mut geometry: Vec<_> = piece[1..piece.len() - 1].iter().map(|p| coordinates[p]).collect();
+				if reversed {
+					geometry.reverse();
+				}
+				for p in [p1, p2] {
+					if added.insert(p) {
+						nodes.push(Node { id: id(p), coordinates: coordinates[&p] });
+					}
+				}
+				roads.push(RoadSegment {
+					p1: id(p1),
+					p2: id(p2),
+					discriminator: None,
+					directed,
+					distance: n64(piece.windows(2).map(|p| data::haversine(coordinates[&p[0]], coordinates[&p[1]])).sum()),
+					sidewalks: if reversed { (right, left) } else { (left, right) },
+					class: class(&w.tags),
+					priority: None,
+					grade: grade.map(|g| if reversed { -g } else { g }),
+					speed: speed(&w.tags),
+					name: w.tags.get("name").cloned(),
+					width: width(&w.tags),
+					consumption: None,
+					zone: None,
+					geometry,
+				});
+			}
+		}
+		let mut roads = RoadGraph { roads, nodes: RoadGraphNodes { nodes }, restrictions: Vec::new(), depots: Vec::new() };
+		let mut issues = diagnostics::discriminate(&mut roads);
+		issues.extend(diagnostics::repair(&mut roads));
+		(roads, issues)
+	}
+}
+
+/// Splits a piece of a way into segments between distinct pairs of nodes, as far as its inner nodes allow
+fn split<'a>(piece: &'a [i64], keys: &mut HashSet<(i64, i64)>, pieces: &mut Vec<&'a [i64]>) {
+	let (p1, p2) = (piece[0], piece[piece.len() - 1]);
+	if p1 != p2 && (keys.insert((p1.min(p2), p1.max(p2))) || piece.len() == 2) {
+		pieces.push(piece);
+	} else if piece.len() > 2 {
+		let mid = piece.len() / 2;
+		split(&piece[..=mid], keys, pieces);
+		split(&piece[mid..], keys, pieces);
+	}
+}
+
+fn id(n: i64) -> NodeId {
+	n.to_string().into()
+}
+
+/// Whether a way is one-way, and if so whether against the order of its nodes
+pub(crate) fn oneway(tags: &HashMap<String, String>) -> (bool, bool) {
+	match tags.get("oneway").map(String::as_str) {
+		Some("yes" | "true" | "1") => (true, false),
+		Some("-1" | "reverse") => (true, true),
+		Some("no" | "false" | "0") => (false, false),
+		_ => (tags.get("highway").is_some_and(|h| h == "motorway") || tags.get("junction").is_some_and(|j| j == "roundabout" || j == "circular"), false),
+	}
+}
+
+pub(crate) fn class(tags: &HashMap<String, String>) -> Option<RoadClass> {
+	match tags.get("highway")?.trim_end_matches("_link") {
+		"motorway" | "trunk" | "primary" => Some(RoadClass::Arterial),
+		"secondary" | "tertiary" => Some(RoadClass::Collector),
+		"residential" | "unclassified" | "living_street" | "service" | "road" => Some(RoadClass::Local),
+		_ => None,
+	}
+}
+
+/// Left & right sidewalks, in the order of the way's nodes - sidewalks mapped as separate ways count
+pub(crate) fn sidewalks(tags: &HashMap<String, String>) -> (bool, bool) {
+	let (mut left, mut right) = match tags.get("sidewalk").or_else(|| tags.get("sidewalk:both")).map(String::as_str) {
+		Some("both" | "yes" | "separate") => (true, true),
+		Some("left") => (true, false),
+		Some("right") => (false, true),
+		_ => (false, false),
+	};
+	let side = |s: &str| tags.get(&format!("sidewalk:{}", s)).map(|v| v == "yes" || v == "separate");
+	if let Some(l) = side("left") {
+		left = l;
+	}
+	if let Some(r) = side("right") {
+		right = r;
+	}
+	(left, right)
+}
+
+/// Speed limit, in km/h
+pub(crate) fn speed(tags: &HashMap<String, String>) -> Option<N64> {
+	let v = tags.get("maxspeed")?;
+	let (v, factor) = match v.strip_suffix("mph") {
+		Some(v) => (v, 1.609344),
+		None => (v.as_str(), 1.0),
+	};
+	v.trim().parse::<f64>().ok().filter(|v| v.is_finite()).map(|v| n64(v * factor))
+}
+
+/// Carriageway width, in m
+pub(crate) fn width(tags: &HashMap<String, String>) -> Option<N64> {
+	tags.get("width")?.trim_end_matches('m').trim().parse::<f64>().ok().filter(|v| v.is_finite() && *v > 0.0).map(n64)
+}
+
+/// Grade, in %, in the order of the way's nodes
+pub(crate) fn grade(tags: &HashMap<String, String>) -> Option<N64> {
+	tags.get("incline")?.trim_end_matches('%').trim().parse::<f64>().ok().filter(|v| v.is_finite()).map(n64)
+}
+
+fn read_xml(input: impl BufRead) -> Result<Extract, String> {
+	use quick_xml::events::{Event, BytesStart};
+	fn value(e: &BytesStart, name: &str) -> Result<String, String> {
+		for a in e.attributes() {
+			let a = a.map_err(|e| e.to_string())?;
+			if a.key.as_ref() == name.as_bytes() {
+				return a.unescape_value().map(|v| v.into_owned()).map_err(|e| e.to_string());
+			}
+		}
+		Err(format!("has a {} without {}", String::from_utf8_lossy(e.name().as_ref()), name))
+	}
+	fn number<T: std::str::FromStr>(e: &BytesStart, name: &str) -> Result<T, String> where T::Err: std::fmt::Display {
+		value(e, name)?.parse().map_err(|err| format!("has a {} with an invalid {}: {}", String::from_utf8_lossy(e.name().as_ref()), name, err))
+	}
+	let mut reader = quick_xml::Reader::from_reader(input);
+	let mut extract = Extract::default();
+	let mut way: Option<(Vec<i64>, HashMap<String, String>)> = None;
+	let mut buf = Vec::new();
+	loop {
+		let position = reader.buffer_position();
+		match reader.read_event_into(&mut buf).map_err(|e| format!("is invalid XML (at byte {}): {}", position, e))? {
+			Event::Start(e) if e.name().as_ref() == b"way" => way = Some((Vec::new(), HashMap::new())),
+			Event::Start(e) | Event::Empty(e) => match e.name().as_ref() {
+				b"node" => {
+					extract.coordinates.insert(number(&e, "id")?, (number(&e, "lon")?, number(&e, "lat")?));
+				},
+				b"nd" => if let Some((nodes, _)) = &mut way {
+					nodes.push(number(&e, "ref")?);
+				},
+				b"tag" => if let Some((_, tags)) = &mut way {
+					tags.insert(value(&e, "k")?, value(&e, "v")?);
+				},
+				_ => {},
+			},
+			Event::End(e) if e.name().as_ref() == b"way" => if let Some((nodes, tags)) = way.take() {
+				extract.add_way(nodes, tags);
+			},
+			Event::Eof => break,
+			_ => {},
+		}
+		buf.clear();
+	}
+	Ok(extract)
+}
+
+/// Minimal protocol buffers decoding, as much as the OSM PBF format needs
+mod proto {
+	pub enum Value<'a> {
+		Varint(u64),
+		Bytes(&'a [u8]),
+	}
+	impl<'a> Value<'a> {
+		pub fn int(&self) -> Result<u64, String> {
+			match self {
+				Self::Varint(v) => Ok(*v),
+				Self::Bytes(_) => Err("has a length-delimited field where an integer is expected".to_string()),
+			}
+		}
+		pub fn bytes(&self) -> Result<&'a [u8], String> {
+			match self {
+				Self::Bytes(b) => Ok(b),
+				Self::Varint(_) => Err("has an integer field where a length-delimited one is expected".to_string()),
+			}
+		}
+	}
+
+	/// Fields of a message, as (number, value)
+	pub struct Fields<'a>(pub &'a [u8]);
+	impl<'a> Fields<'a> {
+		fn take(&mut self, n: usize) -> Result<&'a [u8], String> {
+			if self.0.len() < n {
+				return Err("is truncated".to_string());
+			}
+			let (v, rest) = self.0.split_at(n);
+			self.0 = rest;
+			Ok(v)
+		}
+		fn field(&mut self) -> Result<(u64, Value<'a>), String> {
+			let key = varint(&mut self.0)?;
+			let value = match key & 7 {
+				0 => Value::Varint(varint(&mut self.0)?),
+				1 => Value::Bytes(self.take(8)?),
+				2 => {
+					let n = varint(&mut self.0)? as usize;
+					Value::Bytes(self.take(n)?)
+				},
+				5 => Value::Bytes(self.take(4)?),
+				t => return Err(format!("has an unsupported wire type {}", t)),
+			};
+			Ok((key >> 3, value))
+		}
+	}
+	impl<'a> Iterator for Fields<'a> {
+		type Item = Result<(u64, Value<'a>), String>;
+		fn next(&mut self) -> Option<Self::Item> {
+			(!self.0.is_empty()).then(|| self.field())
+		}
+	}
+
+	pub fn varint(buf: &mut &[u8]) -> Result<u64, String> {
+		let mut v = 0;
+		for shift in (0..64).step_by(7) {
+			let (&b, rest) = buf.split_first().ok_or("is truncated")?;
+			*buf = rest;
+			v |= ((b & 0x7f) as u64) << shift;
+			if b & 0x80 == 0 {
+				return Ok(v);
+			}
+		}
+		Err("has an overlong varint".to_string())
+	}
+
+	/// Packed repeated varints
+	pub fn packed(mut buf: &[u8]) -> impl Iterator<Item = Result<u64, String>> + '_ {
+		std::iter::from_fn(move || (!buf.is_empty()).then(|| varint(&mut buf)))
+	}
+
+	/// Packed repeated delta-coded `sint64`s
+	pub fn deltas(buf: &[u8]) -> Result<Vec<i64>, String> {
+		let mut last = 0;
+		packed(buf).map(|v| {
+			last = i64::checked_add(last, zigzag(v?)).ok_or("has an overflowing delta")?;
+			Ok(last)
+		}).collect()
+	}
+
+	pub fn zigzag(v: u64) -> i64 {
+		(v >> 1) as i64 ^ -((v & 1) as i64)
+	}
+}
+
+fn read_pbf(mut input: impl Read) -> Result<Extract, String> {
+	use proto::Fields;
+	let mut extract = Extract::default();
+	let mut len = [0; 4];
+	loop {
+		match input.read_exact(&mut len) {
+			Ok(()) => {},
+			Err(e) if e.kind() == std::io::ErrorKind::UnexpectedEof => break,
+			Err(e) => return Err(e.to_string()),
+		}
+		let header_size = u32::from_be_bytes(len) as usize;
+		if header_size > MAX_HEADER {
+			return Err(format!("has a {} bytes blob header, over the {} bytes limit", header_size, MAX_HEADER));
+		}
+		let mut header = vec![0; header_size];
+		input.read_exact(&mut header).map_err(|e| e.to_string())?;
+		let (mut kind, mut size) = (String::new(), 0);
+		for f in Fields(&header) {
+			match f? {
+				(1, v) => kind = String::from_utf8_lossy(v.bytes()?).into_owned(),
+				(3, v) => size = v.int()?,
+				_ => {},
+			}
+		}
+		let size = usize::try_from(size).ok().filter(|s| *s <= MAX_BLOB).ok_or_else(|| format!("has a {} bytes blob, over the {} bytes limit", size, MAX_BLOB))?;
+		let mut blob = vec![0; size];
+		input.read_exact(&mut blob).map_err(|e| e.to_string())?;
+		if kind == "OSMData" {
+			primitive_block(&unpack(&blob)?, &mut extract)?;
+		}
+	}
+	Ok(extract)
+}
+
+/// Data of a blob, decompressed up to [`MAX_BLOB`] bytes
+fn unpack(blob: &[u8]) -> Result<Vec<u8>, String> {
+	let bounded = |decoder: &mut dyn Read| {
+		let mut data = Vec::new();
+		decoder.take(MAX_BLOB as u64 + 1).read_to_end(&mut data).map_err(|e| e.to_string())?;
+		if data.len() > MAX_BLOB {
+			return Err(format!("has a blob decompressing to over the {} bytes limit", MAX_BLOB));
+		}
+		Ok(data)
+	};
+	for f in proto::Fields(blob) {
+		match f? {
+			(1, v) => return Ok(v.bytes()?.to_vec()),
+			(3, v) => return bounded(&mut flate2::read::ZlibDecoder::new(v.bytes()?)),
+			(7, v) => return bounded(&mut zstd::Decoder::new(v.bytes()?).map_err(|e| e.to_string())?),
+			(4..=6, _) => return Err("uses an unsupported compression (only zlib & zstd are)".to_string()),
+			_ => {},
+		}
+	}
+	Err("has an empty blob".to_string())
+}
+
+fn primitive_block(block: &[u8], extract: &mut Extract) -> Result<(), String> {
+	use proto::{Fields, packed, deltas, zigzag};
+	let (mut strings, mut groups) = (Vec::new(), Vec::new());
+	let (mut granularity, mut lat_offset, mut lon_offset) = (100, 0, 0);
+	for f in Fields(block) {
+		match f? {
+			(1, v) => for s in Fields(v.bytes()?) {
+				if let (1, s) = s? {
+					strings.push(String::from_utf8_lossy(s.bytes()?).into_owned());
+				}
+			},
+			(2, v) => groups.push(v.bytes()?),
+			(17, v) => granularity = v.int()? as i64,
+			(19, v) => lat_offset = v.int()? as i64,
+			(20, v) => lon_offset = v.int()? as i64,
+			_ => {},
+		}
+	}
+	let coordinates = |lat: i64, lon: i64| {
+		let nano = |offset: i64, v: i64| granularity.checked_mul(v).and_then(|v| v.checked_add(offset)).ok_or_else(|| "has overflowing coordinates".to_string());
+		Ok::<_, String>((nano(lon_offset, lon)? as f64 / 1e9, nano(lat_offset, lat)? as f64 / 1e9))
+	};
+	let string = |i: u64| strings.get(i as usize).cloned().ok_or_else(|| format!("refers to string {} out of its table", i));
+	for group in groups {
+		for f in Fields(group) {
+			match f? {
+				(1, node) => {
+					let (mut id, mut lat, mut lon) = (0, 0, 0);
+					for f in Fields(node.bytes()?) {
+						match f? {
+							(1, v) => id = zigzag(v.int()?),
+							(8, v) => lat = zigzag(v.int()?),
+							(9, v) => lon = zigzag(v.int()?),
+							_ => {},
+						}
+					}
+					extract.coordinates.insert(id, coordinates(lat, lon)?);
+				},
+				(2, dense) => {
+					let (mut ids, mut lats, mut lons) = (Vec::new(), Vec::new(), Vec::new());
+					for f in Fields(dense.bytes()?) {
+						match f? {
+							(1, v) => ids = deltas(v.bytes()?)?,
+							(8, v) => lats = deltas(v.bytes()?)?,
+							(9, v) => lons = deltas(v.bytes()?)?,
+							_ => {},
+						}
+					}
+					for ((id, lat), lon) in ids.into_iter().zip(lats).zip(lons) {
+						extract.coordinates.insert(id, coordinates(lat, lon)?);
+					}
+				},
+				(3, way) => {
+					let (mut keys, mut vals, mut refs) = (Vec::new(), Vec::new(), Vec::new());
+					for f in Fields(way.bytes()?) {
+						match f? {
+							(2, v) => keys = packed(v.bytes()?).collect::<Result<_, _>>()?,
+							(3, v) => vals = packed(v.bytes()?).collect::<Result<_, _>>()?,
+							(8, v) => refs = deltas(v.bytes()?)?,
+							_ => {},
+						}
+					}
+					let tags = keys.into_iter().zip(vals).map(|(k, v)| Ok((string(k)?, string(v)?))).collect::<Result<_, String>>()?;
+					extract.add_way(refs, tags);
+				},
+				_ => {},
+			}
+		}
+	}
+	Ok(())
+}
+
+#[cfg(test)]
+mod test {
+	use super::*;
+
+	fn varint(mut v: u64) -> Vec<u8> {
+		let mut buf = Vec::new();
+		while v >= 0x80 {
+			buf.push((v & 0x7f) as u8 | 0x80);
+			v >>= 7;
+		}
+		buf.push(v as u8);
+		buf
+	}
+
+	fn zigzag(v: i64) -> u64 {
+		((v << 1) ^ (v >> 63)) as u64
+	}
+
+	/// Integer field
+	fn int(n: u64, v: u64) -> Vec<u8> {
+		[varint(n << 3), varint(v)].concat()
+	}
+
+	/// Length-delimited field
+	fn bytes(n: u64, v: &[u8]) -> Vec<u8> {
+		[varint(n << 3 | 2), varint(v.len() as u64), v.to_vec()].concat()
+	}
+
+	/// Packed delta-coded field
+	fn deltas(n: u64, vs: &[i64]) -> Vec<u8> {
+		let mut last = 0;
+		bytes(n, &vs.iter().flat_map(|v| {
+			let delta = v - last;
+			last = *v;
+			varint(zigzag(delta))
+		}).collect::<Vec<_>>())
+	}
+
+	/// File block: length of the header, header, and blob
+	fn block(kind: &str, size: u64, blob: &[u8]) -> Vec<u8> {
+		let header = [bytes(1, kind.as_bytes()), int(3, size)].concat();
+		[(header.len() as u32).to_be_bytes().to_vec(), header, blob.to_vec()].concat()
+	}
+
+	/// Extract of 3 nodes, along a residential way, and a footway
+	fn extract() -> Vec<u8> {
+		let strings = [bytes(1, b""), bytes(1, b"highway"), bytes(1, b"residential"), bytes(1, b"footway")].concat();
+		let dense = [deltas(1, &[1, 2, 3]), deltas(8, &[455_000_000, 455_010_000, 455_020_000]), deltas(9, &[-736_000_000, -736_000_000, -736_000_000])].concat();
+		let way = |refs: &[i64], value: u64| bytes(3, &[int(1, 1), bytes(2, &varint(1)), bytes(3, &varint(value)), deltas(8, refs)].concat());
+		let group = [bytes(2, &dense), way(&[1, 2, 3], 2), way(&[1, 3], 3)].concat();
+		let primitive = [bytes(1, &strings), bytes(2, &group)].concat();
+		[block_of("OSMHeader", &bytes(1, &[])), block_of("OSMData", &bytes(1, &primitive))].concat()
+	}
+
+	/// File block of a whole blob
+	fn block_of(kind: &str, blob: &[u8]) -> Vec<u8> {
+		block(kind, blob.len() as u64, blob)
+	}
+
+	#[test]
+	fn pbf_highways() {
+		let (roads, issues) = read_pbf(&extract()[..]).unwrap().into_roadgraph();
+		assert!(issues.is_empty());
+		assert_eq!(roads.nodes.nodes.len(), 2);
+		assert_eq!(roads.roads.len(), 1);
+		let r = &roads.roads[0];
+		assert_eq!((r.p1.as_ref(), r.p2.as_ref()), ("1", "3"));
+		assert_eq!(r.geometry, vec![(-73.6, 45.501)]);
+		assert!((r.distance.raw() - 222.4).abs() < 1.0, "{}", r.distance);
+	}
+
+	#[test]
+	fn pbf_limits() {
+		// sizes beyond the format's limits are refused before anything is allocated
+		let err = read_pbf(&block("OSMData", MAX_BLOB as u64 + 1, &[])[..]).err().unwrap();
+		assert!(err.contains("limit"), "{}", err);
+		let err = read_pbf(&block("OSMData", u64::MAX, &[])[..]).err().unwrap();
+		assert!(err.contains("limit"), "{}", err);
+		let err = read_pbf(&(MAX_HEADER as u32 + 1).to_be_bytes()[..]).err().unwrap();
+		assert!(err.contains("limit"), "{}", err);
+		// truncated files and overflowing deltas are errors too
+		let file = extract();
+		assert!(read_pbf(&file[..file.len() - 1]).is_err());
+		let blob = bytes(1, &bytes(2, &bytes(2, &bytes(1, &[varint(zigzag(i64::MAX)), varint(zigzag(1))].concat()))));
+		let err = read_pbf(&block_of("OSMData", &blob)[..]).err().unwrap();
+		assert!(err.contains("overflowing"), "{}", err);
+	}
+
+	#[test]
+	fn xml_highways() {
+		let xml = r#"<osm>
+			<node id="1" lat="45.5" lon="-73.6"/>
+			<node id="2" lat="45.501" lon="-73.6"/>
+			<way id="1"><nd ref="1"/><nd ref="2"/><tag k="highway" v="primary"/><tag k="oneway" v="-1"/></way>
+			<way id="2"><nd ref="1"/><nd ref="2"/><tag k="highway" v="footway"/></way>
+		</osm>"#;
+		let (roads, issues) = read_xml(xml.as_bytes()).unwrap().into_roadgraph();
+		assert!(issues.is_empty());
+		assert_eq!(roads.roads.len(), 1);
+		let r = &roads.roads[0];
+		assert!(r.directed);
+		assert_eq!((r.p1.as_ref(), r.p2.as_ref()), ("2", "1"));
+	}
+
+	#[test]
+	fn xml_parallel_ways() {
+		let xml = r#"<osm>
+			<node id="1" lat="45.5" lon="-73.6"/>
+			<node id="2" lat="45.501" lon="-73.6"/>
+			<way id="1"><nd ref="1"/><nd ref="2"/><tag k="highway" v="primary"/></way>
+			<way id="2"><nd ref="1"/><nd ref="2"/><tag k="highway" v="service"/></way>
+			<way id="3"><nd ref="2"/><nd ref="1"/><tag k="highway" v="primary"/></way>
+		</osm>"#;
+		let (roads, issues) = read_xml(xml.as_bytes()).unwrap().into_roadgraph();
+		// the service road is told apart, the primary drawn backwards is a duplicate
+		assert_eq!(roads.roads.len(), 2);
+		assert_eq!(roads.roads.iter().map(|r| r.discriminator.as_deref()).collect::<Vec<_>>(), vec![None, Some("1~2~1")]);
+		assert_eq!(roads.roads[1].class, Some(RoadClass::Local));
+		assert!(roads.nodes.nodes.iter().any(|n| n.id == "1~2~1"));
+		assert!(matches!(&issues[..], [diagnostics::Issue::Discriminated { .. }, diagnostics::Issue::DuplicateSegment { count: 2, .. }]), "{:?}", issues);
+	}
+}
@@ -0,0 +1,2437 @@
+//! ❄ plow problem annealing solver
+
+use crate::*;
+use graph::*;
+use graph::adapt::*;
+use graph::heuristics::Search;
+use meta::*;
+
+use std::{convert::TryFrom, time::{Duration, Instant}};
+use itertools::Itertools;
+use rand::{Rng, prelude::SliceRandom};
+use rayon::prelude::*;
+
+type SID = u64;
+type Coords = (f64, f64);
+/// Weight profile of deadheading (by [`Weighted::deadhead`]) in the shortest paths cache
+const DEADHEAD: u64 = 0;
+/// Weight profile of deadheading kept off roadways, by vehicles that may not drive through them
+const SIDEWALKS: u64 = 1;
+/// Edges that need clearing, with the probability that they actually do
+type Snowy<'a, E> = HashMap<&'a E, N64>;
+/// Paths of each vehicle, their objective value, and costs
+type Solved<'a, E> = (Vec<Vec<&'a E>>, N64, Vec<N64>);
+/// Steps of the random walk the adaptive annealing schedule starts at the temperature of
+const WALK: usize = 20;
+/// How much the adaptive annealing schedule moves its cooling factor, when off its target acceptance rate
+const ADAPT: f64 = 0.8;
+
+trait Weighted {
+	fn weight(&self) -> N64;
+	/// cost of driving through the edge without clearing it
+	fn deadhead(&self) -> N64 {
+		self.weight()
+	}
+	/// snow depth on the edge, in mm
+	fn depth(&self) -> N64 {
+		n64(0.0)
+	}
+	/// whether the edge is a node task, looping on its node - serviced in place, never slowed down
+	fn is_task(&self) -> bool {
+		false
+	}
+	/// service time spent on the edge when clearing it
+	fn service(&self) -> N64 {
+		n64(0.0)
+	}
+	/// road class of the edge
+	fn class(&self) -> Option<data::RoadClass> {
+		None
+	}
+	/// priority tier of the edge, weighting its clearing latency
+	fn priority(&self) -> Option<data::RoadClass> {
+		self.class()
+	}
+	/// width of the edge, in m
+	fn width(&self) -> Option<N64> {
+		None
+	}
+	/// zone of the edge, restricting the vehicles that can clear it
+	fn zone(&self) -> Option<&str> {
+		None
+	}
+	/// consumable used clearing the edge
+	fn consumption(&self) -> N64 {
+		n64(0.0)
+	}
+	/// whether the edge is a reload stop, looping on its depot node
+	fn is_reload(&self) -> bool {
+		false
+	}
+	/// whether the edge is a roadway among sidewalks, only driven through (or cleared) by the vehicles their profile allows to
+	fn is_roadway(&self) -> bool {
+		false
+	}
+	/// node telling the edge apart from others between the same nodes, if any
+	fn discriminator(&self) -> Option<SID> {
+		None
+	}
+}
+
+/// Attributes of the edges of a specialization, as the metadata of [`WeightedEdge`]s - those it doesn't know of defaulting as per [`Weighted`]
+trait Attributes: EdgeMeta {
+	/// travel time when deadheading, if known
+	fn time(&self) -> Option<N64> {
+		None
+	}
+	fn depth(&self) -> N64 {
+		n64(0.0)
+	}
+	fn is_task(&self) -> bool {
+		false
+	}
+	fn service(&self) -> N64 {
+		n64(0.0)
+	}
+	fn class(&self) -> Option<data::RoadClass> {
+		None
+	}
+	fn priority(&self) -> Option<data::RoadClass> {
+		self.class()
+	}
+	fn width(&self) -> Option<N64> {
+		None
+	}
+	fn zone(&self) -> Option<&str> {
+		None
+	}
+	fn consumption(&self) -> N64 {
+		n64(0.0)
+	}
+	fn is_reload(&self) -> bool {
+		false
+	}
+	fn is_roadway(&self) -> bool {
+		false
+	}
+}
+impl Attributes for () {}
+impl<M: Attributes> Weighted for WeightedEdge<SID, M> {
+	fn weight(&self) -> N64 {
+		self.weight
+	}
+	fn discriminator(&self) -> Option<SID> {
+		self.discriminator
+	}
+	fn deadhead(&self) -> N64 {
+		self.meta.time().unwrap_or(self.weight)
+	}
+	fn depth(&self) -> N64 {
+		self.meta.depth()
+	}
+	fn is_task(&self) -> bool {
+		self.meta.is_task()
+	}
+	fn service(&self) -> N64 {
+		self.meta.service()
+	}
+	fn class(&self) -> Option<data::RoadClass> {
+		self.meta.class()
+	}
+	fn priority(&self) -> Option<data::RoadClass> {
+		self.meta.priority()
+	}
+	fn width(&self) -> Option<N64> {
+		self.meta.width()
+	}
+	fn zone(&self) -> Option<&str> {
+		self.meta.zone()
+	}
+	fn consumption(&self) -> N64 {
+		self.meta.consumption()
+	}
+	fn is_reload(&self) -> bool {
+		self.meta.is_reload()
+	}
+	fn is_roadway(&self) -> bool {
+		self.meta.is_roadway()
+	}
+}
+
+/// State of an annealing chain
+struct Chain<'a, E> {
+	/// evaluation order of the vehicles
+	order: Vec<usize>,
+	alloc: Vec<HashSet<&'a E>>,
+	solution: Vec<Vec<&'a E>>,
+	costs: Vec<N64>,
+	value: N64,
+	cost_max: N64,
+}
+impl<E> Clone for Chain<'_, E> {
+	fn clone(&self) -> Self {
+		Self {
+			order: self.order.clone(),
+			alloc: self.alloc.clone(),
+			solution: self.solution.clone(),
+			costs: self.costs.clone(),
+			value: self.value,
+			cost_max: self.cost_max,
+		}
+	}
+}
+
+/// Move of the tabu search, also standing for the moves made tabu
+#[derive(PartialEq, Eq, Hash)]
+enum Move<'a, E> {
+	/// moving a segment to a vehicle
+	Relocate(&'a E, usize),
+	/// swapping two vehicles, lowest first, in evaluation order
+	Swap(usize, usize),
+}
+
+/// State of a tabu search
+struct Tabu<'a, E> {
+	/// solution searched from, kept even when worse than the best one
+	current: Chain<'a, E>,
+	/// tabu moves, with the last iteration they are tabu at
+	tabu: HashMap<Move<'a, E>, u64>,
+	iteration: u64,
+}
+
+/// Solver with a graph attached.
+///
+/// For ~~no~~ a number of reasons, graph node id is forced to `u64`.
+struct PlowSolver<N, E, Gen>
+where
+	N: IdentifiableNode + Positioned,
+	E: graph::Edge<SID> + Weighted,
+	Gen: Fn(&N::Id, SID) -> (SID, SID),
+{
+	graph: GraphAdapter<SID, N, E, SID, Gen>,
+	/// maximum depth each vehicle can clear (unlimited for vehicles past the end)
+	max_depths: Vec<N64>,
+	/// capability profile of each vehicle (unrestricted for vehicles past the end)
+	profiles: Vec<data::VehicleProfile>,
+	/// cost model, of vehicles traversing edges
+	costing: Box<dyn cost::CostModel>,
+	/// cost factors of each vehicle, deadheading and clearing, as per the cost model (those of the default profile for vehicles past the end)
+	paces: Vec<(N64, N64)>,
+	/// forbidden turns, as `(from, via, to)` nodes
+	restrictions: HashSet<(SID, SID, SID)>,
+	/// turning rules and penalties
+	turns: Turns,
+	/// where tours end
+	tours: Tours,
+	/// maximum number of segments allocated to a vehicle for its tour to be routed exactly
+	exact: usize,
+	/// how tours are routed
+	routing: Routing,
+	/// end node of each vehicle's closed tour (its start for vehicles past the end, or without one)
+	ends: Vec<Option<SID>>,
+	/// consumable capacity of each vehicle (unlimited for vehicles past the end)
+	capacities: Vec<N64>,
+	/// depot nodes, where vehicles reload
+	depots: Vec<SID>,
+	/// external distances between nodes, to choose connecting legs by (internal shortest paths if empty)
+	legs: HashMap<(SID, SID), N64>,
+	/// strongly connected region of each node (vehicles reach everywhere if empty)
+	regions: HashMap<SID, usize>,
+	/// region each vehicle starts in, and is confined to
+	starts: Vec<usize>,
+	/// nodes each vehicle kept off roadways can get to and back from
+	sidewalk_regions: HashMap<usize, HashSet<SID>>,
+	/// lowest deadheading cost per meter of straight line, for A* to estimate the cost of legs by (Dijkstra's if 0)
+	straight: N64,
+	/// deadheading shortest paths, reused across iterations
+	cache: DistanceCache<SID, E>,
+	/// contraction hierarchy of the graph by deadheading costs, for legs to be found by (if built)
+	hierarchy: Option<ch::Hierarchy<SID, E, N64>>,
+}
+/// Construct new generic plow solver, with incremental node ids generation
+macro_rules! plow_solver {
+	() => {
+		PlowSolver {
+			graph: GraphAdapter::new(0, |_, id| (id, id+1)),
+			max_depths: Vec::new(),
+			profiles: Vec::new(),
+			costing: Box::new(cost::Slowdown { slowdown: n64(1.0) }),
+			paces: Vec::new(),
+			restrictions: HashSet::default(),
+			turns: Turns::default(),
+			tours: Tours::default(),
+			exact: 0,
+			routing: Routing::default(),
+			ends: Vec::new(),
+			capacities: Vec::new(),
+			depots: Vec::new(),
+			legs: HashMap::default(),
+			regions: HashMap::default(),
+			starts: Vec::new(),
+			sidewalk_regions: HashMap::default(),
+			straight: n64(0.0),
+			cache: DistanceCache::new(0),
+			hierarchy: None,
+		}
+	}
+}
+
+impl<N, E, Gen> PlowSolver<N, E, Gen>
+where
+	N: IdentifiableNode + Positioned + Sync,
+	N::Id: Sync,
+	E: graph::Edge<SID> + Weighted + Send + Sync,
+	Gen: Fn(&N::Id, SID) -> (SID, SID) + Sync,
+{
+	/// Whether a vehicle can clear an edge: deep enough, of a class it services, and within reach
+	fn can_clear(&self, v: usize, e: &E) -> bool {
+		self.can_handle(v, e) && self.can_reach(v, e)
+	}
+	/// Whether a vehicle can clear an edge's snow depth, and service its class and zone - roadways only if its profile has it clear them
+	fn can_handle(&self, v: usize, e: &E) -> bool {
+		self.max_depths.get(v).is_none_or(|d| e.depth() <= *d) && self.profiles.get(v).is_none_or(|p| p.services(e.class()) && p.covers(e.zone())) && (!e.is_roadway() || self.profiles.get(v).is_some_and(|p| p.clears_roadway(e.width())))
+	}
+	/// Whether a vehicle may drive through roadways, as per its profile
+	fn drives_roadways(&self, v: usize) -> bool {
+		self.profiles.get(v).is_none_or(|p| p.drives_roadways())
+	}
+	/// Whether a vehicle may drive through an edge
+	fn can_drive(&self, v: usize, e: &E) -> bool {
+		!e.is_roadway() || self.drives_roadways(v)
+	}
+	/// Sets the vehicles' capability profiles, with the cost factors of their speeds as per the cost model.
+	fn profile(&mut self, profiles: Vec<data::VehicleProfile>, params: &Parameters) {
+		let fastest = profiles.iter().flat_map(|p| vec![p.speed, p.plow_speed]).flatten().filter(|s| *s > 0.0).max();
+		self.costing = params.cost_model();
+		self.paces = profiles.iter().map(|p| self.costing.paces(p, fastest)).collect();
+		self.profiles = profiles;
+	}
+	/// Cost of turning at a node from an edge into another, unless the turn is forbidden.
+	///
+	/// U-turns are always allowed at dead ends, and turning into or out of node task loops is free.
+	fn turn(&self, via: SID, a: &E, b: &E) -> Option<N64> {
+		if a.is_cyclic() || b.is_cyclic() {
+			return Some(n64(0.0));
+		}
+		let (from, to) = (a.other(via), b.other(via));
+		if self.restrictions.contains(&(from, via, to)) {
+			return None;
+		}
+		if from == to {
+			let dead_end = || self.graph.graph.get_edges(via).iter().all(|e| e.is_cyclic() || e.other(via) == from || !e.is_outgoing::<true>(via));
+			return Some(self.turns.u_turn_penalty).filter(|_| self.turns.u_turns || dead_end());
+		}
+		let pos = |n| self.graph.graph.get_node(n).map(|n| n.pos());
+		Some(match (pos(from), pos(via), pos(to)) {
+			(Some(a), Some(b), Some(c)) => {
+				let cos = b.1.to_radians().cos();
+				let (u, v) = (((b.0 - a.0) * cos, b.1 - a.1), ((c.0 - b.0) * cos, c.1 - b.1));
+				let angle = (u.0 * v.1 - u.1 * v.0).atan2(u.0 * v.0 + u.1 * v.1).abs();
+				if angle > std::f64::consts::FRAC_PI_4 { self.turns.penalty } else { n64(0.0) }
+			},
+			_ => n64(0.0),
+		})
+	}
+	/// Cost of the turns along a tour, forbidden ones being as costly as U-turns
+	fn turns_cost(&self, tour: &[&E]) -> N64 {
+		if self.turns.penalty == 0.0 && self.turns.u_turn_penalty == 0.0 {
+			return n64(0.0);
+		}
+		let roads: Vec<_> = tour.iter().filter(|e| !e.is_cyclic()).collect();
+		roads.windows(2).map(|w| {
+			let via = if w[1].p1() == w[0].p2() || w[1].p2() == w[0].p2() { w[0].p2() } else { w[0].p1() };
+			self.turn(via, w[0], w[1]).unwrap_or(self.turns.u_turn_penalty)
+		}).sum()
+	}
+	/// Shortest deadheading leg of a vehicle between nodes, making allowed turns only, from the edge the first is reached by into the edge to take from the second
+	fn leg<'a>(&'a self, v: usize, n1: SID, e1: Option<&'a E>, n2: SID, e2: Option<&E>) -> Option<Vec<&'a E>> {
+		self.graph.graph.pathfind_turns::<_, _, _, true>(n1, e1, n2, e2, |e| Some(e.deadhead()).filter(|_| !e.is_cyclic() && self.can_drive(v, e)), |v, a, b| self.turn(v, a, b))
+	}
+	/// Detours a vehicle's tour around its forbidden turns, from the node of each back to it, into the next edge
+	///
+	/// Returns: the tour, with the detours
+	fn unturn<'a>(&'a self, v: usize, sol: Vec<&'a E>, sp: SID) -> Vec<&'a E>
+	where
+		N::Id: std::fmt::Display,
+	{
+		if self.restrictions.is_empty() && self.turns.u_turns {
+			return sol;
+		}
+		let mut tour = Vec::with_capacity(sol.len());
+		let mut at = sp;
+		let mut last: Option<&E> = None;
+		for e in sol {
+			if !e.is_cyclic() {
+				if let Some(l) = last.filter(|l| self.turn(at, l, e).is_none()) {
+					match self.leg(v, at, Some(l), at, Some(e)) {
+						Some(detour) => tour.extend(detour),
+						None => log::warn!("No way around a forbidden turn at {}", self.graph.nid2id(at).unwrap()),
+					}
+				}
+				last = Some(e);
+				at = e.other(at);
+			}
+			tour.push(e);
+		}
+		tour
+	}
+	/// Whether a vehicle can get to an edge, and back
+	fn can_reach(&self, v: usize, e: &E) -> bool {
+		(self.regions.is_empty() || self.starts.get(v).is_some_and(|r| self.regions.get(&e.p1()) == Some(r) && self.regions.get(&e.p2()) == Some(r)))
+			&& self.sidewalk_regions.get(&v).is_none_or(|region| region.contains(&e.p1()) && region.contains(&e.p2()))
+	}
+	/// Confines vehicles to the strongly connected region they start in, and those kept off roadways to the nodes they get to without them
+	///
+	/// Sidewalks being two-way, vehicles kept to them get back from wherever they get to.
+	fn confine<const DIRESPECT: bool>(&mut self, sps: &[SID]) {
+		let sccs = self.graph.graph.strongly_connected_components::<DIRESPECT, true>();
+		self.regions = sccs.into_iter().enumerate().flat_map(|(i, scc)| scc.into_iter().map(move |n| (n, i))).collect();
+		self.starts = sps.iter().map(|s| self.regions.get(s).copied().unwrap_or(usize::MAX)).collect();
+		self.sidewalk_regions = sps.iter().enumerate().filter(|(v, _)| !self.drives_roadways(*v)).map(|(v, sp)| {
+			let mut region: HashSet<SID> = std::iter::once(*sp).collect();
+			let mut q = vec![*sp];
+			while let Some(u) = q.pop() {
+				for e in self.graph.graph.get_edges(u) {
+					if !e.is_roadway() && e.is_outgoing::<DIRESPECT>(u) && region.insert(e.other(u)) {
+						q.push(e.other(u));
+					}
+				}
+			}
+			(v, region)
+		}).collect();
+	}
+	/// Contracts chains of degree-2 nodes, if enabled, keeping the nodes vehicles start, end and reload at, and those of turn restrictions and distances
+	///
+	/// Returns: the original edges of each contracted edge
+	fn simplify(&mut self, sps: &[SID], params: &Parameters, merge: impl FnMut(&E, SID, &E) -> Option<E>) -> HashMap<E, Vec<E>> {
+		if !params.simplify {
+			return HashMap::default();
+		}
+		if self.turns.penalty != 0.0 {
+			log::warn!("Not simplifying the graph, as turn penalties are measured at every node");
+			return HashMap::default();
+		}
+		let mut keep: HashSet<SID> = sps.iter().chain(self.ends.iter().flatten()).chain(self.depots.iter()).copied().collect();
+		keep.extend(self.restrictions.iter().flat_map(|(from, via, to)| IntoIterator::into_iter([*from, *via, *to])));
+		keep.extend(self.legs.keys().flat_map(|(u, v)| IntoIterator::into_iter([*u, *v])));
+		let edges = self.graph.graph.edge_count();
+		let contracted = self.graph.graph.simplify(|n| keep.contains(&n), merge);
+		log::info!("Simplified graph from {} to {} segments", edges, self.graph.graph.edge_count());
+		contracted
+	}
+	/// Sets the lowest deadheading cost per meter of straight line of any edge, for A* never to overestimate the cost of a leg
+	fn estimate(&mut self) {
+		let pos = |n| self.graph.graph.get_node(n).map(|n| n.pos());
+		self.straight = self.graph.graph.edges().filter(|e| !e.is_cyclic()).filter_map(|e| {
+			let straight = data::haversine(pos(e.p1())?, pos(e.p2())?);
+			Some(e.deadhead() / straight).filter(|_| straight > 0.0)
+		}).min().unwrap_or_else(|| n64(0.0));
+		log::debug!("Straight-line deadheading cost {:.5}/m", self.straight);
+	}
+	/// Builds the contraction hierarchy of the graph by deadheading costs, if the parameters preprocess it so
+	///
+	/// Type Parameters:
+	/// - `DIRESPECT`: whether the directionality of edges is respected
+	fn preprocess<const DIRESPECT: bool>(&mut self, params: &Parameters) {
+		if params.preprocess != Preprocess::ContractionHierarchy {
+			return;
+		}
+		let start = Instant::now();
+		self.hierarchy = Some(ch::Hierarchy::new::<_, _, DIRESPECT>(&self.graph.graph, |e| Some(e.deadhead())));
+		log::info!("Built contraction hierarchy of the graph in {:.3}s", start.elapsed().as_secs_f64());
+	}
+	/// Drops the snowy edges that no vehicle able to clear them can reach
+	///
+	/// Returns: the dropped edges
+	fn unreachable<'a>(&self, vs: usize, snowy: &mut Snowy<'a, E>) -> Vec<&'a E> {
+		let dropped: Vec<_> = snowy.keys().copied().filter(|e| !(0..vs).any(|v| self.can_clear(v, e))).collect();
+		for e in &dropped {
+			snowy.remove(e);
+		}
+		dropped
+	}
+	/// Finds the bridges whose closure would cut snowy edges off from every vehicle - the snowy edges being on one side, and all vehicles on the other
+	///
+	/// Arguments:
+	/// - `sps`: starting node of each vehicle
+	/// - `snowy`: the snowy edges
+	/// - `discriminator`: discriminator of the segment of an edge, the edges between the same nodes with the same discriminator being closed together (e.g. both sides of a road)
+	///
+	/// Returns: the ends of the bridges
+	fn fragile(&self, sps: &[SID], snowy: &Snowy<'_, E>, discriminator: impl Fn(&E) -> Option<SID>) -> Vec<(SID, SID)> {
+		let mut segments: HashMap<(SID, SID, Option<SID>), usize> = HashMap::default();
+		let mut g = data::LightGraph::default();
+		for (n, _) in self.graph.graph.nodes() {
+			g.add_node(n as usize, (0.0, 0.0));
+		}
+		for e in self.graph.graph.edges().filter(|e| !e.is_cyclic()) {
+			let (p1, p2) = (e.p1().min(e.p2()), e.p1().max(e.p2()));
+			let n = segments.len();
+			let index = *segments.entry((p1, p2, discriminator(e))).or_insert(n);
+			g.add_edge(data::SegmentEdge { p1: p1 as usize, p2: p2 as usize, directed: false, index });
+		}
+		let bridges = g.bridges();
+		if bridges.is_empty() {
+			return Vec::new();
+		}
+		// 2-edge-connected components, linked by the bridges into a forest
+		let cut: HashSet<&data::SegmentEdge> = bridges.iter().copied().collect();
+		let mut comp: HashMap<usize, usize> = HashMap::default();
+		let mut n = 0;
+		for (root, _) in g.nodes() {
+			if comp.contains_key(&root) {
+				continue;
+			}
+			comp.insert(root, n);
+			let mut q = vec![root];
+			while let Some(u) = q.pop() {
+				for v in g.get_edges(u).iter().filter(|e| !cut.contains(e)).map(|e| e.other(u)) {
+					if comp.insert(v, n).is_none() {
+						q.push(v);
+					}
+				}
+			}
+			n += 1;
+		}
+		// snowy edges and vehicles in each component, then in each subtree of the forest
+		let mut sub = vec![(0usize, 0usize); n];
+		for e in snowy.keys() {
+			sub[comp[&(e.p1() as usize)]].0 += 1;
+		}
+		for s in sps {
+			sub[comp[&(*s as usize)]].1 += 1;
+		}
+		let mut forest = vec![Vec::new(); n];
+		for (b, e) in bridges.iter().enumerate() {
+			let (c1, c2) = (comp[&e.p1()], comp[&e.p2()]);
+			forest[c1].push((c2, b));
+			forest[c2].push((c1, b));
+		}
+		let (mut tree, mut parent, mut order) = (vec![usize::MAX; n], vec![None; n], Vec::new());
+		for r in 0..n {
+			if tree[r] != usize::MAX {
+				continue;
+			}
+			tree[r] = r;
+			let mut q = vec![r];
+			while let Some(c) = q.pop() {
+				order.push(c);
+				for (d, b) in &forest[c] {
+					if tree[*d] == usize::MAX {
+						tree[*d] = r;
+						parent[*d] = Some((c, *b));
+						q.push(*d);
+					}
+				}
+			}
+		}
+		for c in order.iter().rev() {
+			if let Some((p, _)) = parent[*c] {
+				sub[p].0 += sub[*c].0;
+				sub[p].1 += sub[*c].1;
+			}
+		}
+		let cuts_off = |(snowy, vehicles): (usize, usize), (_, others): (usize, usize)| snowy > 0 && vehicles == 0 && others > 0;
+		order.into_iter().filter_map(|c| parent[c].map(|(_, b)| (c, b))).filter(|(c, _)| {
+			let (inside, total) = (sub[*c], sub[tree[*c]]);
+			let outside = (total.0 - inside.0, total.1 - inside.1);
+			cuts_off(inside, outside) || cuts_off(outside, inside)
+		}).map(|(_, b)| (bridges[b].p1 as SID, bridges[b].p2 as SID)).collect()
+	}
+	/// Removes the snowy edges no vehicle can clear, which require escalation to heavier equipment
+	///
+	/// Returns: the number of edges removed
+	fn escalate(&self, vs: usize, snowy: &mut Snowy<'_, E>) -> usize {
+		let n = snowy.len();
+		snowy.retain(|e, _| (0..vs).any(|v| self.can_handle(v, e)));
+		n - snowy.len()
+	}
+	/// Reports the moves of segments between vehicles from an allocation to the next (see [`progress::on_move`])
+	///
+	/// Arguments:
+	/// - `at`: phase, iteration (or polishing round) and chain of the moves
+	fn report_moves<'a>(&'a self, at: (progress::Phase, u64, usize), before: &[HashSet<&'a E>], after: &[HashSet<&'a E>])
+	where
+		N::Id: std::fmt::Display,
+	{
+		let owners = |alloc: &[HashSet<&'a E>]| -> HashMap<&'a E, usize> { alloc.iter().enumerate().flat_map(|(v, a)| a.iter().map(move |e| (*e, v))).collect() };
+		let (from, to) = (owners(before), owners(after));
+		let id = |nid: SID| self.graph.nid2id(nid).map_or_else(|| nid.to_string(), |id| id.to_string());
+		for e in from.keys().chain(to.keys().filter(|e| !from.contains_key(*e))) {
+			let (f, t) = (from.get(e).copied(), to.get(e).copied());
+			if f != t && !e.is_task() && !e.is_reload() {
+				progress::moved(&progress::Move { phase: at.0, iteration: at.1, chain: at.2, p1: id(e.p1()), p2: id(e.p2()), discriminator: e.discriminator().map(id), from: f, to: t });
+			}
+		}
+	}
+	/// updates allocation from solution
+	fn sol_to_alloc<'a>(&'a self, order: impl Iterator<Item = usize>, sols: &Vec<Vec<&'a E>>, allocs: &mut Vec<HashSet<&'a E>>, snowy: impl Fn(&E) -> bool){
+		for i in order {
+			for e in &sols[i] {
+				if snowy(e) && self.can_clear(i, e) {
+					if allocs[i].insert(e) {
+						for a in 0..allocs.len() {
+							if a != i {
+								allocs[a].remove(e);
+							}
+						}
+					}
+				}
+			}
+		}
+	}
+	/// Routes a vehicle through its targets
+	///
+	/// Returns: the vehicle's path, or the targets it can't reach
+	fn tour<'a, const DIRESPECT: bool>(&'a self, i: usize, sp: SID, targets: HashSet<&'a E>) -> Result<Vec<&'a E>, error::Error>
+	where
+		N::Id: std::fmt::Display,
+	{
+		log::debug!(" solving {}", i);
+		let legs = |u, v| self.legs.get(&(u, v)).copied();
+		let legs: Option<&dyn Fn(SID, SID) -> Option<N64>> = Some(&legs).filter(|_| !self.legs.is_empty()).map(|l| l as _);
+		let ids = |es: &HashSet<&E>| -> Vec<String> { es.iter().map(|e| format!("{}<->{}", self.graph.nid2id(e.p1()).unwrap(), self.graph.nid2id(e.p2()).unwrap())).collect() };
+		let pos = |n| self.graph.graph.get_node(n).map(|n| n.pos());
+		let straight = |u, v| match (pos(u), pos(v)) {
+			(Some(a), Some(b)) => self.straight * data::haversine(a, b),
+			_ => n64(0.0),
+		};
+		// the hierarchy is built through roadways, vehicles kept off them search without it
+		let drives = self.drives_roadways(i);
+		let search = match &self.hierarchy {
+			Some(h) if drives => Search::Hierarchy(h),
+			_ if self.straight > 0.0 => Search::AStar(&straight),
+			_ => Search::Dijkstra,
+		};
+		let weight = |e: &E| Some(e.deadhead()).filter(|_| drives || !e.is_roadway());
+		let profile = if drives { DEADHEAD } else { SIDEWALKS };
+		let route = |targets: HashSet<&'a E>| match self.routing {
+			Routing::Insertion => graph::heuristics::solve_pwrp::<_, _, _, _, _, DIRESPECT>(&self.graph.graph, sp, targets, weight, legs, self.exact, search, Some((&self.cache, profile))),
+			Routing::AntColony { ants, iterations, evaporation, alpha, beta } => {
+				let colony = graph::heuristics::Colony { ants, iterations, evaporation, alpha, beta };
+				graph::heuristics::solve_aco::<_, _, _, _, _, DIRESPECT>(&self.graph.graph, sp, targets, weight, legs, self.exact, search, Some((&self.cache, profile)), &colony, i as u64)
+			},
+		};
+		let sol = match route(targets.clone()) {
+			Ok(sol) => sol,
+			Err(es) => {
+				// unreachable edges are dropped up front, this is the last resort
+				log::warn!("Vehicle {} can't reach {} allocated segments, leaving them out: {}", i, es.len(), ids(&es).iter().take(50).join(", "));
+				let reachable = targets.iter().copied().filter(|e| !es.contains(e)).collect();
+				route(reachable).map_err(|es| {
+					let ids = ids(&es);
+					error::Error::Solver { message: format!("Vehicle {} can't reach {} allocated segments: {}", i, ids.len(), ids.iter().take(50).join(", ")), ids }
+				})?
+			}
+		};
+		Ok(self.reload(i, self.finish(i, self.unturn(i, sol, sp), sp, &targets), sp, &targets))
+	}
+	/// Splits a vehicle's tour with reload stops: whenever clearing its next target would take more consumable than it has left, it detours to the closest depot, reloads, and gets back
+	///
+	/// Returns: the tour, with the detours
+	fn reload<'a>(&'a self, v: usize, sol: Vec<&'a E>, sp: SID, targets: &HashSet<&'a E>) -> Vec<&'a E>
+	where
+		N::Id: std::fmt::Display,
+	{
+		let capacity = self.capacities.get(v).copied().unwrap_or_else(N64::infinity);
+		if !capacity.is_finite() || self.depots.is_empty() {
+			return sol;
+		}
+		let mut left = capacity;
+		let mut cleared = HashSet::default();
+		let mut tour = Vec::with_capacity(sol.len());
+		let mut at = sp;
+		let mut last: Option<&E> = None;
+		for e in sol {
+			let need = if targets.contains(e) && !cleared.contains(e) { e.consumption() } else { n64(0.0) };
+			if need > 0.0 && need > left && left < capacity {
+				let next = Some(e).filter(|e| !e.is_cyclic());
+				let detour = self.depots.iter().filter_map(|d| {
+					let to = self.leg(v, at, last, *d, None)?;
+					let stop = self.graph.graph.get_edges(*d).iter().find(|e| e.is_reload())?;
+					let back = self.leg(v, *d, to.last().copied().or(last), at, next)?;
+					Some((to.iter().chain(back.iter()).map(|e| e.deadhead()).sum::<N64>(), to, stop, back))
+				}).min_by_key(|(cost, ..)| *cost);
+				match detour {
+					Some((_, to, stop, back)) => {
+						tour.extend(to);
+						tour.push(stop);
+						tour.extend(back);
+						left = capacity;
+					},
+					None => log::warn!("Vehicle {} can't get to any depot from {} to reload", v, self.graph.nid2id(at).unwrap()),
+				}
+			}
+			if need > capacity {
+				log::warn!("Vehicle {} needs more than it can carry to clear a segment from {}", v, self.graph.nid2id(at).unwrap());
+			}
+			if need > 0.0 {
+				cleared.insert(e);
+				left -= need;
+			}
+			if !e.is_cyclic() {
+				last = Some(e);
+				at = e.other(at);
+			}
+			tour.push(e);
+		}
+		tour
+	}
+	/// Ends a vehicle's tour as per [`Tours`]: dropping what follows its last target, and heading to its end location instead with closed tours
+	///
+	/// Returns: the tour, or the tour back to its start as it was if it can't get to its end location
+	fn finish<'a>(&'a self, v: usize, sol: Vec<&'a E>, sp: SID, targets: &HashSet<&'a E>) -> Vec<&'a E>
+	where
+		N::Id: std::fmt::Display,
+	{
+		let end = self.ends.get(v).copied().flatten();
+		if self.tours == Tours::Closed && end.is_none_or(|end| end == sp) {
+			return sol;
+		}
+		let last = sol.iter().rposition(|e| targets.contains(e)).map_or(0, |i| i + 1);
+		let mut tour = sol[..last].to_vec();
+		if let (Tours::Closed, Some(end)) = (self.tours, end) {
+			let roads: Vec<_> = tour.iter().copied().filter(|e| !e.is_cyclic()).collect();
+			let at = graph::Graph::<SID, N, E>::path_to_nodes(roads.iter().copied(), sp).last().unwrap().0;
+			match self.leg(v, at, roads.last().copied(), end, None) {
+				Some(leg) => tour.extend(leg),
+				None => {
+					log::warn!("Vehicle {} can't get to its end location {}, going back to its start", v, self.graph.nid2id(end).unwrap());
+					return sol;
+				},
+			}
+		}
+		tour
+	}
+	/// Improves a vehicle's tour by local search over the order it clears its targets in, as per [`Parameters::local_search`]
+	///
+	/// Targets are cleared as first traversed, deadheading between them along shortest paths, and the tour still ends where it did.
+	/// Moves, first improvement wins, until none improves the tour:
+	/// - 2-opt: reversing a run of up to `WINDOW` targets, all two-way (or anyway if directionality isn't respected)
+	/// - Or-opt: relocating a run of up to 3 targets, up to `WINDOW` positions away
+	///
+	/// Tours of vehicles with a limited capacity are left as they are, as the moves would upset where they reload.
+	///
+	/// Returns: the tour, improved if it could be
+	fn local_search<'a, const DIRESPECT: bool>(&'a self, v: usize, sol: &[&'a E], sp: SID, targets: &HashSet<&'a E>, params: &Parameters, clears: impl Fn(&E) -> N64) -> Vec<&'a E>
+	where
+		N::Id: std::fmt::Display,
+	{
+		const WINDOW: usize = 8;
+		const MAX_PASSES: usize = 64;
+		if params.local_search == LocalSearch::No || self.capacities.get(v).is_some_and(|c| c.is_finite()) {
+			return sol.to_vec();
+		}
+		// targets, as `(from, to, edge)` in the order they're cleared
+		let mut seq = Vec::new();
+		let mut seen = HashSet::default();
+		let mut end = sp;
+		for e in sol {
+			let next = e.other(end);
+			if targets.contains(e) && seen.insert(*e) {
+				seq.push((end, next, *e));
+			}
+			end = next;
+		}
+		if seq.len() < 2 {
+			return sol.to_vec();
+		}
+		let path = |u: SID, w: SID| self.graph.graph.pathfind::<_, _, DIRESPECT>(u, w, |e| Some(e.deadhead()).filter(|_| self.can_drive(v, e)));
+		let mut legs: HashMap<(SID, SID), Option<N64>> = HashMap::default();
+		let mut leg = |u: SID, w: SID| *legs.entry((u, w)).or_insert_with(|| path(u, w).map(|p| p.iter().map(|e| self.edge_cost(v, e, clears(e))).sum()));
+		let reversible = |e: &E| !DIRESPECT || !e.directed() || e.is_cyclic();
+		for _ in 0..MAX_PASSES {
+			let n = seq.len();
+			// node before position `k`, and at it
+			let before = |seq: &[(SID, SID, &E)], k: usize| if k == 0 { sp } else { seq[k - 1].1 };
+			let at = |seq: &[(SID, SID, &E)], k: usize| if k == seq.len() { end } else { seq[k].0 };
+			let mut improved = false;
+			if params.local_search.two_opt() {
+				'two_opt: for i in 0..n {
+					if !reversible(seq[i].2) {
+						continue;
+					}
+					let (mut inner_old, mut inner_new) = (Some(n64(0.0)), Some(n64(0.0)));
+					for j in (i + 1)..n.min(i + WINDOW) {
+						if !reversible(seq[j].2) {
+							break;
+						}
+						inner_old = inner_old.and_then(|d| Some(d + leg(seq[j - 1].1, seq[j].0)?));
+						inner_new = inner_new.and_then(|d| Some(d + leg(seq[j].0, seq[j - 1].1)?));
+						let old = (|| Some(leg(before(&seq, i), seq[i].0)? + inner_old? + leg(seq[j].1, at(&seq, j + 1))?))();
+						let new = (|| Some(leg(before(&seq, i), seq[j].1)? + inner_new? + leg(seq[i].0, at(&seq, j + 1))?))();
+						if matches!((old, new), (Some(old), Some(new)) if new < old) {
+							seq[i..=j].reverse();
+							for s in &mut seq[i..=j] {
+								*s = (s.1, s.0, s.2);
+							}
+							improved = true;
+							break 'two_opt;
+						}
+					}
+				}
+			}
+			if !improved && params.local_search.or_opt() {
+				'or_opt: for len in 1..=3 {
+					for i in 0..n.saturating_sub(len - 1) {
+						let last = i + len - 1;
+						let Some(gain) = (|| Some(leg(before(&seq, i), seq[i].0)? + leg(seq[last].1, at(&seq, last + 1))? - leg(before(&seq, i), at(&seq, last + 1))?))() else { continue };
+						let mut rest = seq.clone();
+						let chain: Vec<_> = rest.drain(i..=last).collect();
+						for q in i.saturating_sub(WINDOW)..=(i + WINDOW).min(rest.len()) {
+							if q == i {
+								continue;
+							}
+							let cost = (|| Some(leg(before(&rest, q), seq[i].0)? + leg(seq[last].1, at(&rest, q))? - leg(before(&rest, q), at(&rest, q))?))();
+							if cost.is_some_and(|c| c < gain) {
+								rest.splice(q..q, chain);
+								seq = rest;
+								improved = true;
+								break 'or_opt;
+							}
+						}
+					}
+				}
+			}
+			if !improved {
+				break;
+			}
+		}
+		let mut tour = Vec::with_capacity(sol.len());
+		let mut node = sp;
+		for (from, to, e) in &seq {
+			match path(node, *from) {
+				Some(p) => tour.extend(p),
+				None => return sol.to_vec(),
+			}
+			tour.push(*e);
+			node = *to;
+		}
+		match path(node, end) {
+			Some(p) => tour.extend(p),
+			None => return sol.to_vec(),
+		}
+		let tour = self.unturn(v, tour, sp);
+		if self.tour_cost(v, &tour, &clears) < self.tour_cost(v, sol, &clears) {
+			tour
+		} else {
+			sol.to_vec()
+		}
+	}
+	/// Routes every vehicle through its allocation, in the given evaluation order.
+	///
+	/// With [`Clearing::All`], edges already cleared by vehicles earlier in the order are skipped (and not slowed down for).
+	/// Otherwise, tours are independent, and routed in parallel.
+	/// Vehicles only clear the edges they [can](PlowSolver::can_clear).
+	///
+	/// Returns: paths and their costs, for each vehicle, or why a vehicle can't be routed
+	fn route<'a, const DIRESPECT: bool>(&'a self, sps: &[SID], order: &[usize], alloc: &[HashSet<&'a E>], snowy: &Snowy<'a, E>, params: &Parameters) -> Result<(Vec<Vec<&'a E>>, Vec<N64>), error::Error>
+	where
+		N::Id: std::fmt::Display,
+		E: std::fmt::Debug,
+	{
+		if params.clearing != Clearing::All {
+			let sols = alloc.par_iter().enumerate().map(|(i, a)| self.tour::<DIRESPECT>(i, sps[i], a.clone())).collect::<Result<Vec<_>, _>>()?;
+			let costs = self.tours_costs(&sols, alloc, snowy);
+			return Ok((sols, costs));
+		}
+		let mut sols: Vec<_> = (0..alloc.len()).map(|_| Vec::new()).collect();
+		let mut costs = vec![n64(0.0); alloc.len()];
+		let mut dun = HashSet::default();
+		for &i in order {
+			let targets: HashSet<_> = alloc[i].iter().copied().filter(|e| !dun.contains(e)).collect();
+			let sol = self.tour::<DIRESPECT>(i, sps[i], targets)?;
+			costs[i] = self.tour_cost(i, &sol, |e| snowy.get(e).copied().filter(|_| !dun.contains(e) && self.can_clear(i, e)).unwrap_or_else(|| n64(0.0)));
+			dun.extend(sol.iter().copied().filter(|e| self.can_clear(i, e)));
+			sols[i] = sol;
+		}
+		Ok((sols, costs))
+	}
+	/// Moves cycles between tours sharing a node, from the more expensive to the cheaper one, if it may drive through them.
+	///
+	/// Tours are then detoured around the forbidden turns the moves make.
+	/// Cycles are left where they are when vehicles have a limited capacity, as moving them would upset where they reload.
+	///
+	/// Returns: the tours with the cycles moved
+	fn recycle<'a>(&'a self, sols: &[Vec<&'a E>], costs: &[N64], order: &[usize], sps: &[SID]) -> Vec<Vec<&'a E>>
+	where
+		N::Id: std::fmt::Display,
+	{
+		if self.capacities.iter().any(|c| c.is_finite()) {
+			return sols.to_vec();
+		}
+		let vs = sols.len();
+		let mut sol_improv = sols.to_vec();
+		let mut vycles: Vec<Vec<_>> = sols.iter().zip(sps.iter()).map(|(path, n0)| graph::Graph::<SID, N, E>::path_to_nodes(path.iter().copied(), *n0).into_iter().map(|(v, _)| v).collect()).collect();
+		for i in 0..vs {
+			'nexc: for j in (i+1)..vs {
+				let (i, j) = if costs[order[i]] > costs[order[j]] { (order[i], order[j]) } else { (order[j], order[i]) };
+				for iu in 0..vycles[i].len() {
+					for ju in 0..vycles[j].len() {
+						if vycles[i][iu] == vycles[j][ju] {
+							for iv in (iu+1)..vycles[i].len() {
+								if vycles[i][iv] == vycles[i][iu] && sol_improv[i][iu..iv].iter().all(|e| self.can_drive(j, e)) {
+									// [i][iu..=iv] <=> [j][ju..=ju]
+									// same as
+									log::trace!("  [{}][{}..{}] => [{}][{}..{}]", i, iu, iv, j, ju, ju);
+									let mine: Vec<_> = sol_improv[i].splice(iu..iv, vec![]).collect();
+									sol_improv[j].splice(ju..ju, mine);
+									let mine: Vec<_> = vycles[i].splice(iu..iv, vec![]).collect();
+									vycles[j].splice(ju..ju, mine);
+									//don't update costs to avoid swap-backs idk
+									continue 'nexc;
+								}
+							}
+						}
+					}
+				}
+			}
+		}
+		sol_improv.into_iter().zip(sps.iter()).enumerate().map(|(v, (sol, sp))| self.unturn(v, sol, *sp)).collect()
+	}
+	/// Expected cost of a vehicle traversing an edge: cleared (slowed down, in as many passes as it takes, and serviced) with the probability `p`, deadheading otherwise, at the vehicle's pace
+	fn edge_cost(&self, v: usize, e: &E, p: N64) -> N64 {
+		if e.is_task() {
+			e.weight()
+		} else {
+			let (deadhead, clear) = self.paces.get(v).copied().unwrap_or_else(|| self.costing.paces(&data::VehicleProfile::default(), None));
+			let passes = self.profiles.get(v).map_or(n64(1.0), |pr| pr.passes(e.width()));
+			e.deadhead() * deadhead * (n64(1.0) - p) + e.weight() * clear * passes * p + p * (e.service() + self.costing.service_time())
+		}
+	}
+	/// Expected cost of a vehicle's tour, edges being slowed down (and serviced) with the probability `clears` that they get cleared
+	fn tour_cost(&self, v: usize, tour: &[&E], clears: impl Fn(&E) -> N64) -> N64 {
+		tour.iter().map(|e| self.edge_cost(v, *e, clears(e))).sum::<N64>() + self.turns_cost(tour)
+	}
+	/// Costs of tours, each clearing the snowy edges allocated to it
+	fn tours_costs<'a>(&'a self, sols: &[Vec<&'a E>], alloc: &[HashSet<&'a E>], snowy: &Snowy<'a, E>) -> Vec<N64> {
+		sols.iter().zip(alloc.iter()).enumerate().map(|(i, (sol, alloc))| self.tour_cost(i, sol, |e| snowy.get(e).copied().filter(|_| alloc.contains(e)).unwrap_or_else(|| n64(0.0)))).collect()
+	}
+	/// Costs of tours in a snow scenario, edges being cleared as [`PlowSolver::route`] does
+	fn scenario_costs<'a>(&'a self, sols: &[Vec<&'a E>], order: &[usize], alloc: &[HashSet<&'a E>], scenario: &Snowy<'a, E>, params: &Parameters) -> Vec<N64> {
+		let mut costs = vec![n64(0.0); sols.len()];
+		let mut dun = HashSet::default();
+		for &i in order {
+			costs[i] = self.tour_cost(i, &sols[i], |e| scenario.get(e).copied().filter(|_| if params.clearing == Clearing::All { !dun.contains(e) && self.can_clear(i, e) } else { alloc[i].contains(e) }).unwrap_or_else(|| n64(0.0)));
+			if params.clearing == Clearing::All {
+				dun.extend(sols[i].iter().copied().filter(|e| self.can_clear(i, e)));
+			}
+		}
+		costs
+	}
+	/// Priority-weighted clearing latency of tours in a snow scenario, edges being cleared as [`PlowSolver::route`] does
+	///
+	/// Returns: the sum, over cleared edges, of their priority weight times the cost into the tour at which they get cleared (expected, for uncertain edges)
+	fn latency<'a>(&'a self, sols: &[Vec<&'a E>], order: &[usize], alloc: &[HashSet<&'a E>], scenario: &Snowy<'a, E>, params: &Parameters) -> N64 {
+		let mut latency = n64(0.0);
+		let mut dun = HashSet::default();
+		for &i in order {
+			let mut at = n64(0.0);
+			let mut cleared = HashSet::default();
+			for e in &sols[i] {
+				let p = scenario.get(e).copied().filter(|_| if params.clearing == Clearing::All { !dun.contains(e) && self.can_clear(i, e) } else { alloc[i].contains(e) }).unwrap_or_else(|| n64(0.0));
+				at += self.edge_cost(i, *e, p);
+				if p > 0.0 && cleared.insert(*e) {
+					latency += p * params.priorities.weight(e.priority()) * at;
+				}
+			}
+			if params.clearing == Clearing::All {
+				dun.extend(sols[i].iter().copied().filter(|e| self.can_clear(i, e)));
+			}
+		}
+		latency
+	}
+	/// Objective value of a solution.
+	///
+	/// Without scenarios, this is the [value](Parameters::value) of the (expected) costs and [latency](PlowSolver::latency).
+	/// Otherwise, values in all scenarios are aggregated as per [`Parameters::robustness`].
+	///
+	/// Returns: value and the maximum tour cost
+	#[allow(clippy::too_many_arguments)]
+	fn evaluate<'a>(&'a self, sols: &[Vec<&'a E>], costs: &[N64], order: &[usize], alloc: &[HashSet<&'a E>], snowy: &Snowy<'a, E>, scenarios: &[Snowy<'a, E>], params: &Parameters) -> (N64, N64) {
+		let latency = |scenario| if params.weight_latency > 0.0 { self.latency(sols, order, alloc, scenario, params) } else { n64(0.0) };
+		let scale = Scale {
+			edges: snowy.values().copied().sum(),
+			required: snowy.iter().map(|(e, p)| e.weight() * *p).sum(),
+			vehicles: sols.len(),
+		};
+		if scenarios.is_empty() {
+			return params.value(costs, latency(snowy), &scale);
+		}
+		let cost_max = costs.iter().copied().max().unwrap_or_else(|| n64(0.0));
+		let values: Vec<_> = scenarios.iter().map(|scenario| params.value(&self.scenario_costs(sols, order, alloc, scenario, params), latency(scenario), &scale).0).collect();
+		(params.robustness.aggregate(values), cost_max)
+	}
+	/// Iterative annealing solver, restarted [`Annealing::restarts`] times (in parallel threads with [`Annealing::parallel`]), keeping the best solution of all.
+	///
+	/// Restarts are seeded one after the other from the seed (drawn if there is none), and share the wall-clock budget.
+	///
+	/// Arguments:
+	/// - `DIRESPECT`
+	/// - `sps`: starting locations, on the graph, of each vehicle
+	/// - `snowy`: edges that need to be cleared, with the probability they do
+	/// - `scenarios`: alternative snow scenarios the solution must be robust to (can be none)
+	/// - `params`: meta parameters
+	///
+	/// Returns: paths, for each vehicle, or the vehicles whose tours cost over the cap, with [`Parameters::fail_over_cap`]
+	fn solve<'a, const DIRESPECT: bool>(&'a self, sps: &Vec<SID>, snowy: &Snowy<'a, E>, scenarios: &[Snowy<'a, E>], params: &Parameters) -> Result<Vec<Vec<&'a E>>, error::Error>
+	where
+		N::Id: std::fmt::Display,
+		E: std::fmt::Debug,
+	{
+		let _job = metrics::Job::start();
+		let deadline = params.annealing.max_wall_time.map(|m| Instant::now() + Duration::from_secs_f64(m.max(0.0) * 60.0));
+		let restarts = params.annealing.restarts.max(1);
+		let (solution, value, costs) = if restarts == 1 {
+			self.run::<DIRESPECT>(sps, snowy, scenarios, params, deadline)?
+		} else {
+			let mut params = params.clone();
+			let seed = params.seeded();
+			let run = |r: u32| {
+				let params = Parameters { seed: Some(seed.wrapping_add(r as u64)), ..params.clone() };
+				self.run::<DIRESPECT>(sps, snowy, scenarios, &params, deadline)
+			};
+			let runs: Vec<_> = if params.annealing.parallel { (0..restarts).into_par_iter().map(run).collect::<Result<_, _>>()? } else { (0..restarts).map(run).collect::<Result<_, _>>()? };
+			let values: Vec<_> = runs.iter().map(|(_, value, _)| value.raw()).filter(|v| v.is_finite()).collect();
+			if !values.is_empty() {
+				let mean = values.iter().sum::<f64>() / values.len() as f64;
+				let stddev = (values.iter().map(|v| (v - mean).powi(2)).sum::<f64>() / values.len() as f64).sqrt();
+				log::info!("{} restarts from seed {}, values from {:.1} to {:.1}, {:.1} on average (standard deviation {:.1}): {}", restarts, seed, values.iter().copied().fold(f64::INFINITY, f64::min), values.iter().copied().fold(f64::NEG_INFINITY, f64::max), mean, stddev, runs.iter().map(|(_, value, _)| format!("{:.1}", value)).join(", "));
+			}
+			runs.into_iter().min_by_key(|(_, value, _)| *value).unwrap()
+		};
+		progress::report(|| progress::Progress {
+			phase: progress::Phase::Done,
+			iteration: 0,
+			budget: 0,
+			temperature: 0.0,
+			best: value.raw(),
+			costs: costs.iter().map(|c| c.raw()).collect(),
+		});
+		control::wrapped_up();
+		// out of time before finding a feasible solution: rejected tours over the cap, or failing over it
+		let timed_out = deadline.is_some_and(|d| Instant::now() >= d);
+		if timed_out && !value.is_finite() {
+			return Err(error::Error::timeout(format!("No feasible solution found within max_wall_time {} minutes", params.annealing.max_wall_time.unwrap_or_default())));
+		}
+		if let Some(cap) = params.max_tour_cost {
+			let over: Vec<_> = costs.iter().enumerate().filter(|(_, c)| **c > cap).map(|(i, c)| format!("{} ({:.1})", i, c)).collect();
+			if !over.is_empty() {
+				let message = format!("No solution found keeping every tour within max_tour_cost {}, vehicles over it: {}", cap, over.join(", "));
+				if params.fail_over_cap && timed_out {
+					return Err(error::Error::timeout(format!("{}, within max_wall_time {} minutes", message, params.annealing.max_wall_time.unwrap_or_default())));
+				}
+				if params.fail_over_cap {
+					return Err(error::Error::solver(message));
+				}
+				log::warn!("{}", message);
+			}
+		}
+		Ok(solution)
+	}
+	/// One run of the annealing solver, and polishing its best solution
+	///
+	/// Returns: paths, for each vehicle, their objective value and costs, or why a vehicle can't be routed
+	fn run<'a, const DIRESPECT: bool>(&'a self, sps: &[SID], snowy: &Snowy<'a, E>, scenarios: &[Snowy<'a, E>], params: &Parameters, deadline: Option<Instant>) -> Result<Solved<'a, E>, error::Error>
+	where
+		N::Id: std::fmt::Display,
+		E: std::fmt::Debug,
+	{
+		let vs = sps.len();
+		let alloc = params.allocation.strategy::<_, _, _, DIRESPECT>(params.metric).allocate(&self.graph.graph, sps, &snowy.keys().copied().collect::<Vec<_>>(), &|e| e.deadhead(), &|v, e| self.can_clear(v, e));
+		log::debug!("Initialized allocations: {}", alloc.iter().map(|a| a.len()).join("/"));
+		if progress::recording_moves() {
+			self.report_moves((progress::Phase::Annealing, 0, 0), &[], &alloc);
+		}
+		let chain = Chain {
+			order: (0..vs).collect(),
+			alloc,
+			solution: (0..vs).map(|_| Vec::new()).collect(),
+			costs: vec![n64(0.0); vs],
+			value: N64::infinity(),
+			cost_max: N64::infinity(),
+		};
+		let (size, resample) = match params.algorithm {
+			Algorithm::SingleChain => (1, 0),
+			Algorithm::Population { size, resample } => (size.max(1), resample),
+		};
+		let mut rng = params.rng();
+		let mut chains: Vec<_> = (0..size).map(|_| (chain.clone(), params.acceptance.criterion())).collect();
+		// chains accepting worse solutions drift off the best one found, kept aside
+		let mut best_chain = chain.clone();
+		let mut tabus: Vec<_> = (0..size).map(|_| Tabu { current: chain.clone(), tabu: HashMap::default(), iteration: 0 }).collect();
+		let mut populations: Vec<Vec<Chain<'a, E>>> = (0..size).map(|_| Vec::new()).collect();
+		let mut temperature: f64 = params.annealing.starting_temperature;
+		let mut cooling = params.annealing.cooling_factor;
+		if params.annealing.adaptive && params.solver == Solver::Annealing {
+			match self.walk_temperature::<DIRESPECT>(&chain, sps, snowy, scenarios, params, &mut rng)? {
+				Some(t) => temperature = t,
+				None => log::warn!("Objective values don't vary along a random walk, starting annealing at {}", temperature),
+			}
+			log::info!("Adaptive annealing starting at temperature {:.2}", temperature);
+		}
+		let (mut accepted, mut candidates) = (0, 0);
+		let mut ii = 0u64;
+		let params_base = params;
+		for mi in 0.. {
+			let params = &control::adjusted(params_base);
+			let budget = params.solver.iterations(&params.annealing);
+			if mi >= budget {
+				break;
+			}
+			if mi > 0 && out_of_time(deadline) {
+				log::info!("Out of time after {} iterations, wrapping up", mi);
+				break;
+			}
+			let best = chains.iter().map(|(c, _)| c.value).min().unwrap();
+			log::debug!("iteration {} current best {:.1}", mi, best);
+			metrics::iteration(best_chain.value.raw());
+			progress::report(|| progress::Progress {
+				phase: progress::Phase::Annealing,
+				iteration: mi,
+				budget,
+				temperature,
+				best: best_chain.value.raw(),
+				costs: best_chain.costs.iter().map(|c| c.raw()).collect(),
+			});
+			for (c, (((chain, acceptance), tabu), population)) in chains.iter_mut().zip(tabus.iter_mut()).zip(populations.iter_mut()).enumerate() {
+				let before = progress::recording_moves().then(|| chain.alloc.clone());
+				match params.solver {
+					Solver::Annealing => {
+						let (a, n) = self.anneal::<DIRESPECT>(chain, acceptance.as_mut(), temperature, (mi, c), sps, snowy, scenarios, params, &mut rng)?;
+						accepted += a;
+						candidates += n;
+					},
+					Solver::Tabu { tenure, neighbours } => self.tabu::<DIRESPECT>(chain, tabu, tenure, neighbours, sps, snowy, scenarios, params, &mut rng)?,
+					Solver::Genetic { population: size, elitism, .. } => self.breed::<DIRESPECT>(chain, population, size, elitism, sps, snowy, scenarios, params, &mut rng)?,
+				}
+				if let Some(before) = before {
+					self.report_moves((progress::Phase::Annealing, mi, c), &before, &chain.alloc);
+				}
+			}
+			if let Some(chain) = chains.iter().map(|(c, _)| c).filter(|c| is_better(c.value, c.cost_max, best_chain.value, best_chain.cost_max)).min_by_key(|c| (c.value, c.cost_max)) {
+				best_chain = chain.clone();
+			}
+			if size > 1 {
+				// share improvements: the worst chain takes over a new best solution
+				let (i_best, i_worst) = match (0..size).minmax_by_key(|i| (chains[*i].0.value, chains[*i].0.cost_max)) {
+					itertools::MinMaxResult::MinMax(i, j) => (i, j),
+					_ => (0, 0),
+				};
+				if chains[i_best].0.value < best {
+					log::debug!(" chain {} improved to {:.5}, shared with chain {}", i_best, chains[i_best].0.value, i_worst);
+					chains[i_worst].0 = chains[i_best].0.clone();
+				}
+				// resample proportionally to Boltzmann weights
+				if resample > 0 && (mi+1) % resample == 0 {
+					let best = chains[i_best].0.value;
+					let weights: Vec<_> = chains.iter().map(|(c, _)| if c.value.is_finite() { (-(c.value - best).raw() / temperature).exp() } else { 0.0 }).collect();
+					if let Ok(dist) = rand::distributions::WeightedIndex::new(&weights) {
+						let pool: Vec<_> = chains.iter().map(|(c, _)| c.clone()).collect();
+						for (chain, _) in chains.iter_mut() {
+							*chain = pool[rng.sample(&dist)].clone();
+						}
+						log::debug!(" resampled chains: {}", chains.iter().map(|(c, _)| format!("{:.1}", c.value)).join("|"));
+					}
+				}
+			}
+			//Update the temperature
+			ii += 1;
+			if ii >= params.annealing.ft_iterations {
+				ii = 0;
+				if !params.annealing.adaptive {
+					cooling = params.annealing.cooling_factor;
+				} else if candidates > 0 {
+					// cool faster when accepting more than the target rate, slower when accepting less
+					let rate = accepted as f64 / candidates as f64;
+					cooling = if rate > params.annealing.target_acceptance { cooling * ADAPT } else { 1.0 - (1.0 - cooling) * ADAPT };
+					log::debug!(" acceptance rate {:.2}, cooling by {:.3}", rate, cooling);
+					(accepted, candidates) = (0, 0);
+				}
+				temperature *= cooling;
+				log::debug!(" t={:.2}", temperature);
+			}
+		}
+		self.polish::<DIRESPECT>(sps, snowy, scenarios, params, deadline, best_chain.order, best_chain.alloc, best_chain.solution, best_chain.costs)
+	}
+	/// Reallocation move between vehicles, as per [`Parameters::realloc`]
+	///
+	/// Returns: the new allocations, unless there is no move to make
+	fn realloc<'a>(&'a self, alloc: &[HashSet<&'a E>], params: &Parameters, rng: &mut impl Rng) -> Option<Vec<HashSet<&'a E>>> {
+		let vs = alloc.len();
+		if vs < 2 || params.realloc == Realloc::No {
+			return None;
+		}
+		// edges allocated to `i` that `j` can clear
+		let movable = |i: usize, j: usize| -> Vec<&'a E> { alloc[i].iter().copied().filter(|e| self.can_clear(j, e)).collect() };
+		let mut next = alloc.to_vec();
+		match params.realloc {
+			Realloc::No => unreachable!(),
+			Realloc::Swap2Random => {
+				let i = rng.gen_range(0..vs);
+				let j = (i + rng.gen_range(1..vs)) % vs;
+				let a = *movable(i, j).choose(rng)?;
+				let b = *movable(j, i).choose(rng)?;
+				next[i].remove(a);
+				next[j].remove(b);
+				next[i].insert(b);
+				next[j].insert(a);
+				log::debug!(" swapping allocated links between {} and {}", i, j);
+			},
+			Realloc::MostToLeast => {
+				let load = |i: &usize| alloc[*i].iter().map(|e| e.weight()).sum::<N64>();
+				let (j, i) = match (0..vs).minmax_by_key(load) {
+					itertools::MinMaxResult::MinMax(j, i) => (j, i),
+					_ => return None,
+				};
+				let e = *movable(i, j).choose(rng)?;
+				next[i].remove(e);
+				next[j].insert(e);
+				log::debug!(" moving an allocated link from {} to {}", i, j);
+			},
+		}
+		Some(next)
+	}
+	/// Reorder move of the evaluation order, as per [`Parameters::reorder`]
+	fn reorder(order: &mut [usize], solution: &[Vec<&E>], params: &Parameters, rng: &mut impl Rng) {
+		let vs = order.len();
+		match params.reorder {
+			Reorder::No => {},
+			Reorder::Swap2Random => order.swap(rng.gen_range(0..vs), rng.gen_range(0..vs)),
+			Reorder::Swap2MostLeast => {
+				if let itertools::MinMaxResult::MinMax(i, j) = order.iter().cloned().minmax_by_key(|i| solution[*i].len()) {
+					order.swap(i, j);
+				}
+			},
+			Reorder::RandomReorder => order.shuffle(rng),
+		}
+	}
+	/// Starting temperature of the adaptive annealing schedule: the standard deviation of the objective values along a random walk from the initial chain, by reallocation and reorder moves
+	///
+	/// Returns: the temperature, unless the values don't vary, or why a vehicle can't be routed
+	#[allow(clippy::too_many_arguments)]
+	fn walk_temperature<'a, const DIRESPECT: bool>(&'a self, chain: &Chain<'a, E>, sps: &[SID], snowy: &Snowy<'a, E>, scenarios: &[Snowy<'a, E>], params: &Parameters, rng: &mut impl Rng) -> Result<Option<f64>, error::Error>
+	where
+		N::Id: std::fmt::Display,
+		E: std::fmt::Debug,
+	{
+		let (mut order, mut alloc, mut solution) = (chain.order.clone(), chain.alloc.clone(), chain.solution.clone());
+		let mut values = Vec::new();
+		for _ in 0..WALK {
+			if let Some(next) = self.realloc(&alloc, params, rng) {
+				alloc = next;
+			}
+			Self::reorder(&mut order, &solution, params, rng);
+			let (sol, costs) = self.route::<DIRESPECT>(sps, &order, &alloc, snowy, params)?;
+			let (value, _) = self.evaluate(&sol, &costs, &order, &alloc, snowy, scenarios, params);
+			if value.is_finite() {
+				values.push(value.raw());
+			}
+			solution = sol;
+		}
+		let mean = values.iter().sum::<f64>() / values.len() as f64;
+		let stddev = (values.iter().map(|v| (v - mean).powi(2)).sum::<f64>() / values.len() as f64).sqrt();
+		Ok((stddev > 0.0).then_some(stddev))
+	}
+	/// One annealing iteration of a chain: reallocate, reorder, re-route, and try to improve.
+	///
+	/// The candidate solutions are reported as [`progress::Candidate`]s, of iteration and chain `at`.
+	///
+	/// Returns: how many candidate solutions were accepted, of how many, or why a vehicle can't be routed
+	#[allow(clippy::too_many_arguments)]
+	fn anneal<'a, const DIRESPECT: bool>(&'a self, chain: &mut Chain<'a, E>, acceptance: &mut dyn accept::AcceptanceCriterion, temperature: f64, at: (u64, usize), sps: &[SID], snowy: &Snowy<'a, E>, scenarios: &[Snowy<'a, E>], params: &Parameters, rng: &mut impl Rng) -> Result<(u32, u32), error::Error>
+	where
+		N::Id: std::fmt::Display,
+		E: std::fmt::Debug,
+	{
+		let Chain { order, alloc, solution, costs: costs_best, value: value_best, cost_max: cost_max_best } = chain;
+		//Try to improve allocations
+		let alloc_next = self.realloc(alloc, params, rng);
+		//Shuffle evaluation order
+		Self::reorder(order, solution, params, rng);
+		log::debug!(" new order: {:?}", order);
+		//Provide new solutions
+		let alloc_route = alloc_next.as_deref().unwrap_or(alloc);
+		let (sol_next, costs_next) = self.route::<DIRESPECT>(sps, order, alloc_route, snowy, params)?;
+		//Evaluate
+		let (value_next, cost_next_max) = self.evaluate(&sol_next, &costs_next, order, alloc_route, snowy, scenarios, params);
+		log::debug!(" new value: {:.5} costs: {}", value_next, costs_next.iter().join("|"));
+		let candidate = |step, value: N64, accepted, costs: &[N64]| progress::candidate(|| progress::Candidate { iteration: at.0, chain: at.1, step, temperature, value: value.raw(), accepted, costs: costs.iter().map(|c| c.raw()).collect() });
+		//worse reallocations are kept according to the acceptance criterion, as improvements are
+		let accepted = is_better(value_next, cost_next_max, *value_best, *cost_max_best) || (alloc_next.is_some() && value_best.is_finite() && acceptance.accept(value_next, *value_best, *value_best, temperature, rng));
+		candidate(progress::Step::Route, value_next, accepted, &costs_next);
+		let mut tally = (accepted as u32, 1);
+		let sol_next = if accepted {
+			log::debug!(" solution accepted");
+			if let Some(alloc_next) = alloc_next {
+				*alloc = alloc_next;
+			}
+			*solution = sol_next;
+			*costs_best = costs_next.clone();
+			*value_best = value_next;
+			*cost_max_best = cost_next_max;
+			if params.clearing == Clearing::All {
+				self.sol_to_alloc(order.iter().cloned(), solution, alloc, |e| snowy.contains_key(e));
+			}
+			&*solution
+		} else {
+			&sol_next
+		};
+		//Try to improve
+		if params.recycle == Recycle::ExpensiveToCheap {
+			let sol_improv = self.recycle(sol_next, &costs_next, order, sps);
+			//Evaluate improvements
+			let costs_improv = self.tours_costs(&sol_improv, alloc, snowy);
+			let (value_improv, cost_improv_max) = self.evaluate(&sol_improv, &costs_improv, order, alloc, snowy, scenarios, params);
+			log::debug!(" new value: {:.5} costs: {}", value_improv, costs_improv.iter().join("|"));
+			//if the improved solution is actually better, or acceptable anyway, keep it
+			let accepted = is_better(value_improv, cost_improv_max, *value_best, *cost_max_best) || (value_improv.is_finite() && value_next.is_finite() && acceptance.accept(value_improv, value_next, *value_best, temperature, rng));
+			candidate(progress::Step::Recycle, value_improv, accepted, &costs_improv);
+			tally = (tally.0 + accepted as u32, tally.1 + 1);
+			if accepted {
+				log::debug!(" improvements accepted");
+				*solution = sol_improv;
+				*costs_best = costs_improv;
+				*value_best = value_improv;
+				*cost_max_best = cost_improv_max;
+				self.sol_to_alloc(order.iter().cloned(), solution, alloc, |e| snowy.contains_key(e));
+			}
+		}
+		//Try to improve the tours themselves
+		if params.local_search != LocalSearch::No {
+			let sol_improv: Vec<_> = solution.par_iter().enumerate().map(|(i, sol)| self.local_search::<DIRESPECT>(i, sol, sps[i], &alloc[i], params, |e| snowy.get(e).copied().filter(|_| alloc[i].contains(e)).unwrap_or_else(|| n64(0.0)))).collect();
+			let costs_improv = self.tours_costs(&sol_improv, alloc, snowy);
+			let (value_improv, cost_improv_max) = self.evaluate(&sol_improv, &costs_improv, order, alloc, snowy, scenarios, params);
+			if is_better(value_improv, cost_improv_max, *value_best, *cost_max_best) {
+				log::debug!(" local search improved to {:.5}", value_improv);
+				*solution = sol_improv;
+				*costs_best = costs_improv;
+				*value_best = value_improv;
+				*cost_max_best = cost_improv_max;
+			}
+		}
+		Ok(tally)
+	}
+	/// One tabu search iteration: sample neighbours of the current solution, and move to the best one whose move isn't tabu.
+	///
+	/// Moving makes undoing the move tabu for `tenure` iterations, unless undoing it leads to a solution better than the best one (aspiration).
+	/// The first iteration routes the initial allocation as is.
+	///
+	/// Returns: why a vehicle can't be routed, if it can't
+	#[allow(clippy::too_many_arguments)]
+	fn tabu<'a, const DIRESPECT: bool>(&'a self, chain: &mut Chain<'a, E>, tabu: &mut Tabu<'a, E>, tenure: u64, neighbours: usize, sps: &[SID], snowy: &Snowy<'a, E>, scenarios: &[Snowy<'a, E>], params: &Parameters, rng: &mut impl Rng) -> Result<(), error::Error>
+	where
+		N::Id: std::fmt::Display,
+		E: std::fmt::Debug,
+	{
+		let vs = sps.len();
+		let Tabu { current, tabu, iteration } = tabu;
+		if *iteration == 0 {
+			*current = self.individual::<DIRESPECT>(current.order.clone(), current.alloc.clone(), sps, snowy, scenarios, params)?;
+			*chain = current.clone();
+		}
+		*iteration += 1;
+		//Sample moves, along with their undoing
+		let mut moves = Vec::with_capacity(neighbours);
+		for _ in 0..neighbours {
+			if vs < 2 {
+				break;
+			}
+			let i = rng.gen_range(0..vs);
+			let j = (i + rng.gen_range(1..vs)) % vs;
+			if rng.gen_bool(0.5) {
+				let movable: Vec<_> = current.alloc[i].iter().copied().filter(|e| self.can_clear(j, e)).collect();
+				if let Some(e) = movable.choose(rng) {
+					moves.push((Move::Relocate(*e, j), Move::Relocate(*e, i)));
+				}
+			} else {
+				moves.push((Move::Swap(i.min(j), i.max(j)), Move::Swap(i.min(j), i.max(j))));
+			}
+		}
+		//Evaluate neighbours
+		let nexts: Vec<_> = moves.par_iter().map(|(m, _)| {
+			let (mut order, mut alloc) = (current.order.clone(), current.alloc.clone());
+			match m {
+				Move::Relocate(e, j) => {
+					alloc.iter_mut().for_each(|a| { a.remove(e); });
+					alloc[*j].insert(e);
+				},
+				Move::Swap(a, b) => {
+					let (pa, pb) = (order.iter().position(|v| v == a).unwrap(), order.iter().position(|v| v == b).unwrap());
+					order.swap(pa, pb);
+				},
+			}
+			self.individual::<DIRESPECT>(order, alloc, sps, snowy, scenarios, params)
+		}).collect::<Result<_, _>>()?;
+		//Move to the best allowed neighbour, even if worse
+		let allowed = nexts.into_iter().zip(moves).filter(|(next, (m, _))| tabu.get(m).is_none_or(|last| last < iteration) || is_better(next.value, next.cost_max, chain.value, chain.cost_max));
+		match allowed.min_by_key(|(next, _)| (next.value, next.cost_max)) {
+			Some((next, (m, undo))) => {
+				if tabu.contains_key(&m) {
+					log::debug!(" tabu move aspired to");
+				}
+				log::debug!(" moved to value: {:.5} costs: {}", next.value, next.costs.iter().join("|"));
+				if is_better(next.value, next.cost_max, chain.value, chain.cost_max) {
+					log::debug!(" solution improved");
+					*chain = next.clone();
+				}
+				*current = next;
+				tabu.insert(undo, *iteration + tenure);
+			},
+			None => log::debug!(" all moves tabu"),
+		}
+		tabu.retain(|_, last| *last > *iteration);
+		Ok(())
+	}
+	/// Routes and evaluates an evaluation order and allocations (updated with the segments cleared on the way, with [`Clearing::All`])
+	fn individual<'a, const DIRESPECT: bool>(&'a self, order: Vec<usize>, mut alloc: Vec<HashSet<&'a E>>, sps: &[SID], snowy: &Snowy<'a, E>, scenarios: &[Snowy<'a, E>], params: &Parameters) -> Result<Chain<'a, E>, error::Error>
+	where
+		N::Id: std::fmt::Display,
+		E: std::fmt::Debug,
+	{
+		let (solution, costs) = self.route::<DIRESPECT>(sps, &order, &alloc, snowy, params)?;
+		let (value, cost_max) = self.evaluate(&solution, &costs, &order, &alloc, snowy, scenarios, params);
+		if params.clearing == Clearing::All {
+			self.sol_to_alloc(order.iter().cloned(), &solution, &mut alloc, |e| snowy.contains_key(e));
+		}
+		Ok(Chain { order, alloc, solution, costs, value, cost_max })
+	}
+	/// Crosses two allocations over a random line through a random segment: segments on one side go to their vehicle in `a`, the others to their vehicle in `b`
+	///
+	/// Cutting along a line keeps the spatial clusters of both parents (mostly) whole.
+	fn crossover<'a>(&'a self, a: &[HashSet<&'a E>], b: &[HashSet<&'a E>], rng: &mut impl Rng) -> Vec<HashSet<&'a E>> {
+		let middle = |e: &E| {
+			let (p1, p2) = (self.graph.graph.get_node(e.p1()).unwrap().pos(), self.graph.graph.get_node(e.p2()).unwrap().pos());
+			((p1.0 + p2.0) / 2.0, (p1.1 + p2.1) / 2.0)
+		};
+		let pivot = match a.iter().flatten().collect::<Vec<_>>().choose(rng) {
+			Some(e) => middle(e),
+			None => return a.to_vec(),
+		};
+		let angle = rng.gen_range(0.0..std::f64::consts::PI);
+		let owners: HashMap<&E, usize> = b.iter().enumerate().flat_map(|(v, alloc)| alloc.iter().map(move |e| (*e, v))).collect();
+		let mut child = vec![HashSet::default(); a.len()];
+		for (v, alloc) in a.iter().enumerate() {
+			for e in alloc {
+				let m = middle(e);
+				let side = (m.0 - pivot.0) * angle.sin() - (m.1 - pivot.1) * angle.cos() >= 0.0;
+				child[if side { v } else { owners.get(e).copied().unwrap_or(v) }].insert(*e);
+			}
+		}
+		child
+	}
+	/// One generation of the genetic solver: breed the next population, and keep the best solution found.
+	///
+	/// The first generation mutates the initial allocation more and more, and shuffles the evaluation order of all but the first individual.
+	/// Later ones keep the `elitism` best individuals, and breed the others from parents picked by binary tournaments, inheriting the order of the first.
+	///
+	/// Returns: why a vehicle can't be routed, if it can't
+	#[allow(clippy::too_many_arguments)]
+	fn breed<'a, const DIRESPECT: bool>(&'a self, chain: &mut Chain<'a, E>, population: &mut Vec<Chain<'a, E>>, size: usize, elitism: usize, sps: &[SID], snowy: &Snowy<'a, E>, scenarios: &[Snowy<'a, E>], params: &Parameters, rng: &mut impl Rng) -> Result<(), error::Error>
+	where
+		N::Id: std::fmt::Display,
+		E: std::fmt::Debug,
+	{
+		let size = size.max(1);
+		let mutate = |mut alloc: Vec<HashSet<&'a E>>, times: usize, rng: &mut _| {
+			for _ in 0..times {
+				if let Some(next) = self.realloc(&alloc, params, rng) {
+					alloc = next;
+				}
+			}
+			alloc
+		};
+		let genomes: Vec<_> = if population.is_empty() {
+			(0..size).map(|k| {
+				let mut order = chain.order.clone();
+				if k > 0 {
+					order.shuffle(rng);
+				}
+				(order, mutate(chain.alloc.clone(), k, rng))
+			}).collect()
+		} else {
+			let mut parents = std::mem::take(population);
+			parents.sort_by_key(|c| (c.value, c.cost_max));
+			population.extend(parents.iter().take(elitism.min(size)).cloned());
+			let tournament = |rng: &mut _| {
+				let (a, b) = (parents.choose(rng).unwrap(), parents.choose(rng).unwrap());
+				if is_better(b.value, b.cost_max, a.value, a.cost_max) { b } else { a }
+			};
+			(population.len()..size).map(|_| {
+				let (a, b) = (tournament(rng), tournament(rng));
+				(a.order.clone(), mutate(self.crossover(&a.alloc, &b.alloc, rng), 1, rng))
+			}).collect()
+		};
+		let children: Vec<_> = genomes.into_par_iter().map(|(order, alloc)| self.individual::<DIRESPECT>(order, alloc, sps, snowy, scenarios, params)).collect::<Result<_, _>>()?;
+		population.extend(children);
+		let best = population.iter().min_by_key(|c| (c.value, c.cost_max)).unwrap();
+		log::debug!(" generation values: {}", population.iter().map(|c| format!("{:.1}", c.value)).join("|"));
+		if is_better(best.value, best.cost_max, chain.value, chain.cost_max) {
+			log::debug!(" solution improved");
+			*chain = best.clone();
+		}
+		Ok(())
+	}
+	/// Deterministic hill-climbing over all available moves, until no move improves the solution.
+	///
+	/// Moves, tried in order each round, first improvement wins:
+	/// - moving cycles from expensive to cheap tours
+	/// - swapping neighbours in evaluation order (only with [`Clearing::All`]), each re-routing every vehicle
+	///
+	/// Stops early, with the best solution so far, once out of time.
+	///
+	/// Returns: the polished paths, for each vehicle, their objective value and costs, or why a vehicle can't be routed
+	#[allow(clippy::too_many_arguments)]
+	fn polish<'a, const DIRESPECT: bool>(&'a self, sps: &[SID], snowy: &Snowy<'a, E>, scenarios: &[Snowy<'a, E>], params: &Parameters, deadline: Option<Instant>, mut order: Vec<usize>, mut alloc: Vec<HashSet<&'a E>>, mut solution: Vec<Vec<&'a E>>, mut costs: Vec<N64>) -> Result<Solved<'a, E>, error::Error>
+	where
+		N::Id: std::fmt::Display,
+		E: std::fmt::Debug,
+	{
+		let vs = solution.len();
+		let mut value_best = self.evaluate(&solution, &costs, &order, &alloc, snowy, scenarios, params).0;
+		for round in 0.. {
+			let rounds = control::adjusted(params).polishing.max_rounds;
+			if round >= rounds || out_of_time(deadline) {
+				break;
+			}
+			log::debug!("polishing round {} current best {:.1}", round, value_best);
+			metrics::iteration(value_best.raw());
+			progress::report(|| progress::Progress {
+				phase: progress::Phase::Polishing,
+				iteration: round,
+				budget: rounds,
+				temperature: 0.0,
+				best: value_best.raw(),
+				costs: costs.iter().map(|c| c.raw()).collect(),
+			});
+			// move cycles
+			let sol_next = self.recycle(&solution, &costs, &order, sps);
+			let costs_next = self.tours_costs(&sol_next, &alloc, snowy);
+			let value_next = self.evaluate(&sol_next, &costs_next, &order, &alloc, snowy, scenarios, params).0;
+			if value_next < value_best {
+				log::debug!(" recycling improved to {:.5}", value_next);
+				let before = progress::recording_moves().then(|| alloc.clone());
+				self.sol_to_alloc(order.iter().cloned(), &sol_next, &mut alloc, |e| snowy.contains_key(e));
+				if let Some(before) = before {
+					self.report_moves((progress::Phase::Polishing, round, 0), &before, &alloc);
+				}
+				solution = sol_next;
+				costs = costs_next;
+				value_best = value_next;
+				continue;
+			}
+			// reorder, as many re-routes as vehicles a round at most
+			let mut improved = false;
+			if params.polishing.reorder && params.clearing == Clearing::All {
+				for i in 1..vs {
+					order.swap(i - 1, i);
+					let (sol_next, costs_next) = self.route::<DIRESPECT>(sps, &order, &alloc, snowy, params)?;
+					let value_next = self.evaluate(&sol_next, &costs_next, &order, &alloc, snowy, scenarios, params).0;
+					if value_next < value_best {
+						log::debug!(" swapping {} and {} improved to {:.5}", order[i], order[i - 1], value_next);
+						let before = progress::recording_moves().then(|| alloc.clone());
+						self.sol_to_alloc(order.iter().cloned(), &sol_next, &mut alloc, |e| snowy.contains_key(e));
+						if let Some(before) = before {
+							self.report_moves((progress::Phase::Polishing, round, 0), &before, &alloc);
+						}
+						solution = sol_next;
+						costs = costs_next;
+						value_best = value_next;
+						improved = true;
+						break;
+					}
+					order.swap(i - 1, i);
+				}
+			}
+			if !improved {
+				log::debug!(" no more improvements");
+				break;
+			}
+		}
+		Ok((solution, value_best, costs))
+	}
+}
+
+/// Combines the main snow status with alternative scenarios.
+///
+/// Returns: edges snowy in any of them, and all the scenarios (none if there are no alternatives)
+fn scenarios_union<'a, E: Eq + std::hash::Hash>(snowy: Snowy<'a, E>, alternatives: Vec<Snowy<'a, E>>) -> (Snowy<'a, E>, Vec<Snowy<'a, E>>) {
+	if alternatives.is_empty() {
+		return (snowy, alternatives);
+	}
+	let mut union = snowy.clone();
+	for scenario in &alternatives {
+		for (e, p) in scenario {
+			let up = union.entry(e).or_insert(*p);
+			*up = std::cmp::max(*up, *p);
+		}
+	}
+	(union, std::iter::once(snowy).chain(alternatives).collect())
+}
+
+/// Snow depth on each segment (the deepest observed), or the default depth everywhere if given
+fn snow_depths(snow: &[data::SnowStatusElement], snow_d: Option<f64>) -> impl Fn(&data::RoadSegment) -> N64 {
+	let mut depths: HashMap<data::SegmentKey, N64> = HashMap::default();
+	for s in snow {
+		let d = depths.entry(data::segment_key(&s.p1, &s.p2, &s.discriminator)).or_insert(s.depth);
+		*d = (*d).max(s.depth);
+	}
+	let snow_d = snow_d.filter(|d| *d > 0.0).map(n64);
+	move |r| snow_d.or_else(|| depths.get(&data::segment_key(&r.p1, &r.p2, &r.discriminator)).copied()).unwrap_or_else(|| n64(0.0))
+}
+
+/// Cost multiplier of each segment (multiple multipliers of a segment compound), 1 if not overlaid
+fn cost_multipliers(overlay: &[data::CostMultiplier]) -> Result<impl Fn(&data::RoadSegment) -> N64, error::Error> {
+	let mut multipliers: HashMap<data::SegmentKey, N64> = HashMap::default();
+	for m in overlay {
+		if m.multiplier <= 0.0 {
+			return Err(invalid(format!("Cost multiplier {} of segment {}<->{} is not positive", m.multiplier, m.p1, m.p2)));
+		}
+		*multipliers.entry(data::segment_key(&m.p1, &m.p2, &m.discriminator)).or_insert_with(|| n64(1.0)) *= m.multiplier;
+	}
+	Ok(move |r: &data::RoadSegment| multipliers.get(&data::segment_key(&r.p1, &r.p2, &r.discriminator)).copied().unwrap_or_else(|| n64(1.0)))
+}
+
+/// Snowy segments deeper than any vehicle can clear, which require escalation to heavier equipment (graders, blowers)
+///
+/// Arguments:
+/// - `sidewalk`: whether for sidewalk vehicles (only segments with sidewalks are then considered)
+///
+/// Returns: the segments, with their snow status
+pub fn escalations(roads: &data::RoadGraph, snow: &[data::SnowStatusElement], snow_d: Option<f64>, vehicles: &data::VehiclesConfiguration, sidewalk: bool, params: &Parameters) -> data::SnowStatuses {
+	let max_depth = vehicles.max_depths(sidewalk).into_iter().max().unwrap_or_else(N64::infinity);
+	let snow_d = snow_d.filter(|d| *d > 0.0).map(n64);
+	let statuses: HashMap<_, _> = snow.iter().map(|s| (data::segment_key(&s.p1, &s.p2, &s.discriminator), s)).collect();
+	roads.roads.iter().filter(|r| !sidewalk || r.sidewalks.0 || r.sidewalks.1).filter_map(|r| {
+		let s = match snow_d {
+			Some(depth) => data::SnowStatusElement { p1: r.p1.clone(), p2: r.p2.clone(), discriminator: r.discriminator.clone(), depth, probability: None, variance: None, observed: None, confidence: None },
+			None => (*statuses.get(&data::segment_key(&r.p1, &r.p2, &r.discriminator))?).clone(),
+		};
+		Some(s).filter(|s| s.depth > max_depth && params.depths.is_likely(params.depths.clearing_probability(r.class, s)))
+	}).collect()
+}
+
+/// Whether a solve has to wrap up: told to, or past its deadline
+fn out_of_time(deadline: Option<Instant>) -> bool {
+	control::expired() || deadline.is_some_and(|d| Instant::now() >= d)
+}
+
+/// Whether a solution is better than the best one, ties broken by the maximum tour cost
+fn is_better(value: N64, cost_max: N64, value_best: N64, cost_max_best: N64) -> bool {
+	value < value_best || (value <= value_best && cost_max < cost_max_best)
+}
+
+/// Invalid input, e.g. a cost expression failing on a segment
+fn invalid(message: String) -> error::Error {
+	error::Error::Validation { file: None, message }
+}
+
+/// Graph nodes of the ends and discriminator of a segment, which must only refer to known nodes
+///
+/// Arguments:
+/// - `nid`: graph node of an id, if known
+fn segment_ends(e: &data::RoadSegment, nid: impl Fn(&NodeId) -> Option<SID>) -> Result<(SID, SID, Option<SID>), error::Error> {
+	let nid = |id: &NodeId| nid(id).ok_or_else(|| invalid(format!("Segment {}<->{} refers to unknown nodes", e.p1, e.p2)));
+	Ok((nid(&e.p1)?, nid(&e.p2)?, e.discriminator.as_ref().map(nid).transpose()?))
+}
+
+/// Solver graph of a road graph, compiled ahead of solves (see [`cache`]) for them to take rather than build: the drones' graph, with the graph nodes of node ids and of the ends of each segment
+#[derive(Clone, Debug, serde::Serialize, serde::Deserialize)]
+pub struct Compiled {
+	/// nodes, and segments weighing their length
+	graph: Graph<SID, common::RoadNode, WeightedEdge<SID, ()>>,
+	/// graph node of each node id
+	ids: HashMap<NodeId, SID>,
+	/// graph node of the next node added
+	next: SID,
+	/// graph nodes of the ends and discriminator of each segment, in order
+	ends: Vec<(SID, SID, Option<SID>)>,
+}
+
+impl Compiled {
+	/// Compiles the solver graph of a road graph, whose segments must only refer to known nodes
+	pub fn new(roads: &data::RoadGraph) -> Result<Self, error::Error> {
+		let mut g = GraphAdapter::new(0, |_: &NodeId, id: SID| (id, id+1));
+		for n in &roads.nodes.nodes {
+			g.add_node(common::RoadNode::from(n.clone()));
+		}
+		let ends = roads.roads.iter().map(|e| segment_ends(e, |id| g.id2nid(id))).collect::<Result<Vec<_>, _>>()?;
+		for (e, (p1, p2, discriminator)) in roads.roads.iter().zip(&ends) {
+			g.add_edge(WeightedEdge { p1: *p1, p2: *p2, discriminator: *discriminator, weight: e.distance, meta: () });
+		}
+		let (graph, ids, next) = g.into_parts();
+		Ok(Self { graph, ids, next, ends })
+	}
+}
+
+/// Graph nodes of the ends and discriminator of segments, as compiled, or looked up as they come
+enum Ends {
+	Compiled(std::vec::IntoIter<(SID, SID, Option<SID>)>),
+	Lookup,
+}
+
+impl Ends {
+	/// Graph nodes of the ends and discriminator of the next segment (see [`segment_ends`])
+	fn next(&mut self, e: &data::RoadSegment, nid: impl Fn(&NodeId) -> Option<SID>) -> Result<(SID, SID, Option<SID>), error::Error> {
+		match self {
+			Self::Compiled(ends) => ends.next().ok_or_else(|| invalid(format!("Segment {}<->{} is not in the compiled solver graph", e.p1, e.p2))),
+			Self::Lookup => segment_ends(e, nid),
+		}
+	}
+}
+
+/// Adds the nodes of a road graph to a solver's graph, taking its compiled solver graph's if any
+///
+/// Returns: the turn restrictions and depots of the road graph, and the graph nodes of the ends of its segments
+fn add_nodes<E: Edge<SID>, Gen: Fn(&NodeId, SID) -> (SID, SID)>(g: &mut GraphAdapter<SID, common::RoadNode, E, SID, Gen>, roads: &mut data::Roads) -> (Vec<data::TurnRestriction>, Vec<NodeId>, Ends) {
+	let compiled = roads.take_compiled();
+	let (nodes, restrictions, depots) = roads.take_parts();
+	let ends = match compiled {
+		Some(c) => {
+			g.restore(Graph::new(c.graph.nodes().map(|(nid, n)| (nid, n.clone())).collect(), Default::default()), c.ids, c.next);
+			Ends::Compiled(c.ends.into_iter())
+		},
+		None => {
+			for n in nodes {
+				g.add_node(n.into());
+			}
+			Ends::Lookup
+		},
+	};
+	(restrictions, depots, ends)
+}
+
+/// Common specialization thingies
+mod common {
+	use super::*;
+
+	#[derive(Clone, Debug, serde::Serialize, serde::Deserialize)]
+	pub struct RoadNode {
+		pub id: NodeId,
+		pub coordinates: Coords,
+	}
+	impl IdentifiableNode for RoadNode {
+		type Id = NodeId;
+		fn id(&self) -> &Self::Id {
+			&self.id
+		}
+	}
+	impl Positioned for RoadNode {
+		fn pos(&self) -> Coords {
+			self.coordinates
+		}
+	}
+	impl From<data::Node> for RoadNode {
+		fn from(n: data::Node) -> Self {
+			Self {
+				id: n.id,
+				coordinates: n.coordinates,
+			}
+		}
+	}
+
+	/// Ends of the edge merging 2 edges meeting at a node, `a`'s other end first unless they are directed the other way
+	///
+	/// Returns: the ends, or none if the edges are not directed alike
+	pub fn merged_ends<E: Edge<SID>>(a: &E, v: SID, b: &E) -> Option<(SID, SID)> {
+		match (a.directed(), b.directed()) {
+			(false, false) => Some((a.other(v), b.other(v))),
+			(true, true) if a.p2() == v && b.p1() == v => Some((a.p1(), b.p2())),
+			(true, true) if a.p1() == v && b.p2() == v => Some((b.p1(), a.p2())),
+			_ => None,
+		}
+	}
+
+	#[macro_export]
+	macro_rules! locate {
+		($locs:expr, $g:expr, $v:expr, $metric:expr) => {
+			{
+				let nearest = spatial::Nearest::new($metric, $g.graph.graph.nodes().filter(|(n, _)| !$g.graph.graph.is_orphan(*n)).map(|(n, node)| (node.pos(), n)));
+				let sns: Vec<_> = $locs.iter().try_map_all(|l| match l {
+					data::Location::Node(n) => {
+						let nid = $g.graph.id2nid(n).ok_or_else(|| format!("Couldn't find node {}", n))?;
+						if !$g.graph.graph.is_orphan(nid) {
+							Ok(nid)
+						} else {
+							Err(format!("Explicitly specified node {} is an orphan", nid))
+						}
+					},
+					data::Location::Coordinates(lon, lat) => nearest.nearest((*lon, *lat)).copied().ok_or_else(|| format!("failed to locate ({},{}) to graph", lon, lat))
+				})?.collect();
+				log::info!("Located {}", $v);
+				log::debug!("{:?}", sns.iter().cloned().map(|n| $g.graph.nid2id(n).unwrap()).collect::<Vec<_>>());
+				sns
+			}
+		}
+	}
+
+	#[macro_export]
+	macro_rules! ends {
+		($g:expr, $vehicles:expr, $sidewalk:expr, $params:expr) => {
+			{
+				let ends = $vehicles.ends($sidewalk);
+				if ends.iter().any(Option::is_some) {
+					let located = locate!(ends.iter().flatten().collect::<Vec<_>>(), $g, "vehicle ends", $params.metric);
+					let mut located = located.into_iter();
+					$g.ends = ends.iter().map(|e| e.as_ref().and_then(|_| located.next())).collect();
+				}
+				$g.tours = $params.tours;
+				$g.exact = $params.exact;
+				$g.routing = $params.routing;
+				$g.cache = DistanceCache::new($params.path_cache);
+			}
+		}
+	}
+
+	#[macro_export]
+	macro_rules! escalate {
+		($g:expr, $sns:expr, $snowy:expr, $scenarios:expr) => {
+			{
+				let escalated = $g.escalate($sns.len(), &mut $snowy);
+				if escalated > 0 {
+					log::warn!("{} snowy segments are deeper than any vehicle can clear (or of classes or zones no vehicle services), and require escalation to heavier equipment (see `--escalations`)", escalated);
+				}
+				let unreachable = $g.unreachable($sns.len(), &mut $snowy);
+				if !unreachable.is_empty() {
+					log::warn!("{} snowy segments can't be reached by any vehicle able to clear them, and are left out (see `--unreachable`)", unreachable.len());
+				}
+				for scenario in $scenarios.iter_mut() {
+					$g.escalate($sns.len(), scenario);
+					$g.unreachable($sns.len(), scenario);
+				}
+				unreachable
+			}
+		}
+	}
+
+	#[macro_export]
+	macro_rules! fix_sccs {
+		($g:expr, $sns:expr, $v:expr) => {
+			{
+				let mut sccs = $g.graph.graph.strongly_connected_components::<false, false>();
+				log::debug!("Undirected sccs: {}", sccs.len());
+				if sccs.len() > 1 {
+					sccs.sort_unstable_by_key(|s| -(s.len() as isize));
+					log::warn!(r#"Road graph contains multiple disconnected regions:
+{:?}
+(^nodes in each region^)
+Only the regions with {} will be considered!"#, sccs.iter().map(HashSet::len).collect::<Vec<_>>(), $v);
+					let mut reachable = HashSet::default();
+					for scc in sccs {
+						if $sns.iter().any(|s| scc.contains(s)) {
+							reachable.extend(scc);
+						}
+					}
+					$g.graph.graph.retain_nodes_edges(|n| reachable.contains(&n));
+				} else {
+					log::debug!("Damn, what a clean road graph you go there!");
+				}
+			}
+		};
+		($g:expr, $sns:expr, $v:expr, $dedir:expr) => {
+			{
+				let sccs = $g.graph.graph.strongly_connected_components::<true, false>();
+				log::debug!("Directed sccs: {}", sccs.len());
+				let links = $g.graph.graph.weak_links(&sccs);
+				if !links.is_empty() {
+					log::warn!("{} directed segments trap {} in pockets of the graph, and will be de-directed (see `validate` for details)", links.len(), $v);
+					log::debug!("{:?}", links.iter().map(|e| ($g.graph.nid2id(e.p1()).unwrap(), $g.graph.nid2id(e.p2()).unwrap())).collect::<Vec<_>>());
+				}
+				$g.graph.graph.patch_sccs::<_, true>(&sccs, $dedir);
+				let mut sccs = $g.graph.graph.strongly_connected_components::<false, false>();
+				log::debug!("Undirected sccs after patch: {}", sccs.len());
+				if sccs.len() > 1 {
+					sccs.sort_unstable_by_key(|s| -(s.len() as isize));
+					log::warn!(r#"Road graph contains multiple disconnected regions:
+{:?}
+(^nodes in each region^)
+Only the regions with {} will be considered!"#, sccs.iter().map(HashSet::len).collect::<Vec<_>>(), $v);
+					let mut reachable = HashSet::default();
+					for scc in sccs {
+						if $sns.iter().any(|s| scc.contains(s)) {
+							reachable.extend(scc);
+						}
+					}
+					$g.graph.graph.retain_nodes_edges(|n| reachable.contains(&n));
+				} else {
+					log::debug!("Damn, what a clean road graph you go there!");
+				}
+			}
+		};
+	}
+}
+
+/// Specialization for solving flying surveing paths
+pub mod fly {
+	use super::*;
+	use common::*;
+	
+	/// Flight segment, weighted by its length, flown either way
+	type RoadEdge = WeightedEdge<SID, ()>;
+
+	/// Solves the pathing problem for brrr drones
+	///
+	/// Streamed road graphs have their segments added to the graph as they are parsed, and compiled ones are solved on their graph as is.
+	pub fn solve(roads: impl Into<data::Roads>, drones: data::Drones, params: &Parameters) -> Result<data::Paths, error::Error> {
+		let mut roads = roads.into();
+		let mut g: PlowSolver<RoadNode, RoadEdge, _> = plow_solver!();
+		match roads.take_compiled() {
+			Some(c) => g.graph.restore(c.graph, c.ids, c.next),
+			None => {
+				let (nodes, _, _) = roads.take_parts();
+				for n in nodes {
+					g.graph.add_node(n.into());
+				}
+				roads.for_each_segment(|e| {
+					let (p1, p2, discriminator) = segment_ends(&e, |id| g.graph.id2nid(id))?;
+					g.graph.add_edge(RoadEdge { p1, p2, discriminator, weight: e.distance, meta: () });
+					Ok(())
+				})?;
+			},
+		}
+		let sns = locate!(drones, g, "drones", params.metric);
+		g.costing = params.cost_model();
+		fix_sccs!(g, sns, "drones");
+		g.confine::<false>(&sns);
+		g.estimate();
+		g.preprocess::<false>(params);
+		log::debug!("Constructed graph with {} nodes, {} segments and {} drones", g.graph.graph.node_count(), g.graph.graph.edge_count(), sns.len());
+		let mut segments = g.graph.graph.edges().map(|e| (e, n64(1.0))).collect();
+		let unreachable = g.unreachable(sns.len(), &mut segments);
+		if !unreachable.is_empty() {
+			log::warn!("{} segments can't be reached by any drone, and are left out", unreachable.len());
+		}
+		let solution = g.solve::<false>(&sns, &segments, &[], params)?;
+		Ok(solution.into_iter().zip(sns.into_iter()).map(|(path, n)| Graph::<SID, RoadNode, RoadEdge>::path_to_nodes(path.into_iter(), n).into_iter().map(|(u, e)| data::PathSegment {
+			node: g.graph.nid2id(u).unwrap().clone(),
+			discriminator: e.and_then(|e| e.discriminator).map(|d| g.graph.nid2id(d).unwrap().clone()),
+			service: None,
+			reload: false,
+			eta: None,
+		}).collect()).collect())
+	}
+}
+
+/// Specialization for solving road plowing paths
+pub mod road {
+	use super::*;
+	use common::*;
+
+	/// Attributes of a road segment (or node task, or reload stop), on top of its cost
+	#[derive(Clone, Debug)]
+	struct Road {
+		directed: bool,
+		/// travel time when deadheading, if known
+		time: Option<N64>,
+		class: Option<data::RoadClass>,
+		priority: Option<data::RoadClass>,
+		/// width, in m
+		width: Option<N64>,
+		zone: Option<String>,
+		depth: N64,
+		/// consumable used clearing it
+		consumption: N64,
+		/// node task, looping on its node
+		task: bool,
+		/// reload stop, looping on its depot node
+		reload: bool,
+	}
+	impl Road {
+		/// Attributes of an edge looping on a node, as a node task or a reload stop
+		fn looping(task: bool, reload: bool) -> Self {
+			Self { directed: false, time: None, class: None, priority: None, width: None, zone: None, depth: n64(0.0), consumption: n64(0.0), task, reload }
+		}
+	}
+	impl EdgeMeta for Road {
+		type Key = (bool, bool);
+		fn key(&self) -> Self::Key {
+			(self.task, self.reload)
+		}
+		fn directed(&self) -> bool {
+			self.directed
+		}
+	}
+	impl Attributes for Road {
+		fn time(&self) -> Option<N64> {
+			self.time
+		}
+		fn depth(&self) -> N64 {
+			self.depth
+		}
+		fn class(&self) -> Option<data::RoadClass> {
+			self.class
+		}
+		fn priority(&self) -> Option<data::RoadClass> {
+			self.priority
+		}
+		fn width(&self) -> Option<N64> {
+			self.width
+		}
+		fn zone(&self) -> Option<&str> {
+			self.zone.as_deref()
+		}
+		fn is_task(&self) -> bool {
+			self.task
+		}
+		fn consumption(&self) -> N64 {
+			self.consumption
+		}
+		fn is_reload(&self) -> bool {
+			self.reload
+		}
+	}
+	type RoadEdge = WeightedEdge<SID, Road>;
+
+	/// Edge of a road segment, weighing its cost at its snow depth
+	///
+	/// Arguments:
+	/// - `ends`: graph nodes of the ends and discriminator of the segment
+	/// - `depth`: snow depth on the segment
+	/// - `multiplier`: cost multiplier of the segment, also applying to its travel time
+	/// - `time`: travel time of the segment when deadheading, if known
+	fn road_edge(e: &data::RoadSegment, (p1, p2, discriminator): (SID, SID, Option<SID>), depth: N64, multiplier: N64, time: Option<N64>, params: &Parameters) -> Result<RoadEdge, error::Error> {
+		Ok(RoadEdge {
+			p1,
+			p2,
+			discriminator,
+			weight: params.cost(e, depth).map_err(invalid)? * multiplier,
+			meta: Road {
+				directed: e.directed,
+				time: time.map(|t| t * multiplier),
+				class: e.class,
+				priority: e.tier(),
+				width: e.width,
+				zone: e.zone.clone(),
+				depth,
+				consumption: e.consumption.unwrap_or(params.consumption) * e.distance / 1000.0,
+				task: false,
+				reload: false,
+			},
+		})
+	}
+
+	/// Solves the snow plowing problem for roads.
+	///
+	/// Node tasks are serviced along the way, as edges looping on their node.
+	/// Travel times, where given, replace the segment costs when deadheading.
+	/// The distance matrix, if given, chooses the connecting legs.
+	///
+	/// Except it also converts all the data both ways and does other safety checks.
+	/// Streamed road graphs have their segments added to the graph as they are parsed, and compiled ones have their nodes taken from their solver graph.
+	///
+	/// Returns: the paths, and the snowy segments left out as no vehicle able to clear them can reach them
+	#[allow(clippy::too_many_arguments)]
+	pub fn solve(roads: impl Into<data::Roads>, snow: data::SnowStatuses, scenarios: Vec<data::SnowStatuses>, snow_d: Option<f64>, overlay: &[data::CostMultiplier], traffic: &[data::TravelTime], matrix: Option<&data::DistanceMatrix>, tasks: &[data::NodeTask], vehicles: data::VehiclesConfiguration, params: &Parameters) -> Result<(data::Paths, data::SnowStatuses), error::Error> {
+		let mut roads = roads.into();
+		let mut g: PlowSolver<RoadNode, RoadEdge, _> = plow_solver!();
+		let (restrictions, depots, mut ends) = add_nodes(&mut g.graph, &mut roads);
+		let depth = snow_depths(&snow, snow_d);
+		let multiplier = cost_multipliers(overlay)?;
+		let times: HashMap<_, _> = traffic.iter().map(|t| (data::segment_key(&t.p1, &t.p2, &t.discriminator), t.time)).collect();
+		let scenario_depths: Vec<_> = scenarios.iter().map(|s| snow_depths(s, snow_d)).collect();
+		// depths in each scenario, for segments to be contracted only with alike ones
+		let mut layers: HashMap<(SID, SID, Option<SID>), Vec<N64>> = HashMap::default();
+		roads.for_each_segment(|e| {
+			let time = times.get(&data::segment_key(&e.p1, &e.p2, &e.discriminator)).copied();
+			let edge = road_edge(&e, ends.next(&e, |id| g.graph.id2nid(id))?, depth(&e), multiplier(&e), time, params)?;
+			if params.simplify {
+				layers.insert((edge.p1, edge.p2, edge.discriminator), scenario_depths.iter().map(|d| d(&e)).collect());
+			}
+			g.graph.add_edge(edge);
+			Ok(())
+		})?;
+		let mut services: HashMap<&NodeId, N64> = HashMap::default();
+		for t in tasks {
+			*services.entry(&t.node).or_insert_with(|| n64(0.0)) += t.service;
+		}
+		for (node, service) in services {
+			match g.graph.id2nid(node) {
+				Some(n) => {
+					g.graph.add_edge(RoadEdge { p1: n, p2: n, discriminator: None, weight: service, meta: Road::looping(true, false) });
+				},
+				None => log::warn!("Skipping tasks at unknown node {}", node),
+			}
+		}
+		for depot in &depots {
+			match g.graph.id2nid(depot) {
+				Some(n) => {
+					g.graph.add_edge(RoadEdge { p1: n, p2: n, discriminator: None, weight: params.reload_cost, meta: Road::looping(false, true) });
+					g.depots.push(n);
+				},
+				None => log::warn!("Skipping unknown depot {}", depot),
+			}
+		}
+		g.capacities = vehicles.capacities();
+		if let Some(matrix) = matrix {
+			let nids: Vec<_> = matrix.nodes.iter().map(|n| g.graph.id2nid(n)).collect();
+			if nids.len() != matrix.values.len() || matrix.values.iter().any(|row| row.len() != nids.len()) {
+				return Err(invalid(format!("Distance matrix is not {0}x{0}", nids.len())));
+			}
+			for (u, row) in nids.iter().zip(matrix.values.iter()) {
+				for (v, d) in nids.iter().zip(row.iter()) {
+					if let (Some(u), Some(v), Some(d)) = (u, v, d) {
+						g.legs.insert((*u, *v), *d);
+					}
+				}
+			}
+			log::debug!("Loaded {} distances, {} nodes unknown", g.legs.len(), nids.iter().filter(|n| n.is_none()).count());
+		}
+		let sns = locate!(vehicles.road, g, "vehicles", params.metric);
+		g.max_depths = vehicles.max_depths(false);
+		g.profile(vehicles.profiles(false), params);
+		g.turns = params.turns;
+		for r in &restrictions {
+			match (g.graph.id2nid(&r.from), g.graph.id2nid(&r.via), g.graph.id2nid(&r.to)) {
+				(Some(from), Some(via), Some(to)) => {
+					g.restrictions.insert((from, via, to));
+				},
+				_ => log::warn!("Skipping turn restriction {} -> {} -> {} through unknown nodes", r.from, r.via, r.to),
+			}
+		}
+		fix_sccs!(g, sns, "vehicles", |e| RoadEdge { meta: Road { directed: false, ..e.meta }, ..e });
+		g.confine::<true>(&sns);
+		g.estimate();
+		ends!(g, vehicles, false, params);
+		let contracted = g.simplify(&sns, params, |a, v, b| {
+			let (p1, p2) = merged_ends(a, v, b)?;
+			let layer = layers.get(&(a.p1, a.p2, a.discriminator))?;
+			if a.is_task() || b.is_task() || a.is_reload() || b.is_reload() || (a.class(), a.priority(), a.width(), a.zone(), a.depth()) != (b.class(), b.priority(), b.width(), b.zone(), b.depth()) || Some(layer) != layers.get(&(b.p1, b.p2, b.discriminator)) {
+				return None;
+			}
+			let layer = layer.clone();
+			layers.insert((p1, p2, Some(v)), layer);
+			Some(RoadEdge {
+				p1,
+				p2,
+				discriminator: Some(v),
+				weight: a.weight + b.weight,
+				meta: Road {
+					time: a.meta.time.or(b.meta.time).map(|_| a.deadhead() + b.deadhead()),
+					consumption: a.consumption() + b.consumption(),
+					..a.meta.clone()
+				},
+			})
+		});
+		g.preprocess::<true>(params);
+		let mut originals: HashMap<(SID, SID, Option<SID>), &RoadEdge> = HashMap::default();
+		for (m, chain) in &contracted {
+			let m = g.graph.graph.get_edges(m.p1).get(m).unwrap();
+			for e in chain {
+				originals.insert((e.p1, e.p2, e.discriminator), m);
+				originals.insert((e.p2, e.p1, e.discriminator), m);
+			}
+		}
+		let snowy = |snow: data::SnowStatuses| -> Snowy<_> {
+			if let Some(snow_d) = snow_d.filter(|d| *d > 0.0) {
+				log::debug!("Default snow level {:.5} - every edge counts!", snow_d);
+				g.graph.graph.edges().filter(|e| !e.is_task() && !e.is_reload() && params.depths.is_snowy(e.class(), n64(snow_d))).map(|e| (e, n64(1.0))).collect()
+			} else {
+				snow.into_iter().filter(|s| s.depth > 0.0).filter_map(|s| {
+					let p1 = g.graph.id2nid(&s.p1)?;
+					let p2 = g.graph.id2nid(&s.p2)?;
+					let discr = match &s.discriminator {
+						Some(d) => Some(g.graph.id2nid(d)?),
+						None => None,
+					};
+					let e = g.graph.graph.get_edges_between(p1, p2).into_iter().find(|e| e.discriminator == discr && !e.is_task() && !e.is_reload()).or_else(|| originals.get(&(p1, p2, discr)).copied())?;
+					Some((e, params.depths.clearing_probability(e.class(), &s))).filter(|(_, p)| params.depths.is_likely(*p))
+				}).collect()
+			}
+		};
+		let (mut snowy, mut scenarios) = scenarios_union(snowy(snow), scenarios.into_iter().map(snowy).collect());
+		let unreachable = escalate!(g, sns, snowy, scenarios);
+		let unreachable = unreachable.into_iter().flat_map(|e| contracted.get(e).map_or_else(|| vec![e], |chain| chain.iter().collect())).map(|e| data::SnowStatusElement {
+			p1: g.graph.nid2id(e.p1).unwrap().clone(),
+			p2: g.graph.nid2id(e.p2).unwrap().clone(),
+			discriminator: e.discriminator.map(|d| g.graph.nid2id(d).unwrap().clone()),
+			depth: e.depth(),
+			probability: None,
+			variance: None,
+			observed: None,
+			confidence: None,
+		}).collect();
+		snowy.extend(g.graph.graph.edges().filter(|e| e.is_task() && (0..sns.len()).any(|v| g.can_reach(v, e))).map(|e| (e, n64(1.0))));
+		log::debug!("Constructed graph with {} nodes, {}/{} snowed segments, {} scenarios and {} vehicles", g.graph.graph.node_count(), snowy.len(), g.graph.graph.edge_count(), scenarios.len(), sns.len());
+		let solution = g.solve::<true>(&sns, &snowy, &scenarios, params)?;
+		Ok((solution.into_iter().zip(sns.into_iter()).map(|(path, n)| {
+			let mut segments: Vec<data::PathSegment> = Vec::new();
+			let path = Graph::<SID, RoadNode, RoadEdge>::expand(path.into_iter(), n, &contracted);
+			for (u, e) in Graph::<SID, RoadNode, RoadEdge>::path_to_nodes(path.into_iter(), n) {
+				match (e, segments.last_mut()) {
+					(Some(e), Some(last)) if e.is_task() => *last.service.get_or_insert_with(|| n64(0.0)) += e.weight,
+					(Some(e), Some(last)) if e.is_reload() => last.reload = true,
+					_ => segments.push(data::PathSegment {
+						node: g.graph.nid2id(u).unwrap().clone(),
+						discriminator: e.and_then(|e| e.discriminator).map(|d| g.graph.nid2id(d).unwrap().clone()),
+						service: None,
+						reload: false,
+						eta: None,
+					}),
+				}
+			}
+			segments
+		}).collect(), unreachable))
+	}
+
+	/// Values tours through road segments the way [`solve`] values its solutions, without vehicle profiles.
+	///
+	/// Segments likely to be snowy are cleared by the first tour through them, in vehicles order.
+	///
+	/// Returns: the objective value, and the cost of each tour
+	pub fn evaluate(roads: &data::RoadGraph, snow: &data::SnowStatuses, tours: &[Vec<data::SegmentKey>], params: &Parameters) -> Result<(N64, Vec<N64>), error::Error> {
+		let mut g: PlowSolver<RoadNode, RoadEdge, _> = plow_solver!();
+		for n in &roads.nodes.nodes {
+			g.graph.add_node(n.clone().into());
+		}
+		let depth = snow_depths(snow, None);
+		let mut keys = HashMap::default();
+		for e in &roads.roads {
+			let edge = road_edge(e, segment_ends(e, |id| g.graph.id2nid(id))?, depth(e), n64(1.0), None, params)?;
+			keys.insert(data::segment_key(&e.p1, &e.p2, &e.discriminator), (edge.p1, edge.p2, edge.discriminator));
+			g.graph.add_edge(edge);
+		}
+		g.costing = params.cost_model();
+		g.turns = params.turns;
+		for r in &roads.restrictions {
+			if let (Some(from), Some(via), Some(to)) = (g.graph.id2nid(&r.from), g.graph.id2nid(&r.via), g.graph.id2nid(&r.to)) {
+				g.restrictions.insert((from, via, to));
+			}
+		}
+		let edge = |k: &data::SegmentKey| keys.get(k).and_then(|(p1, p2, d)| g.graph.graph.get_edges_between(*p1, *p2).into_iter().find(|e| e.discriminator == *d));
+		let snowy: Snowy<_> = snow.iter().filter(|s| s.depth > 0.0).filter_map(|s| {
+			let e = edge(&data::segment_key(&s.p1, &s.p2, &s.discriminator))?;
+			Some((e, params.depths.clearing_probability(e.class(), s))).filter(|(_, p)| params.depths.is_likely(*p))
+		}).collect();
+		let sols = tours.iter().map(|tour| tour.iter().map(|k| edge(k).ok_or_else(|| invalid(format!("Path through unknown segment {}<->{}", k.0, k.1)))).collect::<Result<Vec<_>, _>>()).collect::<Result<Vec<_>, _>>()?;
+		let mut cleared = HashSet::default();
+		let alloc: Vec<HashSet<_>> = sols.iter().map(|sol| sol.iter().copied().filter(|e| snowy.contains_key(e) && cleared.insert(*e)).collect()).collect();
+		let order: Vec<_> = (0..sols.len()).collect();
+		let costs = g.tours_costs(&sols, &alloc, &snowy);
+		let (value, _) = g.evaluate(&sols, &costs, &order, &alloc, &snowy, &[], params);
+		Ok((value, costs))
+	}
+}
+
+/// Specialization for solving sidewalk plowing paths
+pub mod sidewalk {
+	use super::*;
+	use common::*;
+
+	#[derive(Clone, Copy, PartialEq, Eq, Hash, Debug)]
+	enum SidewalkSide {
+		Wroom,
+		WroomOneWay,
+		Left,
+		Right
+	}
+	impl From<SidewalkSide> for Option<data::SidewalkSide> {
+		fn from(side: SidewalkSide) -> Self {
+			match side {
+				SidewalkSide::Left => Some(data::SidewalkSide::Left),
+				SidewalkSide::Right => Some(data::SidewalkSide::Right),
+				_ => None,
+			}
+		}
+	}
+	impl SidewalkSide {
+		fn is_road(self) -> bool {
+			self == Self::Wroom || self == Self::WroomOneWay
+		}
+		fn is_sidewalk(self) -> bool {
+			!self.is_road()
+		}
+	}
+
+	/// Attributes of a side of a road segment, on top of its cost
+	#[derive(Clone, Debug)]
+	struct Sidewalk {
+		side: SidewalkSide,
+		class: Option<data::RoadClass>,
+		priority: Option<data::RoadClass>,
+		zone: Option<String>,
+		/// width of the roadway, in m
+		width: Option<N64>,
+		depth: N64,
+		/// indices of the stops on the sidewalk
+		stops: Vec<usize>,
+		/// service time of the stops
+		service: N64,
+	}
+	impl EdgeMeta for Sidewalk {
+		type Key = SidewalkSide;
+		fn key(&self) -> Self::Key {
+			self.side
+		}
+		fn directed(&self) -> bool {
+			self.side == SidewalkSide::WroomOneWay
+		}
+	}
+	impl Attributes for Sidewalk {
+		fn depth(&self) -> N64 {
+			self.depth
+		}
+		fn class(&self) -> Option<data::RoadClass> {
+			self.class
+		}
+		fn priority(&self) -> Option<data::RoadClass> {
+			self.priority
+		}
+		fn zone(&self) -> Option<&str> {
+			self.zone.as_deref()
+		}
+		fn width(&self) -> Option<N64> {
+			self.width
+		}
+		fn service(&self) -> N64 {
+			self.service
+		}
+		fn is_roadway(&self) -> bool {
+			self.side.is_road()
+		}
+	}
+	type RoadEdge = WeightedEdge<SID, Sidewalk>;
+
+	/// Maximum distance of a stop to its sidewalk, in meters
+	const STOP_TOLERANCE: f64 = 50.0;
+
+	/// Snaps stops to the sidewalk of the closest segment having sidewalks, on their side of it (or the other if there's none)
+	///
+	/// Returns: indices of the stops on each sidewalk
+	fn snap_stops(roads: &data::RoadGraph, stops: &[data::Stop]) -> HashMap<(NodeId, NodeId, Option<NodeId>, SidewalkSide), Vec<usize>> {
+		use graph::mapmatch::{project, point_segment};
+		let coords: HashMap<_, _> = roads.nodes.nodes.iter().map(|n| (&n.id, n.coordinates)).collect();
+		let mut snapped: HashMap<_, Vec<usize>> = HashMap::default();
+		for (i, stop) in stops.iter().enumerate() {
+			let lat0 = stop.coordinates.1;
+			let p = project(stop.coordinates, lat0);
+			let closest = roads.roads.iter().filter(|r| r.sidewalks.0 || r.sidewalks.1).filter_map(|r| {
+				let a = project(*coords.get(&r.p1)?, lat0);
+				let b = project(*coords.get(&r.p2)?, lat0);
+				Some((n64(point_segment(p, a, b).0), r, a, b))
+			}).min_by_key(|(d, ..)| *d);
+			match closest {
+				Some((d, r, a, b)) if d <= STOP_TOLERANCE => {
+					let left = (b.0 - a.0) * (p.1 - a.1) - (b.1 - a.1) * (p.0 - a.0) > 0.0;
+					let side = if (left && r.sidewalks.0) || !r.sidewalks.1 { SidewalkSide::Left } else { SidewalkSide::Right };
+					snapped.entry((r.p1.clone(), r.p2.clone(), r.discriminator.clone(), side)).or_default().push(i);
+				},
+				_ => log::warn!("Skipping stop {} at {:?}, farther than {}m from any sidewalk", i, stop.coordinates, STOP_TOLERANCE),
+			}
+		}
+		snapped
+	}
+
+	/// Solves the snow plowing problem for roads.
+	///
+	/// Stops are serviced when clearing their sidewalk, which is then required even if it isn't snowy.
+	///
+	/// Except it also converts all the data both ways and does other safety checks.
+	/// Streamed road graphs have their segments added to the graph as they are parsed, unless there are stops to snap to them, and compiled ones have their nodes taken from their solver graph.
+	///
+	/// Returns: the paths, and the snowy segments left out as no vehicle able to clear them can reach them
+	#[allow(clippy::too_many_arguments)]
+	pub fn solve(roads: impl Into<data::Roads>, snow: data::SnowStatuses, scenarios: Vec<data::SnowStatuses>, snow_d: Option<f64>, overlay: &[data::CostMultiplier], stops: &[data::Stop], vehicles: data::VehiclesConfiguration, params: &Parameters) -> Result<(data::SidewalkPaths, data::SnowStatuses), error::Error> {
+		let mut roads = roads.into();
+		let snapped = if stops.is_empty() { HashMap::default() } else { snap_stops(roads.graph()?, stops) };
+		let stops_service: Vec<_> = stops.iter().map(|s| s.service).collect();
+		let mut g: PlowSolver<RoadNode, RoadEdge, _> = plow_solver!();
+		let (_, _, mut ends) = add_nodes(&mut g.graph, &mut roads);
+		let depth = snow_depths(&snow, snow_d);
+		let multiplier = cost_multipliers(overlay)?;
+		roads.for_each_segment(|e| {
+			let (p1, p2, discriminator) = ends.next(&e, |id| g.graph.id2nid(id))?;
+			let length = params.cost(&e, depth(&e)).map_err(invalid)? * multiplier(&e);
+			macro_rules! edge {
+				($side:expr) => {
+					{
+						let stops = snapped.get(&(e.p1.clone(), e.p2.clone(), e.discriminator.clone(), $side)).cloned().unwrap_or_default();
+						RoadEdge {
+							p1,
+							p2,
+							discriminator,
+							weight: length,
+							meta: Sidewalk {
+								side: $side,
+								class: e.class,
+								priority: e.tier(),
+								zone: e.zone.clone(),
+								width: e.width.filter(|_| $side.is_road()),
+								depth: depth(&e),
+								service: stops.iter().map(|i| stops_service[*i]).sum(),
+								stops,
+							},
+						}
+					}
+				}
+			}
+			g.graph.add_edge(edge!(if e.directed { SidewalkSide::WroomOneWay } else { SidewalkSide::Wroom }));
+			if e.sidewalks.0 {
+				g.graph.add_edge(edge!(SidewalkSide::Left));
+			}
+			if e.sidewalks.1 {
+				g.graph.add_edge(edge!(SidewalkSide::Right));
+			}
+			Ok(())
+		})?;
+		let sns = locate!(vehicles.sidewalk, g, "vehicles", params.metric);
+		g.max_depths = vehicles.max_depths(true);
+		g.profile(vehicles.profiles(true), params);
+		fix_sccs!(g, sns, "vehicles", |e| RoadEdge { meta: Sidewalk { side: SidewalkSide::Wroom, ..e.meta }, ..e });
+		g.confine::<true>(&sns);
+		g.estimate();
+		ends!(g, vehicles, true, params);
+		// only chains of roads without sidewalks have degree-2 nodes, and are only snowy if vehicles clear roadways
+		let contracted = g.simplify(&sns, params, |a, v, b| {
+			let (p1, p2) = merged_ends(a, v, b)?;
+			if a.meta.side != b.meta.side || a.meta.side.is_sidewalk() || (a.class(), a.priority(), a.zone(), a.width(), a.depth()) != (b.class(), b.priority(), b.zone(), b.width(), b.depth()) {
+				return None;
+			}
+			Some(RoadEdge { p1, p2, discriminator: Some(v), weight: a.weight + b.weight, meta: a.meta.clone() })
+		});
+		g.preprocess::<true>(params);
+		// sidewalks need clearing, and so do the roadways vehicles can clear
+		let cleared = |e: &RoadEdge| e.meta.side.is_sidewalk() || (0..sns.len()).any(|v| g.can_handle(v, e));
+		let snowy = |snow: data::SnowStatuses| -> Snowy<_> {
+			if let Some(snow_d) = snow_d.filter(|d| *d > 0.0) {
+				log::debug!("Default snow level {:.5} - every sidewalk counts!", snow_d);
+				g.graph.graph.edges().filter(|e| cleared(e) && params.depths.is_snowy(e.class(), n64(snow_d))).map(|e| (e, n64(1.0))).collect()
+			} else {
+				snow.into_iter().filter(|s| s.depth > 0.0).filter_map(|s| {
+					let p1 = g.graph.id2nid(&s.p1)?;
+					let p2 = g.graph.id2nid(&s.p2)?;
+					let discr = match &s.discriminator {
+						Some(d) => Some(g.graph.id2nid(d)?),
+						None => None,
+					};
+					Some(g.graph.graph.get_edges_between(p1, p2).into_iter().filter(|e| e.discriminator == discr && cleared(e)).map(|e| (e, params.depths.clearing_probability(e.class(), &s))).filter(|(_, p)| params.depths.is_likely(*p)).collect::<Vec<_>>())
+				}).flatten().collect()
+			}
+		};
+		let (mut snowy, mut scenarios) = scenarios_union(snowy(snow), scenarios.into_iter().map(snowy).collect());
+		let unreachable = escalate!(g, sns, snowy, scenarios);
+		let unreachable = unreachable.into_iter().map(|e| (e.p1, e.p2, e.discriminator, e.depth())).unique_by(|s| (s.0, s.1, s.2)).map(|(p1, p2, discriminator, depth)| data::SnowStatusElement {
+			p1: g.graph.nid2id(p1).unwrap().clone(),
+			p2: g.graph.nid2id(p2).unwrap().clone(),
+			discriminator: discriminator.map(|d| g.graph.nid2id(d).unwrap().clone()),
+			depth,
+			probability: None,
+			variance: None,
+			observed: None,
+			confidence: None,
+		}).collect();
+		snowy.extend(g.graph.graph.edges().filter(|e| !e.meta.stops.is_empty() && (0..sns.len()).any(|v| g.can_reach(v, e))).map(|e| (e, n64(1.0))));
+		let fragile = g.fragile(&sns, &snowy, |e| e.discriminator);
+		if !fragile.is_empty() {
+			log::warn!("{} segments would each, if closed, cut snowy sidewalks off from every vehicle: {}", fragile.len(), fragile.iter().take(20).map(|(p1, p2)| format!("{}<->{}", g.graph.nid2id(*p1).unwrap(), g.graph.nid2id(*p2).unwrap())).join(", "));
+		}
+		log::debug!("Constructed graph with {} nodes, {}/{} snowed segments, {} scenarios and {} vehicles", g.graph.graph.node_count(), snowy.len(), g.graph.graph.edge_count(), scenarios.len(), sns.len());
+		let solution = g.solve::<true>(&sns, &snowy, &scenarios, params)?;
+		let mut serviced = HashSet::default();
+		Ok((solution.into_iter().zip(sns.into_iter()).map(|(path, n)| Graph::<SID, RoadNode, RoadEdge>::path_to_nodes(Graph::<SID, RoadNode, RoadEdge>::expand(path.into_iter(), n, &contracted).into_iter(), n).into_iter().map(|(u, e)| data::SidewalkPathSegment {
+			node: g.graph.nid2id(u).unwrap().clone(),
+			discriminator: e.and_then(|e| e.discriminator).map(|d| g.graph.nid2id(d).unwrap().clone()),
+			side: e.and_then(|e| e.meta.side.into()),
+			stops: e.filter(|e| !e.meta.stops.is_empty() && serviced.insert(*e)).map(|e| e.meta.stops.clone()).unwrap_or_default(),
+		}).collect()).collect(), unreachable))
+	}
+}
@@ -0,0 +1,127 @@
+//! Progress of running solves, reported to a hook at every annealing iteration and polishing round
+//!
+//! So that front-ends can show it (as the CLI does with `--progress`), instead of it being dug out of debug logs.
+//! Candidate solutions of annealing iterations can be reported to another hook, for the acceptance behavior to be plotted (as the CLI does with `--trace`).
+//! Allocation moves of segments between vehicles can be reported to a third one, for who serves a segment to be explained (as the CLI does with `--moves`).
+
+use serde::*;
+use std::sync::RwLock;
+
+#[derive(Serialize, Deserialize, Clone, Copy, PartialEq, Eq, Debug)]
+#[serde(rename_all = "lowercase")]
+pub enum Phase {
+	Annealing,
+	Polishing,
+	/// the solve is done, with its final solution
+	Done,
+}
+
+/// Progress of a solve, as it starts an iteration (or polishing round)
+#[derive(Serialize, Deserialize, Clone, PartialEq, Debug)]
+pub struct Progress {
+	pub phase: Phase,
+	/// iteration (or round) being started, from 0
+	pub iteration: u64,
+	/// iterations (or rounds) budget of the phase
+	pub budget: u64,
+	/// annealing temperature
+	pub temperature: f64,
+	/// objective value of the best solution so far
+	pub best: f64,
+	/// tour costs of the vehicles in the best solution so far
+	pub costs: Vec<f64>,
+}
+
+/// Step of an annealing iteration a candidate solution comes from
+#[derive(Serialize, Deserialize, Clone, Copy, PartialEq, Eq, Debug)]
+#[serde(rename_all = "lowercase")]
+pub enum Step {
+	/// routing the reallocated, reordered tours
+	Route,
+	/// recycling the edges of expensive tours into cheap ones
+	Recycle,
+}
+
+/// Candidate solution of an annealing iteration, as evaluated
+#[derive(Serialize, Deserialize, Clone, PartialEq, Debug)]
+pub struct Candidate {
+	pub iteration: u64,
+	/// chain of the population the candidate is of
+	pub chain: usize,
+	pub step: Step,
+	/// annealing temperature
+	pub temperature: f64,
+	/// objective value of the candidate
+	pub value: f64,
+	/// whether the chain moved to the candidate
+	pub accepted: bool,
+	/// tour costs of the vehicles in the candidate
+	pub costs: Vec<f64>,
+}
+
+/// Move of a segment's allocation between vehicles, as a chain (or polishing) moved to a solution allocating it otherwise
+#[derive(Serialize, Deserialize, Clone, PartialEq, Debug)]
+pub struct Move {
+	pub phase: Phase,
+	/// iteration (or polishing round) of the move - the initial allocation being that of annealing iteration 0
+	pub iteration: u64,
+	/// chain of the population that moved
+	pub chain: usize,
+	pub p1: String,
+	pub p2: String,
+	#[serde(default, skip_serializing_if = "Option::is_none")]
+	pub discriminator: Option<String>,
+	/// vehicle the segment was allocated to, if any
+	pub from: Option<usize>,
+	/// vehicle the segment is allocated to, if any
+	pub to: Option<usize>,
+}
+
+type Hook = Box<dyn Fn(&Progress) + Send + Sync>;
+type CandidateHook = Box<dyn Fn(&Candidate) + Send + Sync>;
+type MoveHook = Box<dyn Fn(&Move) + Send + Sync>;
+
+static HOOK: RwLock<Option<Hook>> = RwLock::new(None);
+static CANDIDATE_HOOK: RwLock<Option<CandidateHook>> = RwLock::new(None);
+static MOVE_HOOK: RwLock<Option<MoveHook>> = RwLock::new(None);
+
+/// Reports the progress of solves to a hook, replacing the previous one
+pub fn on_progress(hook: impl Fn(&Progress) + Send + Sync + 'static) {
+	*HOOK.write().unwrap() = Some(Box::new(hook));
+}
+
+/// Reports progress to the hook, if any - only putting it together then
+pub fn report(progress: impl FnOnce() -> Progress) {
+	if let Some(hook) = HOOK.read().unwrap().as_ref() {
+		hook(&progress());
+	}
+}
+
+/// Reports the candidate solutions of annealing iterations to a hook, replacing the previous one
+pub fn on_candidate(hook: impl Fn(&Candidate) + Send + Sync + 'static) {
+	*CANDIDATE_HOOK.write().unwrap() = Some(Box::new(hook));
+}
+
+/// Reports a candidate solution to the hook, if any - only putting it together then
+pub fn candidate(candidate: impl FnOnce() -> Candidate) {
+	if let Some(hook) = CANDIDATE_HOOK.read().unwrap().as_ref() {
+		hook(&candidate());
+	}
+}
+
+/// Reports the allocation moves of solves to a hook, replacing the previous one
+pub fn on_move(hook: impl Fn(&Move) + Send + Sync + 'static) {
+	*MOVE_HOOK.write().unwrap() = Some(Box::new(hook));
+}
+
+/// Whether allocation moves are reported, for solves to only track them then
+pub fn recording_moves() -> bool {
+	MOVE_HOOK.read().unwrap().is_some()
+}
+
+/// Reports an allocation move to the hook, if any
+pub fn moved(m: &Move) {
+	if let Some(hook) = MOVE_HOOK.read().unwrap().as_ref() {
+		hook(m);
+	}
+}
@@ -0,0 +1,482 @@
+//! Analysis reports on produced paths
+
+use crate::*;
+use data::*;
+
+use std::collections::HashSet;
+use indexmap::{IndexMap, IndexSet};
+use serde::*;
+
+/// Segments along a path, in order
+pub fn path_segments<'p>(path: &'p [PathSegment]) -> impl Iterator<Item = SegmentKey> + 'p {
+	path.windows(2).map(|w| segment_key(&w[0].node, &w[1].node, &w[1].discriminator))
+}
+
+/// Segments along each path of a plan, in order, driven on the road
+pub fn road_tours(plan: &Paths) -> Vec<Vec<(SegmentKey, Option<SidewalkSide>)>> {
+	plan.iter().map(|path| path_segments(path).map(|k| (k, None)).collect()).collect()
+}
+
+/// Segments along each sidewalk path of a plan, in order, with the side they're driven on (none when crossing)
+pub fn sidewalk_tours(plan: &SidewalkPaths) -> Vec<Vec<(SegmentKey, Option<SidewalkSide>)>> {
+	plan.iter().map(|path| path.windows(2).map(|w| (segment_key(&w[0].node, &w[1].node, &w[1].discriminator), w[1].side)).collect()).collect()
+}
+
+/// Plan-vs-actual comparison of a single vehicle
+#[derive(Serialize, Deserialize, Clone, PartialEq, Debug)]
+pub struct VehicleAdherence {
+	/// fraction of planned segments actually traversed
+	pub adherence: f64,
+	pub planned_distance: f64,
+	pub actual_distance: f64,
+	/// distance driven on top of the planned distance
+	pub extra_distance: f64,
+	/// planned segments that were not traversed
+	pub skipped: Vec<SegmentKey>,
+	/// traversed segments that were not planned
+	pub unplanned: Vec<SegmentKey>,
+	/// time between the first and the last traversal, in seconds
+	pub duration: f64,
+}
+
+/// Plan-vs-actual comparison of all vehicles
+#[derive(Serialize, Deserialize, Clone, PartialEq, Debug)]
+pub struct Adherence {
+	/// fraction of planned segments actually traversed, overall
+	pub adherence: f64,
+	pub planned_distance: f64,
+	pub actual_distance: f64,
+	pub extra_distance: f64,
+	/// number of planned segments that were not traversed, overall
+	pub skipped: usize,
+	pub vehicles: Vec<VehicleAdherence>,
+}
+
+/// Compares planned paths to what was actually driven
+pub fn adherence(roads: &RoadGraph, plan: &Paths, log: &ExecutionLog) -> Adherence {
+	let lengths: IndexMap<_, _> = roads.roads.iter().map(|r| (segment_key(&r.p1, &r.p2, &r.discriminator), r.distance.raw())).collect();
+	let length = |k: &SegmentKey| lengths.get(k).copied().unwrap_or(0.0);
+	let empty = Vec::new();
+	let vehicles: Vec<_> = plan.iter().enumerate().map(|(i, path)| {
+		let traversals = log.get(i).unwrap_or(&empty);
+		let planned: Vec<_> = path_segments(path).collect();
+		let actual: Vec<_> = traversals.iter().map(|t| segment_key(&t.p1, &t.p2, &t.discriminator)).collect();
+		let planned_set: IndexSet<_> = planned.iter().cloned().collect();
+		let actual_set: HashSet<_> = actual.iter().cloned().collect();
+		let planned_distance: f64 = planned.iter().map(length).sum();
+		let actual_distance: f64 = actual.iter().map(length).sum();
+		let (first, last) = traversals.iter().fold((f64::INFINITY, f64::NEG_INFINITY), |(f, l), t| (f.min(t.timestamp), l.max(t.timestamp)));
+		VehicleAdherence {
+			adherence: if planned_set.is_empty() { 1.0 } else { planned_set.iter().filter(|k| actual_set.contains(*k)).count() as f64 / planned_set.len() as f64 },
+			planned_distance,
+			actual_distance,
+			extra_distance: actual_distance - planned_distance,
+			skipped: planned_set.iter().filter(|k| !actual_set.contains(*k)).cloned().collect(),
+			unplanned: actual.iter().filter(|k| !planned_set.contains(*k)).cloned().collect::<IndexSet<_>>().into_iter().collect(),
+			duration: if last >= first { last - first } else { 0.0 },
+		}
+	}).collect();
+	let planned_count: usize = plan.iter().map(|path| path_segments(path).collect::<HashSet<_>>().len()).sum();
+	let skipped = vehicles.iter().map(|v| v.skipped.len()).sum();
+	let planned_distance = vehicles.iter().map(|v| v.planned_distance).sum();
+	let actual_distance = vehicles.iter().map(|v| v.actual_distance).sum();
+	Adherence {
+		adherence: if planned_count == 0 { 1.0 } else { 1.0 - skipped as f64 / planned_count as f64 },
+		planned_distance,
+		actual_distance,
+		extra_distance: actual_distance - planned_distance,
+		skipped,
+		vehicles,
+	}
+}
+
+/// A traversal of a segment by a vehicle
+#[derive(Serialize, Deserialize, Clone, PartialEq, Debug)]
+pub struct Service {
+	pub vehicle: usize,
+	/// index, in the vehicle's path, of the node the segment is traversed to
+	pub step: usize,
+	/// distance driven from the vehicle's start up until the end of the segment
+	pub distance: f64,
+}
+
+/// How a vehicle relates to a segment
+#[derive(Serialize, Deserialize, Clone, PartialEq, Debug)]
+pub struct Candidate {
+	pub vehicle: usize,
+	pub start: NodeId,
+	/// shortest distance from the vehicle's start to the segment, if it can reach it at all
+	pub distance: Option<f64>,
+	/// cheapest cost from the vehicle's start to the segment, as the solver costs segments without snow, if it can reach it at all
+	#[serde(default, skip_serializing_if = "Option::is_none")]
+	pub cost: Option<f64>,
+	/// how much more that costs than it does the vehicle serving the segment, if any
+	#[serde(default, skip_serializing_if = "Option::is_none")]
+	pub delta: Option<f64>,
+	/// total distance of the vehicle's tour
+	pub tour: f64,
+	/// number of distinct segments the vehicle traverses
+	pub served: usize,
+}
+
+/// Explanation of who serves a segment
+#[derive(Serialize, Deserialize, Clone, PartialEq, Debug)]
+pub struct Explanation {
+	pub segment: SegmentKey,
+	/// first traversal of the segment by each vehicle traversing it, soonest (by distance into the tour) first - that one is what clears it
+	pub services: Vec<Service>,
+	/// all vehicles, closest to the segment first
+	pub candidates: Vec<Candidate>,
+	/// allocation moves of the segment while solving, in order, if recorded
+	#[serde(default, skip_serializing_if = "Vec::is_empty")]
+	pub moves: Vec<progress::Move>,
+}
+
+/// Explains which vehicle serves a segment, and how the other vehicles compare
+///
+/// Alternatives are characterized by what the allocation balances - how far each vehicle is from the segment (and what it costs to get there, as the meta parameters cost segments - by their length if not given), and how loaded its tour already is - and by how the solver moved the segment between vehicles, given the allocation moves it recorded (see [`progress::on_move`]).
+pub fn explain(roads: &IndexedRoadGraph, plan: &Paths, segment: SegmentKey, params: Option<&meta::Parameters>, history: &[progress::Move]) -> Result<Explanation, error::Error> {
+	let (g, indexed, roads) = (&roads.light, roads, &roads.roads);
+	let costs: Vec<N64> = match params {
+		Some(params) => roads.roads.iter().map(|r| params.cost(r, n64(0.0))).collect::<Result<_, _>>().map_err(|e| error::Error::Validation { file: None, message: e })?,
+		None => roads.roads.iter().map(|r| r.distance).collect(),
+	};
+	let lengths: IndexMap<_, _> = roads.roads.iter().map(|r| (segment_key(&r.p1, &r.p2, &r.discriminator), r.distance.raw())).collect();
+	let length = |k: &SegmentKey| lengths.get(k).copied().unwrap_or(0.0);
+	let mut services = Vec::new();
+	for (vehicle, path) in plan.iter().enumerate() {
+		let mut distance = 0.0;
+		let mut found = false;
+		for (i, k) in path_segments(path).enumerate() {
+			distance += length(&k);
+			if k == segment && !found {
+				found = true;
+				services.push(Service { vehicle, step: i + 1, distance });
+			}
+		}
+	}
+	services.sort_by(|a, b| a.distance.total_cmp(&b.distance));
+	let ends: graph::HashSet<_> = indexed.node_index(&segment.0).into_iter().chain(indexed.node_index(&segment.1)).collect();
+	let mut candidates: Vec<_> = plan.iter().enumerate().filter_map(|(vehicle, path)| {
+		let start = path.first()?.node.clone();
+		let from: graph::HashSet<_> = indexed.node_index(&start).into_iter().collect();
+		Some(Candidate {
+			vehicle,
+			distance: g.pathfind_regions::<N64, _, true>(&from, &ends, |e| Some(roads.roads[e.index].distance)).map(|(.., p)| p.into_iter().map(|e| roads.roads[e.index].distance.raw()).sum()),
+			cost: g.pathfind_regions::<N64, _, true>(&from, &ends, |e| Some(costs[e.index])).map(|(.., p)| p.into_iter().map(|e| costs[e.index].raw()).sum()),
+			delta: None,
+			start,
+			tour: path_segments(path).map(|k| length(&k)).sum(),
+			served: path_segments(path).collect::<HashSet<_>>().len(),
+		})
+	}).collect();
+	let served = services.first().and_then(|s| candidates.iter().find(|c| c.vehicle == s.vehicle)).and_then(|c| c.cost);
+	for c in &mut candidates {
+		c.delta = c.cost.zip(served).map(|(c, s)| c - s);
+	}
+	candidates.sort_by(|a, b| a.distance.unwrap_or(f64::INFINITY).total_cmp(&b.distance.unwrap_or(f64::INFINITY)));
+	let moves = history.iter().filter(|m| segment_key(&m.p1.clone().into(), &m.p2.clone().into(), &m.discriminator.clone().map(Into::into)) == segment).cloned().collect();
+	Ok(Explanation {
+		segment,
+		services,
+		candidates,
+		moves,
+	})
+}
+
+/// Summary of a plan, for comparisons
+#[derive(Serialize, Deserialize, Clone, PartialEq, Debug)]
+pub struct PlanSummary {
+	/// objective value, as the solver values it with the meta parameters of the comparison
+	pub objective: f64,
+	pub total_distance: f64,
+	/// distance of the longest tour
+	pub makespan: f64,
+	/// number of snowy segments cleared
+	pub cleared: usize,
+	/// number of snowy segments cleared by no vehicle
+	pub missed: usize,
+	/// tour distance of each vehicle
+	pub vehicles: Vec<f64>,
+	/// tour cost of each vehicle, as the objective value weighs them
+	#[serde(default)]
+	pub costs: Vec<f64>,
+}
+
+/// Tour distances of a vehicle in two plans
+#[derive(Serialize, Deserialize, Clone, PartialEq, Debug)]
+pub struct VehicleDelta {
+	pub vehicle: usize,
+	/// tour distance in plan A, if the vehicle is part of its fleet
+	pub a: Option<f64>,
+	/// tour distance in plan B, if the vehicle is part of its fleet
+	pub b: Option<f64>,
+	/// B - A, taking missing tours as empty
+	pub delta: f64,
+	/// B - A of the tour costs, taking missing tours as empty
+	#[serde(default)]
+	pub cost_delta: f64,
+}
+
+/// A/B comparison of two plans
+#[derive(Serialize, Deserialize, Clone, PartialEq, Debug)]
+pub struct Comparison {
+	pub a: PlanSummary,
+	pub b: PlanSummary,
+	/// B - A
+	pub objective: f64,
+	/// B - A
+	pub total_distance: f64,
+	/// B - A
+	pub makespan: f64,
+	pub vehicles: Vec<VehicleDelta>,
+	/// number of segments traversed only in plan A
+	pub only_a: usize,
+	/// number of segments traversed only in plan B
+	pub only_b: usize,
+	/// segments traversed only in plan B
+	pub covered: Vec<SegmentKey>,
+	/// segments traversed only in plan A
+	pub dropped: Vec<SegmentKey>,
+}
+
+/// Summarizes a plan (of [`road_tours`] or [`sidewalk_tours`]), valued by the road solver ([`plow::road::evaluate`]): snowy segments are cleared when first driven (in vehicles order)
+pub fn summarize(roads: &RoadGraph, snow: &SnowStatuses, plan: &[Vec<(SegmentKey, Option<SidewalkSide>)>], params: &meta::Parameters) -> Result<PlanSummary, error::Error> {
+	let segments: IndexMap<_, _> = roads.roads.iter().map(|r| (segment_key(&r.p1, &r.p2, &r.discriminator), r)).collect();
+	let snowy: HashSet<_> = snow.iter().filter(|s| s.depth > 0.0).filter_map(|s| {
+		let k = segment_key(&s.p1, &s.p2, &s.discriminator);
+		let r = segments.get(&k)?;
+		Some(k).filter(|_| params.depths.is_likely(params.depths.clearing_probability(r.class, s)))
+	}).collect();
+	let tours: Vec<Vec<_>> = plan.iter().map(|path| path.iter().map(|(k, _)| k.clone()).collect()).collect();
+	let (objective, costs) = plow::road::evaluate(roads, snow, &tours, params)?;
+	let cleared: HashSet<_> = tours.iter().flatten().filter(|k| snowy.contains(*k)).collect();
+	let vehicles: Vec<f64> = tours.iter().map(|tour| tour.iter().filter_map(|k| segments.get(k)).map(|r| r.distance.raw()).sum()).collect();
+	Ok(PlanSummary {
+		objective: objective.raw(),
+		total_distance: vehicles.iter().sum(),
+		makespan: vehicles.iter().copied().fold(0.0, f64::max),
+		cleared: cleared.len(),
+		missed: snowy.len() - cleared.len(),
+		vehicles,
+		costs: costs.iter().map(|c| c.raw()).collect(),
+	})
+}
+
+/// Segments traversed by a plan, on either side for sidewalks
+pub fn coverage(plan: &[Vec<(SegmentKey, Option<SidewalkSide>)>]) -> IndexSet<SegmentKey> {
+	plan.iter().flatten().map(|(k, _)| k.clone()).collect()
+}
+
+/// Compares two plans (of [`road_tours`] or [`sidewalk_tours`]) for the same snow status
+pub fn compare(roads: &RoadGraph, snow: &SnowStatuses, a: &[Vec<(SegmentKey, Option<SidewalkSide>)>], b: &[Vec<(SegmentKey, Option<SidewalkSide>)>], params: &meta::Parameters) -> Result<Comparison, error::Error> {
+	let (sa, sb) = (summarize(roads, snow, a, params)?, summarize(roads, snow, b, params)?);
+	let (ca, cb) = (coverage(a), coverage(b));
+	Ok(Comparison {
+		objective: sb.objective - sa.objective,
+		total_distance: sb.total_distance - sa.total_distance,
+		makespan: sb.makespan - sa.makespan,
+		vehicles: (0..sa.vehicles.len().max(sb.vehicles.len())).map(|vehicle| {
+			let (a, b) = (sa.vehicles.get(vehicle).copied(), sb.vehicles.get(vehicle).copied());
+			VehicleDelta {
+				vehicle,
+				a,
+				b,
+				delta: b.unwrap_or(0.0) - a.unwrap_or(0.0),
+				cost_delta: sb.costs.get(vehicle).copied().unwrap_or(0.0) - sa.costs.get(vehicle).copied().unwrap_or(0.0),
+			}
+		}).collect(),
+		only_a: ca.difference(&cb).count(),
+		only_b: cb.difference(&ca).count(),
+		covered: cb.difference(&ca).cloned().collect(),
+		dropped: ca.difference(&cb).cloned().collect(),
+		a: sa,
+		b: sb,
+	})
+}
+
+/// Statistics of a road graph
+#[derive(Serialize, Deserialize, Clone, PartialEq, Debug)]
+pub struct GraphStats {
+	pub nodes: usize,
+	pub segments: usize,
+	/// number of one-way segments
+	pub directed: usize,
+	/// total length of the segments
+	pub length: f64,
+	/// number of nodes by degree (number of segments ending at them, regardless of direction)
+	pub degrees: std::collections::BTreeMap<usize, usize>,
+	/// number of strongly connected components, respecting one-ways (orphan nodes aside)
+	pub sccs: usize,
+	/// number of bridges - segments whose closure disconnects the network, regardless of one-ways
+	#[serde(default)]
+	pub bridges: usize,
+	/// number of articulation points - nodes whose closure disconnects the network, regardless of one-ways
+	#[serde(default)]
+	pub articulation_points: usize,
+}
+
+/// Statistics of a plan
+#[derive(Serialize, Deserialize, Clone, PartialEq, Debug)]
+pub struct PlanStats {
+	/// tour distance of each vehicle
+	pub vehicles: Vec<f64>,
+	pub total_distance: f64,
+	/// fraction of the total distance that clears nothing - driven over segments that aren't snowy, or already cleared (if the snow status is known)
+	#[serde(default, skip_serializing_if = "Option::is_none")]
+	pub deadheading: Option<f64>,
+	/// fraction of the snowy segments the plan traverses (if the snow status is known)
+	#[serde(default, skip_serializing_if = "Option::is_none")]
+	pub coverage: Option<f64>,
+}
+
+/// Statistics of a road graph, and of a plan on it
+#[derive(Serialize, Deserialize, Clone, PartialEq, Debug)]
+pub struct Stats {
+	pub graph: GraphStats,
+	#[serde(default, skip_serializing_if = "Option::is_none")]
+	pub plan: Option<PlanStats>,
+}
+
+/// Computes statistics of a road graph, and of a plan on it if any - snowy segments being those with some snow in the snow status, if any
+pub fn stats(roads: &IndexedRoadGraph, plan: Option<&Paths>, snow: Option<&SnowStatuses>) -> Stats {
+	let (light, roads) = (&roads.light, &roads.roads);
+	let mut degrees: IndexMap<&NodeId, usize> = roads.nodes.nodes.iter().map(|n| (&n.id, 0)).collect();
+	for r in &roads.roads {
+		*degrees.entry(&r.p1).or_default() += 1;
+		*degrees.entry(&r.p2).or_default() += 1;
+	}
+	let mut histogram = std::collections::BTreeMap::new();
+	for d in degrees.values() {
+		*histogram.entry(*d).or_default() += 1;
+	}
+	let graph = GraphStats {
+		nodes: roads.nodes.nodes.len(),
+		segments: roads.roads.len(),
+		directed: roads.roads.iter().filter(|r| r.directed).count(),
+		length: roads.roads.iter().map(|r| r.distance.raw()).sum(),
+		degrees: histogram,
+		sccs: light.strongly_connected_components::<true, false>().len(),
+		bridges: light.bridges().len(),
+		articulation_points: light.articulation_points().len(),
+	};
+	let plan = plan.map(|plan| {
+		let lengths: IndexMap<_, _> = roads.roads.iter().map(|r| (segment_key(&r.p1, &r.p2, &r.discriminator), r.distance.raw())).collect();
+		let length = |k: &SegmentKey| lengths.get(k).copied().unwrap_or(0.0);
+		let snowy: Option<HashSet<_>> = snow.map(|snow| snow.iter().filter(|s| s.depth > 0.0).map(|s| segment_key(&s.p1, &s.p2, &s.discriminator)).collect());
+		let mut cleared = HashSet::new();
+		let mut deadhead = 0.0;
+		let vehicles: Vec<f64> = plan.iter().map(|path| path_segments(path).map(|k| {
+			let l = length(&k);
+			if !snowy.as_ref().is_some_and(|snowy| snowy.contains(&k)) || !cleared.insert(k) {
+				deadhead += l;
+			}
+			l
+		}).sum()).collect();
+		let total_distance = vehicles.iter().sum();
+		PlanStats {
+			deadheading: snowy.as_ref().map(|_| if total_distance > 0.0 { deadhead / total_distance } else { 0.0 }),
+			coverage: snowy.as_ref().map(|snowy| if snowy.is_empty() { 1.0 } else { cleared.len() as f64 / snowy.len() as f64 }),
+			vehicles,
+			total_distance,
+		}
+	});
+	Stats { graph, plan }
+}
+
+/// Computes the totals of each vehicle's tour, and the objective value of the tours
+///
+/// Snowy segments are cleared the first time a vehicle gets through (in vehicles order), which takes `slowdown` times longer - for sidewalk tours, each side of a segment is cleared on its own, and crossings clear nothing.
+/// The objective value is that of the tour costs alone, leaving aside the clearing latency and snow scenarios the solver may also weigh.
+///
+/// Arguments:
+/// - `tours`: segments driven by each vehicle, as given by [`road_tours`] or [`sidewalk_tours`]
+/// - `profiles`: capability profile of each vehicle, whose speeds and plow width (in passes) go into durations, and whose road classes bound what it clears
+/// - `speed`: speed of the vehicles without one in their profile, in km/h, for durations (unless costs are times already)
+///
+/// Returns: totals of each tour, and the objective value
+#[allow(clippy::too_many_arguments)]
+pub fn vehicle_metrics(roads: &RoadGraph, snow: &SnowStatuses, snow_d: Option<f64>, tours: &[Vec<(SegmentKey, Option<SidewalkSide>)>], sidewalks: bool, profiles: &[VehicleProfile], params: &meta::Parameters, speed: f64) -> Result<(Vec<VehicleMetrics>, f64), String> {
+	let (vehicles, _, costs, required_cost, cleared) = drive(roads, snow, snow_d, tours, sidewalks, profiles, params, speed)?;
+	let scale = meta::Scale {
+		edges: n64(cleared as f64),
+		required: required_cost,
+		vehicles: tours.len(),
+	};
+	Ok((vehicles, params.value(&costs, n64(0.0), &scale).0.raw()))
+}
+
+/// Estimates when each vehicle gets to each node of its path, in hours from the start, as per the durations of [`vehicle_metrics`] - plus the service time of the node tasks on the way
+///
+/// Arguments:
+/// - `plan`: the road paths
+/// - `profiles`: capability profile of each vehicle, whose speeds and plow width (in passes) go into durations
+/// - `speed`: speed of the vehicles without one in their profile, in km/h (unless costs are times already)
+///
+/// Returns: the paths, with the estimated arrival times
+#[allow(clippy::too_many_arguments)]
+pub fn etas(roads: &RoadGraph, snow: &SnowStatuses, snow_d: Option<f64>, plan: &Paths, profiles: &[VehicleProfile], params: &meta::Parameters, speed: f64) -> Result<Paths, String> {
+	let (_, arrivals, ..) = drive(roads, snow, snow_d, &road_tours(plan), false, profiles, params, speed)?;
+	let unrestricted = VehicleProfile::default();
+	Ok(plan.iter().zip(arrivals).enumerate().map(|(v, (path, arrivals))| {
+		let speed = profiles.get(v).unwrap_or(&unrestricted).speed.map_or(speed, |s| s.raw());
+		let mut serviced = 0.0;
+		path.iter().enumerate().map(|(i, s)| {
+			let eta = if i == 0 { 0.0 } else { arrivals[i - 1] } + serviced;
+			serviced += s.service.map_or(0.0, |service| params.cost_hours(service.raw(), speed));
+			PathSegment { eta: Some(eta), ..s.clone() }
+		}).collect()
+	}).collect())
+}
+
+/// Drives the tours, snowy segments being cleared the first time a vehicle gets through, as described in [`vehicle_metrics`]
+///
+/// Returns: totals of each tour, hours from the start each vehicle is through each segment of its tour, costs of the tours, cost of clearing, and segments cleared
+#[allow(clippy::too_many_arguments, clippy::type_complexity)]
+fn drive(roads: &RoadGraph, snow: &SnowStatuses, snow_d: Option<f64>, tours: &[Vec<(SegmentKey, Option<SidewalkSide>)>], sidewalks: bool, profiles: &[VehicleProfile], params: &meta::Parameters, speed: f64) -> Result<(Vec<VehicleMetrics>, Vec<Vec<f64>>, Vec<N64>, N64, usize), String> {
+	let segments: IndexMap<_, _> = roads.roads.iter().map(|r| (segment_key(&r.p1, &r.p2, &r.discriminator), r)).collect();
+	let statuses: IndexMap<_, _> = snow.iter().map(|s| (segment_key(&s.p1, &s.p2, &s.discriminator), s)).collect();
+	let snow_d = snow_d.filter(|d| *d > 0.0).map(n64);
+	let depth = |k: &SegmentKey| snow_d.or_else(|| statuses.get(k).map(|s| s.depth)).unwrap_or_else(|| n64(0.0));
+	let required = |k: &SegmentKey, r: &RoadSegment| match snow_d {
+		Some(d) => params.depths.is_snowy(r.class, d),
+		None => statuses.get(k).is_some_and(|s| params.depths.is_likely(params.depths.clearing_probability(r.class, s))),
+	};
+	let mut cleared = HashSet::new();
+	let mut required_cost = n64(0.0);
+	let mut costs = Vec::new();
+	let mut vehicles = Vec::new();
+	let mut arrivals = Vec::new();
+	let unrestricted = VehicleProfile::default();
+	for (v, tour) in tours.iter().enumerate() {
+		let profile = profiles.get(v).unwrap_or(&unrestricted);
+		let speed = profile.speed.map_or(speed, |s| s.raw());
+		let mut cost = n64(0.0);
+		let mut metrics = VehicleMetrics { distance: 0.0, duration: 0.0, cleared: 0, deadhead: 0.0 };
+		let mut through = Vec::with_capacity(tour.len());
+		for (k, side) in tour {
+			let r = segments.get(k).ok_or_else(|| format!("Path through unknown segment {:?}", k))?;
+			let (c, hours) = (params.cost(r, depth(k))?, params.hours(r, depth(k), speed)?);
+			metrics.distance += r.distance.raw();
+			if (!sidewalks || side.is_some() || profile.clears_roadway(r.width)) && profile.services(r.class) && profile.covers(r.zone.as_deref()) && required(k, r) && cleared.insert((k.clone(), *side)) {
+				let passes = if sidewalks { 1.0 } else { profile.passes(r.width).raw() };
+				metrics.cleared += 1;
+				metrics.duration += passes * match profile.plow_speed {
+					Some(plow_speed) => params.hours(r, depth(k), plow_speed.raw())?,
+					None => hours * params.clearing_slowdown().raw(),
+				};
+				cost += c * params.clearing_slowdown();
+				required_cost += c * params.clearing_slowdown();
+			} else {
+				metrics.deadhead += r.distance.raw();
+				metrics.duration += hours;
+				cost += c;
+			}
+			through.push(metrics.duration);
+		}
+		costs.push(cost);
+		vehicles.push(metrics);
+		arrivals.push(through);
+	}
+	Ok((vehicles, arrivals, costs, required_cost, cleared.len()))
+}
@@ -0,0 +1,88 @@
+//! Repro bundles, so that solver bugs reported from the field can actually be reproduced
+//!
+//! A bundle is a zstd-compressed tarball of the exact input files, the produced output (if any), and a `manifest.json` telling the crate version, seed, the command to re-run from within the extracted bundle, and the failure (if any).
+
+use std::path::{Path, PathBuf};
+use indexmap::IndexMap;
+use serde::*;
+
+/// Bundle manifest
+#[derive(Serialize, Deserialize, Clone, PartialEq, Debug)]
+pub struct Manifest {
+	/// crate version that produced the bundle
+	pub version: String,
+	/// command line, with files replaced by their path within the bundle
+	pub command: Vec<String>,
+	/// seed of the solver's random number generator, if it was seeded
+	pub seed: Option<u64>,
+	/// path within the bundle of each input, by role
+	pub inputs: IndexMap<String, String>,
+	/// path within the bundle of the output, if it was produced
+	pub output: Option<String>,
+	/// failure, if any
+	pub error: Option<String>,
+}
+
+/// Repro bundle to be exported
+#[derive(Clone, Debug)]
+pub struct Repro {
+	pub bundle: PathBuf,
+	/// input files, by role
+	pub inputs: Vec<(String, PathBuf)>,
+	pub output: PathBuf,
+	pub seed: Option<u64>,
+}
+
+impl Repro {
+	/// Path within the bundle of an input
+	fn bundled(role: &str, file: &Path) -> String {
+		match file.extension() {
+			Some(ext) => format!("inputs/{}.{}", role, ext.to_string_lossy()),
+			None => format!("inputs/{}", role),
+		}
+	}
+
+	/// Exports the bundle, with the output unless the run failed
+	pub fn export(&self, error: Option<String>) -> std::io::Result<()> {
+		let mut renames: IndexMap<String, String> = self.inputs.iter().map(|(role, f)| (f.to_string_lossy().into_owned(), Self::bundled(role, f))).collect();
+		let output = Some("output.json".to_string()).filter(|_| error.is_none() && self.output.exists());
+		renames.insert(self.output.to_string_lossy().into_owned(), "output.json".to_string());
+		let mut args = std::env::args().skip(1);
+		let mut command = Vec::new();
+		while let Some(arg) = args.next() {
+			if arg == "--export-repro" {
+				args.next();
+			} else if !arg.starts_with("--export-repro=") {
+				command.push(renames.get(&arg).cloned().unwrap_or(arg));
+			}
+		}
+		if let Some(seed) = self.seed.filter(|_| !command.iter().any(|a| a == "--seed" || a.starts_with("--seed="))) {
+			command.push(format!("--seed={}", seed));
+		}
+		let manifest = Manifest {
+			version: env!("CARGO_PKG_VERSION").to_string(),
+			command,
+			seed: self.seed,
+			inputs: self.inputs.iter().map(|(role, f)| (role.clone(), Self::bundled(role, f))).collect(),
+			output: output.clone(),
+			error,
+		};
+		let mut tar = tar::Builder::new(zstd::Encoder::new(std::fs::File::create(&self.bundle)?, 0)?.auto_finish());
+		for (role, f) in &self.inputs {
+			tar.append_path_with_name(f, Self::bundled(role, f))?;
+		}
+		if let Some(output) = output {
+			tar.append_path_with_name(&self.output, output)?;
+		}
+		let manifest = serde_json::to_vec_pretty(&manifest).unwrap();
+		let mut header = tar::Header::new_gnu();
+		header.set_size(manifest.len() as u64);
+		header.set_mode(0o644);
+		header.set_mtime(std::time::SystemTime::now().duration_since(std::time::UNIX_EPOCH).map_or(0, |d| d.as_secs()));
+		header.set_cksum();
+		tar.append_data(&mut header, "manifest.json", manifest.as_slice())?;
+		tar.finish()?;
+		log::info!("Exported repro bundle {}", self.bundle.display());
+		Ok(())
+	}
+}
@@ -0,0 +1,160 @@
+//! Rolling-horizon replanning driver, for live storm operations
+//!
+//! Alternates between solving road plowing for the next time horizon, accepting execution feedback, updating the snow state, and re-solving.
+//!
+//! Each round `k` writes the horizon-truncated plan to `{dir}/plan.{k}.json` and waits for [`data::Progress`] feedback in `{dir}/feedback.{k}.json`.
+//!
+//! [`replan`] does a single such round, given the plan being executed and its progress.
+
+use crate::*;
+use data::*;
+
+use std::{collections::HashSet, path::Path, time::Duration};
+use indexmap::IndexMap;
+
+/// Rolling horizon settings
+#[derive(Clone, Debug)]
+pub struct Rolling<'a> {
+	/// directory where plans are written and feedback is expected
+	pub dir: &'a Path,
+	/// planning horizon, in minutes
+	pub horizon: f64,
+	/// vehicles speed, in km/h
+	pub speed: f64,
+	/// assume plans get executed exactly, instead of waiting for feedback
+	pub simulate: bool,
+	/// maximum number of rounds
+	pub max_rounds: usize,
+}
+
+/// Truncates paths to what can be driven within the horizon
+fn truncate(paths: Paths, lengths: &IndexMap<SegmentKey, N64>, snowy: &HashSet<SegmentKey>, params: &meta::Parameters, settings: &Rolling) -> Paths {
+	let budget = settings.horizon * settings.speed * 1000.0 / 60.0;
+	paths.into_iter().map(|path| {
+		let mut driven = 0.0;
+		let mut dun = HashSet::new();
+		let mut kept = Vec::new();
+		for (i, seg) in path.iter().enumerate() {
+			if i > 0 {
+				let key = segment_key(&path[i-1].node, &seg.node, &seg.discriminator);
+				let length = lengths.get(&key).copied().unwrap_or_else(|| n64(0.0));
+				driven += (length * if snowy.contains(&key) && dun.insert(key) { params.clearing_slowdown() } else { n64(1.0) }).raw();
+				if driven > budget {
+					break;
+				}
+			}
+			kept.push(seg.clone());
+		}
+		kept
+	}).collect()
+}
+
+/// How many times an invalid feedback file is read again, a second apart, before giving up on it - it may still be being written
+const FEEDBACK_RETRIES: usize = 30;
+
+/// Waits for the feedback file to appear, and reads it once complete
+fn wait_feedback(file: &Path) -> std::io::Result<Progress> {
+	log::info!("Waiting for feedback in {}", file.display());
+	let mut retries = 0;
+	loop {
+		if file.exists() {
+			match document::read(file, "Feedback") {
+				Ok(progress) => return Ok(progress),
+				Err(e @ (error::Error::Parse { .. } | error::Error::Validation { .. })) if retries < FEEDBACK_RETRIES => {
+					log::debug!("Feedback not complete yet: {}", e);
+					retries += 1;
+				},
+				Err(e) => return Err(e.into()),
+			}
+		}
+		std::thread::sleep(Duration::from_secs(1));
+	}
+}
+
+/// Segments of a snow status that need clearing, as per the depth thresholds of their road class - like the solver tells
+fn snowy(classes: &IndexMap<SegmentKey, Option<RoadClass>>, snow: &[SnowStatusElement], params: &meta::Parameters) -> HashSet<SegmentKey> {
+	snow.iter().map(|s| (segment_key(&s.p1, &s.p2, &s.discriminator), s))
+		.filter(|(k, s)| classes.get(k).is_some_and(|c| params.depths.is_likely(params.depths.clearing_probability(*c, s))))
+		.map(|(k, _)| k).collect()
+}
+
+/// Accounts for the progress of vehicles along their plan: the segments they got through are cleared, and they now stand where they got to.
+///
+/// Returns: the snow status left, merged with the fresh observations of the progress
+fn advance(plan: &Paths, progress: Progress, snow: SnowStatuses, vehicles: &mut VehiclesConfiguration) -> SnowStatuses {
+	let mut cleared = HashSet::new();
+	for (path, reached) in plan.iter().zip(progress.reached.iter()) {
+		let reached = (*reached).min(path.len().saturating_sub(1));
+		for i in 1..=reached {
+			cleared.insert(segment_key(&path[i-1].node, &path[i].node, &path[i].discriminator));
+		}
+	}
+	for (location, (path, reached)) in vehicles.road.iter_mut().zip(plan.iter().zip(progress.reached.iter())) {
+		if let Some(seg) = path.get((*reached).min(path.len().saturating_sub(1))) {
+			*location = Location::Node(seg.node.clone());
+		}
+	}
+	log::info!("{} segments cleared", cleared.len());
+	let mut snow = snow;
+	snow.retain(|s| !cleared.contains(&segment_key(&s.p1, &s.p2, &s.discriminator)));
+	snow::merge(snow.into_iter().chain(progress.snow), &snow::Mean)
+}
+
+/// Road class of each segment
+fn classes(roads: &Roads) -> Result<IndexMap<SegmentKey, Option<RoadClass>>, error::Error> {
+	let mut classes = IndexMap::new();
+	roads.scan(|r| {
+		classes.insert(segment_key(&r.p1, &r.p2, &r.discriminator), r.class);
+		Ok(())
+	})?;
+	Ok(classes)
+}
+
+/// Re-plans road plowing mid-storm, for the work left: from where each vehicle got along the previous plan, through the fresh snow status but what they cleared since
+///
+/// Returns: the new paths, from the vehicles' current positions
+pub fn replan(roads: impl Into<Roads>, plan: &Paths, progress: Progress, snow: SnowStatuses, overlay: &CostOverlay, mut vehicles: VehiclesConfiguration, params: &meta::Parameters) -> Result<Paths, error::Error> {
+	let roads = roads.into();
+	if plan.len() != vehicles.road.len() || progress.reached.len() != plan.len() {
+		return Err(error::Error::Validation { file: None, message: format!("{} road vehicles, {} paths and {} progress positions don't match", vehicles.road.len(), plan.len(), progress.reached.len()) });
+	}
+	let snow = advance(plan, progress, snow, &mut vehicles);
+	log::info!("{} snowy segments remaining", snowy(&classes(&roads)?, &snow, params).len());
+	Ok(plow::road::solve(roads, snow, Vec::new(), None, overlay, &[], None, &[], vehicles, params)?.0)
+}
+
+/// Runs the rolling horizon replanning loop, until everything is clear (or rounds run out)
+///
+/// Streamed road graphs are streamed again for every round's solve.
+pub fn run(roads: impl Into<Roads>, mut snow: SnowStatuses, overlay: &CostOverlay, mut vehicles: VehiclesConfiguration, params: &meta::Parameters, settings: &Rolling) -> std::io::Result<()> {
+	let roads = roads.into();
+	let mut lengths = IndexMap::new();
+	roads.scan(|r| {
+		lengths.insert(segment_key(&r.p1, &r.p2, &r.discriminator), r.distance);
+		Ok(())
+	}).map_err(std::io::Error::other)?;
+	let classes = classes(&roads).map_err(std::io::Error::other)?;
+	for round in 0..settings.max_rounds {
+		let snowy = snowy(&classes, &snow, params);
+		if snowy.is_empty() {
+			log::info!("All clear after {} rounds", round);
+			return Ok(());
+		}
+		log::info!("Round {}: {} snowy segments remaining", round, snowy.len());
+		let paths = plow::road::solve(roads.clone(), snow.clone(), Vec::new(), None, overlay, &[], None, &[], vehicles.clone(), params).map_err(std::io::Error::other)?.0;
+		let plan = truncate(paths, &lengths, &snowy, params, settings);
+		document::write(settings.dir.join(format!("plan.{}.json", round)), &plan)?;
+		let progress = if settings.simulate {
+			Progress {
+				reached: plan.iter().map(|path| path.len().saturating_sub(1)).collect(),
+				snow: Vec::new(),
+			}
+		} else {
+			wait_feedback(&settings.dir.join(format!("feedback.{}.json", round)))?
+		};
+		log::info!("Round {} done", round);
+		snow = advance(&plan, progress, snow, &mut vehicles);
+	}
+	log::warn!("Ran out of rounds with snow remaining");
+	Ok(())
+}
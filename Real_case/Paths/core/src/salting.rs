@@ -0,0 +1,121 @@
+//! Chained two-stage planning: plowing, then salting the plowed roads
+//!
+//! Salting a segment before it is plowed is wasted salt, so the salting pass covers the segments the plowing plan clears, and salters wait for the plows where they would get ahead of them.
+
+use crate::*;
+use data::*;
+
+use std::collections::HashMap;
+use serde::*;
+
+/// Timing of a segment through both stages
+#[derive(Serialize, Deserialize, Clone, PartialEq, Debug)]
+pub struct Timing {
+	pub p1: NodeId,
+	pub p2: NodeId,
+	pub discriminator: Option<NodeId>,
+	/// when the segment gets plowed, in hours from the start
+	pub plowed: f64,
+	/// when the segment gets salted, in hours from the start (if a salter gets to it at all)
+	pub salted: Option<f64>,
+}
+
+/// Consistent timing of the plowing and salting plans
+#[derive(Serialize, Deserialize, Clone, PartialEq, Debug)]
+pub struct Schedule {
+	/// completion time of each plow, in hours
+	pub plows: Vec<f64>,
+	/// when each salter has to leave so as not to wait for the plows on its first segment, in hours
+	pub salters_start: Vec<f64>,
+	/// completion time of each salter, in hours
+	pub salters: Vec<f64>,
+	pub segments: Vec<Timing>,
+}
+
+/// Segments that paths go through, by key
+fn segments_of(roads: &Roads, paths: &Paths, segments: &mut HashMap<SegmentKey, RoadSegment>) -> Result<(), error::Error> {
+	let keys: std::collections::HashSet<_> = paths.iter().flat_map(|path| report::path_segments(path)).filter(|k| !segments.contains_key(k)).collect();
+	roads.scan(|r| {
+		let k = segment_key(&r.p1, &r.p2, &r.discriminator);
+		if keys.contains(&k) {
+			segments.insert(k, r.clone());
+		}
+		Ok(())
+	})
+}
+
+/// Solves plowing, then salting of the plowed segments
+///
+/// Streamed road graphs are streamed again for each plan, and for the segments each goes through.
+///
+/// Arguments:
+/// - `vehicles`: the plows
+/// - `salters`: the salt trucks (as road vehicles, their maximum depths being irrelevant on plowed roads)
+/// - `speed`: vehicles speed, in km/h
+///
+/// Returns: the plowing plan, the salting plan, and their schedule
+#[allow(clippy::too_many_arguments)]
+pub fn solve(roads: impl Into<Roads>, snow: SnowStatuses, snow_d: Option<f64>, overlay: &[CostMultiplier], vehicles: VehiclesConfiguration, mut salters: VehiclesConfiguration, params: &meta::Parameters, speed: f64) -> Result<(Paths, Paths, Schedule), error::Error> {
+	let roads = roads.into();
+	let plan = plow::road::solve(roads.clone(), snow.clone(), Vec::new(), snow_d, overlay, &[], None, &[], vehicles, params)?.0;
+	log::info!("Constructed plowing paths");
+	let mut segments = HashMap::new();
+	segments_of(&roads, &plan, &mut segments)?;
+	let statuses: HashMap<_, _> = snow.iter().map(|s| (segment_key(&s.p1, &s.p2, &s.discriminator), s)).collect();
+	let snow_d = snow_d.filter(|d| *d > 0.0).map(n64);
+	let depth = |k: &SegmentKey| snow_d.or_else(|| statuses.get(k).map(|s| s.depth)).unwrap_or_else(|| n64(0.0));
+	let required = |k: &SegmentKey, r: &RoadSegment| match snow_d {
+		Some(d) => params.depths.is_snowy(r.class, d),
+		None => statuses.get(k).is_some_and(|s| params.depths.is_likely(params.depths.clearing_probability(r.class, s))),
+	};
+	// plowing: each segment is plowed the first time a plow needing to clear it gets through
+	let mut plowed: HashMap<SegmentKey, f64> = HashMap::new();
+	let mut plows = Vec::new();
+	for path in &plan {
+		let mut cleared = std::collections::HashSet::new();
+		let mut time = 0.0;
+		for k in report::path_segments(path) {
+			let r = segments.get(&k).ok_or_else(|| format!("Plowing path through unknown segment {:?}", k))?;
+			if required(&k, r) && cleared.insert(k.clone()) {
+				time += params.hours(r, depth(&k), speed)? * params.clearing_slowdown().raw();
+				let at = plowed.entry(k).or_insert(time);
+				*at = at.min(time);
+			} else {
+				time += params.hours(r, depth(&k), speed)?;
+			}
+		}
+		plows.push(time);
+	}
+	// salting: the plowed segments, salted after they get plowed
+	let salt: SnowStatuses = plowed.keys().map(|k| match statuses.get(k) {
+		Some(s) if snow_d.is_none() => (*s).clone(),
+		_ => SnowStatusElement { p1: k.0.clone(), p2: k.1.clone(), discriminator: k.2.clone(), depth: depth(k), probability: None, variance: None, observed: None, confidence: None },
+	}).collect();
+	salters.road_max_depth.clear();
+	let salting = plow::road::solve(roads.clone(), salt, Vec::new(), None, overlay, &[], None, &[], salters, params)?.0;
+	log::info!("Constructed salting paths");
+	segments_of(&roads, &salting, &mut segments)?;
+	let mut salted: HashMap<SegmentKey, f64> = HashMap::new();
+	let mut salters_start = Vec::new();
+	let mut salters = Vec::new();
+	for path in &salting {
+		let mut time = 0.0;
+		let mut wait: Option<f64> = None;
+		for k in report::path_segments(path) {
+			let r = segments.get(&k).ok_or_else(|| format!("Salting path through unknown segment {:?}", k))?;
+			match plowed.get(&k) {
+				Some(at) if !salted.contains_key(&k) => {
+					wait.get_or_insert(at - time);
+					time = time.max(*at) + params.hours(r, depth(&k), speed)? * params.clearing_slowdown().raw();
+					salted.insert(k, time);
+				},
+				_ => time += params.hours(r, depth(&k), speed)?,
+			}
+		}
+		salters_start.push(wait.unwrap_or(0.0).max(0.0));
+		salters.push(time);
+	}
+	let mut timings: Vec<_> = plowed.into_iter().map(|(k, plowed)| Timing { salted: salted.get(&k).copied(), p1: k.0, p2: k.1, discriminator: k.2, plowed }).collect();
+	timings.sort_by(|a, b| a.plowed.total_cmp(&b.plowed));
+	Ok((plan, salting, Schedule { plows, salters_start, salters, segments: timings }))
+}
@@ -0,0 +1,65 @@
+//! Import of snow observations from roadside sensor CSV dumps
+
+use crate::*;
+use data::*;
+
+use std::path::Path;
+use error::Error;
+use indexmap::{IndexMap, IndexSet};
+use itertools::Itertools;
+
+/// Reads the snow observations of a sensor CSV dump, `segment_id,depth_cm,timestamp` lines (after an optional header line)
+///
+/// Depths are converted to mm; timestamps, if any, are in seconds since epoch.
+/// Observations of sensors without segments are skipped with a warning.
+///
+/// Arguments:
+/// - `file`: the CSV dump
+/// - `sensors`: segments each sensor observes
+///
+/// Returns: the snow status of the observed segments, or where the dump is invalid
+pub fn read_csv(file: impl AsRef<Path>, sensors: &SensorSegments) -> Result<SnowStatuses, Error> {
+	let text = std::fs::read_to_string(&file).map_err(|e| Error::io(&file, e))?;
+	let mut segments: IndexMap<&str, Vec<&SensorSegment>> = IndexMap::new();
+	for s in sensors {
+		segments.entry(s.sensor.as_str()).or_default().push(s);
+	}
+	let invalid = |line: usize, column: usize, message: String| Error::Parse { file: file.as_ref().to_string_lossy().into_owned(), line, column, message: format!("Sensor dump is invalid CSV: {}", message) };
+	let mut snow = Vec::new();
+	let mut unknown = IndexSet::new();
+	for (i, line) in text.lines().enumerate().filter(|(_, l)| !l.trim().is_empty()) {
+		let fields: Vec<_> = line.split(',').map(|f| f.trim().trim_matches('"')).collect();
+		let column = |f: usize| 1 + line.split(',').take(f).map(|f| f.len() + 1).sum::<usize>();
+		if fields.len() < 2 || fields.len() > 3 {
+			return Err(invalid(i + 1, 1, format!("expected segment_id,depth_cm,timestamp, got {} fields", fields.len())));
+		}
+		let depth = match fields[1].parse::<f64>() {
+			Ok(depth) if depth.is_finite() && depth >= 0.0 => n64(depth * 10.0),
+			_ if i == 0 => continue,
+			_ => return Err(invalid(i + 1, column(1), format!("depth {:?} is not a non-negative number", fields[1]))),
+		};
+		let observed = match fields.get(2).filter(|t| !t.is_empty()) {
+			Some(t) => Some(t.parse::<f64>().ok().filter(|t| t.is_finite()).ok_or_else(|| invalid(i + 1, column(2), format!("timestamp {:?} is not in seconds since epoch", t)))?),
+			None => None,
+		};
+		match segments.get(fields[0]) {
+			Some(observes) => snow.extend(observes.iter().map(|s| SnowStatusElement {
+				p1: s.p1.clone(),
+				p2: s.p2.clone(),
+				discriminator: s.discriminator.clone(),
+				depth,
+				probability: None,
+				variance: None,
+				observed,
+				confidence: None,
+			})),
+			None => {
+				unknown.insert(fields[0].to_string());
+			},
+		}
+	}
+	if !unknown.is_empty() {
+		log::warn!("Skipped observations of {} sensors without segments: {}", unknown.len(), unknown.iter().take(50).join(", "));
+	}
+	Ok(snow)
+}
@@ -0,0 +1,321 @@
+//! HTTP server mode, answering solve requests with a JSON API over a road graph loaded once
+//!
+//! Request bodies carry the same documents as the files (versioned or bare), responses are documents as written to files - or `{ "error": "..." }`.
+//! When clients are configured, requests must carry a known API key in the `X-Api-Key` header, and run within their client's jobs quota.
+
+use crate::*;
+use data::*;
+
+use std::sync::Arc;
+use serde::*;
+use serde_json::Value;
+use tiny_http::{Header, Method, Request, Response};
+
+/// `POST /plow` request body
+#[derive(Deserialize, Clone, Debug)]
+struct Plow {
+	snow: Value,
+	vehicles: Value,
+	meta: meta::Parameters,
+	/// cost multipliers
+	#[serde(default)]
+	multipliers: Option<Value>,
+	/// whether to plow sidewalks rather than roads
+	#[serde(default)]
+	sidewalks: bool,
+}
+
+/// `POST /fly` request body
+#[derive(Deserialize, Clone, Debug)]
+struct Fly {
+	drones: Value,
+	meta: meta::Parameters,
+}
+
+/// `POST /snows/merge` request body
+#[derive(Deserialize, Clone, Debug)]
+struct Merge {
+	/// snow statuses to merge, formal or as GeoJSON
+	snows: Vec<Value>,
+	/// layers of segments known to be cleared
+	#[serde(default)]
+	cleared: Vec<Value>,
+	/// merge strategy, as per [`snow::strategy`] (`mean` if not given)
+	#[serde(default)]
+	strategy: Option<String>,
+	/// snow accumulation rate since observations, in mm/h, for the `latest` strategy
+	#[serde(default)]
+	accumulation: N64,
+	/// time to extrapolate depths to, for the `latest` strategy
+	#[serde(default)]
+	at: Option<f64>,
+}
+
+/// Failed request, as responded
+struct Failed(u16, String);
+
+impl From<error::Error> for Failed {
+	fn from(e: error::Error) -> Self {
+		let status = match e {
+			error::Error::Io { .. } => 500,
+			error::Error::Parse { .. } | error::Error::Validation { .. } => 400,
+			error::Error::Solver { .. } => 422,
+			error::Error::Timeout { .. } => 504,
+		};
+		Failed(status, e.to_string())
+	}
+}
+
+impl From<auth::Denied> for Failed {
+	fn from(e: auth::Denied) -> Self {
+		let status = match e {
+			auth::Denied::Unauthorized => 401,
+			auth::Denied::QuotaExceeded(_) => 429,
+		};
+		Failed(status, e.to_string())
+	}
+}
+
+/// Parses a request body field as a document of a kind
+fn field<T: document::Kind + de::DeserializeOwned>(doc: Value, what: &str) -> Result<T, Failed> {
+	document::parse(doc).map_err(|e| Failed(400, format!("{} {}", what, e)))
+}
+
+/// Checks the meta parameters of a request body are in range
+fn checked(meta: meta::Parameters) -> Result<meta::Parameters, Failed> {
+	match meta.problems() {
+		problems if problems.is_empty() => Ok(meta),
+		problems => Err(Failed(400, format!("Meta parameters out of range: {}", problems.join("; ")))),
+	}
+}
+
+/// Responds a document
+fn ok<T: document::Kind + Serialize>(data: &T) -> Result<String, Failed> {
+	Ok(serde_json::to_string(&document::wrap(data)).unwrap())
+}
+
+/// Server state, shared by the requests being handled
+struct Server {
+	roads: RoadGraph,
+	auth: Option<auth::Auth>,
+	/// wall-clock budget of solves not setting one, in minutes
+	max_wall_time: Option<f64>,
+}
+
+impl Server {
+	/// Handles a request
+	///
+	/// Returns: the JSON body of the response, or why it failed
+	fn handle(&self, request: &mut Request) -> Result<String, Failed> {
+		let (method, url) = (request.method().clone(), request.url().split('?').next().unwrap_or_default().to_string());
+		match (&method, url.as_str()) {
+			(Method::Get, "/health") => return Ok(serde_json::json!({ "status": "ok", "nodes": self.roads.nodes.nodes.len(), "segments": self.roads.roads.len() }).to_string()),
+			(Method::Post, "/plow" | "/fly" | "/snows/merge") => {},
+			(_, "/health" | "/plow" | "/fly" | "/snows/merge") => return Err(Failed(405, format!("{} not allowed on {}", method, url))),
+			_ => return Err(Failed(404, format!("no such endpoint {}", url))),
+		}
+		let _permit = match &self.auth {
+			Some(auth) => {
+				let key = request.headers().iter().find(|h| h.field.equiv("X-Api-Key")).map(|h| h.value.as_str().to_string());
+				let client = auth.authenticate(key.as_deref())?;
+				log::info!("{} {} for {}", method, url, client.name);
+				Some(auth.start(client)?)
+			},
+			None => None,
+		};
+		let mut body = String::new();
+		request.as_reader().read_to_string(&mut body).map_err(|e| Failed(400, format!("Unreadable body: {}", e)))?;
+		let body: Value = serde_json::from_str(&body).map_err(|e| Failed(400, format!("Body is invalid JSON: {}", e)))?;
+		let invalid = |e: serde_json::Error| Failed(400, format!("Body is invalid: {}", e));
+		match url.as_str() {
+			"/plow" => {
+				let Plow { snow, vehicles, meta, multipliers, sidewalks } = serde_json::from_value(body).map_err(invalid)?;
+				let mut meta = checked(meta)?;
+				let snow: SnowStatuses = field(snow, "Snow status")?;
+				let vehicles: VehiclesConfiguration = field(vehicles, "Vehicles configuration")?;
+				let overlay: CostOverlay = match multipliers {
+					Some(m) => field(m, "Cost multipliers")?,
+					None => Vec::new(),
+				};
+				meta.annealing.max_wall_time = meta.annealing.max_wall_time.or(self.max_wall_time);
+				if sidewalks {
+					ok(&plow::sidewalk::solve(self.roads.clone(), snow, Vec::new(), None, &overlay, &[], vehicles, &meta)?.0)
+				} else {
+					ok(&plow::road::solve(self.roads.clone(), snow, Vec::new(), None, &overlay, &[], None, &[], vehicles, &meta)?.0)
+				}
+			},
+			"/fly" => {
+				let Fly { drones, meta } = serde_json::from_value(body).map_err(invalid)?;
+				let mut meta = checked(meta)?;
+				let drones: Drones = field(drones, "Drones config")?;
+				meta.annealing.max_wall_time = meta.annealing.max_wall_time.or(self.max_wall_time);
+				ok(&plow::fly::solve(self.roads.clone(), drones, &meta)?)
+			},
+			_ => {
+				let Merge { snows, cleared, strategy, accumulation, at } = serde_json::from_value(body).map_err(invalid)?;
+				let name = strategy.as_deref().unwrap_or("mean");
+				let strategy = snow::strategy(name, accumulation, at).ok_or_else(|| Failed(400, format!("No such merge strategy {}, expected one of {}", name, snow::STRATEGIES.join(", "))))?;
+				let snows = snows.into_iter().map(|s| document::parse_any(s).map(|s| match s {
+					SnuwDapg::Formal(s) => s,
+					SnuwDapg::Geo(feat) => gj::geofeatures_to_snow(&self.roads, feat),
+				})).collect::<Result<Vec<_>, _>>().map_err(|e| Failed(400, format!("Snow status is invalid: {}", e)))?;
+				let cleared = cleared.into_iter().map(|c| field::<SnowStatuses>(c, "Cleared layer")).collect::<Result<Vec<_>, _>>()?;
+				let mut merged = snow::merge(snows.into_iter().flatten(), strategy.as_ref());
+				clear_snow_statuses(&mut merged, cleared.into_iter());
+				ok(&merged)
+			},
+		}
+	}
+}
+
+/// Serves the JSON API at an address, handling each request in its own thread, forever
+///
+/// Arguments:
+/// - `addr`: address to listen on, such as `127.0.0.1:8080`
+/// - `roads`: the road graph all requests are solved over
+/// - `clients`: clients allowed to make requests, or `None` to allow anyone
+/// - `max_wall_time`: wall-clock budget of solves whose meta parameters don't set one, in minutes
+pub fn serve(addr: &str, roads: RoadGraph, clients: Option<auth::Clients>, max_wall_time: Option<f64>) -> std::io::Result<()> {
+	let server = tiny_http::Server::http(addr).map_err(std::io::Error::other)?;
+	log::info!("Serving on http://{}", addr);
+	listen(server, roads, clients, max_wall_time);
+	Ok(())
+}
+
+/// Answers the requests of a server, forever
+///
+/// A request panicking is answered with an internal error, other requests carry on.
+fn listen(server: tiny_http::Server, roads: RoadGraph, clients: Option<auth::Clients>, max_wall_time: Option<f64>) {
+	let state = Arc::new(Server { roads, auth: clients.map(auth::Auth::new), max_wall_time });
+	for mut request in server.incoming_requests() {
+		let state = state.clone();
+		std::thread::spawn(move || {
+			let handled = std::panic::catch_unwind(std::panic::AssertUnwindSafe(|| state.handle(&mut request))).unwrap_or_else(|panic| {
+				let message = panic.downcast_ref::<&str>().map(|s| s.to_string()).or_else(|| panic.downcast_ref::<String>().cloned()).unwrap_or_else(|| "unknown panic".to_string());
+				Err(Failed(500, format!("Internal error: {}", message)))
+			});
+			let (status, body) = match handled {
+				Ok(body) => (200, body),
+				Err(Failed(status, message)) => {
+					log::warn!("{} {}: {}", request.method(), request.url(), message);
+					(status, serde_json::json!({ "error": message }).to_string())
+				},
+			};
+			let response = Response::from_string(body).with_status_code(status).with_header(Header::from_bytes("Content-Type", "application/json").unwrap());
+			if let Err(e) = request.respond(response) {
+				log::warn!("Failed to respond: {}", e);
+			}
+		});
+	}
+}
+
+#[cfg(test)]
+mod test {
+	use super::*;
+	use std::io::{Read, Write};
+
+	/// Serves a 2 nodes road graph on a free port, to the clients if any
+	///
+	/// Returns: the port
+	fn start(clients: Option<auth::Clients>) -> u16 {
+		let roads: RoadGraph = serde_json::from_value(serde_json::json!({
+			"nodes": [{ "id": "a", "coordinates": [-73.6, 45.5] }, { "id": "b", "coordinates": [-73.6, 45.501] }],
+			"roads": [{ "p1": "a", "p2": "b", "directed": false, "sidewalks": [true, true], "distance": 111.0 }],
+		})).unwrap();
+		let server = tiny_http::Server::http("127.0.0.1:0").unwrap();
+		let port = server.server_addr().to_ip().unwrap().port();
+		std::thread::spawn(move || listen(server, roads, clients, None));
+		port
+	}
+
+	/// Sends a request, with an API key if any
+	///
+	/// Returns: the status code and body of the response
+	fn request_as(key: Option<&str>, port: u16, method: &str, url: &str, body: &str) -> (u16, String) {
+		let mut stream = std::net::TcpStream::connect(("127.0.0.1", port)).unwrap();
+		let key = key.map(|k| format!("X-Api-Key: {}\r\n", k)).unwrap_or_default();
+		write!(stream, "{} {} HTTP/1.1\r\nHost: localhost\r\n{}Content-Length: {}\r\nConnection: close\r\n\r\n{}", method, url, key, body.len(), body).unwrap();
+		let mut response = String::new();
+		stream.read_to_string(&mut response).unwrap();
+		let status = response.split(' ').nth(1).unwrap().parse().unwrap();
+		(status, response.split("\r\n\r\n").nth(1).unwrap_or_default().to_string())
+	}
+
+	/// Sends a request without API key
+	fn request(port: u16, method: &str, url: &str, body: &str) -> (u16, String) {
+		request_as(None, port, method, url, body)
+	}
+
+	#[test]
+	fn infeasible_plow_keeps_serving() {
+		let port = start(None);
+		let plow = serde_json::json!({
+			"snow": [{ "p1": "a", "p2": "b", "depth": 50 }],
+			"vehicles": { "road": ["a"], "sidewalk": [] },
+			"meta": { "seed": 1, "max_tour_cost": 0.001, "fail_over_cap": true, "annealing": { "main_iterations": 2 } },
+		});
+		let (status, body) = request(port, "POST", "/plow", &plow.to_string());
+		assert_eq!(status, 422, "{}", body);
+		assert!(body.contains("max_tour_cost"), "{}", body);
+		let mut plow = plow;
+		plow["meta"]["annealing"]["max_wall_time"] = serde_json::json!(0.0);
+		let (status, body) = request(port, "POST", "/plow", &plow.to_string());
+		assert_eq!(status, 504, "{}", body);
+		assert!(body.contains("max_wall_time"), "{}", body);
+		let (status, body) = request(port, "POST", "/plow", "{ not json");
+		assert_eq!(status, 400, "{}", body);
+		let (status, body) = request(port, "GET", "/health", "");
+		assert_eq!(status, 200, "{}", body);
+		assert!(body.contains(r#""segments":1"#), "{}", body);
+	}
+
+	#[test]
+	fn plows_and_merges() {
+		let port = start(None);
+		let plow = serde_json::json!({
+			"snow": [{ "p1": "a", "p2": "b", "depth": 50 }],
+			"vehicles": { "road": ["a"], "sidewalk": [] },
+			"meta": { "seed": 1, "annealing": { "main_iterations": 2 } },
+		});
+		let (status, body) = request(port, "POST", "/plow", &plow.to_string());
+		assert_eq!(status, 200, "{}", body);
+		let paths: document::Document<Paths> = serde_json::from_str(&body).unwrap();
+		assert_eq!((paths.version, paths.kind.as_str()), (document::VERSION, "paths"));
+		let paths = paths.data;
+		assert_eq!(paths.len(), 1);
+		assert!(paths[0].iter().any(|s| s.node == "b"), "{}", body);
+		let merge = serde_json::json!({
+			"snows": [[{ "p1": "a", "p2": "b", "depth": 10 }], [{ "p1": "a", "p2": "b", "depth": 30 }]],
+			"strategy": "max",
+		});
+		let (status, body) = request(port, "POST", "/snows/merge", &merge.to_string());
+		assert_eq!(status, 200, "{}", body);
+		let merged: document::Document<SnowStatuses> = serde_json::from_str(&body).unwrap();
+		assert_eq!(merged.data.iter().map(|s| s.depth.raw()).collect::<Vec<_>>(), vec![30.0]);
+		let (status, body) = request(port, "POST", "/snows/merge", &serde_json::json!({ "snows": [], "strategy": "nope" }).to_string());
+		assert_eq!(status, 400, "{}", body);
+	}
+
+	#[test]
+	fn routes() {
+		let port = start(None);
+		assert_eq!(request(port, "GET", "/nope", "").0, 404);
+		assert_eq!(request(port, "GET", "/plow", "").0, 405);
+		assert_eq!(request(port, "POST", "/health", "").0, 405);
+		assert_eq!(request(port, "GET", "/health?verbose", "").0, 200);
+	}
+
+	#[test]
+	fn authenticates_clients() {
+		let port = start(Some(vec![auth::Client { name: "roads".to_string(), key: "secret".to_string(), max_jobs: 1 }]));
+		let merge = serde_json::json!({ "snows": [[{ "p1": "a", "p2": "b", "depth": 10 }]] }).to_string();
+		let (status, body) = request(port, "POST", "/snows/merge", &merge);
+		assert_eq!(status, 401, "{}", body);
+		assert_eq!(request_as(Some("wrong"), port, "POST", "/snows/merge", &merge).0, 401);
+		let (status, body) = request_as(Some("secret"), port, "POST", "/snows/merge", &merge);
+		assert_eq!(status, 200, "{}", body);
+		// jobs are over when answered, the quota being available again
+		assert_eq!(request_as(Some("secret"), port, "POST", "/snows/merge", &merge).0, 200);
+		assert_eq!(request(port, "GET", "/health", "").0, 200);
+	}
+}
@@ -0,0 +1,121 @@
+//! Merging of snow statuses sampled by several sources into one, segment by segment, by a [`MergeStrategy`]
+
+use crate::*;
+use data::{SnowStatusElement, SnowStatuses};
+
+/// Merges the samples of a road segment into one
+pub trait MergeStrategy {
+	/// Merges samples
+	///
+	/// Arguments:
+	/// - `samples`: samples of the segment, in input order (at least one)
+	/// - `latest`: latest observation time of all samples, of all segments
+	///
+	/// Returns: the merged sample
+	fn merge(&self, samples: &[SnowStatusElement], latest: Option<f64>) -> SnowStatusElement;
+}
+
+/// Names of the strategies, as given to [`strategy`]
+pub const STRATEGIES: &[&str] = &["mean", "max", "latest", "weighted"];
+
+/// Instantiates a strategy by its name
+///
+/// Arguments:
+/// - `name`: one of [`STRATEGIES`]
+/// - `accumulation`: snow accumulation rate since observations, in mm/h, for the `latest` strategy
+/// - `at`: time to extrapolate depths to, in seconds since epoch, for the `latest` strategy (the latest observation if not given)
+pub fn strategy(name: &str, accumulation: N64, at: Option<f64>) -> Option<Box<dyn MergeStrategy>> {
+	match name {
+		"mean" => Some(Box::new(Mean)),
+		"max" => Some(Box::new(Max)),
+		"latest" => Some(Box::new(Latest { accumulation, at })),
+		"weighted" => Some(Box::new(Weighted)),
+		_ => None,
+	}
+}
+
+/// Merges snow samplings, by a strategy merging the samples of each road segment
+pub fn merge(snows: impl Iterator<Item = SnowStatusElement>, strategy: &dyn MergeStrategy) -> SnowStatuses {
+	let mut keyed: indexmap::IndexMap<_, Vec<SnowStatusElement>> = indexmap::IndexMap::new();
+	for s in snows {
+		keyed.entry((s.p1.clone(), s.p2.clone(), s.discriminator.clone())).or_default().push(s);
+	}
+	let latest = keyed.values().flatten().filter_map(|s| s.observed).max_by(f64::total_cmp);
+	keyed.values().map(|samples| strategy.merge(samples, latest)).collect()
+}
+
+/// Merged sample of a segment, observed at the latest of its samples
+fn merged(samples: &[SnowStatusElement], depth: N64, probability: Option<N64>, variance: Option<N64>) -> SnowStatusElement {
+	let s = &samples[0];
+	SnowStatusElement {
+		p1: s.p1.clone(),
+		p2: s.p2.clone(),
+		discriminator: s.discriminator.clone(),
+		depth,
+		probability,
+		variance,
+		observed: samples.iter().filter_map(|s| s.observed).max_by(f64::total_cmp),
+		confidence: None,
+	}
+}
+
+/// Samples with snow win over those without, and their depths are averaged - so are their uncertainties (probabilities, and variances of the average)
+#[derive(Clone, Copy, Debug, Default)]
+pub struct Mean;
+impl MergeStrategy for Mean {
+	fn merge(&self, samples: &[SnowStatusElement], _latest: Option<f64>) -> SnowStatusElement {
+		let snowy: Vec<_> = samples.iter().filter(|s| s.depth > 0.0).collect();
+		if snowy.is_empty() {
+			return merged(samples, n64(0.0), None, None);
+		}
+		let n = n64(snowy.len() as f64);
+		let mean = |f: fn(&SnowStatusElement) -> Option<N64>, none: f64, div: N64| snowy.iter().any(|s| f(s).is_some()).then(|| snowy.iter().map(|s| f(s).unwrap_or_else(|| n64(none))).sum::<N64>() / div);
+		merged(samples, snowy.iter().map(|s| s.depth).sum::<N64>() / n, mean(|s| s.probability, 1.0, n), mean(|s| s.variance, 0.0, n * n))
+	}
+}
+
+/// The deepest sample wins
+#[derive(Clone, Copy, Debug, Default)]
+pub struct Max;
+impl MergeStrategy for Max {
+	fn merge(&self, samples: &[SnowStatusElement], _latest: Option<f64>) -> SnowStatusElement {
+		let deepest = samples.iter().fold(&samples[0], |a, s| if s.depth > a.depth { s } else { a });
+		merged(samples, deepest.depth, deepest.probability, deepest.variance)
+	}
+}
+
+/// The latest samples win (any observation time over none), merged by [`Mean`] if several, and their depth is extrapolated to a time, snow accumulating at a rate since they were observed
+#[derive(Clone, Copy, Debug)]
+pub struct Latest {
+	/// accumulation rate, in mm/h
+	pub accumulation: N64,
+	/// time to extrapolate to, in seconds since epoch (the latest observation of all segments if not given)
+	pub at: Option<f64>,
+}
+impl MergeStrategy for Latest {
+	fn merge(&self, samples: &[SnowStatusElement], latest: Option<f64>) -> SnowStatusElement {
+		let last = samples.iter().map(|s| s.observed).max_by(|a, b| a.partial_cmp(b).unwrap_or(std::cmp::Ordering::Equal)).flatten();
+		let samples: Vec<_> = samples.iter().filter(|s| s.observed == last).cloned().collect();
+		let mut s = Mean.merge(&samples, latest);
+		if let (Some(observed), Some(at)) = (s.observed, self.at.or(latest)) {
+			s.depth += self.accumulation * ((at - observed).max(0.0) / 3600.0);
+			s.observed = Some(at);
+		}
+		s
+	}
+}
+
+/// Depths are averaged, weighted by the confidence in the source of each sample (1 if not given) - so are their uncertainties
+#[derive(Clone, Copy, Debug, Default)]
+pub struct Weighted;
+impl MergeStrategy for Weighted {
+	fn merge(&self, samples: &[SnowStatusElement], _latest: Option<f64>) -> SnowStatusElement {
+		let weight = |s: &SnowStatusElement| s.confidence.filter(|c| *c >= 0.0).unwrap_or_else(|| n64(1.0));
+		let total = samples.iter().map(weight).sum::<N64>();
+		if total <= 0.0 {
+			return Mean.merge(samples, None);
+		}
+		let mean = |f: fn(&SnowStatusElement) -> Option<N64>, none: f64, square: bool| samples.iter().any(|s| f(s).is_some()).then(|| samples.iter().map(|s| f(s).unwrap_or_else(|| n64(none)) * if square { weight(s) * weight(s) } else { weight(s) }).sum::<N64>() / if square { total * total } else { total });
+		merged(samples, samples.iter().map(|s| s.depth * weight(s)).sum::<N64>() / total, mean(|s| s.probability, 1.0, false), mean(|s| s.variance, 0.0, true))
+	}
+}
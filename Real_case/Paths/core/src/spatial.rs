@@ -0,0 +1,97 @@
+//! Spatial index of items at geographical coordinates, for finding the closest one without scanning them all
+
+use crate::*;
+use meta::Metric;
+use rstar::{RTree, RTreeObject, AABB, PointDistance, primitives::PointWithData};
+
+/// Items at `(lon, lat)` coordinates, indexed for nearest-item queries as per a metric
+///
+/// Coordinates are embedded so that the straight distance between embeddings orders items as the metric does: as they are for the euclidean metric, on the unit sphere for the geodesic one (chords growing with arcs).
+pub struct Nearest<T> {
+	metric: Metric,
+	/// items, with their index in the order they were given
+	tree: RTree<PointWithData<(usize, T), [f64; 3]>>,
+}
+
+impl<T> Nearest<T> {
+	/// Indexes items, in one go
+	pub fn new(metric: Metric, items: impl IntoIterator<Item = ((f64, f64), T)>) -> Self {
+		Self {
+			metric,
+			tree: RTree::bulk_load(items.into_iter().enumerate().map(|(i, (c, t))| PointWithData::new((i, t), embed(metric, c))).collect()),
+		}
+	}
+	/// Closest item to coordinates, if any
+	pub fn nearest(&self, c: (f64, f64)) -> Option<&T> {
+		self.nearest_where(c, |_| true)
+	}
+	/// Closest item to coordinates among those satisfying a predicate, if any - the first one given, among equally close ones
+	pub fn nearest_where(&self, c: (f64, f64), predicate: impl Fn(&T) -> bool) -> Option<&T> {
+		let c = embed(self.metric, c);
+		let mut candidates = self.tree.nearest_neighbor_iter(&c).filter(|p| predicate(&p.data.1));
+		let first = candidates.next()?;
+		let d = first.distance_2(&c);
+		Some(&candidates.take_while(|p| p.distance_2(&c) <= d).fold(first, |a, b| if b.data.0 < a.data.0 { b } else { a }).data.1)
+	}
+}
+
+/// Embeds `(lon, lat)` coordinates as per a metric
+fn embed(metric: Metric, (lon, lat): (f64, f64)) -> [f64; 3] {
+	match metric {
+		Metric::Euclidean => [lon, lat, 0.0],
+		Metric::Geodesic => {
+			let (lon, lat) = (lon.to_radians(), lat.to_radians());
+			[lat.cos() * lon.cos(), lat.cos() * lon.sin(), lat.sin()]
+		},
+	}
+}
+
+/// Items at `(lon, lat)` coordinates, indexed for bounding box queries
+pub struct Within<T> {
+	tree: RTree<PointWithData<T, [f64; 2]>>,
+}
+
+impl<T> Within<T> {
+	/// Indexes items, in one go
+	pub fn new(items: impl IntoIterator<Item = ((f64, f64), T)>) -> Self {
+		Self {
+			tree: RTree::bulk_load(items.into_iter().map(|((lon, lat), t)| PointWithData::new(t, [lon, lat])).collect()),
+		}
+	}
+	/// Items within a bounding box, given by its south-west and north-east corners
+	pub fn rect(&self, min: (f64, f64), max: (f64, f64)) -> impl Iterator<Item = &T> {
+		self.tree.locate_in_envelope(&rstar::AABB::from_corners([min.0, min.1], [max.0, max.1])).map(|p| &p.data)
+	}
+}
+
+/// Items spanning line segments, indexed for queries of those near a point
+pub struct Segments<T> {
+	tree: RTree<Spanning<T>>,
+}
+
+/// Item spanning a line segment, bounded by the segment's box
+struct Spanning<T> {
+	a: [f64; 2],
+	b: [f64; 2],
+	data: T,
+}
+
+impl<T> RTreeObject for Spanning<T> {
+	type Envelope = AABB<[f64; 2]>;
+	fn envelope(&self) -> Self::Envelope {
+		AABB::from_corners(self.a, self.b)
+	}
+}
+
+impl<T> Segments<T> {
+	/// Indexes items, given by the ends of their segment, in one go
+	pub fn new(items: impl IntoIterator<Item = (((f64, f64), (f64, f64)), T)>) -> Self {
+		Self {
+			tree: RTree::bulk_load(items.into_iter().map(|((a, b), data)| Spanning { a: [a.0, a.1], b: [b.0, b.1], data }).collect()),
+		}
+	}
+	/// Items whose segment may be within a distance of a point: all those that are, and some more, as the box around their segment is
+	pub fn near(&self, p: (f64, f64), distance: f64) -> impl Iterator<Item = &T> {
+		self.tree.locate_in_envelope_intersecting(&AABB::from_corners([p.0 - distance, p.1 - distance], [p.0 + distance, p.1 + distance])).map(|s| &s.data)
+	}
+}
@@ -0,0 +1,35 @@
+//! Import of raw vehicle GPS (AVL) traces
+
+use crate::*;
+use data::*;
+use graph::mapmatch::{self, Matching};
+
+use indexmap::IndexSet;
+
+/// Infers cleared road segments from GPS traces of plowing vehicles.
+///
+/// Each trace is map matched to the roads (see [`graph::mapmatch`]), fixes within `tolerance` meters of roads, with `sigma` meters of GPS noise; every segment along the paths matched from at least `fixes` fixes is considered cleared.
+///
+/// Returns: the "cleared" snow status layer (0 depth for every cleared segment)
+pub fn cleared(roads: &IndexedRoadGraph, traces: &GpsTraces, tolerance: f64, sigma: f64, fixes: usize) -> SnowStatuses {
+	let (g, roads) = (&roads.light, &roads.roads);
+	let m = Matching { radius: tolerance, sigma, points: fixes, ..Default::default() };
+	let mut cleared = IndexSet::new();
+	for trace in traces {
+		let points: Vec<_> = trace.iter().map(|fix| fix.coordinates).collect();
+		for path in mapmatch::match_points::<_, _, _, _, true>(g, &points, |e| roads.roads[e.index].distance.raw(), &m) {
+			cleared.extend(path.into_iter().map(|e| &roads.roads[e.index]).map(|r| segment_key(&r.p1, &r.p2, &r.discriminator)));
+		}
+	}
+	log::info!("Matched {} cleared segments", cleared.len());
+	cleared.into_iter().map(|(p1, p2, discriminator)| SnowStatusElement {
+		p1,
+		p2,
+		discriminator,
+		depth: n64(0.0),
+		probability: None,
+		variance: None,
+		observed: None,
+		confidence: None,
+	}).collect()
+}
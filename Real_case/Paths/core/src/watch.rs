@@ -0,0 +1,109 @@
+//! Directory watch driver, for continuous snow status merging
+//!
+//! Polls a directory for newly dropped snow status files, merges them into the ledger as they arrive, and moves them to `{dir}/merged/` so that they are merged exactly once, even across restarts.
+//! Files that can't be read are moved to `{dir}/rejected/` instead, and watching goes on.
+//! Once the directory has been quiet for a while after a merge, road plowing can optionally be re-solved on the updated ledger.
+
+use crate::*;
+use data::*;
+
+use std::{path::{Path, PathBuf}, time::{Duration, Instant, SystemTime}};
+
+/// Re-solve triggered after merging
+#[derive(Clone, Debug)]
+pub struct Resolve<'a> {
+	pub vehicles: VehiclesConfiguration,
+	pub params: meta::Parameters,
+	pub overlay: CostOverlay,
+	/// where the paths are written
+	pub output: &'a Path,
+}
+
+/// Watch settings
+#[derive(Clone, Debug)]
+pub struct Watch<'a> {
+	/// directory where snow status files are dropped
+	pub dir: &'a Path,
+	/// merged snow status, updated as files arrive
+	pub ledger: &'a Path,
+	/// how long the directory must be quiet after a merge before re-solving
+	pub quiet: Duration,
+	/// how often the directory is polled
+	pub poll: Duration,
+	pub resolve: Option<Resolve<'a>>,
+}
+
+/// Lists the snow status files that are ready to be merged - JSONs that haven't been modified for a poll period (so as not to catch them half-written)
+fn arrived(settings: &Watch) -> std::io::Result<Vec<PathBuf>> {
+	let mut files = Vec::new();
+	for entry in std::fs::read_dir(settings.dir)? {
+		let entry = entry?;
+		let path = entry.path();
+		if !entry.file_type()?.is_file() || path.extension().is_none_or(|e| e != "json") || settings.ledger.canonicalize().is_ok_and(|l| path.canonicalize().is_ok_and(|p| p == l)) {
+			continue;
+		}
+		let age = SystemTime::now().duration_since(entry.metadata()?.modified()?).unwrap_or_default();
+		if age >= settings.poll {
+			files.push(path);
+		}
+	}
+	files.sort();
+	Ok(files)
+}
+
+/// Watches the directory forever, merging snow statuses into the ledger as they arrive (and re-solving after quiet periods)
+///
+/// Streamed road graphs are streamed again for every re-solve, and only read whole once a GeoJSON snow status needs matching to their segments.
+pub fn run(roads: impl Into<Roads>, settings: &Watch) -> std::io::Result<()> {
+	let mut roads = roads.into();
+	let merged = settings.dir.join("merged");
+	let rejected = settings.dir.join("rejected");
+	std::fs::create_dir_all(&merged)?;
+	std::fs::create_dir_all(&rejected)?;
+	let mut ledger: SnowStatuses = if settings.ledger.exists() { document::read(settings.ledger, "Ledger")? } else { Vec::new() };
+	let mut last_merge: Option<Instant> = None;
+	log::info!("Watching {} ({} segments in ledger)", settings.dir.display(), ledger.len());
+	loop {
+		let files = arrived(settings)?;
+		if !files.is_empty() {
+			let mut snu = Vec::new();
+			let mut accepted = Vec::new();
+			for f in files {
+				match document::read_any(&f, "Snow status") {
+					Ok(SnuwDapg::Formal(s)) => snu.extend(s),
+					Ok(SnuwDapg::Geo(feat)) => snu.extend(gj::geofeatures_to_snow(roads.graph().map_err(std::io::Error::other)?, feat)),
+					Err(e) => {
+						log::error!("Rejected {}", e);
+						std::fs::rename(&f, rejected.join(f.file_name().unwrap()))?;
+						continue;
+					},
+				}
+				accepted.push(f);
+			}
+			if !accepted.is_empty() {
+				ledger = snow::merge(ledger.into_iter().chain(snu), &snow::Mean);
+				// the ledger is written before the files are moved away, so that a crash in between merges them again rather than losing them
+				document::write(settings.ledger, &ledger)?;
+				for f in &accepted {
+					std::fs::rename(f, merged.join(f.file_name().unwrap()))?;
+				}
+				log::info!("Merged {} files, {} segments in ledger", accepted.len(), ledger.len());
+				last_merge = Some(Instant::now());
+			}
+		}
+		if let (Some(resolve), Some(at)) = (&settings.resolve, last_merge) {
+			if at.elapsed() >= settings.quiet {
+				last_merge = None;
+				log::info!("Quiet for {}s, re-solving", settings.quiet.as_secs());
+				match plow::road::solve(roads.clone(), ledger.clone(), Vec::new(), None, &resolve.overlay, &[], None, &[], resolve.vehicles.clone(), &resolve.params) {
+					Ok((paths, _)) => {
+						document::write(resolve.output, &paths)?;
+						log::info!("Wrote paths to {}", resolve.output.display());
+					},
+					Err(e) => log::error!("Re-solve failed: {}", e),
+				}
+			}
+		}
+		std::thread::sleep(settings.poll);
+	}
+}
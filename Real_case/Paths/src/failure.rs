@@ -0,0 +1,114 @@
+//! Machine-readable failures and stable exit codes, so that orchestration pipelines can branch on failures
+//!
+//! Errors become failures at the boundary of the CLI, as do panics - those are internal errors.
+//! [`Failure::report`] logs the failure and writes it as JSON if asked to with `--error-json`, then the CLI exits with its [code](Kind::exit_code).
+
+use paths_core::error::Error;
+use std::{path::{Path, PathBuf}, sync::Mutex};
+use serde::*;
+
+/// What went wrong
+#[derive(Serialize, Deserialize, Clone, Copy, PartialEq, Eq, Debug)]
+pub enum Kind {
+	/// an input file is missing or invalid
+	BadInput,
+	/// the problem has no solution, e.g. snowy segments no vehicle can reach
+	Infeasible,
+	/// the solve ran out of time before finding a feasible solution
+	Timeout,
+	/// a bug
+	Internal,
+}
+impl Kind {
+	/// Process exit code of the failure
+	pub fn exit_code(self) -> i32 {
+		match self {
+			Self::BadInput => 2,
+			Self::Infeasible => 3,
+			Self::Timeout => 4,
+			Self::Internal => 70,
+		}
+	}
+}
+
+/// Failure, as written with `--error-json`
+#[derive(Serialize, Deserialize, Clone, PartialEq, Debug)]
+pub struct Failure {
+	pub kind: Kind,
+	pub message: String,
+	/// offending file, if any
+	#[serde(default, skip_serializing_if = "Option::is_none")]
+	pub file: Option<String>,
+	/// offending node or segment ids, if any
+	#[serde(default, skip_serializing_if = "Vec::is_empty")]
+	pub ids: Vec<String>,
+}
+
+impl Failure {
+	pub fn bad_input(file: impl AsRef<Path>, message: impl ToString) -> Self {
+		Self { kind: Kind::BadInput, message: message.to_string(), file: Some(file.as_ref().to_string_lossy().into_owned()), ids: Vec::new() }
+	}
+	pub fn infeasible(message: impl ToString, ids: Vec<String>) -> Self {
+		Self { kind: Kind::Infeasible, message: message.to_string(), file: None, ids }
+	}
+	/// Logs the failure, and writes it as JSON where asked to with [`report_to`]
+	pub fn report(&self) {
+		log::error!("{:?}: {}", self.kind, self);
+		if let Some(file) = ERROR_JSON.lock().ok().and_then(|f| f.clone()) {
+			if let Err(e) = std::fs::File::create(&file).map_err(|e| e.to_string()).and_then(|f| serde_json::to_writer_pretty(f, self).map_err(|e| e.to_string())) {
+				log::error!("Failed to write {}: {}", file.display(), e);
+			}
+		}
+	}
+}
+
+impl std::fmt::Display for Failure {
+	fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+		write!(f, "{}", self.message)?;
+		if let Some(file) = &self.file {
+			write!(f, " (in {})", file)?;
+		}
+		Ok(())
+	}
+}
+
+impl From<std::io::Error> for Failure {
+	fn from(e: std::io::Error) -> Self {
+		use std::io::ErrorKind::*;
+		let kind = match e.kind() {
+			NotFound | PermissionDenied | InvalidInput | InvalidData | UnexpectedEof => Kind::BadInput,
+			TimedOut => Kind::Timeout,
+			_ => Kind::Internal,
+		};
+		Self { kind, message: e.to_string(), file: None, ids: Vec::new() }
+	}
+}
+
+impl From<Error> for Failure {
+	fn from(e: Error) -> Self {
+		match e {
+			Error::Io { file, source } => Self { file, ..Failure::from(source) },
+			Error::Parse { file, line, column, message } if line > 0 => Self::bad_input(file, format!("{} (at line {} column {})", message, line, column)),
+			Error::Parse { file, message, .. } | Error::Validation { file: Some(file), message } => Self::bad_input(file, message),
+			Error::Validation { file: None, message } => Self { kind: Kind::BadInput, message, file: None, ids: Vec::new() },
+			Error::Solver { message, ids } => Self::infeasible(message, ids),
+			Error::Timeout { message } => Self { kind: Kind::Timeout, message, file: None, ids: Vec::new() },
+		}
+	}
+}
+
+/// Where the failure gets written, if anywhere
+static ERROR_JSON: Mutex<Option<PathBuf>> = Mutex::new(None);
+
+/// Writes failures as JSON to a file
+pub fn report_to(file: PathBuf) {
+	*ERROR_JSON.lock().unwrap() = Some(file);
+}
+
+/// The internal failure of a caught panic, as per its payload
+pub fn of_panic(payload: &(dyn std::any::Any + Send)) -> Failure {
+	let message = payload.downcast_ref::<&str>().map(|s| s.to_string())
+		.or_else(|| payload.downcast_ref::<String>().cloned())
+		.unwrap_or_else(|| "unknown panic".to_string());
+	Failure { kind: Kind::Internal, message, file: None, ids: Vec::new() }
+}
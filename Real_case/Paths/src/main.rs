@@ -2,18 +2,10 @@
 //!
 //! Universal CLI for solving flight & plow problems, as well as converting spec'd data to/from GeoJSON.
 
-use std::borrow::Cow;
+use clap::{App, AppSettings, Arg, SubCommand, crate_version};
+use paths_core::*;
 
-use clap::{App, Arg, SubCommand, crate_version};
-mod data;
-mod graph;
-mod meta;
-mod plow;
-mod gj;
-pub use try_all::{TryAll, TryMapAll};
-pub use noisy_float::prelude::{N64, n64, Float};
-
-pub type NodeId = Cow<'static, str>;
+mod failure;
 
 #[derive(serde::Serialize, serde::Deserialize, Clone, PartialEq, Debug)]
 #[serde(untagged)]
@@ -24,34 +16,204 @@ enum Wut {
 	Snow(data::SnowStatuses),
 }
 
-#[derive(serde::Serialize, serde::Deserialize, Clone, PartialEq, Debug)]
+/// Paths as produced by `plow`, bare or in a solution
+#[derive(serde::Deserialize)]
 #[serde(untagged)]
-enum SnuwDapg {
-	Formal(data::SnowStatuses),
-	Geo(geojson::FeatureCollection),
+enum Produced<P> {
+	Solution(data::Solution<P>),
+	Paths(P),
+}
+impl<P> Produced<P> {
+	fn paths(self) -> P {
+		match self {
+			Produced::Solution(solution) => solution.paths,
+			Produced::Paths(paths) => paths,
+		}
+	}
 }
 
-/// Merge snow samplings with following rules:
-/// - between a sample without snow and a sample with some snow, sampling with snow wins
-/// - depths of all samples for given road segment are averaged
-fn merge_snow_statuses(snows: impl Iterator<Item = data::SnowStatusElement>) -> data::SnowStatuses {
-	let mut keyed = indexmap::IndexMap::new();
-	for s in snows {
-		let entry = keyed.entry((s.p1, s.p2, s.discriminator)).or_insert(n64(0.0));
-		if *entry <= n64(0.0) || s.depth <= n64(0.0) {
-			*entry = std::cmp::max(*entry, s.depth);
-		} else {
-			*entry = (*entry + s.depth) / n64(2.0);
+/// Repro bundle asked for with `--export-repro`, exported with the failure should the run fail
+static REPRO: std::sync::Mutex<Option<repro::Repro>> = std::sync::Mutex::new(None);
+
+/// Prepares the repro bundle asked for with `--export-repro`, exported with the failure should the run fail
+///
+/// Arguments:
+/// - `matches`: the subcommand's arguments, with an `output`
+/// - `inputs`: names of the input file arguments, numbered in the bundle when given multiple times
+/// - `params`: meta parameters, seeded if they were not, for the bundle to tell the seed
+fn export_repro(matches: &clap::ArgMatches, inputs: &[&str], params: &mut meta::Parameters) -> Option<repro::Repro> {
+	let bundle = matches.value_of("export-repro")?;
+	let mut files = Vec::new();
+	for role in inputs {
+		let values: Vec<_> = matches.values_of(role).into_iter().flatten().collect();
+		for (i, f) in values.iter().enumerate() {
+			let role = if values.len() > 1 { format!("{}.{}", role, i) } else { role.to_string() };
+			files.push((role, std::path::PathBuf::from(f)));
+		}
+	}
+	let repro = repro::Repro { bundle: bundle.into(), inputs: files, output: matches.value_of("output").unwrap().into(), seed: Some(params.seeded()) };
+	*REPRO.lock().unwrap() = Some(repro.clone());
+	Some(repro)
+}
+
+/// Renames node ids in pairs of `input output` files of a type
+fn remap_files<T>(files: Option<clap::Values>, mapping: &data::IdMapping, what: &str) -> std::io::Result<()>
+where
+	T: serde::de::DeserializeOwned + serde::Serialize + data::RemapIds + document::Kind,
+{
+	let files: Vec<_> = files.into_iter().flatten().collect();
+	for io in files.chunks(2) {
+		let mut t: T = document::read(io[0], what)?;
+		t.remap_ids(mapping);
+		document::write(io[1], &t)?;
+		log::info!("Remapped {} {} -> {}", what, io[0], io[1]);
+	}
+	Ok(())
+}
+
+/// Parses the value of an argument, if given
+///
+/// Returns: the value, or a bad input error naming the argument if it doesn't parse
+fn parse_opt<T: std::str::FromStr>(matches: &clap::ArgMatches, name: &str) -> Result<Option<T>, error::Error>
+where
+	T::Err: std::fmt::Display,
+{
+	matches.value_of(name).map(|v| v.parse().map_err(|e| error::Error::Validation { file: None, message: format!("Invalid {} {:?}: {}", name, v, e) })).transpose()
+}
+
+/// Parses the value of an argument that is required or has a default, see [`parse_opt`]
+fn parse<T: std::str::FromStr>(matches: &clap::ArgMatches, name: &str) -> Result<T, error::Error>
+where
+	T::Err: std::fmt::Display,
+{
+	Ok(parse_opt(matches, name)?.expect("argument is required or has a default"))
+}
+
+/// Writes a GeoJSON file
+fn write_geojson(file: impl AsRef<std::path::Path>, geojson: &impl serde::Serialize) -> Result<(), error::Error> {
+	let w = std::fs::File::create(&file).map_err(|e| error::Error::io(&file, e))?;
+	serde_json::to_writer(w, geojson).map_err(|e| error::Error::io(&file, e.into()))
+}
+
+/// Writes the allocation moves of solves to an NDJSON file, a line each
+fn moves_ndjson(file: &str) -> Result<impl Fn(&progress::Move) + Send + Sync, error::Error> {
+	use std::io::Write;
+	let w = std::sync::Mutex::new(std::io::BufWriter::new(std::fs::File::create(file).map_err(|e| error::Error::io(file, e))?));
+	let file = file.to_string();
+	Ok(move |m: &progress::Move| {
+		let mut w = w.lock().unwrap();
+		if let Err(e) = serde_json::to_writer(&mut *w, m).map_err(std::io::Error::from).and_then(|_| writeln!(w)).and_then(|_| w.flush()) {
+			log::warn!("Failed to write the moves to {}: {}", file, e);
 		}
+	})
+}
+
+/// Logs what importing a road graph did to its parallel segments
+fn imported(issues: &[diagnostics::Issue]) {
+	let discriminated = issues.iter().filter(|i| matches!(i, diagnostics::Issue::Discriminated { .. })).count();
+	if discriminated > 0 {
+		log::info!("Told {} parallel segments apart with synthesized discriminators", discriminated);
+	}
+	if issues.len() > discriminated {
+		log::info!("Left out {} duplicate segments", issues.len() - discriminated);
 	}
-	keyed.into_iter().map(|((p1, p2, discriminator), depth)| data::SnowStatusElement { p1, p2, discriminator, depth }).collect()
 }
 
-fn main() -> std::io::Result<()> {
+/// Draws the progress of solves as a progress bar, on stderr
+fn progress_bar(p: &progress::Progress) {
+	const WIDTH: usize = 30;
+	let phase = match p.phase {
+		progress::Phase::Annealing => "annealing",
+		progress::Phase::Polishing => "polishing",
+		progress::Phase::Done => {
+			eprintln!();
+			return;
+		}
+	};
+	let done = if p.budget > 0 { ((p.iteration + 1) as f64 / p.budget as f64).min(1.0) } else { 1.0 };
+	let filled = (done * WIDTH as f64).round() as usize;
+	eprint!("\r{} [{}{}] {:>3.0}% {}/{} best {:.1}\x1b[K", phase, "#".repeat(filled), "-".repeat(WIDTH - filled), done * 100.0, p.iteration + 1, p.budget, p.best);
+}
+
+/// Writes the candidate solutions of annealing iterations to a CSV file, a line each
+///
+/// The header is written with the first candidate, with a `cost_{i}` column for each of its vehicles.
+fn trace_csv(file: &str) -> Result<impl Fn(&progress::Candidate) + Send + Sync, error::Error> {
+	use std::io::Write;
+	let w = std::io::LineWriter::new(std::fs::File::create(file).map_err(|e| error::Error::io(file, e))?);
+	let state = std::sync::Mutex::new((w, false));
+	let file = file.to_string();
+	Ok(move |c: &progress::Candidate| {
+		let (w, header) = &mut *state.lock().unwrap();
+		let step = match c.step {
+			progress::Step::Route => "route",
+			progress::Step::Recycle => "recycle",
+		};
+		let mut line = String::new();
+		if !*header {
+			*header = true;
+			line = format!("iteration,chain,step,temperature,value,accepted{}\n", (0..c.costs.len()).map(|i| format!(",cost_{}", i)).collect::<String>());
+		}
+		line += &format!("{},{},{},{},{},{}{}\n", c.iteration, c.chain, step, c.temperature, c.value, c.accepted, c.costs.iter().map(|c| format!(",{}", c)).collect::<String>());
+		if let Err(e) = w.write_all(line.as_bytes()) {
+			log::warn!("Failed to write the trace to {}: {}", file, e);
+		}
+	})
+}
+
+fn main() {
 	env_logger::init_from_env(env_logger::Env::default().filter_or(env_logger::DEFAULT_FILTER_ENV, "info"));
-	let matches = App::new("Flight Paths Compute")
+	let failure = match std::panic::catch_unwind(run) {
+		Ok(Ok(())) => return,
+		Ok(Err(e)) => failure::Failure::from(e),
+		Err(panic) => failure::of_panic(panic.as_ref()),
+	};
+	failure.report();
+	if let Some(repro) = REPRO.lock().ok().and_then(|r| r.clone()) {
+		if let Err(e) = repro.export(Some(failure.to_string())) {
+			log::error!("Failed to export repro bundle: {}", e);
+		}
+	}
+	std::process::exit(failure.kind.exit_code());
+}
+
+fn run() -> Result<(), error::Error> {
+	let app = App::new("Flight Paths Compute")
 							.version(crate_version!())
 							.about("Make it fly!")
+							.arg(Arg::with_name("error-json")
+									.long("error-json")
+									.takes_value(true)
+									.global(true)
+									.help("On failure, write it to this file as JSON (kind, message, offending file and ids)"))
+							.arg(Arg::with_name("seed")
+									.long("seed")
+									.takes_value(true)
+									.global(true)
+									.validator(|s| s.parse::<u64>().map(|_| ()).map_err(|e| e.to_string()))
+									.help("Seed of the random number generator, for reproducible runs (overrides the meta parameters')"))
+							.arg(Arg::with_name("progress")
+									.long("progress")
+									.takes_value(true)
+									.global(true)
+									.possible_values(&["json", "bar"])
+									.help("Report solve progress: as NDJSON lines on stdout (json), or as a progress bar on stderr (bar)"))
+							.arg(Arg::with_name("trace")
+									.long("trace")
+									.takes_value(true)
+									.global(true)
+									.help("Trace the candidate solutions of annealing iterations to this CSV file: iteration, chain, step, temperature, value, whether accepted, and the cost of each vehicle"))
+							.arg(Arg::with_name("moves")
+									.long("moves")
+									.takes_value(true)
+									.global(true)
+									.help("Record the allocation moves of segments between vehicles to this NDJSON file, for explain --moves"))
+							.arg(Arg::with_name("time-limit")
+									.long("time-limit")
+									.takes_value(true)
+									.global(true)
+									.validator(|s| s.parse::<f64>().map(|_| ()).map_err(|e| e.to_string()))
+									.help("Wall-clock budget of each solve, in minutes, after which the best solution so far is returned (overrides the meta parameters' annealing max_wall_time)"))
 							.subcommand(SubCommand::with_name("fly")
 								.about("Compute flight paths")
 								.arg(Arg::with_name("road-graph")
@@ -74,6 +236,10 @@ fn main() -> std::io::Result<()> {
 										.required(true)
 										.index(4)
 										.help("Output JSON"))
+								.arg(Arg::with_name("export-repro")
+										.long("export-repro")
+										.takes_value(true)
+										.help("Export a repro bundle (.tar.zst) of the inputs, parameters and output, for bug reports"))
 							)
 							.subcommand(SubCommand::with_name("snows")
 								.about("Merge multiple snow status updates")
@@ -88,10 +254,220 @@ fn main() -> std::io::Result<()> {
 										.index(2)
 										.help("Merged snow status output JSON"))
 								.arg(Arg::with_name("snows")
+										.takes_value(true)
+										.required_unless("from-csv")
+										.multiple(true)
+										.help("Let it snow let it snow let it go"))
+								.arg(Arg::with_name("from-csv")
+										.long("from-csv")
+										.takes_value(true)
+										.multiple(true)
+										.number_of_values(1)
+										.requires("sensors")
+										.help("Sensor dump CSV, of segment_id,depth_cm,timestamp lines"))
+								.arg(Arg::with_name("sensors")
+										.long("sensors")
+										.takes_value(true)
+										.help("Segments observed by each sensor of the CSV dumps"))
+								.arg(Arg::with_name("cleared")
+										.short("c")
+										.long("cleared")
+										.takes_value(true)
+										.multiple(true)
+										.number_of_values(1)
+										.help("Cleared segments layer - overrides merged depths of listed segments"))
+								.arg(Arg::with_name("strategy")
+										.long("strategy")
+										.takes_value(true)
+										.possible_values(snow::STRATEGIES)
+										.default_value("mean")
+										.help("How samples of a segment are merged"))
+								.arg(Arg::with_name("accumulation")
+										.long("accumulation")
+										.takes_value(true)
+										.default_value("0")
+										.validator(|s| s.parse::<f64>().map(|_| ()).map_err(|e| e.to_string()))
+										.help("Snow accumulation rate since observations, in mm/h (with the latest strategy)"))
+								.arg(Arg::with_name("at")
+										.long("at")
+										.takes_value(true)
+										.validator(|s| s.parse::<f64>().map(|_| ()).map_err(|e| e.to_string()))
+										.help("Time to extrapolate depths to, in seconds since epoch, with the latest strategy (the latest observation by default)")))
+							.subcommand(SubCommand::with_name("cleared")
+								.about("Infer cleared segments from plowing vehicles GPS traces")
+								.arg(Arg::with_name("road-graph")
+										.takes_value(true)
+										.required(true)
+										.index(1)
+										.help("Road Graph JSON"))
+								.arg(Arg::with_name("traces")
+										.takes_value(true)
+										.required(true)
+										.index(2)
+										.help("GPS traces JSON"))
+								.arg(Arg::with_name("output")
+										.takes_value(true)
+										.required(true)
+										.index(3)
+										.help("Cleared snow status layer output JSON"))
+								.arg(Arg::with_name("tolerance")
+										.long("tolerance")
+										.takes_value(true)
+										.default_value("20")
+										.validator(|s| s.parse::<f64>().map(|_| ()).map_err(|e| e.to_string()))
+										.help("Maximum distance of a GPS fix from the road, in meters"))
+								.arg(Arg::with_name("sigma")
+										.long("sigma")
+										.takes_value(true)
+										.default_value("10")
+										.validator(|s| s.parse::<f64>().map(|_| ()).map_err(|e| e.to_string()))
+										.help("Standard deviation of the GPS noise, in meters"))
+								.arg(Arg::with_name("fixes")
+										.long("fixes")
+										.takes_value(true)
+										.default_value("2")
+										.validator(|s| s.parse::<usize>().map(|_| ()).map_err(|e| e.to_string()))
+										.help("Minimum number of GPS fixes a path must be matched from for its segments to be cleared")))
+							.subcommand(SubCommand::with_name("snap")
+								.about("Merge near-duplicate nodes of a road graph")
+								.arg(Arg::with_name("road-graph")
+										.takes_value(true)
+										.required(true)
+										.index(1)
+										.help("Road Graph JSON"))
+								.arg(Arg::with_name("output")
 										.takes_value(true)
 										.required(true)
+										.index(2)
+										.help("Snapped Road Graph output JSON"))
+								.arg(Arg::with_name("tolerance")
+										.long("tolerance")
+										.takes_value(true)
+										.default_value("1")
+										.validator(|s| s.parse::<f64>().map(|_| ()).map_err(|e| e.to_string()))
+										.help("Maximum distance between merged nodes, in meters"))
+								.arg(Arg::with_name("report")
+										.long("report")
+										.takes_value(true)
+										.help("Id mapping report output JSON - merged node id to kept node id")))
+							.subcommand(SubCommand::with_name("remap-ids")
+								.about("Rename node ids across a road graph and the files depending on it")
+								.arg(Arg::with_name("mapping")
+										.takes_value(true)
+										.required(true)
+										.index(1)
+										.help("Id mapping JSON - old node id to new node id"))
+								.arg(Arg::with_name("road-graph")
+										.short("g")
+										.long("road-graph")
+										.takes_value(true)
+										.multiple(true)
+										.number_of_values(2)
+										.value_names(&["input", "output"])
+										.help("Road Graph to remap"))
+								.arg(Arg::with_name("snow")
+										.short("s")
+										.long("snow")
+										.takes_value(true)
+										.multiple(true)
+										.number_of_values(2)
+										.value_names(&["input", "output"])
+										.help("Snow status to remap"))
+								.arg(Arg::with_name("vehicles")
+										.short("v")
+										.long("vehicles")
+										.takes_value(true)
+										.multiple(true)
+										.number_of_values(2)
+										.value_names(&["input", "output"])
+										.help("Vehicles configuration to remap"))
+								.arg(Arg::with_name("paths")
+										.short("p")
+										.long("paths")
+										.takes_value(true)
+										.multiple(true)
+										.number_of_values(2)
+										.value_names(&["input", "output"])
+										.help("Paths to remap"))
+								.arg(Arg::with_name("sidewalk-paths")
+										.short("w")
+										.long("sidewalk-paths")
+										.takes_value(true)
+										.multiple(true)
+										.number_of_values(2)
+										.value_names(&["input", "output"])
+										.help("Sidewalk paths to remap"))
+								.arg(Arg::with_name("tasks")
+										.long("tasks")
+										.takes_value(true)
 										.multiple(true)
-										.help("Let it snow let it snow let it go")))
+										.number_of_values(2)
+										.value_names(&["input", "output"])
+										.help("Node tasks to remap")))
+							.subcommand(SubCommand::with_name("validate")
+								.about("Diagnose issues in a road graph, and the inputs going with it")
+								.arg(Arg::with_name("road-graph")
+										.takes_value(true)
+										.required(true)
+										.index(1)
+										.help("Road Graph JSON"))
+								.arg(Arg::with_name("output")
+										.takes_value(true)
+										.index(2)
+										.help("Issues report output JSON (stdout if not specified)"))
+								.arg(Arg::with_name("snow")
+										.long("snow")
+										.takes_value(true)
+										.help("Snow Status JSON to check against the road graph"))
+								.arg(Arg::with_name("vehicles")
+										.long("vehicles")
+										.takes_value(true)
+										.help("Vehicles Configuration JSON to check against the road graph"))
+								.arg(Arg::with_name("meta")
+										.long("meta")
+										.takes_value(true)
+										.help("Meta parameters YAML to check, along with its cost expression on the road graph"))
+								.arg(Arg::with_name("multipliers")
+										.short("m")
+										.long("multipliers")
+										.takes_value(true)
+										.help("Cost multipliers overlay JSON to check")))
+							.subcommand(SubCommand::with_name("repair-graph")
+								.about("Repair issues in a road graph, with the suggested resolutions")
+								.arg(Arg::with_name("road-graph")
+										.takes_value(true)
+										.required(true)
+										.index(1)
+										.help("Road Graph JSON"))
+								.arg(Arg::with_name("output")
+										.takes_value(true)
+										.required(true)
+										.index(2)
+										.help("Repaired Road Graph output JSON"))
+								.arg(Arg::with_name("report")
+										.long("report")
+										.takes_value(true)
+										.help("Repaired issues report output JSON"))
+								.arg(Arg::with_name("discriminate")
+										.long("discriminate")
+										.takes_value(false)
+										.help("Tell distinct parallel segments without discriminators apart, with synthesized discriminators, instead of merging them")))
+							.subcommand(SubCommand::with_name("osm")
+								.about("Import a road graph from an OpenStreetMap extract")
+								.arg(Arg::with_name("extract")
+										.takes_value(true)
+										.required(true)
+										.index(1)
+										.help("OpenStreetMap extract, .osm.pbf or .osm (XML)"))
+								.arg(Arg::with_name("output")
+										.takes_value(true)
+										.required(true)
+										.index(2)
+										.help("Road Graph output JSON"))
+								.arg(Arg::with_name("report")
+										.long("report")
+										.takes_value(true)
+										.help("Discriminated and duplicate segments report output JSON")))
 							.subcommand(SubCommand::with_name("plow")
 								.about("Plow dat snow!")
 								.arg(Arg::with_name("road-graph")
@@ -128,85 +504,1547 @@ fn main() -> std::io::Result<()> {
 								.arg(Arg::with_name("sidewalks")
 									.short("w")
 									.takes_value(false)
-									.help("Clean sidewalks")))
-							.subcommand(SubCommand::with_name("geojson")
-								.about("Convert anything into GeoJSONs")
+									.help("Clean sidewalks"))
+								.arg(Arg::with_name("scenario")
+										.short("s")
+										.long("scenario")
+										.takes_value(true)
+										.multiple(true)
+										.number_of_values(1)
+										.help("Alternative snow status scenario to be robust to (along with the main one)"))
+								.arg(Arg::with_name("multipliers")
+										.short("m")
+										.long("multipliers")
+										.takes_value(true)
+										.help("Cost multipliers overlay JSON - segment cost multipliers, applied on top of the road graph"))
+								.arg(Arg::with_name("region")
+										.long("region")
+										.takes_value(true)
+										.help("Region GeoJSON - only plow segments inside its polygons"))
+								.arg(Arg::with_name("buffer")
+										.long("buffer")
+										.takes_value(true)
+										.default_value("500")
+										.validator(|s| s.parse::<f64>().map(|_| ()).map_err(|e| e.to_string()))
+										.help("How far outside the region vehicles may drive, by road, in meters"))
+								.arg(Arg::with_name("traffic")
+										.long("traffic")
+										.takes_value(true)
+										.help("Travel times JSON - segment travel times (e.g. under congestion) overriding costs when deadheading, for road plowing"))
+								.arg(Arg::with_name("matrix")
+										.long("matrix")
+										.takes_value(true)
+										.help("Distance matrix JSON - node-to-node distances from a routing engine, to choose connecting legs by, for road plowing"))
+								.arg(Arg::with_name("tasks")
+										.long("tasks")
+										.takes_value(true)
+										.help("Node tasks JSON - work items at nodes (intersections, hydrants...) to service along the way, for road plowing"))
+								.arg(Arg::with_name("stops")
+										.long("stops")
+										.takes_value(true)
+										.help("Stops JSON - bus stops and crosswalks to service along the way, for sidewalk plowing"))
+								.arg(Arg::with_name("escalations")
+										.long("escalations")
+										.takes_value(true)
+										.help("Escalations output JSON - snow status of the segments deeper than any vehicle can clear"))
+								.arg(Arg::with_name("unreachable")
+										.long("unreachable")
+										.takes_value(true)
+										.help("Unreachable output JSON - snow status of the segments left out, as no vehicle able to clear them can reach them"))
+								.arg(Arg::with_name("with-metrics")
+										.long("with-metrics")
+										.takes_value(false)
+										.help("Output a solution, with the totals of each vehicle's tour and the objective value, rather than bare paths"))
+								.arg(Arg::with_name("speed")
+										.long("speed")
+										.takes_value(true)
+										.default_value("30")
+										.validator(|s| s.parse::<f64>().map(|_| ()).map_err(|e| e.to_string()))
+										.help("Speed of the vehicles without one in their profile, in km/h, for the estimated durations of --with-metrics and --etas"))
+								.arg(Arg::with_name("etas")
+										.long("etas")
+										.takes_value(false)
+										.conflicts_with("sidewalks")
+										.help("Estimate when each vehicle gets to each node of its path, for road plowing"))
+								.arg(Arg::with_name("preprocess")
+										.long("preprocess")
+										.takes_value(true)
+										.possible_values(&["ch"])
+										.help("Preprocess the graph for faster shortest paths - ch: contraction hierarchies (overrides the meta parameters)"))
+								.arg(Arg::with_name("fail-over-cap")
+										.long("fail-over-cap")
+										.takes_value(false)
+										.help("Fail if no solution is found keeping every tour within the meta parameters' max_tour_cost, rather than warning"))
+								.arg(Arg::with_name("export-repro")
+										.long("export-repro")
+										.takes_value(true)
+										.help("Export a repro bundle (.tar.zst) of the inputs, parameters and output, for bug reports"))
+								.arg(Arg::with_name("control")
+										.long("control")
+										.takes_value(true)
+										.help("Control YAML - polled during the solve for adjustments of the meta parameters (iterations budget, wrap-up deadline, moves)"))
+								.setting(AppSettings::SubcommandsNegateReqs)
+								.subcommand(SubCommand::with_name("both")
+									.about("Plow dat snow off roads and sidewalks, sidewalks after their road")
+									.arg(Arg::with_name("road-graph")
+											.takes_value(true)
+											.required(true)
+											.index(1)
+											.help("Road Graph JSON"))
+									.arg(Arg::with_name("snow")
+											.takes_value(true)
+											.required(true)
+											.index(2)
+											.help("Snow status"))
+									.arg(Arg::with_name("vehicles")
+											.takes_value(true)
+											.required(true)
+											.index(3)
+											.help("Vehicles configuration, of both fleets"))
+									.arg(Arg::with_name("meta")
+											.takes_value(true)
+											.required(true)
+											.index(4)
+											.help("Meta parameters"))
+									.arg(Arg::with_name("output")
+											.takes_value(true)
+											.required(true)
+											.index(5)
+											.help("Road paths output JSON"))
+									.arg(Arg::with_name("sidewalk-output")
+											.takes_value(true)
+											.required(true)
+											.index(6)
+											.help("Sidewalk paths output JSON"))
+									.arg(Arg::with_name("schedule")
+											.takes_value(true)
+											.required(true)
+											.index(7)
+											.help("Schedule output JSON"))
+									.arg(Arg::with_name("snow-d")
+											.short("d")
+											.takes_value(true)
+											.default_value("0")
+											.validator(|s| s.parse::<f64>().map(|_| ()).map_err(|e| e.to_string()))
+											.help("Default snow depth"))
+									.arg(Arg::with_name("multipliers")
+											.short("m")
+											.long("multipliers")
+											.takes_value(true)
+											.help("Cost multipliers overlay JSON"))
+									.arg(Arg::with_name("speed")
+											.long("speed")
+											.takes_value(true)
+											.default_value("30")
+											.validator(|s| s.parse::<f64>().map(|_| ()).map_err(|e| e.to_string()))
+											.help("Speed of the vehicles, in km/h, for the schedule"))))
+							.subcommand(SubCommand::with_name("plow-salt")
+								.about("Plow dat snow, then salt the plowed roads")
 								.arg(Arg::with_name("road-graph")
 										.takes_value(true)
 										.required(true)
 										.index(1)
 										.help("Road Graph JSON"))
-								.arg(Arg::with_name("wut")
+								.arg(Arg::with_name("snow")
 										.takes_value(true)
 										.required(true)
 										.index(2)
-										.help("Produced thingy that you want to convert (currently supported: flight paths)"))
-								.arg(Arg::with_name("prefix")
+										.help("Snow status"))
+								.arg(Arg::with_name("vehicles")
 										.takes_value(true)
 										.required(true)
 										.index(3)
-										.help(r#"GeoJSON files prefix - the generated files will be named alike "{prefix}.{...}.geojson""#))
-							)
-							.get_matches();
-	log::info!("Loading...");
-	if let Some(matches) = matches.subcommand_matches("fly") {
-		log::trace!("tracing enabled");
-		let drones: data::Drones = serde_json::from_reader(&std::fs::File::open(matches.value_of("drones").unwrap())?).expect("Drones config invalid JSON");
-		let roads: data::RoadGraph = serde_json::from_reader(&std::fs::File::open(matches.value_of("road-graph").unwrap())?).expect("Road graph invalid JSON");
-		let params: meta::Parameters = serde_yaml::from_reader(&std::fs::File::open(matches.value_of("meta").unwrap())?).expect("Meta parameters invalid JSON");
-		log::info!("Loaded configuration");
-		let paths = plow::fly::solve(roads, drones, &params).unwrap();
-		log::info!("Constructed paths");
-		serde_json::to_writer(&std::fs::File::create(matches.value_of("output").unwrap())?, &paths).unwrap();
-	} else if let Some(matches) = matches.subcommand_matches("snows") {
-		let roads: data::RoadGraph = serde_json::from_reader(&std::fs::File::open(matches.value_of("road-graph").unwrap())?).expect("Road graph invalid JSON");
-		log::info!("Loaded configuration");
-		let mut snu: Vec<SnuwDapg> = Vec::new();
-		for f in matches.values_of("snows").unwrap() {
-			snu.push(serde_json::from_reader(&std::fs::File::open(f)?).expect("Snow status invalid JSON"));
-		}
-		log::info!("Loaded ❄");
-		serde_json::to_writer(&std::fs::File::create(matches.value_of("output").unwrap())?, &merge_snow_statuses(snu.into_iter().map(|s| match s {
-			SnuwDapg::Formal(s) => s,
-			SnuwDapg::Geo(feat) => gj::geofeatures_to_snow(&roads, feat),
-		}).flatten())).unwrap();
-	} else if let Some(matches) = matches.subcommand_matches("plow") {
-		log::trace!("tracing enabled");
-		let roads: data::RoadGraph = serde_json::from_reader(&std::fs::File::open(matches.value_of("road-graph").unwrap())?).expect("Road graph config invalid JSON");
-		let snow: data::SnowStatuses = serde_json::from_reader(&std::fs::File::open(matches.value_of("snow").unwrap())?).expect("Snow status config invalid JSON");
-		let vehicles: data::VehiclesConfiguration = serde_json::from_reader(&std::fs::File::open(matches.value_of("vehicles").unwrap())?).expect("Meta parameters invalid JSON");
-		let params: meta::Parameters = serde_yaml::from_reader(&std::fs::File::open(matches.value_of("meta").unwrap())?).expect("Meta parameters invalid JSON");
-		log::info!("Loaded configuration");
-		if matches.is_present("sidewalks") {
-			let paths = plow::sidewalk::solve(roads, snow, matches.value_of("snow-d").map(|f| f.parse().unwrap()), vehicles, &params).unwrap();
-			log::info!("Constructed paths");
-			serde_json::to_writer(&std::fs::File::create(matches.value_of("output").unwrap())?, &paths).unwrap();
-		} else {
-			let paths = plow::road::solve(roads, snow, matches.value_of("snow-d").map(|f| f.parse().unwrap()), vehicles, &params).unwrap();
-			log::info!("Constructed paths");
-			serde_json::to_writer(&std::fs::File::create(matches.value_of("output").unwrap())?, &paths).unwrap();
-		}
-	} else if let Some(matches) = matches.subcommand_matches("geojson") {
-		let roads: data::RoadGraph = serde_json::from_reader(&std::fs::File::open(matches.value_of("road-graph").unwrap())?).expect("Road graph config invalid JSON");
-		let pref = matches.value_of("prefix").unwrap();
-		let wut = serde_json::from_reader(&std::fs::File::open(matches.value_of("wut").unwrap())?).expect("WUT invalid JSON");
-		log::info!("Loaded configuration");
-		match wut {
+										.help("Plows configuration"))
+								.arg(Arg::with_name("salters")
+										.takes_value(true)
+										.required(true)
+										.index(4)
+										.help("Salt trucks configuration (as road vehicles)"))
+								.arg(Arg::with_name("meta")
+										.takes_value(true)
+										.required(true)
+										.index(5)
+										.help("Meta parameters"))
+								.arg(Arg::with_name("plow-output")
+										.takes_value(true)
+										.required(true)
+										.index(6)
+										.help("Plowing paths output JSON"))
+								.arg(Arg::with_name("salt-output")
+										.takes_value(true)
+										.required(true)
+										.index(7)
+										.help("Salting paths output JSON"))
+								.arg(Arg::with_name("schedule")
+										.takes_value(true)
+										.required(true)
+										.index(8)
+										.help("Schedule output JSON"))
+								.arg(Arg::with_name("snow-d")
+										.short("d")
+										.takes_value(true)
+										.default_value("0")
+										.validator(|s| s.parse::<f64>().map(|_| ()).map_err(|e| e.to_string()))
+										.help("Default snow depth"))
+								.arg(Arg::with_name("multipliers")
+										.short("m")
+										.long("multipliers")
+										.takes_value(true)
+										.help("Cost multipliers overlay JSON"))
+								.arg(Arg::with_name("speed")
+										.long("speed")
+										.takes_value(true)
+										.default_value("30")
+										.validator(|s| s.parse::<f64>().map(|_| ()).map_err(|e| e.to_string()))
+										.help("Vehicles speed, in km/h")))
+							.subcommand(SubCommand::with_name("rolling")
+								.about("Plow dat snow, live - replan roads plowing over a rolling horizon")
+								.arg(Arg::with_name("road-graph")
+										.takes_value(true)
+										.required(true)
+										.index(1)
+										.help("Road Graph JSON"))
+								.arg(Arg::with_name("snow")
+										.takes_value(true)
+										.required(true)
+										.index(2)
+										.help("Snow status"))
+								.arg(Arg::with_name("vehicles")
+										.takes_value(true)
+										.required(true)
+										.index(3)
+										.help("Vehicles configuration"))
+								.arg(Arg::with_name("meta")
+										.takes_value(true)
+										.required(true)
+										.index(4)
+										.help("Meta parameters"))
+								.arg(Arg::with_name("dir")
+										.takes_value(true)
+										.required(true)
+										.index(5)
+										.help(r#"Working directory - plans are written to "plan.{round}.json", feedback is read from "feedback.{round}.json""#))
+								.arg(Arg::with_name("horizon")
+										.short("t")
+										.long("horizon")
+										.takes_value(true)
+										.default_value("60")
+										.validator(|s| s.parse::<f64>().map(|_| ()).map_err(|e| e.to_string()))
+										.help("Planning horizon, in minutes"))
+								.arg(Arg::with_name("speed")
+										.long("speed")
+										.takes_value(true)
+										.default_value("30")
+										.validator(|s| s.parse::<f64>().map(|_| ()).map_err(|e| e.to_string()))
+										.help("Vehicles speed, in km/h"))
+								.arg(Arg::with_name("rounds")
+										.long("rounds")
+										.takes_value(true)
+										.default_value("100")
+										.validator(|s| s.parse::<usize>().map(|_| ()).map_err(|e| e.to_string()))
+										.help("Maximum number of rounds"))
+								.arg(Arg::with_name("simulate")
+										.long("simulate")
+										.takes_value(false)
+										.help("Assume plans are executed as planned instead of waiting for feedback"))
+								.arg(Arg::with_name("metrics")
+										.long("metrics")
+										.takes_value(true)
+										.help("Serve solver metrics on http://{address}/metrics, for Prometheus"))
+								.arg(Arg::with_name("multipliers")
+										.short("m")
+										.long("multipliers")
+										.takes_value(true)
+										.help("Cost multipliers overlay JSON - segment cost multipliers, applied on top of the road graph")))
+							.subcommand(SubCommand::with_name("replan")
+								.about("Plow dat snow, again - replan roads plowing for the work left, from where the vehicles got along their paths")
+								.arg(Arg::with_name("road-graph")
+										.takes_value(true)
+										.required(true)
+										.index(1)
+										.help("Road Graph JSON"))
+								.arg(Arg::with_name("paths")
+										.takes_value(true)
+										.required(true)
+										.index(2)
+										.help("Paths being driven"))
+								.arg(Arg::with_name("feedback")
+										.takes_value(true)
+										.required(true)
+										.index(3)
+										.help("Progress of the vehicles along their paths, as rolling horizon feedback"))
+								.arg(Arg::with_name("snow")
+										.takes_value(true)
+										.required(true)
+										.index(4)
+										.help("Fresh snow status"))
+								.arg(Arg::with_name("vehicles")
+										.takes_value(true)
+										.required(true)
+										.index(5)
+										.help("Vehicles configuration, as of when the paths were planned"))
+								.arg(Arg::with_name("meta")
+										.takes_value(true)
+										.required(true)
+										.index(6)
+										.help("Meta parameters"))
+								.arg(Arg::with_name("output")
+										.takes_value(true)
+										.required(true)
+										.index(7)
+										.help("Output file"))
+								.arg(Arg::with_name("multipliers")
+										.short("m")
+										.long("multipliers")
+										.takes_value(true)
+										.help("Cost multipliers overlay JSON - segment cost multipliers, applied on top of the road graph")))
+							.subcommand(SubCommand::with_name("watch")
+								.about("Merge snow statuses dropped in a directory as they arrive, and re-solve once it calms down")
+								.arg(Arg::with_name("road-graph")
+										.takes_value(true)
+										.required(true)
+										.index(1)
+										.help("Road Graph JSON"))
+								.arg(Arg::with_name("dir")
+										.takes_value(true)
+										.required(true)
+										.index(2)
+										.help(r#"Watched directory - merged files are moved to "merged/" within"#))
+								.arg(Arg::with_name("ledger")
+										.takes_value(true)
+										.required(true)
+										.index(3)
+										.help("Merged snow status JSON, updated as files arrive"))
+								.arg(Arg::with_name("solve")
+										.long("solve")
+										.takes_value(true)
+										.number_of_values(3)
+										.value_names(&["vehicles", "meta", "output"])
+										.help("Re-solve road plowing after quiet periods, writing the paths to output"))
+								.arg(Arg::with_name("quiet")
+										.long("quiet")
+										.takes_value(true)
+										.default_value("60")
+										.validator(|s| s.parse::<u64>().map(|_| ()).map_err(|e| e.to_string()))
+										.help("Quiet period after merging before re-solving, in seconds"))
+								.arg(Arg::with_name("poll")
+										.long("poll")
+										.takes_value(true)
+										.default_value("1")
+										.validator(|s| s.parse::<u64>().map(|_| ()).map_err(|e| e.to_string()))
+										.help("Polling period, in seconds"))
+								.arg(Arg::with_name("metrics")
+										.long("metrics")
+										.takes_value(true)
+										.help("Serve solver metrics on http://{address}/metrics, for Prometheus"))
+								.arg(Arg::with_name("multipliers")
+										.short("m")
+										.long("multipliers")
+										.takes_value(true)
+										.help("Cost multipliers overlay JSON - segment cost multipliers, applied on top of the road graph")))
+							.subcommand(SubCommand::with_name("serve")
+								.about("Serve a JSON API over a road graph loaded once: POST /plow, /fly and /snows/merge, GET /health")
+								.arg(Arg::with_name("road-graph")
+										.takes_value(true)
+										.required(true)
+										.index(1)
+										.help("Road Graph JSON"))
+								.arg(Arg::with_name("address")
+										.short("a")
+										.long("address")
+										.takes_value(true)
+										.default_value("127.0.0.1:8080")
+										.help("Address to listen on"))
+								.arg(Arg::with_name("clients")
+										.long("clients")
+										.takes_value(true)
+										.help("Clients JSON - API keys and jobs quotas, required of requests if given"))
+								.arg(Arg::with_name("metrics")
+										.long("metrics")
+										.takes_value(true)
+										.help("Serve solver metrics on http://{address}/metrics, for Prometheus")))
+							.subcommand(SubCommand::with_name("compare")
+								.about("Compare two road plowing plans - what if we add two more trucks?")
+								.arg(Arg::with_name("road-graph")
+										.takes_value(true)
+										.required(true)
+										.index(1)
+										.help("Road Graph JSON"))
+								.arg(Arg::with_name("snow")
+										.takes_value(true)
+										.required(true)
+										.index(2)
+										.help("Snow status"))
+								.arg(Arg::with_name("meta")
+										.takes_value(true)
+										.required(true)
+										.index(3)
+										.help("Meta parameters the plans are valued with"))
+								.arg(Arg::with_name("output")
+										.takes_value(true)
+										.required(true)
+										.index(4)
+										.help("Comparison output JSON"))
+								.arg(Arg::with_name("paths-a")
+										.long("paths-a")
+										.takes_value(true)
+										.required_unless("solve-a")
+										.help("Plan A paths"))
+								.arg(Arg::with_name("solve-a")
+										.long("solve-a")
+										.takes_value(true)
+										.number_of_values(2)
+										.value_names(&["vehicles", "meta"])
+										.conflicts_with("paths-a")
+										.help("Solve plan A instead"))
+								.arg(Arg::with_name("paths-b")
+										.long("paths-b")
+										.takes_value(true)
+										.required_unless("solve-b")
+										.help("Plan B paths"))
+								.arg(Arg::with_name("solve-b")
+										.long("solve-b")
+										.takes_value(true)
+										.number_of_values(2)
+										.value_names(&["vehicles", "meta"])
+										.conflicts_with("paths-b")
+										.help("Solve plan B instead"))
+								.arg(Arg::with_name("geojson")
+										.long("geojson")
+										.takes_value(true)
+										.help("Map diff output GeoJSON - segments traversed by either plan, with the \"plan\" (a, b or both) they are traversed by")))
+							.subcommand(SubCommand::with_name("diff")
+								.about("Diff two plans against the same road graph - for A/B testing parameter changes")
+								.arg(Arg::with_name("road-graph")
+										.takes_value(true)
+										.required(true)
+										.index(1)
+										.help("Road Graph JSON"))
+								.arg(Arg::with_name("snow")
+										.takes_value(true)
+										.required(true)
+										.index(2)
+										.help("Snow status"))
+								.arg(Arg::with_name("meta")
+										.takes_value(true)
+										.required(true)
+										.index(3)
+										.help("Meta parameters the plans are valued with"))
+								.arg(Arg::with_name("a")
+										.takes_value(true)
+										.required(true)
+										.index(4)
+										.help("Plan A paths JSON"))
+								.arg(Arg::with_name("b")
+										.takes_value(true)
+										.required(true)
+										.index(5)
+										.help("Plan B paths JSON"))
+								.arg(Arg::with_name("output")
+										.takes_value(true)
+										.required(true)
+										.index(6)
+										.help("Comparison output JSON"))
+								.arg(Arg::with_name("sidewalks")
+										.short("w")
+										.takes_value(false)
+										.help("Sidewalk paths"))
+								.arg(Arg::with_name("geojson")
+										.long("geojson")
+										.takes_value(true)
+										.help("Map diff output GeoJSON - segments traversed by either plan, with the \"plan\" (a, b or both) they are traversed by")))
+							.subcommand(SubCommand::with_name("robustness")
+								.about("Evaluate how fragile a road plowing plan is, by Monte Carlo sampling of snow depths, vehicle speeds and availability")
+								.arg(Arg::with_name("road-graph")
+										.takes_value(true)
+										.required(true)
+										.index(1)
+										.help("Road Graph JSON"))
+								.arg(Arg::with_name("snow")
+										.takes_value(true)
+										.required(true)
+										.index(2)
+										.help("Snow status"))
+								.arg(Arg::with_name("paths")
+										.takes_value(true)
+										.required(true)
+										.index(3)
+										.help("Paths JSON"))
+								.arg(Arg::with_name("meta")
+										.takes_value(true)
+										.required(true)
+										.index(4)
+										.help("Meta parameters"))
+								.arg(Arg::with_name("output")
+										.takes_value(true)
+										.index(5)
+										.help("Output JSON (stdout if not given)"))
+								.arg(Arg::with_name("samples")
+										.long("samples")
+										.takes_value(true)
+										.default_value("1000")
+										.validator(|s| s.parse::<usize>().map_err(|e| e.to_string()).and_then(|n| if n > 0 { Ok(()) } else { Err("at least 1 sample".to_string()) }))
+										.help("Number of samples"))
+								.arg(Arg::with_name("speed")
+										.long("speed")
+										.takes_value(true)
+										.default_value("30")
+										.validator(|s| s.parse::<f64>().map(|_| ()).map_err(|e| e.to_string()))
+										.help("Nominal vehicles speed, in km/h"))
+								.arg(Arg::with_name("speed-noise")
+										.long("speed-noise")
+										.takes_value(true)
+										.default_value("0.1")
+										.validator(|s| s.parse::<f64>().map(|_| ()).map_err(|e| e.to_string()))
+										.help("Relative standard deviation of vehicle speeds"))
+								.arg(Arg::with_name("depth-noise")
+										.long("depth-noise")
+										.takes_value(true)
+										.default_value("0.2")
+										.validator(|s| s.parse::<f64>().map(|_| ()).map_err(|e| e.to_string()))
+										.help("Relative standard deviation of snow depths"))
+								.arg(Arg::with_name("unavailability")
+										.long("unavailability")
+										.takes_value(true)
+										.default_value("0")
+										.validator(|s| s.parse::<f64>().map(|_| ()).map_err(|e| e.to_string()))
+										.help("Probability that a vehicle is unavailable"))
+								.arg(Arg::with_name("deadline")
+										.long("deadline")
+										.takes_value(true)
+										.validator(|s| s.parse::<f64>().map(|_| ()).map_err(|e| e.to_string()))
+										.help("Deadline for completion, in hours")))
+							.subcommand(SubCommand::with_name("bench")
+								.about("Benchmark the solvers on a synthetic city, reporting their wall times, peak memory and objective values")
+								.arg(Arg::with_name("meta")
+										.takes_value(true)
+										.required(true)
+										.index(1)
+										.help("Meta parameters"))
+								.arg(Arg::with_name("output")
+										.takes_value(true)
+										.index(2)
+										.help("Output JSON (stdout if not given)"))
+								.arg(Arg::with_name("layout")
+										.long("layout")
+										.takes_value(true)
+										.possible_values(&["grid", "radial"])
+										.default_value("grid")
+										.help("Street layout of the city"))
+								.arg(Arg::with_name("size")
+										.long("size")
+										.takes_value(true)
+										.default_value("10")
+										.validator(|s| s.parse::<usize>().map_err(|e| e.to_string()).and_then(|n| if n > 0 { Ok(()) } else { Err("at least 1 block".to_string()) }))
+										.help("Blocks on a side of the grid, or rings around the center"))
+								.arg(Arg::with_name("block")
+										.long("block")
+										.takes_value(true)
+										.default_value("100")
+										.validator(|s| s.parse::<f64>().map(|_| ()).map_err(|e| e.to_string()))
+										.help("Block length, in m"))
+								.arg(Arg::with_name("vehicles")
+										.long("vehicles")
+										.takes_value(true)
+										.default_value("3")
+										.validator(|s| s.parse::<usize>().map_err(|e| e.to_string()).and_then(|n| if n > 0 { Ok(()) } else { Err("at least 1 vehicle".to_string()) }))
+										.help("Vehicles of each kind"))
+								.arg(Arg::with_name("snowy")
+										.long("snowy")
+										.takes_value(true)
+										.default_value("0.5")
+										.validator(|s| s.parse::<f64>().map(|_| ()).map_err(|e| e.to_string()))
+										.help("Probability that a segment is snowy"))
+								.arg(Arg::with_name("solver")
+										.long("solver")
+										.takes_value(true)
+										.multiple(true)
+										.number_of_values(1)
+										.possible_values(bench::SOLVERS)
+										.help("Solver to run (all if not given)"))
+								.arg(Arg::with_name("speed")
+										.long("speed")
+										.takes_value(true)
+										.default_value("30")
+										.validator(|s| s.parse::<f64>().map(|_| ()).map_err(|e| e.to_string()))
+										.help("Vehicles speed, in km/h")))
+							.subcommand(SubCommand::with_name("audit")
+								.about("Compare planned paths to what was actually driven")
+								.arg(Arg::with_name("road-graph")
+										.takes_value(true)
+										.required(true)
+										.index(1)
+										.help("Road Graph JSON"))
+								.arg(Arg::with_name("paths")
+										.takes_value(true)
+										.required(true)
+										.index(2)
+										.help("Planned paths JSON"))
+								.arg(Arg::with_name("log")
+										.takes_value(true)
+										.required(true)
+										.index(3)
+										.help("Execution log JSON - timestamped traversed segments, for each vehicle"))
+								.arg(Arg::with_name("output")
+										.takes_value(true)
+										.required(true)
+										.index(4)
+										.help("Comparison report output JSON")))
+							.subcommand(SubCommand::with_name("from-geojson")
+								.about("Convert GeoJSON LineStrings (e.g. paths edited in a GIS) back into paths")
+								.arg(Arg::with_name("road-graph")
+										.takes_value(true)
+										.required(true)
+										.index(1)
+										.help("Road Graph JSON"))
+								.arg(Arg::with_name("output")
+										.takes_value(true)
+										.required(true)
+										.index(2)
+										.help("Paths output JSON"))
+								.arg(Arg::with_name("geojsons")
+										.takes_value(true)
+										.required(true)
+										.multiple(true)
+										.index(3)
+										.help("GeoJSONs - every LineString becomes a path, in order"))
+								.arg(Arg::with_name("tolerance")
+										.long("tolerance")
+										.takes_value(true)
+										.default_value("10")
+										.validator(|s| s.parse::<f64>().map(|_| ()).map_err(|e| e.to_string()))
+										.help("Maximum distance of a LineString vertex from the node it is snapped to, in meters")))
+							.subcommand(SubCommand::with_name("check")
+								.about("Check that paths can actually be driven")
+								.arg(Arg::with_name("road-graph")
+										.takes_value(true)
+										.required(true)
+										.index(1)
+										.help("Road Graph JSON"))
+								.arg(Arg::with_name("paths")
+										.takes_value(true)
+										.required(true)
+										.index(2)
+										.help("Paths JSON"))
+								.arg(Arg::with_name("sidewalks")
+										.short("w")
+										.takes_value(false)
+										.help("Sidewalk paths")))
+							.subcommand(SubCommand::with_name("verify")
+								.about("Verify that paths cover every snowy segment, through existing segments only")
+								.arg(Arg::with_name("road-graph")
+										.takes_value(true)
+										.required(true)
+										.index(1)
+										.help("Road Graph JSON"))
+								.arg(Arg::with_name("snow")
+										.takes_value(true)
+										.required(true)
+										.index(2)
+										.help("Snow status"))
+								.arg(Arg::with_name("paths")
+										.takes_value(true)
+										.required(true)
+										.index(3)
+										.help("Paths JSON, bare or in a solution"))
+								.arg(Arg::with_name("output")
+										.takes_value(true)
+										.index(4)
+										.help("Verification output JSON (stdout if not specified)"))
+								.arg(Arg::with_name("sidewalks")
+										.short("w")
+										.takes_value(false)
+										.help("Sidewalk paths, to cover both sides of the snowy segments"))
+								.arg(Arg::with_name("meta")
+										.long("meta")
+										.takes_value(true)
+										.help("Meta parameters, for the segments needing clearing as the solver tells them (any with snow if not given)"))
+								.arg(Arg::with_name("geojson")
+										.long("geojson")
+										.takes_value(true)
+										.help("Uncovered segments output GeoJSON - styled red, for visual QA")))
+							.subcommand(SubCommand::with_name("stats")
+								.about("Statistics of a road graph, and of paths on it")
+								.arg(Arg::with_name("road-graph")
+										.takes_value(true)
+										.required(true)
+										.index(1)
+										.help("Road Graph JSON"))
+								.arg(Arg::with_name("output")
+										.takes_value(true)
+										.index(2)
+										.help("Statistics output JSON (stdout if not specified)"))
+								.arg(Arg::with_name("paths")
+										.long("paths")
+										.takes_value(true)
+										.help("Paths JSON"))
+								.arg(Arg::with_name("snow")
+										.long("snow")
+										.takes_value(true)
+										.help("Snow Status JSON, for the deadheading and coverage of the paths")))
+							.subcommand(SubCommand::with_name("schedule")
+								.about("Timetable of paths: when each vehicle gets to each node of its path")
+								.arg(Arg::with_name("road-graph")
+										.takes_value(true)
+										.required(true)
+										.index(1)
+										.help("Road Graph JSON"))
+								.arg(Arg::with_name("snow")
+										.takes_value(true)
+										.required(true)
+										.index(2)
+										.help("Snow status"))
+								.arg(Arg::with_name("paths")
+										.takes_value(true)
+										.required(true)
+										.index(3)
+										.help("Paths JSON"))
+								.arg(Arg::with_name("vehicles")
+										.takes_value(true)
+										.required(true)
+										.index(4)
+										.help("Vehicles configuration, for the profiles of the road vehicles"))
+								.arg(Arg::with_name("meta")
+										.takes_value(true)
+										.required(true)
+										.index(5)
+										.help("Meta parameters"))
+								.arg(Arg::with_name("output")
+										.takes_value(true)
+										.required(true)
+										.index(6)
+										.help("Timetable output JSON - the paths, with estimated arrival times"))
+								.arg(Arg::with_name("snow-d")
+										.short("d")
+										.takes_value(true)
+										.default_value("0")
+										.validator(|s| s.parse::<f64>().map(|_| ()).map_err(|e| e.to_string()))
+										.help("Default snow depth"))
+								.arg(Arg::with_name("speed")
+										.long("speed")
+										.takes_value(true)
+										.default_value("30")
+										.validator(|s| s.parse::<f64>().map(|_| ()).map_err(|e| e.to_string()))
+										.help("Speed of the vehicles without one in their profile, in km/h")))
+							.subcommand(SubCommand::with_name("graph")
+								.about("Manage road graphs")
+								.setting(AppSettings::SubcommandRequiredElseHelp)
+								.subcommand(SubCommand::with_name("compile")
+									.about("Compile the binary cache of a road graph, read instead of its JSON while it's up to date")
+									.arg(Arg::with_name("road-graph")
+											.takes_value(true)
+											.required(true)
+											.index(1)
+											.help("Road Graph JSON"))))
+							.subcommand(SubCommand::with_name("meta")
+								.about("Manage meta parameters")
+								.setting(AppSettings::SubcommandRequiredElseHelp)
+								.subcommand(SubCommand::with_name("check")
+									.about("Check meta parameters, and print them as in effect - defaults filled in, and overridden by --seed and --time-limit")
+									.arg(Arg::with_name("meta")
+											.takes_value(true)
+											.required(true)
+											.index(1)
+											.help("Meta parameters"))))
+							.subcommand(SubCommand::with_name("migrate")
+								.about("Upgrade documents to the current version of the spec")
+								.arg(Arg::with_name("input")
+										.takes_value(true)
+										.required(true)
+										.index(1)
+										.help("Input JSON"))
+								.arg(Arg::with_name("output")
+										.takes_value(true)
+										.required(true)
+										.index(2)
+										.help("Output JSON"))
+								.arg(Arg::with_name("kind")
+										.short("k")
+										.long("kind")
+										.takes_value(true)
+										.possible_values(document::KINDS)
+										.help("Kind of the document, required for unversioned documents")))
+							.subcommand(SubCommand::with_name("explain")
+								.about("Explain which vehicle serves a road segment, and why not the others")
+								.arg(Arg::with_name("road-graph")
+										.takes_value(true)
+										.required(true)
+										.index(1)
+										.help("Road Graph JSON"))
+								.arg(Arg::with_name("paths")
+										.takes_value(true)
+										.required(true)
+										.index(2)
+										.help("Paths JSON"))
+								.arg(Arg::with_name("p1")
+										.takes_value(true)
+										.required(true)
+										.index(3)
+										.help("Segment first node"))
+								.arg(Arg::with_name("p2")
+										.takes_value(true)
+										.required(true)
+										.index(4)
+										.help("Segment second node"))
+								.arg(Arg::with_name("discriminator")
+										.short("d")
+										.long("discriminator")
+										.takes_value(true)
+										.help("Segment discriminator"))
+								.arg(Arg::with_name("meta")
+										.long("meta")
+										.takes_value(true)
+										.help("Meta parameters, for the vehicles to be compared by what reaching the segment costs them as the solver tells it (its length if not given)"))
+								.arg(Arg::with_name("history")
+										.long("history")
+										.takes_value(true)
+										.help("Allocation moves recorded with --moves by the solve of the paths, for how the segment came to be allocated")))
+							.subcommand(SubCommand::with_name("geojson")
+								.about("Convert anything into GeoJSONs")
+								.arg(Arg::with_name("road-graph")
+										.takes_value(true)
+										.required(true)
+										.index(1)
+										.help("Road Graph JSON"))
+								.arg(Arg::with_name("wut")
+										.takes_value(true)
+										.required(true)
+										.index(2)
+										.help("Produced thingy that you want to convert (currently supported: flight paths)"))
+								.arg(Arg::with_name("prefix")
+										.takes_value(true)
+										.required(true)
+										.index(3)
+										.help(r#"GeoJSON files prefix - the generated files will be named alike "{prefix}.{...}.geojson""#))
+								.arg(Arg::with_name("gpx")
+										.long("gpx")
+										.takes_value(false)
+										.help(r#"Convert paths into a GPX file instead, "{prefix}.gpx" - a track per vehicle, for GPS units"#))
+								.arg(Arg::with_name("animate")
+										.long("animate")
+										.takes_value(false)
+										.conflicts_with("gpx")
+										.help(r#"Convert paths with arrival times (see schedule) into a single "{prefix}.animated.geojson" instead - a timed line per vehicle, to replay as an animation (Leaflet.TimeDimension, Kepler.gl)"#))
+								.arg(Arg::with_name("start")
+										.long("start")
+										.takes_value(true)
+										.requires("animate")
+										.validator(|s| s.parse::<f64>().map(|_| ()).map_err(|e| e.to_string()))
+										.help("When the vehicles start, in seconds since epoch (now by default)"))
+								.setting(AppSettings::SubcommandsNegateReqs)
+								.subcommand(SubCommand::with_name("import")
+									.about("Build a road graph from GeoJSON LineStrings, with OpenStreetMap-like properties")
+									.arg(Arg::with_name("input")
+											.takes_value(true)
+											.required(true)
+											.index(1)
+											.help("GeoJSON"))
+									.arg(Arg::with_name("output")
+											.takes_value(true)
+											.required(true)
+											.index(2)
+											.help("Road graph output JSON"))
+									.arg(Arg::with_name("tolerance")
+											.long("tolerance")
+											.takes_value(true)
+											.default_value("1")
+											.validator(|s| s.parse::<f64>().map(|_| ()).map_err(|e| e.to_string()))
+											.help("Maximum distance between line ends snapped into the same node, in meters"))
+									.arg(Arg::with_name("report")
+											.long("report")
+											.takes_value(true)
+											.help("Discriminated segments report output JSON")))
+							);
+	#[cfg(feature = "kafka")]
+	let app = app.subcommand(SubCommand::with_name("kafka")
+								.about("Plow dat snow, live - consume snow & vehicle events from Kafka, publishing updated plans")
+								.arg(Arg::with_name("road-graph")
+										.takes_value(true)
+										.required(true)
+										.index(1)
+										.help("Road Graph JSON"))
+								.arg(Arg::with_name("snow")
+										.takes_value(true)
+										.required(true)
+										.index(2)
+										.help("Initial snow status"))
+								.arg(Arg::with_name("vehicles")
+										.takes_value(true)
+										.required(true)
+										.index(3)
+										.help("Vehicles configuration"))
+								.arg(Arg::with_name("meta")
+										.takes_value(true)
+										.required(true)
+										.index(4)
+										.help("Meta parameters"))
+								.arg(Arg::with_name("brokers")
+										.long("brokers")
+										.takes_value(true)
+										.default_value("localhost:9092")
+										.help("Kafka bootstrap servers"))
+								.arg(Arg::with_name("group")
+										.long("group")
+										.takes_value(true)
+										.default_value("wfbfa-solver")
+										.help("Consumer group"))
+								.arg(Arg::with_name("snow-topic")
+										.long("snow-topic")
+										.takes_value(true)
+										.default_value("snow")
+										.help("Topic of snow status updates"))
+								.arg(Arg::with_name("progress-topic")
+										.long("progress-topic")
+										.takes_value(true)
+										.default_value("progress")
+										.help("Topic of vehicle progress events"))
+								.arg(Arg::with_name("plans-topic")
+										.long("plans-topic")
+										.takes_value(true)
+										.default_value("plans")
+										.help("Topic updated plans are published to"))
+								.arg(Arg::with_name("interval")
+										.long("interval")
+										.takes_value(true)
+										.default_value("300")
+										.validator(|s| s.parse::<u64>().map(|_| ()).map_err(|e| e.to_string()))
+										.help("Minimum period between plans, in seconds"))
+								.arg(Arg::with_name("metrics")
+										.long("metrics")
+										.takes_value(true)
+										.help("Serve solver metrics on http://{address}/metrics, for Prometheus"))
+								.arg(Arg::with_name("multipliers")
+										.short("m")
+										.long("multipliers")
+										.takes_value(true)
+										.help("Cost multipliers overlay JSON - segment cost multipliers, applied on top of the road graph")));
+	let matches = match app.get_matches_safe() {
+		Ok(matches) => matches,
+		Err(e) if matches!(e.kind, clap::ErrorKind::HelpDisplayed | clap::ErrorKind::VersionDisplayed) => e.exit(),
+		// invalid arguments are bad input as any other, rather than clap's own exit code
+		Err(e) => return Err(error::Error::Validation { file: None, message: e.message.lines().next().unwrap_or_default().trim_start_matches("error: ").to_string() }),
+	};
+	if let Some(f) = matches.value_of("error-json").or_else(|| matches.subcommand().1.and_then(|m| m.value_of("error-json"))) {
+		failure::report_to(f.into());
+	}
+	match matches.value_of("progress").or_else(|| matches.subcommand().1.and_then(|m| m.value_of("progress"))) {
+		Some("json") => progress::on_progress(|p| println!("{}", serde_json::to_string(p).unwrap())),
+		Some("bar") => progress::on_progress(progress_bar),
+		_ => {},
+	}
+	if let Some(f) = matches.value_of("trace").or_else(|| matches.subcommand().1.and_then(|m| m.value_of("trace"))) {
+		progress::on_candidate(trace_csv(f)?);
+	}
+	if let Some(f) = matches.value_of("moves").or_else(|| matches.subcommand().1.and_then(|m| m.value_of("moves"))) {
+		progress::on_move(moves_ndjson(f)?);
+	}
+	let sub = matches.subcommand().1.unwrap_or(&matches);
+	let seed: Option<u64> = parse_opt(if matches.is_present("seed") { &matches } else { sub }, "seed")?;
+	let time_limit: Option<f64> = parse_opt(if matches.is_present("time-limit") { &matches } else { sub }, "time-limit")?;
+	let read_meta = |file: &str| meta::read(file).map(|mut params| {
+		params.seed = seed.or(params.seed);
+		params.annealing.max_wall_time = time_limit.or(params.annealing.max_wall_time);
+		params
+	});
+	log::info!("Loading...");
+	#[cfg(feature = "kafka")]
+	if let Some(matches) = matches.subcommand_matches("kafka") {
+		let roads: data::RoadGraph = cache::read(matches.value_of("road-graph").unwrap(), "Road graph config")?;
+		let snow: data::SnowStatuses = document::read(matches.value_of("snow").unwrap(), "Snow status config")?;
+		let vehicles: data::VehiclesConfiguration = document::read(matches.value_of("vehicles").unwrap(), "Vehicles configuration")?;
+		let params: meta::Parameters = read_meta(matches.value_of("meta").unwrap())?;
+		let overlay: data::CostOverlay = match matches.value_of("multipliers") {
+			Some(f) => document::read(f, "Cost multipliers")?,
+			None => Vec::new(),
+		};
+		if let Some(addr) = matches.value_of("metrics") {
+			metrics::serve(addr)?;
+		}
+		log::info!("Loaded configuration");
+		kafka::run(roads, snow, &overlay, vehicles, &params, &kafka::Kafka {
+			brokers: matches.value_of("brokers").unwrap(),
+			group: matches.value_of("group").unwrap(),
+			snow_topic: matches.value_of("snow-topic").unwrap(),
+			progress_topic: matches.value_of("progress-topic").unwrap(),
+			plans_topic: matches.value_of("plans-topic").unwrap(),
+			interval: std::time::Duration::from_secs(parse(matches, "interval")?),
+		}).map_err(std::io::Error::other)?;
+		return Ok(());
+	}
+	if let Some(matches) = matches.subcommand_matches("fly") {
+		log::trace!("tracing enabled");
+		let mut params: meta::Parameters = read_meta(matches.value_of("meta").unwrap())?;
+		let repro = export_repro(matches, &["road-graph", "drones", "meta"], &mut params);
+		let drones: data::Drones = document::read(matches.value_of("drones").unwrap(), "Drones config")?;
+		let roads = cache::stream(matches.value_of("road-graph").unwrap(), "Road graph")?;
+		log::info!("Loaded configuration");
+		let paths = plow::fly::solve(roads, drones, &params)?;
+		log::info!("Constructed paths");
+		document::write(matches.value_of("output").unwrap(), &paths)?;
+		if let Some(repro) = repro {
+			repro.export(None)?;
+		}
+	} else if let Some(matches) = matches.subcommand_matches("snows") {
+		let roads: data::RoadGraph = cache::read(matches.value_of("road-graph").unwrap(), "Road graph")?;
+		log::info!("Loaded configuration");
+		let mut snu: Vec<SnuwDapg> = Vec::new();
+		for f in matches.values_of("snows").into_iter().flatten() {
+			snu.push(document::read_any(f, "Snow status")?);
+		}
+		if let Some(sensors) = matches.value_of("sensors") {
+			let sensors: data::SensorSegments = document::read(sensors, "Sensor segments")?;
+			for f in matches.values_of("from-csv").into_iter().flatten() {
+				snu.push(SnuwDapg::Formal(sensors::read_csv(f, &sensors)?));
+			}
+		}
+		let mut cleared: Vec<data::SnowStatuses> = Vec::new();
+		for f in matches.values_of("cleared").into_iter().flatten() {
+			cleared.push(document::read(f, "Cleared layer")?);
+		}
+		log::info!("Loaded ❄");
+		let snows = snu.into_iter().map(|s| match s {
+			SnuwDapg::Formal(s) => s,
+			SnuwDapg::Geo(feat) => gj::geofeatures_to_snow(&roads, feat),
+		}).flatten();
+		let accumulation = n64(parse(matches, "accumulation")?);
+		let strategy = snow::strategy(matches.value_of("strategy").unwrap(), accumulation, parse_opt(matches, "at")?).unwrap();
+		let mut merged = snow::merge(snows, strategy.as_ref());
+		clear_snow_statuses(&mut merged, cleared.into_iter());
+		document::write(matches.value_of("output").unwrap(), &merged)?;
+	} else if let Some(matches) = matches.subcommand_matches("cleared") {
+		let roads: data::IndexedRoadGraph = cache::read_indexed(matches.value_of("road-graph").unwrap(), "Road graph")?;
+		let traces: data::GpsTraces = document::read(matches.value_of("traces").unwrap(), "GPS traces")?;
+		log::info!("Loaded configuration");
+		let cleared = traces::cleared(&roads, &traces, parse(matches, "tolerance")?, parse(matches, "sigma")?, parse(matches, "fixes")?);
+		document::write(matches.value_of("output").unwrap(), &cleared)?;
+	} else if let Some(matches) = matches.subcommand_matches("snap") {
+		let mut roads: data::RoadGraph = cache::read(matches.value_of("road-graph").unwrap(), "Road graph")?;
+		log::info!("Loaded configuration");
+		let mapping = roads.snap(parse(matches, "tolerance")?);
+		document::write(matches.value_of("output").unwrap(), &roads)?;
+		if let Some(report) = matches.value_of("report") {
+			document::write(report, &mapping)?;
+		}
+	} else if let Some(matches) = matches.subcommand_matches("remap-ids") {
+		let mapping: data::IdMapping = document::read(matches.value_of("mapping").unwrap(), "Id mapping")?;
+		log::info!("Loaded configuration");
+		remap_files::<data::RoadGraph>(matches.values_of("road-graph"), &mapping, "road graph")?;
+		remap_files::<data::SnowStatuses>(matches.values_of("snow"), &mapping, "snow status")?;
+		remap_files::<data::VehiclesConfiguration>(matches.values_of("vehicles"), &mapping, "vehicles configuration")?;
+		remap_files::<data::Paths>(matches.values_of("paths"), &mapping, "paths")?;
+		remap_files::<data::SidewalkPaths>(matches.values_of("sidewalk-paths"), &mapping, "sidewalk paths")?;
+		remap_files::<data::NodeTasks>(matches.values_of("tasks"), &mapping, "node tasks")?;
+	} else if let Some(matches) = matches.subcommand_matches("validate") {
+		let mut issues = Vec::new();
+		let roads = diagnostics::reported(diagnostics::read_roads(matches.value_of("road-graph").unwrap()), &mut issues).map(|(roads, flags)| {
+			issues.extend(flags);
+			roads
+		});
+		let snow = matches.value_of("snow").and_then(|f| diagnostics::reported(document::read::<data::SnowStatuses>(f, "Snow status"), &mut issues));
+		let vehicles = matches.value_of("vehicles").and_then(|f| diagnostics::reported(document::read::<data::VehiclesConfiguration>(f, "Vehicles configuration"), &mut issues));
+		let params = matches.value_of("meta").and_then(|f| diagnostics::reported(meta::read(f), &mut issues));
+		if let Some(overlay) = matches.value_of("multipliers").and_then(|f| diagnostics::reported(document::read::<data::CostOverlay>(f, "Cost multipliers"), &mut issues)) {
+			issues.extend(diagnostics::multiplier_issues(&overlay));
+		}
+		log::info!("Loaded configuration");
+		if let Some(roads) = &roads {
+			issues.extend(diagnostics::spec_issues(roads));
+			issues.extend(diagnostics::segment_issues(roads));
+			issues.extend(diagnostics::one_way_issues(roads));
+			if let Some(snow) = &snow {
+				issues.extend(diagnostics::snow_issues(roads, snow));
+			}
+			if let Some(vehicles) = &vehicles {
+				issues.extend(diagnostics::vehicle_issues(roads, vehicles));
+			}
+			if let Some(params) = &params {
+				issues.extend(diagnostics::cost_issues(roads, snow.as_deref().unwrap_or_default(), params));
+			}
+		}
+		log::info!("Found {} issues", issues.len());
+		match matches.value_of("output") {
+			Some(output) => document::write(output, &issues)?,
+			None => document::print(&issues)?,
+		}
+	} else if let Some(matches) = matches.subcommand_matches("repair-graph") {
+		let mut roads: data::RoadGraph = cache::read(matches.value_of("road-graph").unwrap(), "Road graph")?;
+		log::info!("Loaded configuration");
+		let mut issues = if matches.is_present("discriminate") { diagnostics::discriminate(&mut roads) } else { Vec::new() };
+		issues.extend(diagnostics::repair(&mut roads));
+		log::info!("Repaired {} issues", issues.len());
+		document::write(matches.value_of("output").unwrap(), &roads)?;
+		if let Some(report) = matches.value_of("report") {
+			document::write(report, &issues)?;
+		}
+	} else if let Some(matches) = matches.subcommand_matches("osm") {
+		let (roads, issues) = osm::read(matches.value_of("extract").unwrap())?;
+		log::info!("Imported {} nodes, {} segments", roads.nodes.nodes.len(), roads.roads.len());
+		imported(&issues);
+		document::write(matches.value_of("output").unwrap(), &roads)?;
+		if let Some(report) = matches.value_of("report") {
+			document::write(report, &issues)?;
+		}
+	} else if let Some(matches) = matches.subcommand_matches("plow").and_then(|m| m.subcommand_matches("both")) {
+		let roads: data::RoadGraph = cache::read(matches.value_of("road-graph").unwrap(), "Road graph config")?;
+		let snow: data::SnowStatuses = document::read(matches.value_of("snow").unwrap(), "Snow status config")?;
+		let vehicles: data::VehiclesConfiguration = document::read(matches.value_of("vehicles").unwrap(), "Vehicles configuration")?;
+		let params: meta::Parameters = read_meta(matches.value_of("meta").unwrap())?;
+		let overlay: data::CostOverlay = match matches.value_of("multipliers") {
+			Some(f) => document::read(f, "Cost multipliers")?,
+			None => Vec::new(),
+		};
+		log::info!("Loaded configuration");
+		let snow_d = parse_opt(matches, "snow-d")?;
+		let (plan, sidewalk_plan, schedule) = combined::solve(&roads, snow, snow_d, &overlay, vehicles, &params, parse(matches, "speed")?)?;
+		log::info!("Plowed roads in {:.2}h, sidewalks in {:.2}h, sidewalk vehicles waiting {:.2}h for the plows", schedule.plows.iter().copied().fold(0.0, f64::max), schedule.sidewalk_vehicles.iter().copied().fold(0.0, f64::max), schedule.waits.iter().sum::<f64>());
+		document::write(matches.value_of("output").unwrap(), &plan)?;
+		document::write(matches.value_of("sidewalk-output").unwrap(), &sidewalk_plan)?;
+		document::write(matches.value_of("schedule").unwrap(), &schedule)?;
+	} else if let Some(matches) = matches.subcommand_matches("plow") {
+		log::trace!("tracing enabled");
+		let mut params: meta::Parameters = read_meta(matches.value_of("meta").unwrap())?;
+		if matches.value_of("preprocess") == Some("ch") {
+			params.preprocess = meta::Preprocess::ContractionHierarchy;
+		}
+		params.fail_over_cap |= matches.is_present("fail-over-cap");
+		let repro = export_repro(matches, &["road-graph", "snow", "vehicles", "meta", "scenario", "multipliers", "traffic", "matrix", "tasks", "stops", "region"], &mut params);
+		if let Some(f) = matches.value_of("control") {
+			control::watch(f.into());
+		}
+		// the road graph's segments are only read as the solver takes them in, unless needed whole
+		let mut roads = cache::stream(matches.value_of("road-graph").unwrap(), "Road graph config")?;
+		let mut snow: data::SnowStatuses = document::read(matches.value_of("snow").unwrap(), "Snow status config")?;
+		let mut vehicles: data::VehiclesConfiguration = document::read(matches.value_of("vehicles").unwrap(), "Vehicles configuration")?;
+		let mut scenarios: Vec<data::SnowStatuses> = Vec::new();
+		for f in matches.values_of("scenario").into_iter().flatten() {
+			scenarios.push(document::read(f, "Snow scenario")?);
+		}
+		let overlay: data::CostOverlay = match matches.value_of("multipliers") {
+			Some(f) => document::read(f, "Cost multipliers")?,
+			None => Vec::new(),
+		};
+		let traffic: data::TravelTimes = match matches.value_of("traffic") {
+			Some(f) => document::read(f, "Travel times")?,
+			None => Vec::new(),
+		};
+		let matrix: Option<data::DistanceMatrix> = match matches.value_of("matrix") {
+			Some(f) => Some(document::read(f, "Distance matrix")?),
+			None => None,
+		};
+		let tasks: data::NodeTasks = match matches.value_of("tasks") {
+			Some(f) => document::read(f, "Node tasks")?,
+			None => Vec::new(),
+		};
+		let stops: data::Stops = match matches.value_of("stops") {
+			Some(f) => document::read(f, "Stops")?,
+			None => Vec::new(),
+		};
+		let mut snow_d = parse_opt(matches, "snow-d")?;
+		if let Some(f) = matches.value_of("region") {
+			use geo::intersects::Intersects;
+			let region = gj::geofeatures_to_geometries(gj::geojson_to_geofeatures(serde_json::from_reader(&std::fs::File::open(f)?).map_err(|e| error::Error::json(f, "Region GeoJSON", e))?));
+			let roads = roads.whole()?;
+			let coords: std::collections::HashMap<_, _> = roads.nodes.nodes.iter().map(|n| (n.id.clone(), n.coordinates)).collect();
+			let inside = roads.restrict(|n| region.iter().any(|g| g.intersects(&geo::Geometry::<f64>::from(n))), parse(matches, "buffer")?);
+			let kept: std::collections::HashSet<_> = roads.nodes.nodes.iter().map(|n| n.id.clone()).collect();
+			for l in vehicles.road.iter_mut().chain(vehicles.sidewalk.iter_mut()) {
+				if let data::Location::Node(n) = l {
+					if let Some((lon, lat)) = coords.get(n).filter(|_| !kept.contains(n)) {
+						log::warn!("Vehicle at {} is outside the region, it will start from the closest node within", n);
+						*l = data::Location::Coordinates(*lon, *lat);
+					}
+				}
+			}
+			if let Some(d) = snow_d.take().filter(|d| *d > 0.0) {
+				snow = inside.iter().map(|(p1, p2, discriminator)| data::SnowStatusElement { p1: p1.clone(), p2: p2.clone(), discriminator: discriminator.clone(), depth: n64(d), probability: None, variance: None, observed: None, confidence: None }).collect();
+			}
+			for snow in std::iter::once(&mut snow).chain(scenarios.iter_mut()) {
+				snow.retain(|s| inside.contains(&data::segment_key(&s.p1, &s.p2, &s.discriminator)));
+			}
+		}
+		log::info!("Loaded configuration");
+		if let Some(f) = matches.value_of("escalations") {
+			let escalations = plow::escalations(roads.graph()?, &snow, snow_d, &vehicles, matches.is_present("sidewalks"), &params);
+			log::info!("{} segments require escalation", escalations.len());
+			document::write(f, &escalations)?;
+		}
+		let speed: f64 = parse(matches, "speed")?;
+		let metrics = if matches.is_present("with-metrics") || matches.is_present("etas") {
+			Some((roads.graph()?.clone(), snow.clone(), vehicles.profiles(matches.is_present("sidewalks")), speed))
+		} else {
+			None
+		};
+		let unreachable = if matches.is_present("sidewalks") {
+			let (paths, unreachable) = plow::sidewalk::solve(roads, snow, scenarios, snow_d, &overlay, &stops, vehicles, &params)?;
+			log::info!("Constructed paths");
+			match metrics {
+				Some((roads, snow, profiles, speed)) if matches.is_present("with-metrics") => {
+					let (vehicles, objective) = report::vehicle_metrics(&roads, &snow, snow_d, &report::sidewalk_tours(&paths), true, &profiles, &params, speed)?;
+					document::write(matches.value_of("output").unwrap(), &data::Solution { paths, vehicles, objective })?;
+				},
+				_ => document::write(matches.value_of("output").unwrap(), &paths)?,
+			}
+			unreachable
+		} else {
+			let (paths, unreachable) = plow::road::solve(roads, snow, scenarios, snow_d, &overlay, &traffic, matrix.as_ref(), &tasks, vehicles, &params)?;
+			log::info!("Constructed paths");
+			let paths = match &metrics {
+				Some((roads, snow, profiles, speed)) if matches.is_present("etas") => report::etas(roads, snow, snow_d, &paths, profiles, &params, *speed)?,
+				_ => paths,
+			};
+			match metrics {
+				Some((roads, snow, profiles, speed)) if matches.is_present("with-metrics") => {
+					let (vehicles, objective) = report::vehicle_metrics(&roads, &snow, snow_d, &report::road_tours(&paths), false, &profiles, &params, speed)?;
+					document::write(matches.value_of("output").unwrap(), &data::Solution { paths, vehicles, objective })?;
+				},
+				_ => document::write(matches.value_of("output").unwrap(), &paths)?,
+			}
+			unreachable
+		};
+		if let Some(f) = matches.value_of("unreachable") {
+			document::write(f, &unreachable)?;
+		}
+		if let Some(repro) = repro {
+			repro.export(None)?;
+		}
+	} else if let Some(matches) = matches.subcommand_matches("plow-salt") {
+		let roads = cache::stream(matches.value_of("road-graph").unwrap(), "Road graph config")?;
+		let snow: data::SnowStatuses = document::read(matches.value_of("snow").unwrap(), "Snow status config")?;
+		let vehicles: data::VehiclesConfiguration = document::read(matches.value_of("vehicles").unwrap(), "Plows config")?;
+		let salters: data::VehiclesConfiguration = document::read(matches.value_of("salters").unwrap(), "Salt trucks config")?;
+		let params: meta::Parameters = read_meta(matches.value_of("meta").unwrap())?;
+		let overlay: data::CostOverlay = match matches.value_of("multipliers") {
+			Some(f) => document::read(f, "Cost multipliers")?,
+			None => Vec::new(),
+		};
+		log::info!("Loaded configuration");
+		let snow_d = parse_opt(matches, "snow-d")?;
+		let (plan, salting, schedule) = salting::solve(roads, snow, snow_d, &overlay, vehicles, salters, &params, parse(matches, "speed")?)?;
+		log::info!("Plowed in {:.2}h, salted in {:.2}h", schedule.plows.iter().copied().fold(0.0, f64::max), schedule.salters.iter().copied().fold(0.0, f64::max));
+		document::write(matches.value_of("plow-output").unwrap(), &plan)?;
+		document::write(matches.value_of("salt-output").unwrap(), &salting)?;
+		document::write(matches.value_of("schedule").unwrap(), &schedule)?;
+	} else if let Some(matches) = matches.subcommand_matches("rolling") {
+		let roads = cache::stream(matches.value_of("road-graph").unwrap(), "Road graph config")?;
+		let snow: data::SnowStatuses = document::read(matches.value_of("snow").unwrap(), "Snow status config")?;
+		let vehicles: data::VehiclesConfiguration = document::read(matches.value_of("vehicles").unwrap(), "Vehicles configuration")?;
+		let params: meta::Parameters = read_meta(matches.value_of("meta").unwrap())?;
+		let overlay: data::CostOverlay = match matches.value_of("multipliers") {
+			Some(f) => document::read(f, "Cost multipliers")?,
+			None => Vec::new(),
+		};
+		if let Some(addr) = matches.value_of("metrics") {
+			metrics::serve(addr)?;
+		}
+		log::info!("Loaded configuration");
+		rolling::run(roads, snow, &overlay, vehicles, &params, &rolling::Rolling {
+			dir: std::path::Path::new(matches.value_of("dir").unwrap()),
+			horizon: parse(matches, "horizon")?,
+			speed: parse(matches, "speed")?,
+			simulate: matches.is_present("simulate"),
+			max_rounds: parse(matches, "rounds")?,
+		})?;
+	} else if let Some(matches) = matches.subcommand_matches("replan") {
+		let roads = cache::stream(matches.value_of("road-graph").unwrap(), "Road graph config")?;
+		let paths: data::Paths = document::read(matches.value_of("paths").unwrap(), "Paths")?;
+		let progress: data::Progress = document::read(matches.value_of("feedback").unwrap(), "Feedback")?;
+		let snow: data::SnowStatuses = document::read(matches.value_of("snow").unwrap(), "Snow status config")?;
+		let vehicles: data::VehiclesConfiguration = document::read(matches.value_of("vehicles").unwrap(), "Vehicles configuration")?;
+		let params: meta::Parameters = read_meta(matches.value_of("meta").unwrap())?;
+		let overlay: data::CostOverlay = match matches.value_of("multipliers") {
+			Some(f) => document::read(f, "Cost multipliers")?,
+			None => Vec::new(),
+		};
+		log::info!("Loaded configuration");
+		let paths = rolling::replan(roads, &paths, progress, snow, &overlay, vehicles, &params)?;
+		log::info!("Constructed paths");
+		document::write(matches.value_of("output").unwrap(), &paths)?;
+	} else if let Some(matches) = matches.subcommand_matches("watch") {
+		let roads = cache::stream(matches.value_of("road-graph").unwrap(), "Road graph config")?;
+		let resolve = match matches.values_of("solve") {
+			Some(solve) => {
+				let solve: Vec<_> = solve.collect();
+				Some(watch::Resolve {
+					vehicles: document::read(solve[0], "Vehicles configuration")?,
+					params: read_meta(solve[1])?,
+					overlay: match matches.value_of("multipliers") {
+						Some(f) => document::read(f, "Cost multipliers")?,
+						None => Vec::new(),
+					},
+					output: std::path::Path::new(solve[2]),
+				})
+			},
+			None => None,
+		};
+		if let Some(addr) = matches.value_of("metrics") {
+			metrics::serve(addr)?;
+		}
+		log::info!("Loaded configuration");
+		watch::run(roads, &watch::Watch {
+			dir: std::path::Path::new(matches.value_of("dir").unwrap()),
+			ledger: std::path::Path::new(matches.value_of("ledger").unwrap()),
+			quiet: std::time::Duration::from_secs(parse(matches, "quiet")?),
+			poll: std::time::Duration::from_secs(parse(matches, "poll")?),
+			resolve,
+		})?;
+	} else if let Some(matches) = matches.subcommand_matches("serve") {
+		let roads: data::RoadGraph = cache::read(matches.value_of("road-graph").unwrap(), "Road graph config")?;
+		let clients: Option<auth::Clients> = match matches.value_of("clients") {
+			Some(f) => Some(document::read(f, "Clients")?),
+			None => None,
+		};
+		if let Some(addr) = matches.value_of("metrics") {
+			metrics::serve(addr)?;
+		}
+		log::info!("Loaded configuration");
+		serve::serve(matches.value_of("address").unwrap(), roads, clients, time_limit)?;
+	} else if let Some(matches) = matches.subcommand_matches("compare") {
+		let roads: data::RoadGraph = cache::read(matches.value_of("road-graph").unwrap(), "Road graph config")?;
+		let snow: data::SnowStatuses = document::read(matches.value_of("snow").unwrap(), "Snow status config")?;
+		let params: meta::Parameters = read_meta(matches.value_of("meta").unwrap())?;
+		log::info!("Loaded configuration");
+		let mut plans = Vec::new();
+		for side in ["a", "b"] {
+			plans.push(match matches.values_of(format!("solve-{}", side)) {
+				Some(solve) => {
+					let solve: Vec<_> = solve.collect();
+					let vehicles: data::VehiclesConfiguration = document::read(solve[0], "Vehicles configuration")?;
+					let params: meta::Parameters = read_meta(solve[1])?;
+					let paths = plow::road::solve(roads.clone(), snow.clone(), Vec::new(), None, &[], &[], None, &[], vehicles, &params)?.0;
+					log::info!("Solved plan {}", side.to_uppercase());
+					paths
+				},
+				None => document::read(matches.value_of(format!("paths-{}", side)).unwrap(), "Paths")?,
+			});
+		}
+		let plans: Vec<_> = plans.iter().map(report::road_tours).collect();
+		let comparison = report::compare(&roads, &snow, &plans[0], &plans[1], &params)?;
+		log::info!("Objective {:+.1}, total distance {:+.0}m, makespan {:+.0}m (B - A)", comparison.objective, comparison.total_distance, comparison.makespan);
+		document::write(matches.value_of("output").unwrap(), &comparison)?;
+		if let Some(f) = matches.value_of("geojson") {
+			write_geojson(f, &gj::coverage_diff_to_geofeatures(&roads, &report::coverage(&plans[0]), &report::coverage(&plans[1])))?;
+		}
+	} else if let Some(matches) = matches.subcommand_matches("diff") {
+		let roads: data::RoadGraph = cache::read(matches.value_of("road-graph").unwrap(), "Road graph config")?;
+		let snow: data::SnowStatuses = document::read(matches.value_of("snow").unwrap(), "Snow status config")?;
+		let params: meta::Parameters = read_meta(matches.value_of("meta").unwrap())?;
+		let mut plans = Vec::new();
+		for side in ["a", "b"] {
+			let f = matches.value_of(side).unwrap();
+			plans.push(if matches.is_present("sidewalks") {
+				report::sidewalk_tours(&document::read(f, "Sidewalk paths")?)
+			} else {
+				report::road_tours(&document::read(f, "Paths")?)
+			});
+		}
+		log::info!("Loaded configuration");
+		let comparison = report::compare(&roads, &snow, &plans[0], &plans[1], &params)?;
+		log::info!("Objective {:+.1}, total distance {:+.0}m, makespan {:+.0}m, {} segments covered, {} dropped (B - A)", comparison.objective, comparison.total_distance, comparison.makespan, comparison.covered.len(), comparison.dropped.len());
+		document::write(matches.value_of("output").unwrap(), &comparison)?;
+		if let Some(f) = matches.value_of("geojson") {
+			write_geojson(f, &gj::coverage_diff_to_geofeatures(&roads, &report::coverage(&plans[0]), &report::coverage(&plans[1])))?;
+		}
+	} else if let Some(matches) = matches.subcommand_matches("robustness") {
+		let roads: data::RoadGraph = cache::read(matches.value_of("road-graph").unwrap(), "Road graph config")?;
+		let snow: data::SnowStatuses = document::read(matches.value_of("snow").unwrap(), "Snow status config")?;
+		let paths: data::Paths = document::read(matches.value_of("paths").unwrap(), "Paths")?;
+		let params: meta::Parameters = read_meta(matches.value_of("meta").unwrap())?;
+		log::info!("Loaded configuration");
+		let fragility = montecarlo::evaluate(&roads, &snow, &paths, &params, &montecarlo::MonteCarlo {
+			samples: parse(matches, "samples")?,
+			speed: parse(matches, "speed")?,
+			speed_noise: parse(matches, "speed-noise")?,
+			depth_noise: parse(matches, "depth-noise")?,
+			unavailability: parse(matches, "unavailability")?,
+			deadline: parse_opt(matches, "deadline")?,
+		})?;
+		if let Some(completion) = &fragility.completion {
+			log::info!("Completion in {:.2}h on average, {:.2}h at the 95th percentile", completion.mean, completion.p95);
+		}
+		if fragility.incomplete > 0.0 {
+			log::warn!("No vehicle available with probability {:.1}%", fragility.incomplete * 100.0);
+		}
+		if let Some(p) = fragility.missed_deadline {
+			log::info!("Deadline missed with probability {:.1}%", p * 100.0);
+		}
+		match matches.value_of("output") {
+			Some(output) => document::write(output, &fragility)?,
+			None => document::print(&fragility)?,
+		}
+	} else if let Some(matches) = matches.subcommand_matches("bench") {
+		let params: meta::Parameters = read_meta(matches.value_of("meta").unwrap())?;
+		let city = bench::City {
+			layout: if matches.value_of("layout") == Some("radial") { bench::Layout::Radial } else { bench::Layout::Grid },
+			size: parse(matches, "size")?,
+			block: parse(matches, "block")?,
+			vehicles: parse(matches, "vehicles")?,
+			snowy: parse(matches, "snowy")?,
+			// the city is seeded like the solvers, or always the same
+			seed: params.seed.unwrap_or(0),
+		};
+		let solvers = matches.values_of("solver").map_or_else(|| bench::SOLVERS.to_vec(), |s| s.collect());
+		let bench = bench::run(&city, &solvers, &params, parse(matches, "speed")?)?;
+		match matches.value_of("output") {
+			Some(output) => document::write(output, &bench)?,
+			None => document::print(&bench)?,
+		}
+	} else if let Some(matches) = matches.subcommand_matches("audit") {
+		let roads: data::RoadGraph = cache::read(matches.value_of("road-graph").unwrap(), "Road graph config")?;
+		let paths: data::Paths = document::read(matches.value_of("paths").unwrap(), "Paths")?;
+		let log: data::ExecutionLog = document::read(matches.value_of("log").unwrap(), "Execution log")?;
+		log::info!("Loaded configuration");
+		let report = report::adherence(&roads, &paths, &log);
+		log::info!("Adherence {:.1}%, {} segments skipped, {:.0}m extra distance", report.adherence * 100.0, report.skipped, report.extra_distance);
+		document::write(matches.value_of("output").unwrap(), &report)?;
+	} else if let Some(matches) = matches.subcommand_matches("from-geojson") {
+		let roads: data::IndexedRoadGraph = cache::read_indexed(matches.value_of("road-graph").unwrap(), "Road graph config")?;
+		let tolerance = parse(matches, "tolerance")?;
+		let mut paths = Vec::new();
+		for f in matches.values_of("geojsons").unwrap() {
+			let gj: geojson::GeoJson = serde_json::from_reader(&std::fs::File::open(f)?).map_err(|e| error::Error::json(f, "GeoJSON", e))?;
+			paths.extend(gj::geofeatures_to_paths(&roads, gj::geojson_to_geofeatures(gj), tolerance));
+		}
+		log::info!("Reconstructed {} paths", paths.len());
+		document::write(matches.value_of("output").unwrap(), &paths)?;
+	} else if let Some(matches) = matches.subcommand_matches("check") {
+		let roads: data::RoadGraph = cache::read(matches.value_of("road-graph").unwrap(), "Road graph config")?;
+		let file = matches.value_of("paths").unwrap();
+		let violations = if matches.is_present("sidewalks") {
+			legality::check_sidewalk_paths(&roads, &document::read(file, "Sidewalk paths")?)
+		} else {
+			legality::check_paths(&roads, &document::read(file, "Paths")?)
+		};
+		document::print(&violations)?;
+		if !violations.is_empty() {
+			log::error!("{} violations", violations.len());
+			std::process::exit(1);
+		}
+		log::info!("All paths can be driven");
+	} else if let Some(matches) = matches.subcommand_matches("verify") {
+		let roads: data::RoadGraph = cache::read(matches.value_of("road-graph").unwrap(), "Road graph config")?;
+		let snow: data::SnowStatuses = document::read(matches.value_of("snow").unwrap(), "Snow status config")?;
+		let params: Option<meta::Parameters> = matches.value_of("meta").map(read_meta).transpose()?;
+		let file = matches.value_of("paths").unwrap();
+		log::info!("Loaded configuration");
+		let verification = if matches.is_present("sidewalks") {
+			legality::verify_sidewalk_paths(&roads, &snow, &document::read_any::<Produced<_>>(file, "Sidewalk paths")?.paths(), params.as_ref())
+		} else {
+			legality::verify_paths(&roads, &snow, &document::read_any::<Produced<_>>(file, "Paths")?.paths(), params.as_ref())
+		};
+		match matches.value_of("output") {
+			Some(output) => document::write(output, &verification)?,
+			None => document::print(&verification)?,
+		}
+		if let Some(f) = matches.value_of("geojson") {
+			write_geojson(f, &gj::uncovered_to_geofeatures(&roads, &verification.uncovered))?;
+		}
+		if !verification.is_ok() {
+			return Err(error::Error::invalid(file, format!("Paths leave {} snowy segments uncovered, and traverse {} nonexistent segments", verification.uncovered.len(), verification.nonexistent.len())));
+		}
+		log::info!("Paths cover every snowy segment");
+	} else if let Some(matches) = matches.subcommand_matches("schedule") {
+		let roads: data::RoadGraph = cache::read(matches.value_of("road-graph").unwrap(), "Road graph config")?;
+		let snow: data::SnowStatuses = document::read(matches.value_of("snow").unwrap(), "Snow status config")?;
+		let paths: data::Paths = document::read(matches.value_of("paths").unwrap(), "Paths")?;
+		let vehicles: data::VehiclesConfiguration = document::read(matches.value_of("vehicles").unwrap(), "Vehicles configuration")?;
+		let params: meta::Parameters = read_meta(matches.value_of("meta").unwrap())?;
+		log::info!("Loaded configuration");
+		let snow_d = parse_opt(matches, "snow-d")?;
+		let timetable = report::etas(&roads, &snow, snow_d, &paths, &vehicles.profiles(false), &params, parse(matches, "speed")?)?;
+		log::info!("Last vehicle done in {:.2}h", timetable.iter().filter_map(|p| p.last().and_then(|s| s.eta)).fold(0.0, f64::max));
+		document::write(matches.value_of("output").unwrap(), &timetable)?;
+	} else if let Some(matches) = matches.subcommand_matches("stats") {
+		let roads: data::IndexedRoadGraph = cache::read_indexed(matches.value_of("road-graph").unwrap(), "Road graph config")?;
+		let paths: Option<data::Paths> = matches.value_of("paths").map(|f| document::read(f, "Paths")).transpose()?;
+		let snow: Option<data::SnowStatuses> = matches.value_of("snow").map(|f| document::read(f, "Snow status config")).transpose()?;
+		log::info!("Loaded configuration");
+		let stats = report::stats(&roads, paths.as_ref(), snow.as_ref());
+		log::info!("{} nodes, {} segments, {:.0}m of road, {} SCCs, {} bridges, {} articulation points", stats.graph.nodes, stats.graph.segments, stats.graph.length, stats.graph.sccs, stats.graph.bridges, stats.graph.articulation_points);
+		if let Some(plan) = &stats.plan {
+			log::info!("{} vehicles, {:.0}m driven", plan.vehicles.len(), plan.total_distance);
+		}
+		match matches.value_of("output") {
+			Some(output) => document::write(output, &stats)?,
+			None => document::print(&stats)?,
+		}
+	} else if let Some(matches) = matches.subcommand_matches("graph").and_then(|m| m.subcommand_matches("compile")) {
+		let file = matches.value_of("road-graph").unwrap();
+		cache::compile(file, "Road graph")?;
+		log::info!("Compiled {}", cache::path(file).display());
+	} else if let Some(matches) = matches.subcommand_matches("meta").and_then(|m| m.subcommand_matches("check")) {
+		let params: meta::Parameters = read_meta(matches.value_of("meta").unwrap())?;
+		print!("{}", serde_yaml::to_string(&params).map_err(std::io::Error::other)?);
+	} else if let Some(matches) = matches.subcommand_matches("migrate") {
+		let input = matches.value_of("input").unwrap();
+		let doc = serde_json::from_reader(&std::fs::File::open(input)?).map_err(|e| error::Error::json(input, "Document", e))?;
+		let doc = document::upgrade(doc, matches.value_of("kind")).map_err(|e| error::Error::invalid(input, format!("Document {}", e)))?;
+		log::info!("Upgraded {} document to version {}", doc.kind, doc.version);
+		write_geojson(matches.value_of("output").unwrap(), &doc)?;
+	} else if let Some(matches) = matches.subcommand_matches("explain") {
+		let roads: data::IndexedRoadGraph = cache::read_indexed(matches.value_of("road-graph").unwrap(), "Road graph config")?;
+		let paths: data::Paths = document::read(matches.value_of("paths").unwrap(), "Paths")?;
+		let params: Option<meta::Parameters> = matches.value_of("meta").map(read_meta).transpose()?;
+		log::info!("Loaded configuration");
+		let history: Vec<progress::Move> = match matches.value_of("history") {
+			Some(f) => {
+				use std::io::BufRead;
+				std::io::BufReader::new(std::fs::File::open(f).map_err(|e| error::Error::io(f, e))?).lines().filter(|l| l.as_ref().map_or(true, |l| !l.trim().is_empty()))
+					.map(|l| l.map_err(|e| error::Error::io(f, e)).and_then(|l| serde_json::from_str(&l).map_err(|e| error::Error::json(f, "Allocation moves", e))))
+					.collect::<Result<_, _>>()?
+			},
+			None => Vec::new(),
+		};
+		let segment = data::segment_key(&matches.value_of("p1").unwrap().to_string().into(), &matches.value_of("p2").unwrap().to_string().into(), &matches.value_of("discriminator").map(|d| d.to_string().into()));
+		let explanation = report::explain(&roads, &paths, segment, params.as_ref(), &history)?;
+		match explanation.services.first() {
+			Some(s) => log::info!("Served by vehicle {} at step {}, {:.0}m into its tour", s.vehicle, s.step, s.distance),
+			None => log::warn!("Not served by any vehicle"),
+		}
+		if matches.is_present("history") {
+			log::info!("Moved {} times while solving", explanation.moves.iter().filter(|m| m.from.is_some()).count());
+		}
+		document::print(&explanation)?;
+	} else if let Some(matches) = matches.subcommand_matches("geojson").and_then(|m| m.subcommand_matches("import")) {
+		let input = matches.value_of("input").unwrap();
+		let feat = gj::geojson_to_geofeatures(serde_json::from_reader(&std::fs::File::open(input)?).map_err(|e| error::Error::json(input, "Road graph GeoJSON", e))?);
+		let (roads, issues) = gj::geofeatures_to_roadgraph(feat, parse(matches, "tolerance")?);
+		log::info!("Built a road graph of {} nodes and {} segments", roads.nodes.nodes.len(), roads.roads.len());
+		imported(&issues);
+		document::write(matches.value_of("output").unwrap(), &roads)?;
+		if let Some(report) = matches.value_of("report") {
+			document::write(report, &issues)?;
+		}
+	} else if let Some(matches) = matches.subcommand_matches("geojson") {
+		let roads: data::RoadGraph = cache::read(matches.value_of("road-graph").unwrap(), "Road graph config")?;
+		let pref = matches.value_of("prefix").unwrap();
+		let wut = document::read_any(matches.value_of("wut").unwrap(), "WUT")?;
+		log::info!("Loaded configuration");
+		match wut {
+			Wut::Paths(paths) if matches.is_present("gpx") => {
+				std::fs::write(format!("{}.gpx", pref), gpx::paths_to_gpx(&roads, &paths))?;
+			}
+			Wut::Paths(paths) if matches.is_present("animate") => {
+				let start = match parse_opt(matches, "start")? {
+					Some(s) => s,
+					None => std::time::SystemTime::now().duration_since(std::time::UNIX_EPOCH).map_or(0.0, |d| d.as_secs() as f64),
+				};
+				let features = gj::timed_paths_to_geofeatures(&roads, &paths, start).ok_or_else(|| error::Error::invalid(matches.value_of("wut").unwrap(), "Paths have no arrival times, see schedule"))?;
+				write_geojson(format!("{}.animated.geojson", pref), &features)?;
+			}
 			Wut::Paths(paths) => {
-				let g = gj::roads_to_nodes(roads.nodes);
 				for (i, path) in (0..paths.len()).zip(paths.into_iter()) {
-					serde_json::to_writer(&std::fs::File::create(format!("{}.{}.geojson", pref, i))?, &gj::path_to_geojson(&g, path)).unwrap();
+					write_geojson(format!("{}.{}.geojson", pref, i), &gj::path_to_geojson(&roads, path))?;
 				}
 			}
 			Wut::Drones(drones) => {
-				serde_json::to_writer(&std::fs::File::create(format!("{}.geojson", pref))?, &gj::locations_to_geojson(&roads.nodes, drones)).unwrap();
+				write_geojson(format!("{}.geojson", pref), &gj::locations_to_geojson(&roads.nodes, drones))?;
 			}
 			Wut::Vehicles(vc) => {
-				serde_json::to_writer(&std::fs::File::create(format!("{}.road.geojson", pref))?, &gj::locations_to_geojson(&roads.nodes, vc.road)).unwrap();
-				serde_json::to_writer(&std::fs::File::create(format!("{}.sidewalk.geojson", pref))?, &gj::locations_to_geojson(&roads.nodes, vc.sidewalk)).unwrap();
+				write_geojson(format!("{}.road.geojson", pref), &gj::locations_to_geojson(&roads.nodes, vc.road))?;
+				write_geojson(format!("{}.sidewalk.geojson", pref), &gj::locations_to_geojson(&roads.nodes, vc.sidewalk))?;
 			}
 			Wut::Snow(snows) => {
-				serde_json::to_writer(&std::fs::File::create(format!("{}.geojson", pref))?, &gj::snows_to_geofeatures(&roads, snows)).unwrap();
+				write_geojson(format!("{}.geojson", pref), &gj::snows_to_geofeatures(&roads, snows))?;
 			}
 		}
 	}